@@ -0,0 +1,72 @@
+//! Token counting of large messages, e.g. tool results holding whole files.
+//!
+//! Run with `cargo bench -p chat_cli --bench token_counter`.
+
+use std::hint::black_box;
+
+use chat_cli::cli::chat::token_counter::Tokenizer;
+use criterion::{
+    BatchSize,
+    Criterion,
+    criterion_group,
+    criterion_main,
+};
+
+const MESSAGE_LEN: usize = 400 * 1024;
+
+fn repeat_to_len(text: &str) -> String {
+    text.repeat(MESSAGE_LEN.div_ceil(text.len()))
+}
+
+/// Text without any whitespace, like minified code or base64 data.
+fn base64_like() -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut state = 0x2545_f491_u32;
+    (0..MESSAGE_LEN)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            ALPHABET[state as usize % ALPHABET.len()] as char
+        })
+        .collect()
+}
+
+fn bench_count(c: &mut Criterion) {
+    let inputs = [
+        (
+            "prose",
+            repeat_to_len("The quick brown fox jumps over the lazy dog while the cat naps. "),
+        ),
+        (
+            "code",
+            repeat_to_len("fn main() {\n    let values = vec![1, 2, 3];\n    println!(\"{:?}\", values);\n}\n"),
+        ),
+        ("cjk", repeat_to_len("日本語のテキストを数える。")),
+        ("base64", base64_like()),
+        ("single_letter", "a".repeat(MESSAGE_LEN)),
+    ];
+
+    let mut group = c.benchmark_group("count_400kb");
+    group.sample_size(10);
+    for tokenizer in [Tokenizer::Cl100kBase, Tokenizer::O200kBase] {
+        for (name, text) in &inputs {
+            // Counts of identical texts are cached, so each iteration counts a distinct one.
+            let mut n = 0_usize;
+            group.bench_function(format!("{tokenizer:?}/{name}"), |b| {
+                b.iter_batched(
+                    || {
+                        n += 1;
+                        format!("{n} {text}")
+                    },
+                    |text| black_box(tokenizer.count(&text)),
+                    BatchSize::LargeInput,
+                );
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_count);
+criterion_main!(benches);
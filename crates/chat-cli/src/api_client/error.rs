@@ -19,6 +19,8 @@ use aws_smithy_runtime_api::http::Response;
 use aws_smithy_types::event_stream::RawMessage;
 use thiserror::Error;
 
+use crate::api_client::openai::OpenAiError;
+use crate::api_client::recording::ReplayError;
 use crate::auth::AuthError;
 use crate::aws_common::SdkErrorDisplay;
 use crate::telemetry::ReasonCode;
@@ -104,6 +106,12 @@ pub enum ApiClientError {
 
     #[error(transparent)]
     GetProfileError(#[from] SdkError<GetProfileError, HttpResponse>),
+
+    #[error(transparent)]
+    Replay(#[from] ReplayError),
+
+    #[error(transparent)]
+    OpenAi(#[from] OpenAiError),
 }
 
 impl ApiClientError {
@@ -130,6 +138,8 @@ impl ApiClientError {
             Self::ListAvailableModelsError(e) => sdk_status_code(e),
             Self::DefaultModelNotFound => None,
             Self::GetProfileError(e) => sdk_status_code(e),
+            Self::Replay(_) => None,
+            Self::OpenAi(e) => e.status_code(),
         }
     }
 }
@@ -158,6 +168,8 @@ impl ReasonCode for ApiClientError {
             Self::ListAvailableModelsError(e) => sdk_error_code(e),
             Self::DefaultModelNotFound => "DefaultModelNotFound".to_string(),
             Self::GetProfileError(e) => sdk_error_code(e),
+            Self::Replay(_) => "ReplayError".to_string(),
+            Self::OpenAi(_) => "OpenAiError".to_string(),
         }
     }
 }
@@ -238,6 +250,12 @@ mod tests {
                 raw_message(),
             )),
             ApiClientError::SmithyBuild(aws_smithy_types::error::operation::BuildError::other("<other>")),
+            ApiClientError::Replay(ReplayError::Exhausted {
+                sampling_server: None,
+                index: 2,
+                recorded: 1,
+            }),
+            ApiClientError::OpenAi(OpenAiError::NotConfigured),
         ]
     }
 
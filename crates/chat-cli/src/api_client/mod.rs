@@ -4,8 +4,10 @@ mod delay_interceptor;
 mod endpoints;
 mod error;
 pub mod model;
+pub mod openai;
 mod opt_out;
 pub mod profile;
+pub mod recording;
 mod retry_classifier;
 pub mod send_message_output;
 use std::sync::Arc;
@@ -48,7 +50,20 @@ use crate::api_client::model::{
     ChatResponseStream,
     ConversationState,
 };
+use crate::api_client::openai::{
+    OPENAI_API_KEY_ENV,
+    OPENAI_MODEL_PREFIX,
+    OpenAiClient,
+    OpenAiError,
+};
 use crate::api_client::opt_out::OptOutInterceptor;
+use crate::api_client::recording::{
+    Exchange,
+    RECORD_FILE_ENV,
+    REPLAY_FILE_ENV,
+    Recorder,
+    Replayer,
+};
 use crate::api_client::send_message_output::SendMessageOutput;
 use crate::auth::builder_id::BearerResolver;
 use crate::aws_common::{
@@ -94,6 +109,12 @@ pub struct ApiClient {
     streaming_client: Option<CodewhispererStreamingClient>,
     sigv4_streaming_client: Option<QDeveloperStreamingClient>,
     mock_client: Option<Arc<Mutex<std::vec::IntoIter<Vec<ChatResponseStream>>>>>,
+    /// Client for the models prefixed with [OPENAI_MODEL_PREFIX], set when an OpenAI-compatible
+    /// endpoint is configured.
+    openai_client: Option<OpenAiClient>,
+    /// Set when responses are served from a recording instead of the backend.
+    replayer: Option<Replayer>,
+    recorder: Option<Recorder>,
     profile: Option<AuthProfile>,
     model_cache: ModelCache,
 }
@@ -128,12 +149,41 @@ impl ApiClient {
                 .build(),
         );
 
+        let recorder = env.get(RECORD_FILE_ENV).ok().map(|path| Recorder::new(fs, path));
+        let openai_client = database
+            .settings
+            .get_string(Setting::ApiOpenAiEndpoint)
+            .and_then(|endpoint| match crate::request::new_client() {
+                Ok(http) => Some(OpenAiClient::new(http, &endpoint, env.get(OPENAI_API_KEY_ENV).ok())),
+                Err(err) => {
+                    error!(?err, "Failed to create the client for the OpenAI-compatible endpoint");
+                    None
+                },
+            });
+
+        if let Ok(path) = env.get(REPLAY_FILE_ENV) {
+            return Ok(Self {
+                client,
+                streaming_client: None,
+                sigv4_streaming_client: None,
+                mock_client: None,
+                openai_client: None,
+                replayer: Some(Replayer::load(fs, path).await?),
+                recorder: None,
+                profile: None,
+                model_cache: Arc::new(RwLock::new(None)),
+            });
+        }
+
         if cfg!(test) {
             let mut this = Self {
                 client,
                 streaming_client: None,
                 sigv4_streaming_client: None,
                 mock_client: None,
+                openai_client,
+                replayer: None,
+                recorder,
                 profile: None,
                 model_cache: Arc::new(RwLock::new(None)),
             };
@@ -213,6 +263,9 @@ impl ApiClient {
             streaming_client,
             sigv4_streaming_client,
             mock_client: None,
+            openai_client,
+            replayer: None,
+            recorder,
             profile,
             model_cache: Arc::new(RwLock::new(None)),
         })
@@ -225,7 +278,7 @@ impl ApiClient {
         telemetry_enabled: bool,
         model: Option<String>,
     ) -> Result<(), ApiClientError> {
-        if cfg!(test) {
+        if cfg!(test) || self.is_replaying() {
             return Ok(());
         }
 
@@ -268,7 +321,44 @@ impl ApiClient {
         Ok(profiles)
     }
 
+    /// Lists the models of the backend, along with the ones served by the OpenAI-compatible
+    /// endpoint if configured. The endpoint's first model is the default if the backend cannot be
+    /// reached.
     pub async fn list_available_models(&self) -> Result<ModelListResult, ApiClientError> {
+        if let Some(replayer) = &self.replayer {
+            return replayer.models().ok_or(ApiClientError::DefaultModelNotFound);
+        }
+
+        let Some(openai_client) = &self.openai_client else {
+            return self.list_backend_models().await;
+        };
+        let openai_models = match openai_client.list_models().await {
+            Ok(models) => models,
+            Err(err) => {
+                error!(?err, "Failed to list the models of the OpenAI-compatible endpoint");
+                return self.list_backend_models().await;
+            },
+        };
+        match self.list_backend_models().await {
+            Ok(mut result) => {
+                result.models.extend(openai_models);
+                Ok(result)
+            },
+            Err(err) => {
+                debug!(
+                    ?err,
+                    "Failed to list the backend models, using the OpenAI-compatible endpoint"
+                );
+                let default_model = openai_models.first().cloned().ok_or(err)?;
+                Ok(ModelListResult {
+                    models: openai_models,
+                    default_model,
+                })
+            },
+        }
+    }
+
+    async fn list_backend_models(&self) -> Result<ModelListResult, ApiClientError> {
         if cfg!(test) {
             let m = Model::builder()
                 .model_id("model-1")
@@ -345,6 +435,10 @@ impl ApiClient {
     }
 
     pub async fn is_mcp_enabled(&self) -> Result<bool, ApiClientError> {
+        if self.is_replaying() {
+            return Ok(true);
+        }
+
         let request = self
             .client
             .get_profile()
@@ -375,9 +469,91 @@ impl ApiClient {
             .map_err(ApiClientError::CreateSubscriptionToken)
     }
 
+    /// Whether responses are served from the recording given by [REPLAY_FILE_ENV] instead of the
+    /// backend.
+    pub fn is_replaying(&self) -> bool {
+        self.replayer.is_some()
+    }
+
+    /// Whether chatting with `model_id` requires being logged in, which is not the case when
+    /// replaying a recording or when the model is served by the OpenAI-compatible endpoint.
+    pub fn requires_auth(&self, model_id: Option<&str>) -> bool {
+        let openai_model = model_id.is_some_and(|model_id| model_id.starts_with(OPENAI_MODEL_PREFIX));
+        self.replayer.is_none() && !(openai_model && self.openai_client.is_some())
+    }
+
     pub async fn send_message(&self, conversation: ConversationState) -> Result<SendMessageOutput, ApiClientError> {
+        self.send_recorded_message(None, conversation).await
+    }
+
+    /// Sends a request made by the mcp server `server_name` through sampling. These are recorded
+    /// and replayed apart from the requests of the chat.
+    pub async fn send_sampling_message(
+        &self,
+        server_name: &str,
+        conversation: ConversationState,
+    ) -> Result<SendMessageOutput, ApiClientError> {
+        self.send_recorded_message(Some(server_name), conversation).await
+    }
+
+    async fn send_recorded_message(
+        &self,
+        sampling_server: Option<&str>,
+        conversation: ConversationState,
+    ) -> Result<SendMessageOutput, ApiClientError> {
+        if let Some(replayer) = &self.replayer {
+            let Exchange {
+                request_id, mut events, ..
+            } = match sampling_server {
+                Some(server_name) => replayer.next_sampling(server_name, &conversation)?,
+                None => replayer.next(&conversation)?,
+            };
+            events.reverse();
+            return Ok(SendMessageOutput::Replay { request_id, events });
+        }
+
+        let Some(recorder) = &self.recorder else {
+            return self.send_message_to_backend(conversation).await;
+        };
+        let request = conversation.clone();
+        let output = self.send_message_to_backend(conversation).await?;
+        Ok(SendMessageOutput::Recording {
+            recorder: recorder.clone(),
+            exchange: Some(Exchange {
+                request,
+                request_id: output.request_id().map(str::to_string),
+                events: Vec::new(),
+                sampling_server: sampling_server.map(str::to_string),
+            }),
+            output: Box::new(output),
+        })
+    }
+
+    async fn send_message_to_backend(
+        &self,
+        conversation: ConversationState,
+    ) -> Result<SendMessageOutput, ApiClientError> {
         debug!("Sending conversation: {:#?}", conversation);
 
+        let openai_model = conversation
+            .user_input_message
+            .model_id
+            .as_deref()
+            .and_then(|model_id| model_id.strip_prefix(OPENAI_MODEL_PREFIX))
+            .map(str::to_string);
+        if let Some(model) = openai_model {
+            let Some(client) = &self.openai_client else {
+                return Err(OpenAiError::NotConfigured.into());
+            };
+            return match client.send_message(conversation, &model).await {
+                Ok(output) => Ok(SendMessageOutput::OpenAi(output)),
+                Err(err) if err.is_context_window_overflow() => Err(ApiClientError::ContextWindowOverflow {
+                    status_code: err.status_code(),
+                }),
+                Err(err) => Err(err.into()),
+            };
+        }
+
         let ConversationState {
             conversation_id,
             user_input_message,
@@ -691,6 +867,25 @@ mod tests {
         let _ = ApiClient::new(&env, &fs, &mut database, None).await;
     }
 
+    #[tokio::test]
+    async fn test_requires_auth() {
+        let env = Env::new();
+        let fs = Fs::new();
+        let mut database = crate::database::Database::new().await.unwrap();
+        let client = ApiClient::new(&env, &fs, &mut database, None).await.unwrap();
+        assert!(client.requires_auth(Some("openai:local")));
+
+        database
+            .settings
+            .set(Setting::ApiOpenAiEndpoint, "http://localhost:8000/v1")
+            .await
+            .unwrap();
+        let client = ApiClient::new(&env, &fs, &mut database, None).await.unwrap();
+        assert!(!client.requires_auth(Some("openai:local")));
+        assert!(client.requires_auth(Some("claude-sonnet-4")));
+        assert!(client.requires_auth(None));
+    }
+
     #[tokio::test]
     async fn test_mock() {
         let env = Env::new();
@@ -754,4 +949,44 @@ mod tests {
         }
         assert_eq!(output_content, "Hello! How can I assist you today?");
     }
+
+    #[tokio::test]
+    async fn test_record_and_replay() {
+        let fs = Fs::new();
+        let mut database = crate::database::Database::new().await.unwrap();
+        let request = || ConversationState {
+            conversation_id: None,
+            user_input_message: UserInputMessage {
+                images: None,
+                content: "Hello".into(),
+                user_input_message_context: None,
+                user_intent: None,
+                model_id: Some("model".to_owned()),
+            },
+            history: None,
+        };
+
+        let env = Env::from_slice(&[(RECORD_FILE_ENV, "/recording.jsonl")]);
+        let mut client = ApiClient::new(&env, &fs, &mut database, None).await.unwrap();
+        client.set_mock_output(serde_json::json!([["Hello!", " Bye."]]));
+        let mut output = client.send_message(request()).await.unwrap();
+        while output.recv().await.unwrap().is_some() {}
+
+        let env = Env::from_slice(&[(REPLAY_FILE_ENV, "/recording.jsonl")]);
+        let client = ApiClient::new(&env, &fs, &mut database, None).await.unwrap();
+        assert!(client.is_replaying());
+        assert_eq!(
+            client.list_available_models().await.unwrap().default_model.model_id(),
+            "model"
+        );
+        let mut output = client.send_message(request()).await.unwrap();
+        let mut output_content = String::new();
+        while let Some(ChatResponseStream::AssistantResponseEvent { content }) = output.recv().await.unwrap() {
+            output_content.push_str(&content);
+        }
+        assert_eq!(output_content, "Hello! Bye.");
+
+        let err = client.send_message(request()).await.unwrap_err();
+        assert!(matches!(err, ApiClientError::Replay(_)), "{err}");
+    }
 }
@@ -92,14 +92,14 @@ pub struct Recommendation {
 // Streaming
 // =========
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConversationState {
     pub conversation_id: Option<String>,
     pub user_input_message: UserInputMessage,
     pub history: Option<Vec<ChatMessage>>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ChatMessage {
     AssistantResponseMessage(AssistantResponseMessage),
     UserInputMessage(UserInputMessage),
@@ -316,6 +316,20 @@ impl<'de> Deserialize<'de> for FigDocument {
     }
 }
 
+fn serialize_aws_document<S>(document: &AwsDocument, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    FigDocumentRef(document).serialize(serializer)
+}
+
+fn deserialize_aws_document<'de, D>(deserializer: D) -> Result<AwsDocument, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    FigDocument::deserialize(deserializer).map(Into::into)
+}
+
 /// Information about a tool that can be used.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Tool {
@@ -424,7 +438,7 @@ impl From<ToolUse> for amzn_qdeveloper_streaming_client::types::ToolUse {
 }
 
 /// A tool result that contains the results for a tool request that was previously made.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolResult {
     /// The ID for the tool request.
     pub tool_use_id: String,
@@ -456,10 +470,16 @@ impl From<ToolResult> for amzn_qdeveloper_streaming_client::types::ToolResult {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ToolResultContentBlock {
     /// A tool result that is JSON format data.
-    Json(AwsDocument),
+    Json(
+        #[serde(
+            serialize_with = "serialize_aws_document",
+            deserialize_with = "deserialize_aws_document"
+        )]
+        AwsDocument,
+    ),
     /// A tool result that is text.
     Text(String),
 }
@@ -507,7 +527,7 @@ impl From<ToolResultStatus> for amzn_qdeveloper_streaming_client::types::ToolRes
 }
 
 /// Markdown text message.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AssistantResponseMessage {
     /// Unique identifier for the chat message
     pub message_id: Option<String>,
@@ -542,7 +562,7 @@ impl TryFrom<AssistantResponseMessage> for amzn_qdeveloper_streaming_client::typ
 }
 
 #[non_exhaustive]
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ChatResponseStream {
     AssistantResponseEvent {
         content: String,
@@ -551,9 +571,14 @@ pub enum ChatResponseStream {
     CodeEvent {
         content: String,
     },
+    /// Licenses of the code that the preceding content was found to be similar to.
+    CodeReferenceEvent {
+        references: Vec<CodeReference>,
+    },
+    FollowupPromptEvent {
+        followup_prompt: Option<FollowupPrompt>,
+    },
     // TODO: finish events here
-    CodeReferenceEvent(()),
-    FollowupPromptEvent(()),
     IntentsEvent(()),
     InvalidStateEvent {
         reason: String,
@@ -563,7 +588,9 @@ pub enum ChatResponseStream {
         conversation_id: Option<String>,
         utterance_id: Option<String>,
     },
-    SupplementaryWebLinksEvent(()),
+    SupplementaryWebLinksEvent {
+        supplementary_web_links: Vec<SupplementaryWebLink>,
+    },
     ToolUseEvent {
         tool_use_id: String,
         name: String,
@@ -584,11 +611,15 @@ impl From<amzn_codewhisperer_streaming_client::types::ChatResponseStream> for Ch
             amzn_codewhisperer_streaming_client::types::ChatResponseStream::CodeEvent(
                 amzn_codewhisperer_streaming_client::types::CodeEvent { content, .. },
             ) => ChatResponseStream::CodeEvent { content },
-            amzn_codewhisperer_streaming_client::types::ChatResponseStream::CodeReferenceEvent(_) => {
-                ChatResponseStream::CodeReferenceEvent(())
+            amzn_codewhisperer_streaming_client::types::ChatResponseStream::CodeReferenceEvent(
+                amzn_codewhisperer_streaming_client::types::CodeReferenceEvent { references, .. },
+            ) => ChatResponseStream::CodeReferenceEvent {
+                references: references.unwrap_or_default().into_iter().map(Into::into).collect(),
             },
-            amzn_codewhisperer_streaming_client::types::ChatResponseStream::FollowupPromptEvent(_) => {
-                ChatResponseStream::FollowupPromptEvent(())
+            amzn_codewhisperer_streaming_client::types::ChatResponseStream::FollowupPromptEvent(
+                amzn_codewhisperer_streaming_client::types::FollowupPromptEvent { followup_prompt, .. },
+            ) => ChatResponseStream::FollowupPromptEvent {
+                followup_prompt: followup_prompt.map(Into::into),
             },
             amzn_codewhisperer_streaming_client::types::ChatResponseStream::IntentsEvent(_) => {
                 ChatResponseStream::IntentsEvent(())
@@ -623,8 +654,17 @@ impl From<amzn_codewhisperer_streaming_client::types::ChatResponseStream> for Ch
                 input,
                 stop,
             },
-            amzn_codewhisperer_streaming_client::types::ChatResponseStream::SupplementaryWebLinksEvent(_) => {
-                ChatResponseStream::SupplementaryWebLinksEvent(())
+            amzn_codewhisperer_streaming_client::types::ChatResponseStream::SupplementaryWebLinksEvent(
+                amzn_codewhisperer_streaming_client::types::SupplementaryWebLinksEvent {
+                    supplementary_web_links,
+                    ..
+                },
+            ) => ChatResponseStream::SupplementaryWebLinksEvent {
+                supplementary_web_links: supplementary_web_links
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(Into::into)
+                    .collect(),
             },
             _ => ChatResponseStream::Unknown,
         }
@@ -640,11 +680,15 @@ impl From<amzn_qdeveloper_streaming_client::types::ChatResponseStream> for ChatR
             amzn_qdeveloper_streaming_client::types::ChatResponseStream::CodeEvent(
                 amzn_qdeveloper_streaming_client::types::CodeEvent { content, .. },
             ) => ChatResponseStream::CodeEvent { content },
-            amzn_qdeveloper_streaming_client::types::ChatResponseStream::CodeReferenceEvent(_) => {
-                ChatResponseStream::CodeReferenceEvent(())
+            amzn_qdeveloper_streaming_client::types::ChatResponseStream::CodeReferenceEvent(
+                amzn_qdeveloper_streaming_client::types::CodeReferenceEvent { references, .. },
+            ) => ChatResponseStream::CodeReferenceEvent {
+                references: references.unwrap_or_default().into_iter().map(Into::into).collect(),
             },
-            amzn_qdeveloper_streaming_client::types::ChatResponseStream::FollowupPromptEvent(_) => {
-                ChatResponseStream::FollowupPromptEvent(())
+            amzn_qdeveloper_streaming_client::types::ChatResponseStream::FollowupPromptEvent(
+                amzn_qdeveloper_streaming_client::types::FollowupPromptEvent { followup_prompt, .. },
+            ) => ChatResponseStream::FollowupPromptEvent {
+                followup_prompt: followup_prompt.map(Into::into),
             },
             amzn_qdeveloper_streaming_client::types::ChatResponseStream::IntentsEvent(_) => {
                 ChatResponseStream::IntentsEvent(())
@@ -679,14 +723,114 @@ impl From<amzn_qdeveloper_streaming_client::types::ChatResponseStream> for ChatR
                 input,
                 stop,
             },
-            amzn_qdeveloper_streaming_client::types::ChatResponseStream::SupplementaryWebLinksEvent(_) => {
-                ChatResponseStream::SupplementaryWebLinksEvent(())
+            amzn_qdeveloper_streaming_client::types::ChatResponseStream::SupplementaryWebLinksEvent(
+                amzn_qdeveloper_streaming_client::types::SupplementaryWebLinksEvent {
+                    supplementary_web_links,
+                    ..
+                },
+            ) => ChatResponseStream::SupplementaryWebLinksEvent {
+                supplementary_web_links: supplementary_web_links
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(Into::into)
+                    .collect(),
             },
             _ => ChatResponseStream::Unknown,
         }
     }
 }
 
+/// Code found to be similar to the generated content, along with its license.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CodeReference {
+    pub license_name: Option<String>,
+    pub repository: Option<String>,
+    pub url: Option<String>,
+    /// Range of the generated content that the reference applies to.
+    pub recommendation_content_span: Option<Span>,
+}
+
+impl From<amzn_codewhisperer_streaming_client::types::Reference> for CodeReference {
+    fn from(value: amzn_codewhisperer_streaming_client::types::Reference) -> Self {
+        Self {
+            license_name: value.license_name,
+            repository: value.repository,
+            url: value.url,
+            recommendation_content_span: value.recommendation_content_span.map(|span| Span {
+                start: span.start,
+                end: span.end,
+            }),
+        }
+    }
+}
+
+impl From<amzn_qdeveloper_streaming_client::types::Reference> for CodeReference {
+    fn from(value: amzn_qdeveloper_streaming_client::types::Reference) -> Self {
+        Self {
+            license_name: value.license_name,
+            repository: value.repository,
+            url: value.url,
+            recommendation_content_span: value.recommendation_content_span.map(|span| Span {
+                start: span.start,
+                end: span.end,
+            }),
+        }
+    }
+}
+
+/// Range of characters, end exclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Span {
+    pub start: Option<i32>,
+    pub end: Option<i32>,
+}
+
+/// Web page related to the response.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SupplementaryWebLink {
+    pub url: String,
+    pub title: String,
+    pub snippet: Option<String>,
+}
+
+impl From<amzn_codewhisperer_streaming_client::types::SupplementaryWebLink> for SupplementaryWebLink {
+    fn from(value: amzn_codewhisperer_streaming_client::types::SupplementaryWebLink) -> Self {
+        Self {
+            url: value.url,
+            title: value.title,
+            snippet: value.snippet,
+        }
+    }
+}
+
+impl From<amzn_qdeveloper_streaming_client::types::SupplementaryWebLink> for SupplementaryWebLink {
+    fn from(value: amzn_qdeveloper_streaming_client::types::SupplementaryWebLink) -> Self {
+        Self {
+            url: value.url,
+            title: value.title,
+            snippet: value.snippet,
+        }
+    }
+}
+
+/// Prompt suggested to continue the conversation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FollowupPrompt {
+    pub content: String,
+}
+
+impl From<amzn_codewhisperer_streaming_client::types::FollowupPrompt> for FollowupPrompt {
+    fn from(value: amzn_codewhisperer_streaming_client::types::FollowupPrompt) -> Self {
+        Self { content: value.content }
+    }
+}
+
+impl From<amzn_qdeveloper_streaming_client::types::FollowupPrompt> for FollowupPrompt {
+    fn from(value: amzn_qdeveloper_streaming_client::types::FollowupPrompt) -> Self {
+        Self { content: value.content }
+    }
+}
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct EnvState {
     pub operating_system: Option<String>,
@@ -742,7 +886,7 @@ impl From<EnvironmentVariable> for amzn_qdeveloper_streaming_client::types::Envi
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GitState {
     pub status: String,
 }
@@ -853,7 +997,7 @@ impl From<ImageBlock> for amzn_qdeveloper_streaming_client::types::ImageBlock {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserInputMessage {
     pub content: String,
     pub user_input_message_context: Option<UserInputMessageContext>,
@@ -890,7 +1034,7 @@ impl From<UserInputMessage> for amzn_qdeveloper_streaming_client::types::UserInp
     }
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct UserInputMessageContext {
     pub env_state: Option<EnvState>,
     pub git_state: Option<GitState>,
@@ -920,7 +1064,7 @@ impl From<UserInputMessageContext> for amzn_qdeveloper_streaming_client::types::
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum UserIntent {
     ApplyCommonBestPractices,
 }
@@ -1073,11 +1217,34 @@ mod tests {
         });
 
         let code_reference_event = amzn_codewhisperer_streaming_client::types::ChatResponseStream::CodeReferenceEvent(
-            amzn_codewhisperer_streaming_client::types::CodeReferenceEvent::builder().build(),
+            amzn_codewhisperer_streaming_client::types::CodeReferenceEvent::builder()
+                .references(
+                    amzn_codewhisperer_streaming_client::types::Reference::builder()
+                        .license_name("MIT")
+                        .repository("repo")
+                        .recommendation_content_span(
+                            amzn_codewhisperer_streaming_client::types::Span::builder()
+                                .start(1)
+                                .end(5)
+                                .build(),
+                        )
+                        .build(),
+                )
+                .build(),
         );
         assert_eq!(
             ChatResponseStream::from(code_reference_event),
-            ChatResponseStream::CodeReferenceEvent(())
+            ChatResponseStream::CodeReferenceEvent {
+                references: vec![CodeReference {
+                    license_name: Some("MIT".into()),
+                    repository: Some("repo".into()),
+                    url: None,
+                    recommendation_content_span: Some(Span {
+                        start: Some(1),
+                        end: Some(5)
+                    }),
+                }]
+            }
         );
 
         let code_reference_event = amzn_qdeveloper_streaming_client::types::ChatResponseStream::CodeReferenceEvent(
@@ -1085,15 +1252,24 @@ mod tests {
         );
         assert_eq!(
             ChatResponseStream::from(code_reference_event),
-            ChatResponseStream::CodeReferenceEvent(())
+            ChatResponseStream::CodeReferenceEvent { references: vec![] }
         );
 
         let followup_prompt_event = amzn_codewhisperer_streaming_client::types::ChatResponseStream::FollowupPromptEvent(
-            amzn_codewhisperer_streaming_client::types::FollowupPromptEvent::builder().build(),
+            amzn_codewhisperer_streaming_client::types::FollowupPromptEvent::builder()
+                .followup_prompt(
+                    amzn_codewhisperer_streaming_client::types::FollowupPrompt::builder()
+                        .content("next")
+                        .build()
+                        .unwrap(),
+                )
+                .build(),
         );
         assert_eq!(
             ChatResponseStream::from(followup_prompt_event),
-            ChatResponseStream::FollowupPromptEvent(())
+            ChatResponseStream::FollowupPromptEvent {
+                followup_prompt: Some(FollowupPrompt { content: "next".into() })
+            }
         );
 
         let followup_prompt_event = amzn_qdeveloper_streaming_client::types::ChatResponseStream::FollowupPromptEvent(
@@ -1101,7 +1277,7 @@ mod tests {
         );
         assert_eq!(
             ChatResponseStream::from(followup_prompt_event),
-            ChatResponseStream::FollowupPromptEvent(())
+            ChatResponseStream::FollowupPromptEvent { followup_prompt: None }
         );
 
         let intents_event = amzn_codewhisperer_streaming_client::types::ChatResponseStream::IntentsEvent(
@@ -1175,11 +1351,25 @@ mod tests {
 
         let user_input_event =
             amzn_codewhisperer_streaming_client::types::ChatResponseStream::SupplementaryWebLinksEvent(
-                amzn_codewhisperer_streaming_client::types::SupplementaryWebLinksEvent::builder().build(),
+                amzn_codewhisperer_streaming_client::types::SupplementaryWebLinksEvent::builder()
+                    .supplementary_web_links(
+                        amzn_codewhisperer_streaming_client::types::SupplementaryWebLink::builder()
+                            .url("https://example.com")
+                            .title("Example")
+                            .build()
+                            .unwrap(),
+                    )
+                    .build(),
             );
         assert_eq!(
             ChatResponseStream::from(user_input_event),
-            ChatResponseStream::SupplementaryWebLinksEvent(())
+            ChatResponseStream::SupplementaryWebLinksEvent {
+                supplementary_web_links: vec![SupplementaryWebLink {
+                    url: "https://example.com".into(),
+                    title: "Example".into(),
+                    snippet: None,
+                }]
+            }
         );
 
         let user_input_event = amzn_qdeveloper_streaming_client::types::ChatResponseStream::SupplementaryWebLinksEvent(
@@ -1187,7 +1377,9 @@ mod tests {
         );
         assert_eq!(
             ChatResponseStream::from(user_input_event),
-            ChatResponseStream::SupplementaryWebLinksEvent(())
+            ChatResponseStream::SupplementaryWebLinksEvent {
+                supplementary_web_links: vec![]
+            }
         );
 
         let user_input_event = amzn_codewhisperer_streaming_client::types::ChatResponseStream::ToolUseEvent(
@@ -0,0 +1,805 @@
+//! Model provider for endpoints implementing the OpenAI chat completions API, e.g. a local vLLM
+//! or llama.cpp server.
+//!
+//! Models served by the endpoint are listed with the [OPENAI_MODEL_PREFIX] prefix, and requests
+//! for those models are sent to `<endpoint>/chat/completions` instead of the Q backend.
+
+use std::collections::VecDeque;
+
+use amzn_codewhisperer_client::types::{
+    Model,
+    TokenLimits,
+};
+use base64::Engine;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use thiserror::Error;
+use tracing::debug;
+
+use super::model::{
+    AssistantResponseMessage,
+    ChatMessage,
+    ChatResponseStream,
+    ConversationState,
+    ImageBlock,
+    ImageFormat,
+    ImageSource,
+    Tool,
+    ToolResult,
+    ToolResultContentBlock,
+    UserInputMessage,
+};
+
+/// Prefix of the ids of the models served by the OpenAI-compatible endpoint.
+pub const OPENAI_MODEL_PREFIX: &str = "openai:";
+
+/// Environment variable holding the API key sent to the endpoint, if it requires one.
+pub const OPENAI_API_KEY_ENV: &str = "OPENAI_API_KEY";
+
+#[derive(Debug, Error)]
+pub enum OpenAiError {
+    #[error("no OpenAI-compatible endpoint is configured, set one with `q settings api.openai.endpoint <URL>`")]
+    NotConfigured,
+    #[error(transparent)]
+    Request(#[from] reqwest::Error),
+    #[error("the endpoint returned {status}: {message}")]
+    Status { status: u16, message: String },
+    #[error("invalid response from the endpoint: {0}")]
+    InvalidResponse(String),
+}
+
+impl OpenAiError {
+    pub fn status_code(&self) -> Option<u16> {
+        match self {
+            Self::Request(err) => err.status().map(|status| status.as_u16()),
+            Self::Status { status, .. } => Some(*status),
+            Self::NotConfigured | Self::InvalidResponse(_) => None,
+        }
+    }
+
+    /// Whether the endpoint rejected the request for being larger than the context window of the
+    /// model.
+    pub fn is_context_window_overflow(&self) -> bool {
+        match self {
+            Self::Status { status, message } => {
+                let message = message.to_lowercase();
+                *status == 400 && (message.contains("context length") || message.contains("context window"))
+            },
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OpenAiClient {
+    http: reqwest::Client,
+    endpoint: String,
+    api_key: Option<String>,
+}
+
+impl OpenAiClient {
+    /// `endpoint` is the base URL of the API, e.g. `http://localhost:8000/v1`.
+    pub fn new(http: reqwest::Client, endpoint: &str, api_key: Option<String>) -> Self {
+        Self {
+            http,
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            api_key,
+        }
+    }
+
+    /// Lists the models served by the endpoint, with their ids prefixed by [OPENAI_MODEL_PREFIX].
+    pub async fn list_models(&self) -> Result<Vec<Model>, OpenAiError> {
+        let response = self.request(reqwest::Method::GET, "models").send().await?;
+        let response = check_status(response).await?;
+        let models: ModelList = response.json().await?;
+
+        Ok(models
+            .data
+            .into_iter()
+            .filter_map(|model| {
+                Model::builder()
+                    .model_id(format!("{OPENAI_MODEL_PREFIX}{}", model.id))
+                    .set_token_limits(
+                        model
+                            .max_model_len
+                            .and_then(|len| i32::try_from(len).ok())
+                            .map(|len| TokenLimits::builder().max_input_tokens(len).build()),
+                    )
+                    .build()
+                    .ok()
+            })
+            .collect())
+    }
+
+    /// Sends `conversation` to the model `model`, given without the [OPENAI_MODEL_PREFIX].
+    pub async fn send_message(
+        &self,
+        conversation: ConversationState,
+        model: &str,
+    ) -> Result<OpenAiStream, OpenAiError> {
+        let request = ChatCompletionRequest::new(conversation, model);
+        debug!(?request.model, messages = request.messages.len(), "sending chat completion request");
+
+        let response = self
+            .request(reqwest::Method::POST, "chat/completions")
+            .json(&request)
+            .send()
+            .await?;
+        let response = check_status(response).await?;
+        let request_id = response
+            .headers()
+            .get("x-request-id")
+            .and_then(|id| id.to_str().ok())
+            .map(str::to_string);
+
+        Ok(OpenAiStream {
+            response,
+            request_id,
+            decoder: StreamDecoder::default(),
+        })
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let request = self.http.request(method, format!("{}/{path}", self.endpoint));
+        match &self.api_key {
+            Some(api_key) => request.bearer_auth(api_key),
+            None => request,
+        }
+    }
+}
+
+async fn check_status(response: reqwest::Response) -> Result<reqwest::Response, OpenAiError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let body = response.text().await.unwrap_or_default();
+    let message = serde_json::from_str::<ErrorBody>(&body)
+        .map(|body| body.error.message)
+        .unwrap_or(body);
+    Err(OpenAiError::Status {
+        status: status.as_u16(),
+        message,
+    })
+}
+
+/// Streamed response of a chat completion request, converted to [ChatResponseStream] events.
+#[derive(Debug)]
+pub struct OpenAiStream {
+    response: reqwest::Response,
+    request_id: Option<String>,
+    decoder: StreamDecoder,
+}
+
+impl OpenAiStream {
+    pub fn request_id(&self) -> Option<&str> {
+        self.request_id.as_deref()
+    }
+
+    pub async fn recv(&mut self) -> Result<Option<ChatResponseStream>, OpenAiError> {
+        loop {
+            if let Some(event) = self.decoder.events.pop_front() {
+                return Ok(Some(event));
+            }
+            if self.decoder.done {
+                return Ok(None);
+            }
+            match self.response.chunk().await? {
+                Some(chunk) => self.decoder.push(&chunk)?,
+                None => self.decoder.finish(),
+            }
+        }
+    }
+}
+
+/// Decodes the server-sent events of a streamed chat completion.
+///
+/// Text is emitted as soon as it is received. Tool calls are buffered until the end of the
+/// stream since their arguments can be split across chunks, and are then emitted one after the
+/// other as expected by the response parser.
+#[derive(Debug, Default)]
+struct StreamDecoder {
+    buffer: Vec<u8>,
+    events: VecDeque<ChatResponseStream>,
+    tool_calls: Vec<PendingToolCall>,
+    done: bool,
+}
+
+#[derive(Debug, Default)]
+struct PendingToolCall {
+    index: usize,
+    id: String,
+    name: String,
+    arguments: String,
+}
+
+impl StreamDecoder {
+    fn push(&mut self, bytes: &[u8]) -> Result<(), OpenAiError> {
+        self.buffer.extend_from_slice(bytes);
+        while let Some(end) = self.buffer.iter().position(|b| *b == b'\n') {
+            let line = self.buffer.drain(..=end).collect::<Vec<_>>();
+            let line = String::from_utf8_lossy(&line);
+            let Some(data) = line.trim().strip_prefix("data:").map(str::trim) else {
+                continue;
+            };
+            if data == "[DONE]" {
+                self.finish();
+                return Ok(());
+            }
+            self.push_chunk(data)?;
+        }
+        Ok(())
+    }
+
+    fn push_chunk(&mut self, data: &str) -> Result<(), OpenAiError> {
+        let chunk: ChatCompletionChunk =
+            serde_json::from_str(data).map_err(|err| OpenAiError::InvalidResponse(err.to_string()))?;
+        if let Some(error) = chunk.error {
+            return Err(OpenAiError::InvalidResponse(error.message));
+        }
+
+        for choice in chunk.choices {
+            if let Some(content) = choice.delta.content.filter(|c| !c.is_empty()) {
+                self.events
+                    .push_back(ChatResponseStream::AssistantResponseEvent { content });
+            }
+            for delta in choice.delta.tool_calls {
+                let index = match self.tool_calls.iter().position(|call| call.index == delta.index) {
+                    Some(index) => index,
+                    None => {
+                        self.tool_calls.push(PendingToolCall {
+                            index: delta.index,
+                            ..Default::default()
+                        });
+                        self.tool_calls.len() - 1
+                    },
+                };
+                let call = &mut self.tool_calls[index];
+                if let Some(id) = delta.id {
+                    call.id = id;
+                }
+                if let Some(function) = delta.function {
+                    if let Some(name) = function.name {
+                        call.name.push_str(&name);
+                    }
+                    if let Some(arguments) = function.arguments {
+                        call.arguments.push_str(&arguments);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Emits the buffered tool calls and marks the stream as ended.
+    fn finish(&mut self) {
+        if self.done {
+            return;
+        }
+        self.done = true;
+
+        for (i, call) in std::mem::take(&mut self.tool_calls).into_iter().enumerate() {
+            let tool_use_id = match call.id.is_empty() {
+                true => format!("tooluse_{i}"),
+                false => call.id,
+            };
+            let arguments = match call.arguments.trim().is_empty() {
+                true => "{}".to_string(),
+                false => call.arguments,
+            };
+            self.events.extend([
+                ChatResponseStream::ToolUseEvent {
+                    tool_use_id: tool_use_id.clone(),
+                    name: call.name.clone(),
+                    input: None,
+                    stop: None,
+                },
+                ChatResponseStream::ToolUseEvent {
+                    tool_use_id: tool_use_id.clone(),
+                    name: call.name.clone(),
+                    input: Some(arguments),
+                    stop: None,
+                },
+                ChatResponseStream::ToolUseEvent {
+                    tool_use_id,
+                    name: call.name,
+                    input: None,
+                    stop: Some(true),
+                },
+            ]);
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct ChatCompletionRequest {
+    model: String,
+    messages: Vec<Message>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<FunctionTool>,
+    stream: bool,
+}
+
+impl ChatCompletionRequest {
+    fn new(conversation: ConversationState, model: &str) -> Self {
+        let ConversationState {
+            user_input_message,
+            history,
+            ..
+        } = conversation;
+
+        let tools = user_input_message
+            .user_input_message_context
+            .as_ref()
+            .and_then(|context| context.tools.clone())
+            .unwrap_or_default()
+            .into_iter()
+            .map(FunctionTool::from)
+            .collect();
+
+        let mut messages = Vec::new();
+        for message in history.unwrap_or_default() {
+            match message {
+                ChatMessage::UserInputMessage(message) => push_user_message(&mut messages, message),
+                ChatMessage::AssistantResponseMessage(message) => messages.push(message.into()),
+            }
+        }
+        push_user_message(&mut messages, user_input_message);
+
+        Self {
+            model: model.to_string(),
+            messages,
+            tools,
+            stream: true,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "role", rename_all = "lowercase")]
+enum Message {
+    User {
+        content: UserContent,
+    },
+    Assistant {
+        content: String,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        tool_calls: Vec<ToolCall>,
+    },
+    Tool {
+        tool_call_id: String,
+        content: String,
+    },
+}
+
+/// Tool results are sent as separate `tool` messages, followed by the prompt if there is one.
+fn push_user_message(messages: &mut Vec<Message>, message: UserInputMessage) {
+    let tool_results = message
+        .user_input_message_context
+        .and_then(|context| context.tool_results)
+        .unwrap_or_default();
+    let has_tool_results = !tool_results.is_empty();
+    messages.extend(tool_results.into_iter().map(Message::from));
+
+    let images = message.images.unwrap_or_default();
+    if has_tool_results && message.content.trim().is_empty() && images.is_empty() {
+        return;
+    }
+    let content = match images.is_empty() {
+        true => UserContent::Text(message.content),
+        false => UserContent::Parts(
+            std::iter::once(ContentPart::Text { text: message.content })
+                .chain(images.into_iter().filter_map(ContentPart::image))
+                .collect(),
+        ),
+    };
+    messages.push(Message::User { content });
+}
+
+impl From<AssistantResponseMessage> for Message {
+    fn from(value: AssistantResponseMessage) -> Self {
+        Self::Assistant {
+            content: value.content,
+            tool_calls: value
+                .tool_uses
+                .unwrap_or_default()
+                .into_iter()
+                .map(|tool_use| ToolCall {
+                    id: tool_use.tool_use_id,
+                    kind: FUNCTION,
+                    function: FunctionCall {
+                        name: tool_use.name,
+                        arguments: serde_json::to_string(&tool_use.input).unwrap_or_default(),
+                    },
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<ToolResult> for Message {
+    fn from(value: ToolResult) -> Self {
+        let content = value
+            .content
+            .into_iter()
+            .map(|block| match block {
+                ToolResultContentBlock::Text(text) => text,
+                ToolResultContentBlock::Json(document) => {
+                    serde_json::to_string(&super::model::FigDocument::from(document)).unwrap_or_default()
+                },
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        Self::Tool {
+            tool_call_id: value.tool_use_id,
+            content,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum UserContent {
+    Text(String),
+    Parts(Vec<ContentPart>),
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ContentPart {
+    Text { text: String },
+    ImageUrl { image_url: ImageUrl },
+}
+
+impl ContentPart {
+    /// Converts an image to a data URL.
+    fn image(image: ImageBlock) -> Option<Self> {
+        let ImageSource::Bytes(bytes) = image.source else {
+            return None;
+        };
+        let mime = match image.format {
+            ImageFormat::Gif => "image/gif",
+            ImageFormat::Jpeg => "image/jpeg",
+            ImageFormat::Png => "image/png",
+            ImageFormat::Webp => "image/webp",
+        };
+        let data = base64::engine::general_purpose::STANDARD.encode(bytes);
+        Some(Self::ImageUrl {
+            image_url: ImageUrl {
+                url: format!("data:{mime};base64,{data}"),
+            },
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct ImageUrl {
+    url: String,
+}
+
+const FUNCTION: &str = "function";
+
+#[derive(Debug, Serialize)]
+struct ToolCall {
+    id: String,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    function: FunctionCall,
+}
+
+#[derive(Debug, Serialize)]
+struct FunctionCall {
+    name: String,
+    arguments: String,
+}
+
+#[derive(Debug, Serialize)]
+struct FunctionTool {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    function: FunctionDefinition,
+}
+
+#[derive(Debug, Serialize)]
+struct FunctionDefinition {
+    name: String,
+    description: String,
+    parameters: serde_json::Value,
+}
+
+impl From<Tool> for FunctionTool {
+    fn from(value: Tool) -> Self {
+        let Tool::ToolSpecification(spec) = value;
+        let parameters = spec
+            .input_schema
+            .json
+            .and_then(|schema| serde_json::to_value(schema).ok())
+            .unwrap_or_else(|| serde_json::json!({ "type": "object", "properties": {} }));
+        Self {
+            kind: FUNCTION,
+            function: FunctionDefinition {
+                name: spec.name,
+                description: spec.description,
+                parameters,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatCompletionChunk {
+    #[serde(default)]
+    choices: Vec<ChunkChoice>,
+    error: Option<ErrorMessage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChunkChoice {
+    #[serde(default)]
+    delta: Delta,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Delta {
+    content: Option<String>,
+    #[serde(default)]
+    tool_calls: Vec<ToolCallDelta>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ToolCallDelta {
+    #[serde(default)]
+    index: usize,
+    id: Option<String>,
+    function: Option<FunctionDelta>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FunctionDelta {
+    name: Option<String>,
+    arguments: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModelList {
+    data: Vec<ModelEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModelEntry {
+    id: String,
+    /// Context length, as reported by vLLM
+    max_model_len: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorBody {
+    error: ErrorMessage,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorMessage {
+    message: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use aws_smithy_types::Document;
+    use serde_json::json;
+    use tokio::io::{
+        AsyncReadExt,
+        AsyncWriteExt,
+    };
+
+    use super::*;
+    use crate::api_client::model::{
+        ToolInputSchema,
+        ToolResultStatus,
+        ToolSpecification,
+        ToolUse,
+        UserInputMessageContext,
+    };
+
+    fn user_message(content: &str) -> UserInputMessage {
+        UserInputMessage {
+            content: content.to_string(),
+            user_input_message_context: None,
+            user_intent: None,
+            images: None,
+            model_id: Some("openai:local".to_string()),
+        }
+    }
+
+    fn conversation() -> ConversationState {
+        let mut prompt = user_message("");
+        prompt.user_input_message_context = Some(UserInputMessageContext {
+            tool_results: Some(vec![ToolResult {
+                tool_use_id: "call_1".to_string(),
+                content: vec![
+                    ToolResultContentBlock::Text("line".to_string()),
+                    ToolResultContentBlock::Json(Document::Object([("ok".to_string(), true.into())].into())),
+                ],
+                status: ToolResultStatus::Success,
+            }]),
+            tools: Some(vec![Tool::ToolSpecification(ToolSpecification {
+                name: "fs_read".to_string(),
+                description: "Reads files".to_string(),
+                input_schema: ToolInputSchema {
+                    json: Some(Document::Object([("type".to_string(), "object".into())].into()).into()),
+                },
+            })]),
+            ..Default::default()
+        });
+        let mut first = user_message("read a.txt");
+        first.images = Some(vec![ImageBlock {
+            format: ImageFormat::Png,
+            source: ImageSource::Bytes(vec![1, 2, 3]),
+        }]);
+
+        ConversationState {
+            conversation_id: None,
+            user_input_message: prompt,
+            history: Some(vec![
+                ChatMessage::UserInputMessage(first),
+                ChatMessage::AssistantResponseMessage(AssistantResponseMessage {
+                    message_id: None,
+                    content: "Reading".to_string(),
+                    tool_uses: Some(vec![ToolUse {
+                        tool_use_id: "call_1".to_string(),
+                        name: "fs_read".to_string(),
+                        input: Document::Object([("path".to_string(), "a.txt".into())].into()).into(),
+                    }]),
+                }),
+            ]),
+        }
+    }
+
+    #[test]
+    fn test_request_from_conversation() {
+        let request = serde_json::to_value(ChatCompletionRequest::new(conversation(), "local")).unwrap();
+        assert_eq!(
+            request,
+            json!({
+                "model": "local",
+                "stream": true,
+                "tools": [{
+                    "type": "function",
+                    "function": { "name": "fs_read", "description": "Reads files", "parameters": { "type": "object" } }
+                }],
+                "messages": [
+                    { "role": "user", "content": [
+                        { "type": "text", "text": "read a.txt" },
+                        { "type": "image_url", "image_url": { "url": "data:image/png;base64,AQID" } }
+                    ] },
+                    { "role": "assistant", "content": "Reading", "tool_calls": [{
+                        "id": "call_1",
+                        "type": "function",
+                        "function": { "name": "fs_read", "arguments": "{\"path\":\"a.txt\"}" }
+                    }] },
+                    { "role": "tool", "tool_call_id": "call_1", "content": "line\n{\"ok\":true}" }
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn test_decode_stream() {
+        let mut decoder = StreamDecoder::default();
+        let body = concat!(
+            "data: {\"choices\":[{\"delta\":{\"role\":\"assistant\",\"content\":\"Let me\"}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\" check\"}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{\"tool_calls\":[{\"index\":0,\"id\":\"call_1\",\"type\":\"function\",",
+            "\"function\":{\"name\":\"fs_read\",\"arguments\":\"{\\\"pa\"}}]}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{\"tool_calls\":[{\"index\":0,\"function\":{\"arguments\":\"th\\\": 1}\"}}]},",
+            "\"finish_reason\":\"tool_calls\"}]}\n\n",
+            "data: [DONE]\n\n",
+        );
+        // Split in the middle of a line to check that partial lines are buffered.
+        let (a, b) = body.split_at(90);
+        decoder.push(a.as_bytes()).unwrap();
+        decoder.push(b.as_bytes()).unwrap();
+
+        assert!(decoder.done);
+        assert_eq!(decoder.events.into_iter().collect::<Vec<_>>(), vec![
+            ChatResponseStream::AssistantResponseEvent {
+                content: "Let me".to_string()
+            },
+            ChatResponseStream::AssistantResponseEvent {
+                content: " check".to_string()
+            },
+            ChatResponseStream::ToolUseEvent {
+                tool_use_id: "call_1".to_string(),
+                name: "fs_read".to_string(),
+                input: None,
+                stop: None
+            },
+            ChatResponseStream::ToolUseEvent {
+                tool_use_id: "call_1".to_string(),
+                name: "fs_read".to_string(),
+                input: Some("{\"path\": 1}".to_string()),
+                stop: None
+            },
+            ChatResponseStream::ToolUseEvent {
+                tool_use_id: "call_1".to_string(),
+                name: "fs_read".to_string(),
+                input: None,
+                stop: Some(true)
+            },
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_send_message() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}/v1", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0; 4096];
+            // Read the headers and the body, whose length is given by content-length.
+            loop {
+                let n = socket.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&request).to_lowercase();
+                if let Some(end) = text.find("\r\n\r\n") {
+                    let len = text
+                        .lines()
+                        .find_map(|l| l.strip_prefix("content-length:"))
+                        .and_then(|l| l.trim().parse::<usize>().ok())
+                        .unwrap_or(0);
+                    if request.len() >= end + 4 + len {
+                        break;
+                    }
+                }
+            }
+            let body = "data: {\"choices\":[{\"delta\":{\"content\":\"Hi\"}}]}\n\ndata: [DONE]\n\n";
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\nx-request-id: req-1\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        let client = OpenAiClient::new(reqwest::Client::new(), &endpoint, Some("key".to_string()));
+        let mut stream = client
+            .send_message(
+                ConversationState {
+                    conversation_id: None,
+                    user_input_message: user_message("hello"),
+                    history: None,
+                },
+                "local",
+            )
+            .await
+            .unwrap();
+        assert_eq!(stream.request_id(), Some("req-1"));
+        assert_eq!(
+            stream.recv().await.unwrap(),
+            Some(ChatResponseStream::AssistantResponseEvent {
+                content: "Hi".to_string()
+            })
+        );
+        assert_eq!(stream.recv().await.unwrap(), None);
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /v1/chat/completions"), "{request}");
+        assert!(
+            request.to_lowercase().contains("authorization: bearer key"),
+            "{request}"
+        );
+        assert!(request.contains("\"model\":\"local\""), "{request}");
+    }
+
+    #[test]
+    fn test_context_window_overflow() {
+        let err = OpenAiError::Status {
+            status: 400,
+            message: "This model's maximum context length is 4096 tokens".to_string(),
+        };
+        assert!(err.is_context_window_overflow());
+    }
+}
@@ -0,0 +1,433 @@
+//! Recording and replaying of the traffic with the chat backend.
+//!
+//! Setting [RECORD_FILE_ENV] appends every request sent with [super::ApiClient::send_message] or
+//! [super::ApiClient::send_sampling_message], along with the events of its response, to a JSON
+//! Lines file. Setting [REPLAY_FILE_ENV] serves the responses from such a file instead of calling
+//! the backend, in the order they were recorded. Sampling requests of each mcp server are served
+//! in order of their own, since they may interleave differently with the chat requests.
+
+use std::collections::HashMap;
+use std::path::{
+    Path,
+    PathBuf,
+};
+use std::sync::Arc;
+
+use amzn_codewhisperer_client::types::Model;
+use parking_lot::Mutex;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use thiserror::Error;
+
+use super::ModelListResult;
+use super::model::{
+    ChatResponseStream,
+    ConversationState,
+    Tool,
+    ToolResultStatus,
+};
+use crate::os::Fs;
+
+/// Path of the file that requests and responses are appended to.
+pub const RECORD_FILE_ENV: &str = "Q_CHAT_RECORD_FILE";
+/// Path of the file that responses are served from.
+pub const REPLAY_FILE_ENV: &str = "Q_CHAT_REPLAY_FILE";
+
+/// Prefix of the line added to the prompt with the time at which it was sent.
+const CURRENT_TIME_PREFIX: &str = "Current time: ";
+
+/// A request sent to the backend, along with the events of its response.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Exchange {
+    pub request: ConversationState,
+    pub request_id: Option<String>,
+    pub events: Vec<ChatResponseStream>,
+    /// The mcp server that sent the request through sampling, if it was not sent by the chat
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sampling_server: Option<String>,
+}
+
+#[derive(Debug, Error)]
+pub enum ReplayError {
+    #[error("failed to read the recording {}: {source}", .path.display())]
+    Read {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("invalid exchange on line {line} of the recording {}: {source}", .path.display())]
+    Parse {
+        path: PathBuf,
+        line: usize,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error("{} {index} was not recorded, the recording only contains {recorded}", describe(.sampling_server))]
+    Exhausted {
+        sampling_server: Option<String>,
+        index: usize,
+        recorded: usize,
+    },
+    #[error("{} {index} does not match the recording: {reason}", describe(.sampling_server))]
+    Mismatch {
+        sampling_server: Option<String>,
+        index: usize,
+        reason: String,
+    },
+}
+
+/// Appends exchanges to the file given by [RECORD_FILE_ENV].
+#[derive(Debug, Clone)]
+pub struct Recorder {
+    fs: Fs,
+    path: PathBuf,
+}
+
+impl Recorder {
+    pub fn new(fs: &Fs, path: impl AsRef<Path>) -> Self {
+        Self {
+            fs: fs.clone(),
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    pub async fn record(&self, exchange: &Exchange) -> Result<(), std::io::Error> {
+        let mut line = serde_json::to_string(exchange)?;
+        line.push('\n');
+        self.fs.append(&self.path, line).await
+    }
+}
+
+fn describe(sampling_server: &Option<String>) -> String {
+    match sampling_server {
+        Some(server) => format!("sampling request from {server}"),
+        None => "request".to_string(),
+    }
+}
+
+/// Serves the exchanges of a recording, checking that each request matches the recorded one.
+#[derive(Debug, Clone)]
+pub struct Replayer {
+    exchanges: Arc<Vec<Exchange>>,
+    /// Number of exchanges served, keyed by the mcp server that sent them through sampling
+    served: Arc<Mutex<HashMap<Option<String>, usize>>>,
+}
+
+impl Replayer {
+    pub async fn load(fs: &Fs, path: impl AsRef<Path>) -> Result<Self, ReplayError> {
+        let path = path.as_ref();
+        let content = fs.read_to_string(path).await.map_err(|source| ReplayError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        let mut exchanges = Vec::new();
+        for (i, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            exchanges.push(serde_json::from_str(line).map_err(|source| ReplayError::Parse {
+                path: path.to_path_buf(),
+                line: i + 1,
+                source,
+            })?);
+        }
+
+        Ok(Self::new(exchanges))
+    }
+
+    pub fn new(exchanges: Vec<Exchange>) -> Self {
+        Self {
+            exchanges: Arc::new(exchanges),
+            served: Default::default(),
+        }
+    }
+
+    /// Returns the next recorded chat exchange if `request` matches its request.
+    ///
+    /// Requests are numbered from 1 in error messages.
+    pub fn next(&self, request: &ConversationState) -> Result<Exchange, ReplayError> {
+        self.next_from(None, request)
+    }
+
+    /// Returns the next recorded sampling exchange of `server_name` if `request` matches its
+    /// request.
+    pub fn next_sampling(&self, server_name: &str, request: &ConversationState) -> Result<Exchange, ReplayError> {
+        self.next_from(Some(server_name), request)
+    }
+
+    fn next_from(&self, sampling_server: Option<&str>, request: &ConversationState) -> Result<Exchange, ReplayError> {
+        let mut served = self.served.lock();
+        let served = served.entry(sampling_server.map(str::to_string)).or_default();
+        let mut recorded = self
+            .exchanges
+            .iter()
+            .filter(|exchange| exchange.sampling_server.as_deref() == sampling_server);
+        let index = *served + 1;
+        let Some(exchange) = recorded.nth(*served) else {
+            return Err(ReplayError::Exhausted {
+                sampling_server: sampling_server.map(str::to_string),
+                index,
+                recorded: *served,
+            });
+        };
+        if let Some(reason) = mismatch(&exchange.request, request) {
+            return Err(ReplayError::Mismatch {
+                sampling_server: sampling_server.map(str::to_string),
+                index,
+                reason,
+            });
+        }
+
+        *served += 1;
+        Ok(exchange.clone())
+    }
+
+    /// Lists the models used by the recorded chat requests, the first one being the default.
+    pub fn models(&self) -> Option<ModelListResult> {
+        let mut models: Vec<Model> = Vec::new();
+        for exchange in self.exchanges.iter().filter(|e| e.sampling_server.is_none()) {
+            let Some(model_id) = &exchange.request.user_input_message.model_id else {
+                continue;
+            };
+            if models.iter().any(|m| m.model_id() == model_id) {
+                continue;
+            }
+            models.extend(Model::builder().model_id(model_id).build().ok());
+        }
+
+        let default_model = models.first()?.clone();
+        Some(ModelListResult { models, default_model })
+    }
+}
+
+/// Describes the first difference found between a recorded request and the one being sent, if
+/// any.
+///
+/// Only what determines the response is compared: the prompt (ignoring the current time), the
+/// tool results, the available tools, and the length of the history.
+fn mismatch(recorded: &ConversationState, actual: &ConversationState) -> Option<String> {
+    let history_len = |state: &ConversationState| state.history.as_ref().map_or(0, Vec::len);
+    if history_len(recorded) != history_len(actual) {
+        return Some(format!(
+            "expected {} message(s) of history, found {}",
+            history_len(recorded),
+            history_len(actual)
+        ));
+    }
+
+    let (recorded_prompt, actual_prompt) = (
+        normalize_prompt(&recorded.user_input_message.content),
+        normalize_prompt(&actual.user_input_message.content),
+    );
+    if recorded_prompt != actual_prompt {
+        return Some(format!(
+            "the prompt differs\n  expected: {recorded_prompt:?}\n  found:    {actual_prompt:?}"
+        ));
+    }
+
+    let (recorded_results, actual_results) = (tool_results(recorded), tool_results(actual));
+    if recorded_results != actual_results {
+        return Some(format!(
+            "the tool results differ\n  expected: {recorded_results:?}\n  found:    {actual_results:?}"
+        ));
+    }
+
+    let (recorded_tools, actual_tools) = (tool_names(recorded), tool_names(actual));
+    if recorded_tools != actual_tools {
+        let missing = recorded_tools.iter().filter(|t| !actual_tools.contains(t));
+        let added = actual_tools.iter().filter(|t| !recorded_tools.contains(t));
+        return Some(format!(
+            "the available tools differ, missing: {:?}, added: {:?}",
+            missing.collect::<Vec<_>>(),
+            added.collect::<Vec<_>>()
+        ));
+    }
+
+    None
+}
+
+fn normalize_prompt(content: &str) -> String {
+    content
+        .lines()
+        .filter(|line| !line.starts_with(CURRENT_TIME_PREFIX))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Ids and statuses of the tool results sent with the request.
+fn tool_results(state: &ConversationState) -> Vec<(String, &'static str)> {
+    let context = state.user_input_message.user_input_message_context.as_ref();
+    context
+        .and_then(|c| c.tool_results.as_ref())
+        .into_iter()
+        .flatten()
+        .map(|result| {
+            let status = match result.status {
+                ToolResultStatus::Success => "success",
+                ToolResultStatus::Error => "error",
+            };
+            (result.tool_use_id.clone(), status)
+        })
+        .collect()
+}
+
+/// Sorted names of the tools available to the model for the request.
+fn tool_names(state: &ConversationState) -> Vec<String> {
+    let context = state.user_input_message.user_input_message_context.as_ref();
+    let mut names = context
+        .and_then(|c| c.tools.as_ref())
+        .into_iter()
+        .flatten()
+        .map(|tool| match tool {
+            Tool::ToolSpecification(spec) => spec.name.clone(),
+        })
+        .collect::<Vec<_>>();
+    names.sort();
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api_client::model::{
+        AssistantResponseMessage,
+        ChatMessage,
+        ToolResult,
+        ToolResultContentBlock,
+        UserInputMessage,
+        UserInputMessageContext,
+    };
+
+    fn request(prompt: &str, history: Vec<ChatMessage>) -> ConversationState {
+        ConversationState {
+            conversation_id: Some("conv".to_string()),
+            user_input_message: UserInputMessage {
+                content: prompt.to_string(),
+                user_input_message_context: None,
+                user_intent: None,
+                images: None,
+                model_id: Some("model-1".to_string()),
+            },
+            history: Some(history),
+        }
+    }
+
+    fn exchange(request: ConversationState, text: &str) -> Exchange {
+        Exchange {
+            request,
+            request_id: Some("req".to_string()),
+            events: vec![ChatResponseStream::AssistantResponseEvent {
+                content: text.to_string(),
+            }],
+            sampling_server: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_record_and_load() {
+        let fs = Fs::new();
+        let recorder = Recorder::new(&fs, "/recording.jsonl");
+        let mut second = request("Current time: Monday\nuse a tool", vec![]);
+        second.user_input_message.user_input_message_context = Some(UserInputMessageContext {
+            tool_results: Some(vec![ToolResult {
+                tool_use_id: "t1".to_string(),
+                content: vec![ToolResultContentBlock::Json(aws_smithy_types::Document::Object(
+                    [("ok".to_string(), true.into())].into(),
+                ))],
+                status: ToolResultStatus::Success,
+            }]),
+            ..Default::default()
+        });
+        recorder
+            .record(&exchange(request("hello", vec![]), "hi"))
+            .await
+            .unwrap();
+        recorder.record(&exchange(second.clone(), "done")).await.unwrap();
+
+        let replayer = Replayer::load(&fs, "/recording.jsonl").await.unwrap();
+        assert_eq!(replayer.exchanges.len(), 2);
+        assert_eq!(replayer.models().unwrap().default_model.model_id(), "model-1");
+        replayer.next(&request("hello", vec![])).unwrap();
+        second.user_input_message.content = "Current time: Tuesday\nuse a tool".to_string();
+        let replayed = replayer.next(&second).unwrap();
+        assert_eq!(replayed.events, vec![ChatResponseStream::AssistantResponseEvent {
+            content: "done".to_string()
+        }]);
+    }
+
+    #[test]
+    fn test_replay_mismatch() {
+        let history = vec![
+            ChatMessage::UserInputMessage(request("hello", vec![]).user_input_message),
+            ChatMessage::AssistantResponseMessage(AssistantResponseMessage {
+                message_id: None,
+                content: "hi".to_string(),
+                tool_uses: None,
+            }),
+        ];
+        let replayer = Replayer::new(vec![
+            exchange(request("hello", vec![]), "hi"),
+            exchange(request("bye", history.clone()), "bye"),
+        ]);
+
+        let err = replayer.next(&request("goodbye", vec![])).unwrap_err();
+        assert!(matches!(err, ReplayError::Mismatch { index: 1, .. }), "{err}");
+        assert!(err.to_string().contains("the prompt differs"), "{err}");
+
+        replayer.next(&request("hello", vec![])).unwrap();
+        let err = replayer.next(&request("bye", vec![])).unwrap_err();
+        assert!(
+            err.to_string().contains("expected 2 message(s) of history, found 0"),
+            "{err}"
+        );
+
+        replayer.next(&request("bye", history)).unwrap();
+        let err = replayer.next(&request("again", vec![])).unwrap_err();
+        assert!(
+            matches!(err, ReplayError::Exhausted {
+                sampling_server: None,
+                index: 3,
+                recorded: 2
+            }),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_replay_sampling() {
+        let mut sampling = exchange(request("summarize", vec![]), "summary");
+        sampling.sampling_server = Some("server".to_string());
+        sampling.request.user_input_message.model_id = Some("model-2".to_string());
+        let replayer = Replayer::new(vec![
+            exchange(request("hello", vec![]), "hi"),
+            sampling,
+            exchange(request("bye", vec![]), "bye"),
+        ]);
+        assert_eq!(replayer.models().unwrap().models.len(), 1);
+
+        // Sampling requests are served apart from the chat requests they were recorded between
+        let err = replayer.next_sampling("server", &request("hello", vec![])).unwrap_err();
+        assert!(err.to_string().starts_with("sampling request from server 1"), "{err}");
+        replayer.next(&request("hello", vec![])).unwrap();
+        replayer.next(&request("bye", vec![])).unwrap();
+        let replayed = replayer.next_sampling("server", &request("summarize", vec![])).unwrap();
+        assert_eq!(replayed.events, vec![ChatResponseStream::AssistantResponseEvent {
+            content: "summary".to_string()
+        }]);
+
+        let err = replayer
+            .next_sampling("other_server", &request("summarize", vec![]))
+            .unwrap_err();
+        assert!(
+            matches!(&err, ReplayError::Exhausted {
+                sampling_server: Some(server),
+                index: 1,
+                recorded: 0
+            } if server == "other_server"),
+            "{err}"
+        );
+    }
+}
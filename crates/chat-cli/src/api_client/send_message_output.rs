@@ -1,7 +1,13 @@
 use aws_types::request_id::RequestId;
+use tracing::error;
 
 use crate::api_client::ApiClientError;
 use crate::api_client::model::ChatResponseStream;
+use crate::api_client::openai::OpenAiStream;
+use crate::api_client::recording::{
+    Exchange,
+    Recorder,
+};
 
 #[derive(Debug)]
 pub enum SendMessageOutput {
@@ -9,7 +15,19 @@ pub enum SendMessageOutput {
         amzn_codewhisperer_streaming_client::operation::generate_assistant_response::GenerateAssistantResponseOutput,
     ),
     QDeveloper(amzn_qdeveloper_streaming_client::operation::send_message::SendMessageOutput),
+    OpenAi(OpenAiStream),
     Mock(Vec<ChatResponseStream>),
+    /// Events served from a recording, in reverse order.
+    Replay {
+        request_id: Option<String>,
+        events: Vec<ChatResponseStream>,
+    },
+    /// Wraps another output, recording its events once the stream ends.
+    Recording {
+        output: Box<SendMessageOutput>,
+        recorder: Recorder,
+        exchange: Option<Exchange>,
+    },
 }
 
 impl SendMessageOutput {
@@ -17,7 +35,10 @@ impl SendMessageOutput {
         match self {
             SendMessageOutput::Codewhisperer(output) => output.request_id(),
             SendMessageOutput::QDeveloper(output) => output.request_id(),
+            SendMessageOutput::OpenAi(output) => output.request_id(),
             SendMessageOutput::Mock(_) => None,
+            SendMessageOutput::Replay { request_id, .. } => request_id.as_deref(),
+            SendMessageOutput::Recording { output, .. } => output.request_id(),
         }
     }
 
@@ -29,7 +50,31 @@ impl SendMessageOutput {
                 .await?
                 .map(|s| s.into())),
             SendMessageOutput::QDeveloper(output) => Ok(output.send_message_response.recv().await?.map(|s| s.into())),
+            SendMessageOutput::OpenAi(output) => Ok(output.recv().await?),
             SendMessageOutput::Mock(vec) => Ok(vec.pop()),
+            SendMessageOutput::Replay { events, .. } => Ok(events.pop()),
+            SendMessageOutput::Recording {
+                output,
+                recorder,
+                exchange,
+            } => {
+                let event = Box::pin(output.recv()).await?;
+                match &event {
+                    Some(event) => {
+                        if let Some(exchange) = exchange.as_mut() {
+                            exchange.events.push(event.clone());
+                        }
+                    },
+                    None => {
+                        if let Some(exchange) = exchange.take() {
+                            if let Err(err) = recorder.record(&exchange).await {
+                                error!(?err, "failed to record the response");
+                            }
+                        }
+                    },
+                }
+                Ok(event)
+            },
         }
     }
 }
@@ -39,7 +84,10 @@ impl RequestId for SendMessageOutput {
         match self {
             SendMessageOutput::Codewhisperer(output) => output.request_id(),
             SendMessageOutput::QDeveloper(output) => output.request_id(),
+            SendMessageOutput::OpenAi(output) => output.request_id(),
             SendMessageOutput::Mock(_) => Some("<mock-request-id>"),
+            SendMessageOutput::Replay { request_id, .. } => request_id.as_deref(),
+            SendMessageOutput::Recording { output, .. } => RequestId::request_id(output.as_ref()),
         }
     }
 }
@@ -1,4 +1,7 @@
-use std::collections::HashMap;
+use std::collections::{
+    HashMap,
+    HashSet,
+};
 use std::fmt::Display;
 
 use schemars::JsonSchema;
@@ -7,6 +10,8 @@ use serde::{
     Serialize,
 };
 
+use crate::util::pattern_matching::matches_any_pattern;
+
 const DEFAULT_TIMEOUT_MS: u64 = 30_000;
 const DEFAULT_MAX_OUTPUT_SIZE: usize = 1024 * 10;
 const DEFAULT_CACHE_TTL_SECONDS: u64 = 0;
@@ -21,6 +26,20 @@ pub enum HookTrigger {
     AgentSpawn,
     /// Triggered per user message submission
     UserPromptSubmit,
+    /// Triggered before a tool is executed. The hook may allow, deny, or rewrite the tool use.
+    PreToolUse,
+    /// Triggered after a tool has been executed
+    PostToolUse,
+    /// Triggered when the assistant finishes responding to the user
+    Stop,
+}
+
+impl HookTrigger {
+    /// Whether hooks for this trigger run around tool uses and therefore receive a tool context
+    /// on stdin.
+    pub fn is_tool_trigger(&self) -> bool {
+        matches!(self, HookTrigger::PreToolUse | HookTrigger::PostToolUse)
+    }
 }
 
 impl Display for HookTrigger {
@@ -28,6 +47,9 @@ impl Display for HookTrigger {
         match self {
             HookTrigger::AgentSpawn => write!(f, "agentSpawn"),
             HookTrigger::UserPromptSubmit => write!(f, "userPromptSubmit"),
+            HookTrigger::PreToolUse => write!(f, "preToolUse"),
+            HookTrigger::PostToolUse => write!(f, "postToolUse"),
+            HookTrigger::Stop => write!(f, "stop"),
         }
     }
 }
@@ -61,6 +83,12 @@ pub struct Hook {
     #[serde(default = "Hook::default_cache_ttl_seconds")]
     pub cache_ttl_seconds: u64,
 
+    /// Tool name pattern this hook applies to. Only used by `preToolUse` and `postToolUse`
+    /// hooks. Supports `*` and `?` wildcards, e.g. `fs_*` or `@git/*`. Matches every tool if
+    /// omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matcher: Option<String>,
+
     #[schemars(skip)]
     #[serde(default, skip_serializing)]
     pub source: Source,
@@ -73,10 +101,19 @@ impl Hook {
             timeout_ms: Self::default_timeout_ms(),
             max_output_size: Self::default_max_output_size(),
             cache_ttl_seconds: Self::default_cache_ttl_seconds(),
+            matcher: None,
             source,
         }
     }
 
+    /// Whether this hook should run for the tool with the given name.
+    pub fn matches_tool(&self, tool_name: &str) -> bool {
+        match &self.matcher {
+            Some(matcher) => matches_any_pattern(&HashSet::from([matcher.clone()]), tool_name),
+            None => true,
+        }
+    }
+
     fn default_timeout_ms() -> u64 {
         DEFAULT_TIMEOUT_MS
     }
@@ -89,3 +126,40 @@ impl Hook {
         DEFAULT_CACHE_TTL_SECONDS
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hook_matches_tool() {
+        let mut hook = Hook::new("echo hi".to_string(), Source::Agent);
+        assert!(hook.matches_tool("fs_write"));
+
+        hook.matcher = Some("fs_*".to_string());
+        assert!(hook.matches_tool("fs_write"));
+        assert!(hook.matches_tool("fs_read"));
+        assert!(!hook.matches_tool("execute_bash"));
+
+        hook.matcher = Some("@git/*".to_string());
+        assert!(hook.matches_tool("@git/git_status"));
+        assert!(!hook.matches_tool("fs_write"));
+    }
+
+    #[test]
+    fn test_hook_trigger_deser() {
+        let hooks: HashMap<HookTrigger, Vec<Hook>> = serde_json::from_value(serde_json::json!({
+            "preToolUse": [{ "command": "./check.sh", "matcher": "execute_bash" }],
+            "postToolUse": [{ "command": "cargo fmt" }],
+            "stop": [{ "command": "cargo clippy" }],
+        }))
+        .unwrap();
+
+        let pre = &hooks[&HookTrigger::PreToolUse][0];
+        assert_eq!(pre.matcher.as_deref(), Some("execute_bash"));
+        assert!(hooks[&HookTrigger::PostToolUse][0].matcher.is_none());
+        assert!(hooks.contains_key(&HookTrigger::Stop));
+        assert!(HookTrigger::PreToolUse.is_tool_trigger());
+        assert!(!HookTrigger::Stop.is_tool_trigger());
+    }
+}
@@ -816,6 +816,7 @@ impl Agents {
     fn default_permission_label(&self, tool_name: &str) -> String {
         let label = match tool_name {
             "fs_read" => "trusted".dark_green().bold(),
+            "code_search" => "trusted".dark_green().bold(),
             "fs_write" => "not trusted".dark_grey(),
             #[cfg(not(windows))]
             "execute_bash" => "trust read-only commands".dark_grey(),
@@ -826,6 +827,7 @@ impl Agents {
             "introspect" => "trusted".dark_green().bold(),
             "thinking" => "trusted (prerelease)".dark_green().bold(),
             "todo_list" => "trusted".dark_green().bold(),
+            "mcp_resources" => "trust listing resources".dark_grey(),
             _ if self.trust_all_tools => "trusted".dark_grey().bold(),
             _ => "not trusted".dark_grey(),
         };
@@ -0,0 +1,127 @@
+use std::collections::{
+    BTreeMap,
+    VecDeque,
+};
+
+use chrono::{
+    DateTime,
+    Utc,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use thiserror::Error;
+
+use super::conversation::HistoryEntry;
+use super::parser::RequestMetadata;
+
+/// Name of the branch that every conversation starts on.
+pub const DEFAULT_BRANCH: &str = "main";
+
+#[derive(Debug, Error)]
+pub enum BranchError {
+    #[error("A branch named '{0}' already exists")]
+    AlreadyExists(String),
+    #[error("No branch named '{0}'")]
+    NotFound(String),
+    #[error("Already on branch '{0}'")]
+    AlreadyCurrent(String),
+    #[error("Cannot delete '{0}', it is the current branch")]
+    DeleteCurrent(String),
+    #[error("Branch names cannot be empty or contain whitespace")]
+    InvalidName,
+    #[error("No turn {turn}, the current branch has {turns} turn(s)")]
+    InvalidTurn { turn: usize, turns: usize },
+    #[error("Branches cannot be changed while in tangent mode")]
+    TangentMode,
+}
+
+/// Where a branch was forked from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BranchOrigin {
+    /// Name of the branch that was forked
+    pub branch: String,
+    /// Number of user turns of the forked branch that the new branch started with
+    pub turns: usize,
+    pub created_at: DateTime<Utc>,
+}
+
+/// History of a branch that the conversation is not currently on.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Branch {
+    pub history: VecDeque<HistoryEntry>,
+    pub transcript: VecDeque<String>,
+    pub latest_summary: Option<(String, RequestMetadata)>,
+    pub origin: Option<BranchOrigin>,
+}
+
+/// Summary of a branch, as listed by `/branch list`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BranchInfo {
+    pub name: String,
+    pub current: bool,
+    /// Number of user turns in the history of the branch
+    pub turns: usize,
+    pub origin: Option<BranchOrigin>,
+}
+
+/// Named branches of a conversation. The history of the current branch lives in the
+/// conversation itself, the histories of the other branches are kept here.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BranchStore {
+    pub current: String,
+    pub current_origin: Option<BranchOrigin>,
+    pub inactive: BTreeMap<String, Branch>,
+}
+
+impl Default for BranchStore {
+    fn default() -> Self {
+        Self {
+            current: DEFAULT_BRANCH.to_string(),
+            current_origin: None,
+            inactive: BTreeMap::new(),
+        }
+    }
+}
+
+impl BranchStore {
+    /// Whether the conversation was never branched.
+    pub fn is_default(&self) -> bool {
+        self.current == DEFAULT_BRANCH && self.current_origin.is_none() && self.inactive.is_empty()
+    }
+
+    /// Checks that `name` can be used for a new branch.
+    pub fn validate_new_name(&self, name: &str) -> Result<(), BranchError> {
+        if name.is_empty() || name.chars().any(char::is_whitespace) {
+            return Err(BranchError::InvalidName);
+        }
+        if name == self.current || self.inactive.contains_key(name) {
+            return Err(BranchError::AlreadyExists(name.to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Returns the indices of the history entries that start a user turn, i.e. that hold a prompt
+/// rather than tool results.
+pub fn user_turn_indices(history: &VecDeque<HistoryEntry>) -> Vec<usize> {
+    history
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| entry.user().prompt().is_some())
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Number of history entries shared by two branches, starting from the beginning.
+pub fn common_prefix_len(a: &VecDeque<HistoryEntry>, b: &VecDeque<HistoryEntry>) -> usize {
+    a.iter()
+        .zip(b.iter())
+        .take_while(|(a, b)| {
+            a.user().prompt() == b.user().prompt()
+                && a.assistant().message_id() == b.assistant().message_id()
+                && a.assistant().content() == b.assistant().content()
+        })
+        .count()
+}
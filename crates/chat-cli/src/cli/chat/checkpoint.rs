@@ -0,0 +1,279 @@
+use std::collections::BTreeMap;
+use std::path::{
+    Path,
+    PathBuf,
+};
+
+use chrono::{
+    DateTime,
+    Local,
+};
+use eyre::Result;
+
+use crate::os::Os;
+
+/// Maximum number of characters of the prompt kept as the label of a checkpoint.
+const MAX_LABEL_LEN: usize = 60;
+
+/// Snapshot of the files modified by `fs_write` during a single turn of the conversation.
+#[derive(Debug, Clone)]
+pub struct Checkpoint {
+    /// Number used to refer to the checkpoint in `/checkpoint` commands
+    pub id: usize,
+    /// First line of the prompt that started the turn
+    pub label: String,
+    pub created_at: DateTime<Local>,
+    /// Length of the conversation history when the turn started. [None] if the history has since
+    /// been cleared or compacted, in which case the conversation can no longer be rewound to it.
+    pub history_len: Option<usize>,
+    /// Contents of each file before it was first modified during the turn, or [None] if the file
+    /// did not exist yet.
+    pub files: BTreeMap<PathBuf, Option<Vec<u8>>>,
+}
+
+/// Change made to a file when restoring a checkpoint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RestoredFile {
+    Restored(PathBuf),
+    Removed(PathBuf),
+}
+
+/// Difference between a file as it was at the start of a checkpoint and its current contents.
+#[derive(Debug, Clone)]
+pub struct FileDiff {
+    pub path: PathBuf,
+    pub before: Option<Vec<u8>>,
+    pub after: Option<Vec<u8>>,
+}
+
+/// Records what the files written by the agent looked like at the start of each turn so that
+/// they can be diffed against and restored later on, independently of any version control.
+///
+/// Checkpoints only live for the duration of the chat session.
+#[derive(Debug, Clone, Default)]
+pub struct CheckpointStore {
+    checkpoints: Vec<Checkpoint>,
+    next_id: usize,
+}
+
+impl CheckpointStore {
+    /// Starts a new checkpoint for the turn started by `prompt`.
+    pub fn begin_turn(&mut self, prompt: &str, history_len: usize) {
+        let first_line = prompt.trim().lines().next().unwrap_or_default();
+        let mut label = first_line.chars().take(MAX_LABEL_LEN).collect::<String>();
+        if first_line.chars().count() > MAX_LABEL_LEN {
+            label.push('…');
+        }
+
+        self.next_id += 1;
+        self.checkpoints.push(Checkpoint {
+            id: self.next_id,
+            label,
+            created_at: Local::now(),
+            history_len: Some(history_len),
+            files: BTreeMap::new(),
+        });
+    }
+
+    /// Records the current contents of `path` in the checkpoint of the current turn, unless the
+    /// file was already recorded during this turn.
+    pub async fn snapshot(&mut self, os: &Os, path: impl AsRef<Path>) -> Result<()> {
+        if self.checkpoints.is_empty() {
+            self.begin_turn("", 0);
+        }
+        let Some(checkpoint) = self.checkpoints.last_mut() else {
+            return Ok(());
+        };
+
+        let path = path.as_ref();
+        if checkpoint.files.contains_key(path) {
+            return Ok(());
+        }
+        let contents = if os.fs.exists(path) {
+            Some(os.fs.read(path).await?)
+        } else {
+            None
+        };
+        checkpoint.files.insert(path.to_path_buf(), contents);
+
+        Ok(())
+    }
+
+    pub fn checkpoints(&self) -> &[Checkpoint] {
+        &self.checkpoints
+    }
+
+    pub fn get(&self, id: usize) -> Option<&Checkpoint> {
+        self.checkpoints.iter().find(|c| c.id == id)
+    }
+
+    /// Marks every checkpoint as no longer pointing to a valid position in the conversation
+    /// history. Should be called whenever the history is replaced, e.g. when cleared or compacted.
+    pub fn forget_history(&mut self) {
+        for checkpoint in &mut self.checkpoints {
+            checkpoint.history_len = None;
+        }
+    }
+
+    /// Forgets the history position of checkpoints that start past `history_len`.
+    pub fn truncate_history(&mut self, history_len: usize) {
+        for checkpoint in &mut self.checkpoints {
+            if checkpoint.history_len.is_some_and(|len| len > history_len) {
+                checkpoint.history_len = None;
+            }
+        }
+    }
+
+    /// Moves the history position of every checkpoint back by `drained`, after that many entries
+    /// were dropped from the front of the history. Checkpoints that started in the dropped entries
+    /// can no longer be rewound to.
+    pub fn shift_history(&mut self, drained: usize) {
+        for checkpoint in &mut self.checkpoints {
+            checkpoint.history_len = checkpoint.history_len.and_then(|len| len.checked_sub(drained));
+        }
+    }
+
+    /// Returns the contents of every file modified since the start of checkpoint `id`, as they
+    /// were at that point.
+    fn contents_at(&self, id: usize) -> BTreeMap<PathBuf, Option<Vec<u8>>> {
+        let mut files = BTreeMap::new();
+        for checkpoint in self.checkpoints.iter().filter(|c| c.id >= id) {
+            for (path, contents) in &checkpoint.files {
+                files.entry(path.clone()).or_insert_with(|| contents.clone());
+            }
+        }
+        files
+    }
+
+    /// Returns how every file modified since the start of checkpoint `id` differs from its
+    /// current contents. Files that are unchanged are omitted.
+    pub async fn diff(&self, os: &Os, id: usize) -> Result<Vec<FileDiff>> {
+        let mut diffs = Vec::new();
+        for (path, before) in self.contents_at(id) {
+            let after = if os.fs.exists(&path) {
+                Some(os.fs.read(&path).await?)
+            } else {
+                None
+            };
+            if before != after {
+                diffs.push(FileDiff { path, before, after });
+            }
+        }
+        Ok(diffs)
+    }
+
+    /// Restores every file modified since the start of checkpoint `id` to what it was at that
+    /// point, removing the files that did not exist yet. The checkpoint and all of the following
+    /// ones are dropped.
+    ///
+    /// Returns the files that were changed.
+    pub async fn restore(&mut self, os: &Os, id: usize) -> Result<Vec<RestoredFile>> {
+        let mut restored = Vec::new();
+        for (path, contents) in self.contents_at(id) {
+            match contents {
+                Some(contents) => {
+                    let current = if os.fs.exists(&path) {
+                        Some(os.fs.read(&path).await?)
+                    } else {
+                        None
+                    };
+                    if current.as_ref() == Some(&contents) {
+                        continue;
+                    }
+                    if let Some(parent) = path.parent() {
+                        os.fs.create_dir_all(parent).await?;
+                    }
+                    os.fs.write(&path, contents).await?;
+                    restored.push(RestoredFile::Restored(path));
+                },
+                None if os.fs.exists(&path) => {
+                    os.fs.remove_file(&path).await?;
+                    restored.push(RestoredFile::Removed(path));
+                },
+                None => (),
+            }
+        }
+        self.checkpoints.retain(|c| c.id < id);
+
+        Ok(restored)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_snapshot_keeps_first_contents_of_turn() {
+        let os = Os::new().await.unwrap();
+        os.fs.write("/a.txt", "one").await.unwrap();
+
+        let mut store = CheckpointStore::default();
+        store.begin_turn("edit a", 0);
+        store.snapshot(&os, "/a.txt").await.unwrap();
+        os.fs.write("/a.txt", "two").await.unwrap();
+        store.snapshot(&os, "/a.txt").await.unwrap();
+        store.snapshot(&os, "/b.txt").await.unwrap();
+
+        let checkpoint = store.get(1).unwrap();
+        assert_eq!(checkpoint.label, "edit a");
+        assert_eq!(checkpoint.files.get(Path::new("/a.txt")), Some(&Some(b"one".to_vec())));
+        assert_eq!(checkpoint.files.get(Path::new("/b.txt")), Some(&None));
+    }
+
+    #[tokio::test]
+    async fn test_diff_and_restore() {
+        let os = Os::new().await.unwrap();
+        os.fs.write("/a.txt", "a1").await.unwrap();
+
+        let mut store = CheckpointStore::default();
+        store.begin_turn("first", 0);
+        store.snapshot(&os, "/a.txt").await.unwrap();
+        os.fs.write("/a.txt", "a2").await.unwrap();
+
+        store.begin_turn("second", 1);
+        store.snapshot(&os, "/a.txt").await.unwrap();
+        os.fs.write("/a.txt", "a3").await.unwrap();
+        store.snapshot(&os, "/new.txt").await.unwrap();
+        os.fs.write("/new.txt", "created").await.unwrap();
+
+        let diffs = store.diff(&os, 2).await.unwrap();
+        assert_eq!(diffs.len(), 2);
+        assert_eq!(diffs[0].before.as_deref(), Some(b"a2".as_slice()));
+        assert_eq!(diffs[0].after.as_deref(), Some(b"a3".as_slice()));
+        assert_eq!(diffs[1].before, None);
+
+        let restored = store.restore(&os, 1).await.unwrap();
+        assert_eq!(restored, vec![
+            RestoredFile::Restored(PathBuf::from("/a.txt")),
+            RestoredFile::Removed(PathBuf::from("/new.txt")),
+        ]);
+        assert_eq!(os.fs.read_to_string("/a.txt").await.unwrap(), "a1");
+        assert!(!os.fs.exists("/new.txt"));
+        assert!(store.checkpoints().is_empty());
+    }
+
+    #[test]
+    fn test_truncate_history() {
+        let mut store = CheckpointStore::default();
+        store.begin_turn("first", 0);
+        store.begin_turn("second", 2);
+        store.truncate_history(1);
+        assert_eq!(store.get(1).unwrap().history_len, Some(0));
+        assert_eq!(store.get(2).unwrap().history_len, None);
+        store.forget_history();
+        assert_eq!(store.get(1).unwrap().history_len, None);
+    }
+
+    #[test]
+    fn test_shift_history() {
+        let mut store = CheckpointStore::default();
+        store.begin_turn("first", 0);
+        store.begin_turn("second", 2);
+        store.begin_turn("third", 4);
+        store.shift_history(2);
+        assert_eq!(store.get(1).unwrap().history_len, None);
+        assert_eq!(store.get(2).unwrap().history_len, Some(0));
+        assert_eq!(store.get(3).unwrap().history_len, Some(2));
+    }
+}
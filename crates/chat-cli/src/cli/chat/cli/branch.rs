@@ -0,0 +1,263 @@
+use clap::Subcommand;
+use crossterm::execute;
+use crossterm::style::{
+    self,
+    Color,
+};
+
+use crate::cli::chat::branch::{
+    BranchError,
+    common_prefix_len,
+    user_turn_indices,
+};
+use crate::cli::chat::conversation::HistoryEntry;
+use crate::cli::chat::util::truncate_safe;
+use crate::cli::chat::{
+    ChatError,
+    ChatSession,
+    ChatState,
+};
+use crate::os::Os;
+
+/// Maximum number of bytes of a prompt or response shown by `/branch turns` and
+/// `/branch compare`.
+const PREVIEW_LEN: usize = 200;
+
+/// Commands for forking the conversation and switching between its branches
+#[deny(missing_docs)]
+#[derive(Debug, PartialEq, Subcommand)]
+pub enum BranchSubcommand {
+    /// List the branches of the conversation
+    List,
+    /// List the user turns of the current branch
+    Turns,
+    /// Create a branch and switch to it
+    Fork {
+        /// Name of the new branch
+        name: String,
+        /// Fork right before this user turn, as numbered by /branch turns, instead of at the end of
+        /// the history
+        #[arg(long)]
+        at: Option<usize>,
+    },
+    /// Switch to another branch
+    Switch {
+        /// Name of the branch
+        name: String,
+    },
+    /// Rename a branch
+    Rename {
+        /// Current name of the branch
+        from: String,
+        /// New name of the branch
+        to: String,
+    },
+    /// Delete a branch
+    Delete {
+        /// Name of the branch
+        name: String,
+    },
+    /// Show the turns of two branches since they diverged
+    Compare {
+        /// Name of the branch to compare
+        name: String,
+        /// Name of the branch to compare against, defaults to the current branch
+        other: Option<String>,
+    },
+}
+
+impl BranchSubcommand {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::List => "list",
+            Self::Turns => "turns",
+            Self::Fork { .. } => "fork",
+            Self::Switch { .. } => "switch",
+            Self::Rename { .. } => "rename",
+            Self::Delete { .. } => "delete",
+            Self::Compare { .. } => "compare",
+        }
+    }
+
+    pub async fn execute(self, os: &mut Os, session: &mut ChatSession) -> Result<ChatState, ChatError> {
+        let branch_error = |err: BranchError| ChatError::Custom(err.to_string().into());
+        match self {
+            Self::List => {
+                execute!(session.stderr, style::Print("\n"))?;
+                for branch in session.conversation.branches() {
+                    let origin = branch
+                        .origin
+                        .map(|origin| {
+                            format!(
+                                ", forked from {} after {} turn(s) on {}",
+                                origin.branch,
+                                origin.turns,
+                                origin.created_at.format("%Y-%m-%d %H:%M")
+                            )
+                        })
+                        .unwrap_or_default();
+                    execute!(
+                        session.stderr,
+                        style::SetForegroundColor(Color::Green),
+                        style::Print(if branch.current { "* " } else { "  " }),
+                        style::Print(&branch.name),
+                        style::SetForegroundColor(Color::DarkGrey),
+                        style::Print(format!(" ({} turn(s){origin})\n", branch.turns)),
+                        style::SetForegroundColor(Color::Reset),
+                    )?;
+                }
+                execute!(session.stderr, style::Print("\n"))?;
+            },
+            Self::Turns => {
+                let history = session.conversation.history();
+                let turns = user_turn_indices(history);
+                if turns.is_empty() {
+                    execute!(
+                        session.stderr,
+                        style::SetForegroundColor(Color::DarkGrey),
+                        style::Print("\nNo turns yet.\n\n"),
+                        style::SetForegroundColor(Color::Reset),
+                    )?;
+                }
+                for (i, index) in turns.into_iter().enumerate() {
+                    let prompt = preview(history[index].user().prompt().unwrap_or_default());
+                    execute!(
+                        session.stderr,
+                        style::SetForegroundColor(Color::Green),
+                        style::Print(format!("[{}] ", i + 1)),
+                        style::SetForegroundColor(Color::Reset),
+                        style::Print(format!("{prompt}\n")),
+                    )?;
+                }
+            },
+            Self::Fork { name, at } => {
+                session.conversation.fork_branch(&name, at).map_err(branch_error)?;
+                save_session(os, session);
+                execute!(
+                    session.stderr,
+                    style::SetForegroundColor(Color::Green),
+                    style::Print(format!("\nSwitched to new branch {name}{}.\n\n", match at {
+                        Some(turn) => format!(", forked before turn {turn}"),
+                        None => String::new(),
+                    })),
+                    style::SetForegroundColor(Color::Reset),
+                )?;
+            },
+            Self::Switch { name } => {
+                session.conversation.switch_branch(&name).map_err(branch_error)?;
+                save_session(os, session);
+                execute!(
+                    session.stderr,
+                    style::SetForegroundColor(Color::Green),
+                    style::Print(format!("\nSwitched to branch {name}.\n")),
+                    style::SetForegroundColor(Color::DarkGrey),
+                    style::Print("Files on disk are unchanged, use /checkpoint restore to roll them back.\n\n"),
+                    style::SetForegroundColor(Color::Reset),
+                )?;
+            },
+            Self::Rename { from, to } => {
+                session.conversation.rename_branch(&from, &to).map_err(branch_error)?;
+                save_session(os, session);
+                execute!(
+                    session.stderr,
+                    style::SetForegroundColor(Color::Green),
+                    style::Print(format!("\nRenamed branch {from} to {to}.\n\n")),
+                    style::SetForegroundColor(Color::Reset),
+                )?;
+            },
+            Self::Delete { name } => {
+                session.conversation.delete_branch(&name).map_err(branch_error)?;
+                save_session(os, session);
+                execute!(
+                    session.stderr,
+                    style::SetForegroundColor(Color::Green),
+                    style::Print(format!("\nDeleted branch {name}.\n\n")),
+                    style::SetForegroundColor(Color::Reset),
+                )?;
+            },
+            Self::Compare { name, other } => {
+                let other = other.unwrap_or_else(|| session.conversation.current_branch().to_string());
+                let Some(a) = session.conversation.branch_history(&name) else {
+                    return Err(ChatError::Custom(format!("No branch named '{name}'").into()));
+                };
+                let Some(b) = session.conversation.branch_history(&other) else {
+                    return Err(ChatError::Custom(format!("No branch named '{other}'").into()));
+                };
+                let shared = common_prefix_len(a, b);
+                let shared_turns = user_turn_indices(a).iter().filter(|&&i| i < shared).count();
+                let sections = [
+                    (name.clone(), describe_entries(a.iter().skip(shared))),
+                    (other.clone(), describe_entries(b.iter().skip(shared))),
+                ];
+
+                execute!(
+                    session.stderr,
+                    style::SetForegroundColor(Color::DarkGrey),
+                    style::Print(format!(
+                        "\n{name} and {other} share their first {shared_turns} turn(s).\n"
+                    )),
+                    style::SetForegroundColor(Color::Reset),
+                )?;
+                for (branch, entries) in sections {
+                    execute!(
+                        session.stderr,
+                        style::SetForegroundColor(Color::Green),
+                        style::Print(format!("\n{branch}\n")),
+                        style::SetForegroundColor(Color::Reset),
+                    )?;
+                    if entries.is_empty() {
+                        execute!(
+                            session.stderr,
+                            style::SetForegroundColor(Color::DarkGrey),
+                            style::Print("  (no further turns)\n"),
+                            style::SetForegroundColor(Color::Reset),
+                        )?;
+                    }
+                    for (prompt, response) in entries {
+                        if let Some(prompt) = prompt {
+                            execute!(
+                                session.stderr,
+                                style::SetForegroundColor(Color::Magenta),
+                                style::Print(format!("  > {prompt}\n")),
+                                style::SetForegroundColor(Color::Reset),
+                            )?;
+                        }
+                        if !response.is_empty() {
+                            execute!(session.stderr, style::Print(format!("    {response}\n")))?;
+                        }
+                    }
+                }
+                execute!(session.stderr, style::Print("\n"))?;
+            },
+        }
+
+        Ok(ChatState::PromptUser {
+            skip_printing_tools: true,
+        })
+    }
+}
+
+/// Saves the branches along with the session right away, rather than after the next response.
+fn save_session(os: &mut Os, session: &ChatSession) {
+    if let Ok(cwd) = os.env.current_dir() {
+        os.database.set_session_by_path(cwd, &session.conversation).ok();
+    }
+}
+
+/// Previews of the prompt, if any, and response of each entry.
+fn describe_entries<'a>(entries: impl Iterator<Item = &'a HistoryEntry>) -> Vec<(Option<String>, String)> {
+    entries
+        .map(|entry| (entry.user().prompt().map(preview), preview(entry.assistant().content())))
+        .collect()
+}
+
+/// First line of `text`, truncated to [PREVIEW_LEN] bytes.
+fn preview(text: &str) -> String {
+    let mut lines = text.trim().lines();
+    let first_line = lines.next().unwrap_or_default();
+    let truncated = truncate_safe(first_line, PREVIEW_LEN);
+    match truncated.len() < first_line.len() || lines.next().is_some() {
+        true => format!("{truncated}…"),
+        false => truncated.to_string(),
+    }
+}
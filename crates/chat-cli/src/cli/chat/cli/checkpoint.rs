@@ -0,0 +1,212 @@
+use clap::Subcommand;
+use crossterm::execute;
+use crossterm::style::{
+    self,
+    Attribute,
+    Color,
+};
+
+use crate::cli::chat::checkpoint::{
+    FileDiff,
+    RestoredFile,
+};
+use crate::cli::chat::tools::format_path;
+use crate::cli::chat::{
+    ChatError,
+    ChatSession,
+    ChatState,
+};
+use crate::os::Os;
+
+/// Number of unchanged lines shown around each change by `/checkpoint diff`.
+const DIFF_CONTEXT_LINES: usize = 3;
+
+/// Commands for inspecting and restoring the files written by the agent
+#[deny(missing_docs)]
+#[derive(Debug, PartialEq, Subcommand)]
+pub enum CheckpointSubcommand {
+    /// List the checkpoints taken at the start of each turn
+    List,
+    /// Show how the files written by the agent changed since the given checkpoint
+    Diff {
+        /// Id of the checkpoint, as shown by /checkpoint list
+        id: usize,
+    },
+    /// Restore the files written by the agent to what they were at the given checkpoint
+    Restore {
+        /// Id of the checkpoint, as shown by /checkpoint list
+        id: usize,
+        /// Also rewind the conversation history to the start of the checkpoint's turn
+        #[arg(long, short)]
+        rewind: bool,
+    },
+}
+
+impl CheckpointSubcommand {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::List => "list",
+            Self::Diff { .. } => "diff",
+            Self::Restore { .. } => "restore",
+        }
+    }
+
+    pub async fn execute(self, os: &Os, session: &mut ChatSession) -> Result<ChatState, ChatError> {
+        let cwd = os.env.current_dir()?;
+        match self {
+            Self::List => {
+                let checkpoints = session.conversation.checkpoints.checkpoints();
+                if checkpoints.is_empty() {
+                    execute!(
+                        session.stderr,
+                        style::SetForegroundColor(Color::DarkGrey),
+                        style::Print("\nNo checkpoints yet.\n\n"),
+                        style::SetForegroundColor(Color::Reset),
+                    )?;
+                }
+                for checkpoint in checkpoints {
+                    execute!(
+                        session.stderr,
+                        style::SetForegroundColor(Color::Green),
+                        style::Print(format!("[{}] ", checkpoint.id)),
+                        style::SetForegroundColor(Color::DarkGrey),
+                        style::Print(format!("{} ", checkpoint.created_at.format("%H:%M:%S"))),
+                        style::SetForegroundColor(Color::Reset),
+                        style::Print(&checkpoint.label),
+                        style::SetForegroundColor(Color::DarkGrey),
+                        style::Print(format!(" ({} file(s) modified)", checkpoint.files.len())),
+                        style::SetForegroundColor(Color::Reset),
+                        style::Print("\n"),
+                    )?;
+                    for path in checkpoint.files.keys() {
+                        execute!(
+                            session.stderr,
+                            style::SetForegroundColor(Color::DarkGrey),
+                            style::Print(format!("    {}\n", format_path(&cwd, path))),
+                            style::SetForegroundColor(Color::Reset),
+                        )?;
+                    }
+                }
+            },
+            Self::Diff { id } => {
+                if session.conversation.checkpoints.get(id).is_none() {
+                    return Err(ChatError::Custom(format!("No checkpoint with id {id}").into()));
+                }
+                let diffs = session
+                    .conversation
+                    .checkpoints
+                    .diff(os, id)
+                    .await
+                    .map_err(|e| ChatError::Custom(format!("Failed to compute the diff: {e}").into()))?;
+                if diffs.is_empty() {
+                    execute!(
+                        session.stderr,
+                        style::SetForegroundColor(Color::DarkGrey),
+                        style::Print(format!("\nNo changes since checkpoint {id}.\n\n")),
+                        style::SetForegroundColor(Color::Reset),
+                    )?;
+                }
+                for diff in diffs {
+                    print_diff(session, &format_path(&cwd, &diff.path), &diff)?;
+                }
+            },
+            Self::Restore { id, rewind } => {
+                let Some(checkpoint) = session.conversation.checkpoints.get(id) else {
+                    return Err(ChatError::Custom(format!("No checkpoint with id {id}").into()));
+                };
+                let history_len = checkpoint.history_len;
+                if rewind && history_len.is_none() {
+                    return Err(ChatError::Custom(
+                        format!("The conversation history of checkpoint {id} was cleared or compacted").into(),
+                    ));
+                }
+
+                let restored = session
+                    .conversation
+                    .checkpoints
+                    .restore(os, id)
+                    .await
+                    .map_err(|e| ChatError::Custom(format!("Failed to restore checkpoint {id}: {e}").into()))?;
+                for file in &restored {
+                    let (action, path) = match file {
+                        RestoredFile::Restored(path) => ("Restored: ", path),
+                        RestoredFile::Removed(path) => ("Removed: ", path),
+                    };
+                    execute!(
+                        session.stderr,
+                        style::Print(action),
+                        style::SetForegroundColor(Color::Green),
+                        style::Print(format_path(&cwd, path)),
+                        style::SetForegroundColor(Color::Reset),
+                        style::Print("\n"),
+                    )?;
+                }
+
+                if let (true, Some(history_len)) = (rewind, history_len) {
+                    session.conversation.rewind_history(history_len);
+                }
+
+                execute!(
+                    session.stderr,
+                    style::SetForegroundColor(Color::Green),
+                    style::Print(format!(
+                        "\nRestored {} file(s) to checkpoint {id}{}.\n\n",
+                        restored.len(),
+                        if rewind { " and rewound the conversation" } else { "" }
+                    )),
+                    style::SetForegroundColor(Color::Reset),
+                )?;
+            },
+        }
+
+        Ok(ChatState::PromptUser {
+            skip_printing_tools: true,
+        })
+    }
+}
+
+fn print_diff(session: &mut ChatSession, path: &str, diff: &FileDiff) -> Result<(), ChatError> {
+    let before = diff.before.as_deref().map(String::from_utf8_lossy).unwrap_or_default();
+    let after = diff.after.as_deref().map(String::from_utf8_lossy).unwrap_or_default();
+    let old_header = match diff.before {
+        Some(_) => format!("a/{path}"),
+        None => "/dev/null".to_string(),
+    };
+    let new_header = match diff.after {
+        Some(_) => format!("b/{path}"),
+        None => "/dev/null".to_string(),
+    };
+
+    let text_diff = similar::TextDiff::from_lines(before.as_ref(), after.as_ref());
+    let unified = text_diff
+        .unified_diff()
+        .context_radius(DIFF_CONTEXT_LINES)
+        .header(&old_header, &new_header)
+        .to_string();
+
+    for line in unified.lines() {
+        let color = if line.starts_with("+++") || line.starts_with("---") {
+            execute!(session.stderr, style::SetAttribute(Attribute::Bold))?;
+            Color::Reset
+        } else if line.starts_with("@@") {
+            Color::Cyan
+        } else if line.starts_with('+') {
+            Color::Green
+        } else if line.starts_with('-') {
+            Color::Red
+        } else {
+            Color::Reset
+        };
+        execute!(
+            session.stderr,
+            style::SetForegroundColor(color),
+            style::Print(line),
+            style::SetAttribute(Attribute::Reset),
+            style::SetForegroundColor(Color::Reset),
+            style::Print("\n"),
+        )?;
+    }
+    execute!(session.stderr, style::Print("\n"))?;
+
+    Ok(())
+}
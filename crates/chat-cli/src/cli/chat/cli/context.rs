@@ -15,13 +15,14 @@ use crate::cli::chat::context::{
     ContextFilePath,
     calc_max_context_files_size,
 };
-use crate::cli::chat::token_counter::TokenCounter;
+use crate::cli::chat::token_counter::Tokenizer;
 use crate::cli::chat::util::drop_matched_context_files;
 use crate::cli::chat::{
     ChatError,
     ChatSession,
     ChatState,
 };
+use crate::mcp_client::resources::parse_resource_path;
 use crate::os::Os;
 
 #[deny(missing_docs)]
@@ -35,6 +36,7 @@ more accurate and helpful responses.
 
 Notes:
 • You can add specific files or use glob patterns (e.g., \"*.py\", \"src/**/*.js\")
+• You can add resources offered by MCP servers with mcp://<server>/<uri> (see /mcp resources)
 • Agent rules apply only to the current agent 
 • Context changes are NOT preserved between chat sessions. To make these changes permanent, edit the agent config file."
 )]
@@ -152,12 +154,18 @@ impl ContextSubcommand {
                         style::SetForegroundColor(Color::Reset)
                     )?;
                 } else {
+                    let resource_store = session.conversation.tool_manager.resource_store();
                     for path in &session_owned_list {
                         execute!(session.stderr, style::Print(format!("    {} ", path.get_path_as_str())))?;
-                        if let Ok(context_files) = context_manager
-                            .get_context_files_by_path(os, path.get_path_as_str())
-                            .await
-                        {
+                        let context_files = match resource_store.content_for_path(path.get_path_as_str()) {
+                            Some(content) => Ok(vec![(path.get_path_as_str().to_string(), content)]),
+                            None => {
+                                context_manager
+                                    .get_context_files_by_path(os, path.get_path_as_str())
+                                    .await
+                            },
+                        };
+                        if let Ok(context_files) = context_files {
                             execute!(
                                 session.stderr,
                                 style::SetForegroundColor(Color::Green),
@@ -184,10 +192,11 @@ impl ContextSubcommand {
                         style::SetForegroundColor(Color::Reset)
                     )?;
                 } else {
+                    let tokenizer = Tokenizer::for_model(session.conversation.model_info.as_ref());
                     let total = profile_context_files.len();
                     let total_tokens = profile_context_files
                         .iter()
-                        .map(|(_, content, _)| TokenCounter::count_tokens(content))
+                        .map(|(_, content, _)| tokenizer.count(content))
                         .sum::<usize>();
                     execute!(
                         session.stderr,
@@ -203,7 +212,7 @@ impl ContextSubcommand {
                     )?;
 
                     for (filename, content, is_temporary) in &profile_context_files {
-                        let est_tokens = TokenCounter::count_tokens(content);
+                        let est_tokens = tokenizer.count(content);
                         let icon = if *is_temporary { "💬" } else { "👤" };
                         execute!(
                             session.stderr,
@@ -231,7 +240,8 @@ impl ContextSubcommand {
                         .iter()
                         .map(|(path, content, _)| (path.clone(), content.clone()))
                         .collect::<Vec<_>>();
-                    let dropped_files = drop_matched_context_files(&mut files_as_vec, context_files_max_size).ok();
+                    let dropped_files =
+                        drop_matched_context_files(&mut files_as_vec, context_files_max_size, tokenizer).ok();
 
                     execute!(
                         session.stderr,
@@ -254,7 +264,7 @@ impl ContextSubcommand {
                             let truncated_dropped_files = &dropped_files[..10];
 
                             for (filename, content) in truncated_dropped_files {
-                                let est_tokens = TokenCounter::count_tokens(content);
+                                let est_tokens = tokenizer.count(content);
                                 execute!(
                                     session.stderr,
                                     style::Print(format!("{} ", filename)),
@@ -298,27 +308,60 @@ impl ContextSubcommand {
                     }
                 }
             },
-            Self::Add { force, paths } => match context_manager.add_paths(os, paths.clone(), force).await {
-                Ok(_) => {
-                    execute!(
-                        session.stderr,
-                        style::SetForegroundColor(Color::Green),
-                        style::Print(format!("\nAdded {} path(s) to context.\n", paths.len())),
-                        style::Print("Note: Context modifications via slash command is temporary.\n\n"),
-                        style::SetForegroundColor(Color::Reset)
-                    )?;
-                },
-                Err(e) => {
-                    execute!(
-                        session.stderr,
-                        style::SetForegroundColor(Color::Red),
-                        style::Print(format!("\nError: {}\n\n", e)),
-                        style::SetForegroundColor(Color::Reset)
-                    )?;
-                },
+            Self::Add { force, paths } => {
+                for path in &paths {
+                    let Some((server_name, uri)) = parse_resource_path(path) else {
+                        continue;
+                    };
+                    if let Err(e) = session
+                        .conversation
+                        .tool_manager
+                        .attach_resource(server_name, uri)
+                        .await
+                    {
+                        execute!(
+                            session.stderr,
+                            style::SetForegroundColor(Color::Red),
+                            style::Print(format!("\nError: Failed to read resource '{}': {}\n\n", path, e)),
+                            style::SetForegroundColor(Color::Reset)
+                        )?;
+
+                        return Ok(ChatState::PromptUser {
+                            skip_printing_tools: true,
+                        });
+                    }
+                }
+
+                match context_manager.add_paths(os, paths.clone(), force).await {
+                    Ok(_) => {
+                        execute!(
+                            session.stderr,
+                            style::SetForegroundColor(Color::Green),
+                            style::Print(format!("\nAdded {} path(s) to context.\n", paths.len())),
+                            style::Print("Note: Context modifications via slash command is temporary.\n\n"),
+                            style::SetForegroundColor(Color::Reset)
+                        )?;
+                    },
+                    Err(e) => {
+                        execute!(
+                            session.stderr,
+                            style::SetForegroundColor(Color::Red),
+                            style::Print(format!("\nError: {}\n\n", e)),
+                            style::SetForegroundColor(Color::Reset)
+                        )?;
+                    },
+                }
             },
             Self::Remove { paths } => match context_manager.remove_paths(paths.clone()) {
                 Ok(_) => {
+                    for (server_name, uri) in paths.iter().filter_map(|path| parse_resource_path(path)) {
+                        session
+                            .conversation
+                            .tool_manager
+                            .detach_resource(server_name, uri)
+                            .await;
+                    }
+
                     execute!(
                         session.stderr,
                         style::SetForegroundColor(Color::Green),
@@ -337,6 +380,15 @@ impl ContextSubcommand {
                 },
             },
             Self::Clear => {
+                for path in &context_manager.paths {
+                    if let Some((server_name, uri)) = parse_resource_path(path.get_path_as_str()) {
+                        session
+                            .conversation
+                            .tool_manager
+                            .detach_resource(server_name, uri)
+                            .await;
+                    }
+                }
                 context_manager.clear();
                 execute!(
                     session.stderr,
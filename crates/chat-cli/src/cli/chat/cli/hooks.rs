@@ -28,10 +28,19 @@ use futures::stream::{
     FuturesUnordered,
     StreamExt,
 };
+use serde::{
+    Deserialize,
+    Serialize,
+};
 use spinners::{
     Spinner,
     Spinners,
 };
+use tokio::io::AsyncWriteExt;
+use tracing::{
+    debug,
+    warn,
+};
 
 use crate::cli::agent::hook::{
     Hook,
@@ -45,16 +54,169 @@ use crate::cli::chat::{
     ChatState,
 };
 
+/// Exit code a hook can use to block the action it was triggered for. Anything the hook wrote to
+/// stderr is used as the reason.
+pub const BLOCKING_EXIT_CODE: i32 = 2;
+
+/// Event specific data that is written to a hook's stdin as JSON, alongside the trigger name
+/// (`hook_event_name`) and the current working directory (`cwd`).
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct HookPayload {
+    /// Name of the tool, e.g. `fs_write` or `@server/tool` for MCP tools.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_name: Option<String>,
+    /// The input the tool is (or was) invoked with.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_input: Option<serde_json::Value>,
+    /// The result of the tool. Only set for [HookTrigger::PostToolUse].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_response: Option<serde_json::Value>,
+    /// The final assistant response of the turn. Only set for [HookTrigger::Stop].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub assistant_response: Option<String>,
+    /// Whether the assistant is already continuing because a [HookTrigger::Stop] hook blocked it
+    /// previously. Hooks should use this to avoid looping forever.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub stop_hook_active: bool,
+}
+
+impl HookPayload {
+    pub fn tool_use(tool_name: String, tool_input: serde_json::Value) -> Self {
+        Self {
+            tool_name: Some(tool_name),
+            tool_input: Some(tool_input),
+            ..Default::default()
+        }
+    }
+}
+
+/// Output of a hook that ran to completion.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HookOutput {
+    pub exit_code: i32,
+    /// Stdout of the hook. Truncated to [Hook::max_output_size] for hooks that add context, and
+    /// kept whole for the hooks that decide on an action, since it holds their JSON reply.
+    pub stdout: String,
+    /// Stderr of the hook, truncated to [Hook::max_output_size].
+    pub stderr: String,
+}
+
+/// What a [HookTrigger::PreToolUse], [HookTrigger::PostToolUse], or [HookTrigger::Stop] hook
+/// decided about the action it was triggered for.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HookDecision {
+    Allow,
+    /// Block the action. The reason is sent back to the model.
+    Deny {
+        reason: String,
+    },
+    /// Run the tool with the given input instead. Only meaningful for [HookTrigger::PreToolUse].
+    Modify {
+        tool_input: serde_json::Value,
+    },
+}
+
+/// JSON reply a hook may print to stdout to control the action.
+#[derive(Debug, Deserialize)]
+struct HookReply {
+    #[serde(default)]
+    decision: Option<HookReplyDecision>,
+    #[serde(default)]
+    reason: Option<String>,
+    #[serde(default)]
+    updated_input: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum HookReplyDecision {
+    Allow,
+    #[serde(alias = "block")]
+    Deny,
+}
+
+impl HookOutput {
+    /// Interprets the output of the hook. A hook blocks the action by exiting with
+    /// [BLOCKING_EXIT_CODE] or by replying with `{"decision": "deny", "reason": "..."}`, and
+    /// rewrites the tool input by replying with `{"updated_input": {...}}`. A reply that looks
+    /// like JSON but cannot be parsed blocks the action too, and any other output allows it.
+    ///
+    /// Reasons are truncated to `max_output_size`.
+    pub fn decision(&self, max_output_size: usize) -> HookDecision {
+        if self.exit_code == BLOCKING_EXIT_CODE {
+            let reason = self.stderr.trim();
+            return HookDecision::Deny {
+                reason: if reason.is_empty() {
+                    "Blocked by hook".to_string()
+                } else {
+                    reason.to_string()
+                },
+            };
+        }
+
+        let stdout = self.stdout.trim();
+        let reply = match serde_json::from_str::<HookReply>(stdout) {
+            Ok(reply) => reply,
+            Err(err) if stdout.starts_with('{') => {
+                return HookDecision::Deny {
+                    reason: truncate_output(&format!("Invalid reply from hook: {err}"), max_output_size),
+                };
+            },
+            Err(_) => return HookDecision::Allow,
+        };
+
+        match (reply.decision, reply.updated_input) {
+            (Some(HookReplyDecision::Deny), _) => HookDecision::Deny {
+                reason: reply.reason.map_or_else(
+                    || "Blocked by hook".to_string(),
+                    |reason| truncate_output(&reason, max_output_size),
+                ),
+            },
+            (_, Some(tool_input)) => HookDecision::Modify { tool_input },
+            _ => HookDecision::Allow,
+        }
+    }
+}
+
+/// Combines the decisions of every hook that ran for a single action. Any denial wins, and the
+/// reasons of all denying hooks are joined. Otherwise, the first input modification is used.
+pub fn combine_decisions(results: &[((HookTrigger, Hook), HookOutput)]) -> HookDecision {
+    let mut reasons = Vec::new();
+    let mut modified = None;
+    for ((_, hook), output) in results {
+        match output.decision(hook.max_output_size) {
+            HookDecision::Allow => (),
+            HookDecision::Deny { reason } => reasons.push(reason),
+            HookDecision::Modify { tool_input } => match modified {
+                Some(_) => warn!(command = hook.command, "ignoring input modification from hook"),
+                None => modified = Some(tool_input),
+            },
+        }
+    }
+
+    match (reasons.is_empty(), modified) {
+        (false, _) => HookDecision::Deny {
+            reason: reasons.join("\n"),
+        },
+        (true, Some(tool_input)) => HookDecision::Modify { tool_input },
+        (true, None) => HookDecision::Allow,
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct CachedHook {
-    output: String,
+    output: HookOutput,
     expiry: Option<Instant>,
 }
 
+/// Identifies a cached hook execution. Hooks that take a payload are additionally keyed by it so
+/// that a cached decision is never applied to a different tool input or assistant response.
+type CacheKey = (HookTrigger, Hook, Option<String>);
+
 /// Maps a hook name to a [`CachedHook`]
 #[derive(Debug, Clone, Default)]
 pub struct HookExecutor {
-    pub cache: HashMap<(HookTrigger, Hook), CachedHook>,
+    pub cache: HashMap<CacheKey, CachedHook>,
 }
 
 impl HookExecutor {
@@ -63,30 +225,46 @@ impl HookExecutor {
     }
 
     /// Run and cache [`Hook`]s. Any hooks that are already cached will be returned without
-    /// executing. Hooks that fail to execute will not be returned. Returned hook order is
-    /// undefined.
+    /// executing. Hooks that fail to execute will not be returned, except for tool hooks, which
+    /// are returned as blocking so that a broken policy hook never lets a tool run. Failures are
+    /// not cached. Returned hook order is undefined.
     ///
     /// If `updates` is `Some`, progress on hook execution will be written to it.
     /// Errors encountered with write operations to `updates` are ignored.
     ///
+    /// `payload` is serialized to the stdin of every hook together with the trigger name.
+    ///
     /// Note: [`HookTrigger::AgentSpawn`] hooks never leave the cache.
     pub async fn run_hooks(
         &mut self,
         hooks: HashMap<HookTrigger, Vec<Hook>>,
         output: &mut impl Write,
         prompt: Option<&str>,
-    ) -> Result<Vec<((HookTrigger, Hook), String)>, ChatError> {
+        payload: Option<&HookPayload>,
+    ) -> Result<Vec<((HookTrigger, Hook), HookOutput)>, ChatError> {
+        let cwd = std::env::current_dir().unwrap_or_default();
         let mut cached = vec![];
         let mut futures = FuturesUnordered::new();
         for hook in hooks
             .into_iter()
             .flat_map(|(trigger, hooks)| hooks.into_iter().map(move |hook| (trigger, hook)))
         {
-            if let Some(cache) = self.get_cache(&hook) {
+            let mut stdin =
+                serde_json::to_value(payload.cloned().unwrap_or_default()).unwrap_or_else(|_| serde_json::json!({}));
+            if let Some(obj) = stdin.as_object_mut() {
+                obj.insert("hook_event_name".to_string(), hook.0.to_string().into());
+                obj.insert("cwd".to_string(), cwd.to_string_lossy().into());
+                if let Some(prompt) = prompt {
+                    obj.insert("prompt".to_string(), prompt.into());
+                }
+            }
+
+            let cache_key = cache_key(&hook, payload);
+            if let Some(cache) = self.get_cache(&cache_key) {
                 cached.push((hook.clone(), cache.clone()));
                 continue;
             }
-            futures.push(self.run_hook(hook, prompt));
+            futures.push(self.run_hook(hook, prompt, stdin.to_string()));
         }
 
         let mut complete = 0;
@@ -106,6 +284,7 @@ impl HookExecutor {
 
         // Process results as they complete
         let mut results = vec![];
+        let mut failed = vec![];
         let start_time = Instant::now();
         while let Some((hook, result, duration)) = futures.next().await {
             // If output is enabled, handle that first
@@ -138,9 +317,20 @@ impl HookExecutor {
             }
 
             // Process results regardless of output enabled
-            if let Ok(output) = result {
-                complete += 1;
-                results.push((hook, output));
+            match result {
+                Ok(output) => {
+                    complete += 1;
+                    results.push((hook, output));
+                },
+                Err(err) if hook.0.is_tool_trigger() => {
+                    let stderr = format!("Hook `{}` failed: {err}", hook.1.command);
+                    failed.push((hook, HookOutput {
+                        exit_code: BLOCKING_EXIT_CODE,
+                        stdout: String::new(),
+                        stderr,
+                    }));
+                },
+                Err(_) => (),
             }
 
             // Display ending summary or add a new spinner
@@ -167,17 +357,22 @@ impl HookExecutor {
         drop(futures);
 
         // Fill cache with executed results, skipping what was already from cache
-        for ((trigger, hook), output) in &results {
-            self.cache.insert((*trigger, hook.clone()), CachedHook {
+        for (hook, output) in &results {
+            let (trigger, hook_def) = hook;
+            self.cache.insert(cache_key(hook, payload), CachedHook {
                 output: output.clone(),
                 expiry: match trigger {
                     HookTrigger::AgentSpawn => None,
-                    HookTrigger::UserPromptSubmit => Some(Instant::now() + Duration::from_secs(hook.cache_ttl_seconds)),
+                    HookTrigger::UserPromptSubmit
+                    | HookTrigger::PreToolUse
+                    | HookTrigger::PostToolUse
+                    | HookTrigger::Stop => Some(Instant::now() + Duration::from_secs(hook_def.cache_ttl_seconds)),
                 },
             });
         }
 
         results.append(&mut cached);
+        results.append(&mut failed);
 
         Ok(results)
     }
@@ -186,7 +381,8 @@ impl HookExecutor {
         &self,
         hook: (HookTrigger, Hook),
         prompt: Option<&str>,
-    ) -> ((HookTrigger, Hook), Result<String>, Duration) {
+        stdin: String,
+    ) -> ((HookTrigger, Hook), Result<HookOutput>, Duration) {
         let start_time = Instant::now();
 
         let command = &hook.1.command;
@@ -220,23 +416,41 @@ impl HookExecutor {
             cmd.env("USER_PROMPT", sanitized_prompt);
         }
 
-        let command_future = cmd.output();
+        let command_future = async move {
+            let mut child = cmd.kill_on_drop(true).spawn()?;
+            let child_stdin = child.stdin.take();
+            // The input is written while the output is read, as hooks may write more than fits in
+            // a pipe before reading their input.
+            let write_stdin = async move {
+                if let Some(mut child_stdin) = child_stdin {
+                    // Hooks are free to ignore their input, so a closed pipe is not an error.
+                    if let Err(err) = child_stdin.write_all(stdin.as_bytes()).await {
+                        debug!(?err, "failed to write hook input");
+                    }
+                }
+            };
+            let ((), output) = tokio::join!(write_stdin, child.wait_with_output());
+            output
+        };
 
         // Run with timeout
+        let max_output_size = hook.1.max_output_size;
+        let truncate = |bytes: &[u8]| truncate_output(&bytes.to_str_lossy(), max_output_size);
+        // Only hooks that can block an action are allowed to exit with the blocking code, and their
+        // reply is parsed from the whole stdout.
+        let can_block = hook.0.is_tool_trigger() || hook.0 == HookTrigger::Stop;
         let result = match tokio::time::timeout(timeout, command_future).await {
             Ok(Ok(result)) => {
-                if result.status.success() {
-                    let stdout = result.stdout.to_str_lossy();
-                    let stdout = format!(
-                        "{}{}",
-                        truncate_safe(&stdout, hook.1.max_output_size),
-                        if stdout.len() > hook.1.max_output_size {
-                            " ... truncated"
-                        } else {
-                            ""
-                        }
-                    );
-                    Ok(stdout)
+                let exit_code = result.status.code().unwrap_or(-1);
+                if result.status.success() || (can_block && exit_code == BLOCKING_EXIT_CODE) {
+                    Ok(HookOutput {
+                        exit_code,
+                        stdout: match can_block {
+                            true => result.stdout.to_str_lossy().into_owned(),
+                            false => truncate(&result.stdout),
+                        },
+                        stderr: truncate(&result.stderr),
+                    })
                 } else {
                     Err(eyre!("command returned non-zero exit code: {}", result.status))
                 }
@@ -249,7 +463,7 @@ impl HookExecutor {
     }
 
     /// Will return a cached hook's output if it exists and isn't expired.
-    fn get_cache(&self, hook: &(HookTrigger, Hook)) -> Option<String> {
+    fn get_cache(&self, hook: &CacheKey) -> Option<HookOutput> {
         self.cache.get(hook).and_then(|o| {
             if let Some(expiry) = o.expiry {
                 if Instant::now() < expiry {
@@ -264,6 +478,19 @@ impl HookExecutor {
     }
 }
 
+fn cache_key(hook: &(HookTrigger, Hook), payload: Option<&HookPayload>) -> CacheKey {
+    let payload = payload.and_then(|payload| serde_json::to_string(payload).ok());
+    (hook.0, hook.1.clone(), payload)
+}
+
+/// Cuts `text` to `max_output_size` bytes, noting when it was truncated.
+fn truncate_output(text: &str, max_output_size: usize) -> String {
+    match text.len() > max_output_size {
+        true => format!("{} ... truncated", truncate_safe(text, max_output_size)),
+        false => text.to_string(),
+    }
+}
+
 /// Sanitizes a string value to be used as an environment variable
 fn sanitize_user_prompt(input: &str) -> String {
     // Limit the size of input to first 4096 characters
@@ -284,7 +511,10 @@ Refer to the documentation for how to configure hooks with your agent: https://g
 Notes:
 • Hooks are executed in parallel
 • 'conversation_start' hooks run on the first user prompt and are attached once to the conversation history sent to Amazon Q
-• 'per_prompt' hooks run on each user prompt and are attached to the prompt, but are not stored in conversation history"
+• 'per_prompt' hooks run on each user prompt and are attached to the prompt, but are not stored in conversation history
+• 'preToolUse' hooks run before each matching tool use and can allow, deny, or rewrite it
+• 'postToolUse' hooks run after each matching tool use, e.g. to format edited files
+• 'stop' hooks run when the assistant finishes responding and can ask it to keep going"
 )]
 /// Arguments for the hooks command that displays configured context hooks
 pub struct HooksArgs;
@@ -304,7 +534,10 @@ impl HooksArgs {
                 true => writeln!(&mut out, "<none>")?,
                 false => {
                     for hook in hooks {
-                        writeln!(&mut out, "  - {}", hook.command)?;
+                        match &hook.matcher {
+                            Some(matcher) => writeln!(&mut out, "  - {} (matcher: {matcher})", hook.command)?,
+                            None => writeln!(&mut out, "  - {}", hook.command)?,
+                        }
                     }
                 },
             }
@@ -330,3 +563,217 @@ impl HooksArgs {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::agent::hook::Source;
+
+    fn output(exit_code: i32, stdout: &str, stderr: &str) -> HookOutput {
+        HookOutput {
+            exit_code,
+            stdout: stdout.to_string(),
+            stderr: stderr.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_hook_decision() {
+        const DEFAULT: usize = 1024;
+        assert_eq!(output(0, "", "").decision(DEFAULT), HookDecision::Allow);
+        assert_eq!(output(0, "some context", "").decision(DEFAULT), HookDecision::Allow);
+        assert_eq!(
+            output(2, "", "no writes to /etc\n").decision(DEFAULT),
+            HookDecision::Deny {
+                reason: "no writes to /etc".to_string()
+            }
+        );
+        assert_eq!(
+            output(0, r#"{"decision": "block", "reason": "not allowed"}"#, "").decision(DEFAULT),
+            HookDecision::Deny {
+                reason: "not allowed".to_string()
+            }
+        );
+        assert_eq!(
+            output(0, r#"{"updated_input": {"command": "ls"}}"#, "").decision(DEFAULT),
+            HookDecision::Modify {
+                tool_input: serde_json::json!({ "command": "ls" })
+            }
+        );
+        // Replies that cannot be parsed block the action instead of allowing it
+        let HookDecision::Deny { reason } = output(0, r#"{"updated_input": {"command": "#, "").decision(DEFAULT) else {
+            panic!("expected an invalid reply to deny");
+        };
+        assert!(reason.starts_with("Invalid reply from hook"), "{reason}");
+        assert_eq!(
+            output(0, r#"{"decision": "deny", "reason": "too long"}"#, "").decision(3),
+            HookDecision::Deny {
+                reason: "too ... truncated".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_combine_decisions() {
+        let hook = |cmd: &str| (HookTrigger::PreToolUse, Hook::new(cmd.to_string(), Source::Agent));
+        let results = vec![
+            (hook("a"), output(0, r#"{"updated_input": {"command": "ls"}}"#, "")),
+            (hook("b"), output(0, "", "")),
+        ];
+        assert!(matches!(combine_decisions(&results), HookDecision::Modify { .. }));
+
+        let results = vec![
+            (hook("a"), output(0, r#"{"updated_input": {"command": "ls"}}"#, "")),
+            (hook("b"), output(2, "", "denied")),
+        ];
+        assert_eq!(combine_decisions(&results), HookDecision::Deny {
+            reason: "denied".to_string()
+        });
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run_tool_hooks() {
+        let mut executor = HookExecutor::new();
+        let mut hook = Hook::new(
+            r#"input=$(cat); case "$input" in *'"rm'*) echo "rm is not allowed" >&2; exit 2;; esac"#.to_string(),
+            Source::Agent,
+        );
+        hook.cache_ttl_seconds = 60;
+        let hooks = HashMap::from([(HookTrigger::PreToolUse, vec![hook])]);
+
+        let payload = HookPayload::tool_use(
+            "execute_bash".to_string(),
+            serde_json::json!({ "command": "rm -rf target" }),
+        );
+        let results = executor
+            .run_hooks(hooks.clone(), &mut std::io::sink(), None, Some(&payload))
+            .await
+            .unwrap();
+        assert_eq!(combine_decisions(&results), HookDecision::Deny {
+            reason: "rm is not allowed".to_string()
+        });
+
+        let payload = HookPayload::tool_use("execute_bash".to_string(), serde_json::json!({ "command": "ls" }));
+        let results = executor
+            .run_hooks(hooks, &mut std::io::sink(), None, Some(&payload))
+            .await
+            .unwrap();
+        assert_eq!(combine_decisions(&results), HookDecision::Allow);
+
+        // Both tool uses are cached separately.
+        assert_eq!(executor.cache.len(), 2);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run_stop_hooks_cached_per_response() {
+        let mut executor = HookExecutor::new();
+        let mut hook = Hook::new(
+            r#"input=$(cat); case "$input" in *TODO*) echo "finish the TODOs" >&2; exit 2;; esac"#.to_string(),
+            Source::Agent,
+        );
+        hook.cache_ttl_seconds = 60;
+        let hooks = HashMap::from([(HookTrigger::Stop, vec![hook])]);
+
+        let stop = |response: &str| HookPayload {
+            assistant_response: Some(response.to_string()),
+            ..Default::default()
+        };
+        let results = executor
+            .run_hooks(hooks.clone(), &mut std::io::sink(), None, Some(&stop("TODO: tests")))
+            .await
+            .unwrap();
+        assert_eq!(combine_decisions(&results), HookDecision::Deny {
+            reason: "finish the TODOs".to_string()
+        });
+
+        let results = executor
+            .run_hooks(hooks, &mut std::io::sink(), None, Some(&stop("All done")))
+            .await
+            .unwrap();
+        assert_eq!(combine_decisions(&results), HookDecision::Allow);
+        assert_eq!(executor.cache.len(), 2);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_tool_hook_reply_larger_than_max_output_size() {
+        let mut executor = HookExecutor::new();
+        let command = "a".repeat(100);
+        let mut hook = Hook::new(
+            format!(r#"cat > /dev/null; echo '{{"updated_input": {{"command": "{command}"}}}}'"#),
+            Source::Agent,
+        );
+        hook.max_output_size = 10;
+        let hooks = HashMap::from([(HookTrigger::PreToolUse, vec![hook])]);
+        let payload = HookPayload::tool_use("execute_bash".to_string(), serde_json::json!({ "command": "ls" }));
+        let results = executor
+            .run_hooks(hooks, &mut std::io::sink(), None, Some(&payload))
+            .await
+            .unwrap();
+        assert_eq!(combine_decisions(&results), HookDecision::Modify {
+            tool_input: serde_json::json!({ "command": command })
+        });
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_failing_tool_hooks_block() {
+        let mut executor = HookExecutor::new();
+        let mut timeout = Hook::new("sleep 5".to_string(), Source::Agent);
+        timeout.timeout_ms = 100;
+        let hooks = HashMap::from([(HookTrigger::PreToolUse, vec![
+            Hook::new("exit 1".to_string(), Source::Agent),
+            timeout,
+        ])]);
+        let payload = HookPayload::tool_use("execute_bash".to_string(), serde_json::json!({ "command": "ls" }));
+        let results = executor
+            .run_hooks(hooks, &mut std::io::sink(), None, Some(&payload))
+            .await
+            .unwrap();
+
+        let HookDecision::Deny { reason } = combine_decisions(&results) else {
+            panic!("expected the tool use to be denied: {results:?}");
+        };
+        assert!(reason.contains("Hook `exit 1` failed"), "{reason}");
+        assert!(reason.contains("Hook `sleep 5` failed: command timed out"), "{reason}");
+        // Failures are retried the next time
+        assert!(executor.cache.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_hook_writing_before_reading_input() {
+        let mut executor = HookExecutor::new();
+        // Fills the stdout pipe before reading the input, which is larger than a pipe too.
+        let mut hook = Hook::new("head -c 1000000 /dev/zero; cat > /dev/null".to_string(), Source::Agent);
+        hook.timeout_ms = 10_000;
+        let hooks = HashMap::from([(HookTrigger::PostToolUse, vec![hook])]);
+        let payload = HookPayload::tool_use(
+            "fs_read".to_string(),
+            serde_json::json!({ "content": "a".repeat(1_000_000) }),
+        );
+        let results = executor
+            .run_hooks(hooks, &mut std::io::sink(), None, Some(&payload))
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].1.exit_code, 0, "{:?}", results[0].1.stderr);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_non_blocking_hook_cannot_block() {
+        let mut executor = HookExecutor::new();
+        let hooks = HashMap::from([(HookTrigger::UserPromptSubmit, vec![Hook::new(
+            "exit 2".to_string(),
+            Source::Agent,
+        )])]);
+        let results = executor
+            .run_hooks(hooks, &mut std::io::sink(), Some("hello"), None)
+            .await
+            .unwrap();
+        assert!(results.is_empty());
+    }
+}
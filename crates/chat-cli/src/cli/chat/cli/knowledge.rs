@@ -10,6 +10,7 @@ use eyre::Result;
 use semantic_search_client::{
     OperationStatus,
     SystemStatus,
+    WatchStatus,
 };
 
 use crate::cli::chat::tools::sanitize_path_tool_arg;
@@ -36,7 +37,7 @@ pub enum KnowledgeSubcommand {
         /// Exclude patterns (e.g., `node_modules/**`, `target/**`)
         #[arg(long, action = clap::ArgAction::Append)]
         exclude: Vec<String>,
-        /// Index type to use (Fast, Best)
+        /// Index type to use (Fast, Best, Hybrid)
         #[arg(long)]
         index_type: Option<String>,
     },
@@ -45,6 +46,10 @@ pub enum KnowledgeSubcommand {
     Remove { path: String },
     /// Update a file or directory in knowledge base
     Update { path: String },
+    /// Re-index a knowledge base entry automatically when its files change
+    Watch { path: String },
+    /// Stop re-indexing a knowledge base entry automatically
+    Unwatch { path: String },
     /// Remove all knowledge base entries
     Clear,
     /// Show background operation status
@@ -123,6 +128,8 @@ impl KnowledgeSubcommand {
             } => Self::handle_add(os, session, path, include, exclude, index_type).await,
             KnowledgeSubcommand::Remove { path } => Self::handle_remove(os, session, path).await,
             KnowledgeSubcommand::Update { path } => Self::handle_update(os, session, path).await,
+            KnowledgeSubcommand::Watch { path } => Self::handle_watch(os, session, path, true).await,
+            KnowledgeSubcommand::Unwatch { path } => Self::handle_watch(os, session, path, false).await,
             KnowledgeSubcommand::Clear => Self::handle_clear(os, session).await,
             KnowledgeSubcommand::Status => Self::handle_status(os, session).await,
             KnowledgeSubcommand::Cancel { operation_id } => {
@@ -157,7 +164,7 @@ impl KnowledgeSubcommand {
                             style::SetForegroundColor(Color::Reset)
                         )?;
                     } else {
-                        Self::format_knowledge_entries_with_indent(session, &contexts, "    ")?;
+                        Self::format_knowledge_entries_with_indent(session, &store, &contexts, "    ")?;
                     }
                 },
                 Err(_) => {
@@ -176,6 +183,7 @@ impl KnowledgeSubcommand {
 
     fn format_knowledge_entries_with_indent(
         session: &mut ChatSession,
+        store: &KnowledgeStore,
         contexts: &[semantic_search_client::KnowledgeContext],
         indent: &str,
     ) -> Result<(), std::io::Error> {
@@ -218,12 +226,60 @@ impl KnowledgeSubcommand {
                 style::SetForegroundColor(Color::DarkGrey),
                 style::Print(format!("{}", ctx.updated_at.format("%m/%d %H:%M"))),
                 style::SetForegroundColor(Color::Reset),
-                style::Print("\n\n")
+                style::Print("\n")
             )?;
+
+            // Freshness line for watched entries
+            if ctx.watch {
+                let (color, freshness) = Self::format_watch_status(store.watch_status(&ctx.id).as_ref());
+                queue!(
+                    session.stderr,
+                    style::Print(format!("{}   ", indent)),
+                    style::SetForegroundColor(color),
+                    style::Print(freshness),
+                    style::SetForegroundColor(Color::Reset),
+                    style::Print("\n")
+                )?;
+            }
+
+            queue!(session.stderr, style::Print("\n"))?;
         }
         Ok(())
     }
 
+    /// Format how fresh a watched entry is
+    fn format_watch_status(status: Option<&WatchStatus>) -> (Color, String) {
+        let Some(status) = status else {
+            return (Color::Yellow, "👀 Watching is not active".to_string());
+        };
+        if let Some(error) = &status.error {
+            return (Color::Red, format!("❌ Watching stopped • {}", error));
+        }
+        let paused = if status.paused { " • paused while idle" } else { "" };
+
+        match (status.pending_changes, status.stale_since) {
+            (changes, _) if status.updating => (
+                Color::Cyan,
+                format!(
+                    "🔄 Updating • {} file{} changed",
+                    changes,
+                    if changes == 1 { "" } else { "s" }
+                ),
+            ),
+            (0, _) | (_, None) => (Color::DarkGrey, format!("👀 Watching • up to date{}", paused)),
+            (changes, Some(since)) => (
+                Color::Yellow,
+                format!(
+                    "⚠️  Stale • {} file{} changed since {}{}",
+                    changes,
+                    if changes == 1 { "" } else { "s" },
+                    since.format("%m/%d %H:%M"),
+                    paused
+                ),
+            ),
+        }
+    }
+
     /// Handle add operation
     fn get_db_patterns(os: &crate::os::Os, setting: crate::database::settings::Setting) -> Vec<String> {
         os.database
@@ -342,6 +398,29 @@ impl KnowledgeSubcommand {
         }
     }
 
+    /// Handle watch and unwatch operations
+    async fn handle_watch(os: &Os, session: &ChatSession, path: &str, watch: bool) -> OperationResult {
+        let sanitized_path = sanitize_path_tool_arg(os, path);
+        let agent = Self::get_agent(session);
+        let async_knowledge_store = match KnowledgeStore::get_async_instance(os, agent).await {
+            Ok(store) => store,
+            Err(e) => return OperationResult::Error(format!("Error accessing knowledge base directory: {}", e)),
+        };
+        let mut store = async_knowledge_store.lock().await;
+
+        // Try path first, then name
+        let result = match store.set_watch(&sanitized_path.to_string_lossy(), watch).await {
+            Ok(message) => Ok(message),
+            Err(_) => store.set_watch(path, watch).await,
+        };
+
+        match result {
+            Ok(message) if watch => OperationResult::Info(message),
+            Ok(message) => OperationResult::Success(message),
+            Err(e) => OperationResult::Error(format!("Failed to {}: {}", if watch { "watch" } else { "unwatch" }, e)),
+        }
+    }
+
     /// Handle clear operation
     async fn handle_clear(os: &Os, session: &mut ChatSession) -> OperationResult {
         // Require confirmation
@@ -582,6 +661,8 @@ impl KnowledgeSubcommand {
             KnowledgeSubcommand::Add { .. } => "add",
             KnowledgeSubcommand::Remove { .. } => "remove",
             KnowledgeSubcommand::Update { .. } => "update",
+            KnowledgeSubcommand::Watch { .. } => "watch",
+            KnowledgeSubcommand::Unwatch { .. } => "unwatch",
             KnowledgeSubcommand::Clear => "clear",
             KnowledgeSubcommand::Status => "status",
             KnowledgeSubcommand::Cancel { .. } => "cancel",
@@ -715,4 +796,59 @@ mod tests {
             panic!("Expected Add subcommand");
         }
     }
+
+    #[test]
+    fn test_watch_subcommands_parsing() {
+        let cli = TestCli::try_parse_from(["test", "watch", "/some/path"]).unwrap();
+        assert_eq!(cli.knowledge, KnowledgeSubcommand::Watch {
+            path: "/some/path".to_string()
+        });
+
+        let cli = TestCli::try_parse_from(["test", "unwatch", "docs"]).unwrap();
+        assert_eq!(cli.knowledge, KnowledgeSubcommand::Unwatch {
+            path: "docs".to_string()
+        });
+    }
+
+    #[test]
+    fn test_format_watch_status() {
+        let (_, text) = KnowledgeSubcommand::format_watch_status(Some(&WatchStatus::default()));
+        assert_eq!(text, "👀 Watching • up to date");
+
+        let since = chrono::DateTime::parse_from_rfc3339("2025-03-04T05:06:00Z")
+            .unwrap()
+            .to_utc();
+        let status = WatchStatus {
+            paused: true,
+            pending_changes: 2,
+            stale_since: Some(since),
+            ..Default::default()
+        };
+        let (color, text) = KnowledgeSubcommand::format_watch_status(Some(&status));
+        assert_eq!(color, Color::Yellow);
+        assert_eq!(
+            text,
+            "⚠️  Stale • 2 files changed since 03/04 05:06 • paused while idle"
+        );
+
+        let status = WatchStatus {
+            pending_changes: 1,
+            stale_since: Some(since),
+            updating: true,
+            ..Default::default()
+        };
+        let (_, text) = KnowledgeSubcommand::format_watch_status(Some(&status));
+        assert_eq!(text, "🔄 Updating • 1 file changed");
+
+        let status = WatchStatus {
+            error: Some("Background worker unavailable".to_string()),
+            ..Default::default()
+        };
+        let (color, text) = KnowledgeSubcommand::format_watch_status(Some(&status));
+        assert_eq!(color, Color::Red);
+        assert_eq!(text, "❌ Watching stopped • Background worker unavailable");
+
+        let (_, text) = KnowledgeSubcommand::format_watch_status(None);
+        assert_eq!(text, "👀 Watching is not active");
+    }
 }
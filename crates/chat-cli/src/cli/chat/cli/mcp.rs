@@ -1,9 +1,14 @@
+use std::collections::BTreeSet;
 use std::io::Write;
 
-use clap::Args;
+use clap::{
+    Args,
+    Subcommand,
+};
 use crossterm::queue;
 use crossterm::style::{
     self,
+    Attribute,
     Color,
 };
 
@@ -13,6 +18,7 @@ use crate::cli::chat::{
     ChatSession,
     ChatState,
 };
+use crate::mcp_client::resources::RESOURCE_PATH_PREFIX;
 
 /// Arguments for the MCP (Model Context Protocol) command.
 ///
@@ -20,7 +26,19 @@ use crate::cli::chat::{
 /// the status of MCP servers and their loading progress.
 #[deny(missing_docs)]
 #[derive(Debug, PartialEq, Args)]
-pub struct McpArgs;
+pub struct McpArgs {
+    #[command(subcommand)]
+    subcommand: Option<McpSubcommand>,
+}
+
+/// Subcommands for MCP operations
+#[deny(missing_docs)]
+#[derive(Clone, Debug, PartialEq, Subcommand)]
+pub enum McpSubcommand {
+    /// List the resources offered by mcp servers. These can be added to context with /context add
+    /// mcp://<server>/<uri>
+    Resources,
+}
 
 impl McpArgs {
     pub async fn execute(self, session: &mut ChatSession) -> Result<ChatState, ChatError> {
@@ -39,6 +57,10 @@ impl McpArgs {
             });
         }
 
+        if let Some(McpSubcommand::Resources) = self.subcommand {
+            return list_resources(session);
+        }
+
         let terminal_width = session.terminal_width();
         let still_loading = session
             .conversation
@@ -88,3 +110,94 @@ impl McpArgs {
         })
     }
 }
+
+/// Lists the resources and resource templates offered by each server, along with the path with
+/// which they can be added to context.
+fn list_resources(session: &mut ChatSession) -> Result<ChatState, ChatError> {
+    let terminal_width = session.terminal_width();
+    let resource_store = session.conversation.tool_manager.resource_store();
+    let resources = resource_store.resources();
+    let templates = resource_store.templates();
+
+    if resources.values().all(Vec::is_empty) && templates.values().all(Vec::is_empty) {
+        queue!(
+            session.stderr,
+            style::SetForegroundColor(Color::DarkGrey),
+            style::Print("\nNo resources are offered by the mcp servers loaded.\n\n"),
+            style::SetForegroundColor(Color::Reset),
+        )?;
+        session.stderr.flush()?;
+        return Ok(ChatState::PromptUser {
+            skip_printing_tools: true,
+        });
+    }
+
+    let server_names = resources.keys().chain(templates.keys()).collect::<BTreeSet<_>>();
+    for server_name in server_names {
+        queue!(
+            session.stderr,
+            style::Print("\n"),
+            style::SetAttribute(Attribute::Bold),
+            style::Print(server_name),
+            style::SetAttribute(Attribute::Reset),
+            style::Print("\n"),
+            style::Print(format!("{}\n", "▔".repeat(terminal_width))),
+        )?;
+
+        for resource in resources.get(server_name).into_iter().flatten() {
+            queue!(
+                session.stderr,
+                style::Print("- "),
+                style::SetForegroundColor(Color::Green),
+                style::Print(format!("{RESOURCE_PATH_PREFIX}{server_name}/{}", resource.uri)),
+                style::SetForegroundColor(Color::Reset),
+                style::Print(format!(" {}", resource.name)),
+            )?;
+            if let Some(description) = &resource.description {
+                queue!(
+                    session.stderr,
+                    style::SetForegroundColor(Color::DarkGrey),
+                    style::Print(format!(" - {description}")),
+                    style::SetForegroundColor(Color::Reset),
+                )?;
+            }
+            queue!(session.stderr, style::Print("\n"))?;
+        }
+
+        let server_templates = templates.get(server_name).map(Vec::as_slice).unwrap_or_default();
+        if !server_templates.is_empty() {
+            queue!(
+                session.stderr,
+                style::SetForegroundColor(Color::DarkGrey),
+                style::Print("Templates:\n"),
+                style::SetForegroundColor(Color::Reset),
+            )?;
+            for template in server_templates {
+                queue!(
+                    session.stderr,
+                    style::Print("- "),
+                    style::SetForegroundColor(Color::Green),
+                    style::Print(&template.uri_template),
+                    style::SetForegroundColor(Color::Reset),
+                    style::Print(format!(" {}", template.name)),
+                )?;
+                if let Some(description) = &template.description {
+                    queue!(
+                        session.stderr,
+                        style::SetForegroundColor(Color::DarkGrey),
+                        style::Print(format!(" - {description}")),
+                        style::SetForegroundColor(Color::Reset),
+                    )?;
+                }
+                queue!(session.stderr, style::Print("\n"))?;
+            }
+        }
+    }
+
+    queue!(session.stderr, style::Print("\n"))?;
+    session.stderr.flush()?;
+
+    Ok(ChatState::PromptUser {
+        skip_printing_tools: true,
+    })
+}
@@ -1,4 +1,6 @@
+pub mod branch;
 pub mod changelog;
+pub mod checkpoint;
 pub mod clear;
 pub mod compact;
 pub mod context;
@@ -11,13 +13,16 @@ pub mod model;
 pub mod persist;
 pub mod profile;
 pub mod prompts;
+pub mod shell;
 pub mod subscribe;
 pub mod tangent;
 pub mod todos;
 pub mod tools;
 pub mod usage;
 
+use branch::BranchSubcommand;
 use changelog::ChangelogArgs;
+use checkpoint::CheckpointSubcommand;
 use clap::Parser;
 use clear::ClearArgs;
 use compact::CompactArgs;
@@ -31,6 +36,7 @@ use model::ModelArgs;
 use persist::PersistSubcommand;
 use profile::AgentSubcommand;
 use prompts::PromptsArgs;
+use shell::ShellArgs;
 use tangent::TangentArgs;
 use todos::TodoSubcommand;
 use tools::ToolsArgs;
@@ -73,6 +79,14 @@ pub enum SlashCommand {
     PromptEditor(EditorArgs),
     /// Summarize the conversation to free up context space
     Compact(CompactArgs),
+    /// List, diff and restore the files written by the agent at the start of each turn
+    #[command(subcommand)]
+    Checkpoint(CheckpointSubcommand),
+    /// Fork the conversation at an earlier turn and switch between its branches
+    #[command(subcommand)]
+    Branch(BranchSubcommand),
+    /// Show or reset the persistent shell session of execute_bash
+    Shell(ShellArgs),
     /// View tools and permissions
     Tools(ToolsArgs),
     /// Create a new Github issue or make a feature request
@@ -140,6 +154,9 @@ impl SlashCommand {
             Self::Knowledge(subcommand) => subcommand.execute(os, session).await,
             Self::PromptEditor(args) => args.execute(session).await,
             Self::Compact(args) => args.execute(os, session).await,
+            Self::Checkpoint(subcommand) => subcommand.execute(os, session).await,
+            Self::Branch(subcommand) => subcommand.execute(os, session).await,
+            Self::Shell(args) => args.execute(session).await,
             Self::Tools(args) => args.execute(session).await,
             Self::Issue(args) => {
                 if let Err(err) = args.execute(os).await {
@@ -183,6 +200,9 @@ impl SlashCommand {
             Self::Knowledge(_) => "knowledge",
             Self::PromptEditor(_) => "editor",
             Self::Compact(_) => "compact",
+            Self::Checkpoint(_) => "checkpoint",
+            Self::Branch(_) => "branch",
+            Self::Shell(_) => "shell",
             Self::Tools(_) => "tools",
             Self::Issue(_) => "issue",
             Self::Changelog(_) => "changelog",
@@ -197,6 +217,7 @@ impl SlashCommand {
             Self::Persist(sub) => match sub {
                 PersistSubcommand::Save { .. } => "save",
                 PersistSubcommand::Load { .. } => "load",
+                PersistSubcommand::Export { .. } => "export",
             },
             Self::Todos(_) => "todos",
         }
@@ -207,6 +228,9 @@ impl SlashCommand {
             SlashCommand::Agent(sub) => Some(sub.name()),
             SlashCommand::Context(sub) => Some(sub.name()),
             SlashCommand::Knowledge(sub) => Some(sub.name()),
+            SlashCommand::Checkpoint(sub) => Some(sub.name()),
+            SlashCommand::Branch(sub) => Some(sub.name()),
+            SlashCommand::Shell(arg) => arg.subcommand_name(),
             SlashCommand::Tools(arg) => arg.subcommand_name(),
             SlashCommand::Prompts(arg) => arg.subcommand_name(),
             _ => None,
@@ -7,6 +7,10 @@ use crossterm::style::{
 };
 
 use crate::cli::ConversationState;
+use crate::cli::chat::export::{
+    ExportFormat,
+    export,
+};
 use crate::cli::chat::{
     ChatError,
     ChatSession,
@@ -31,6 +35,20 @@ pub enum PersistSubcommand {
         /// Path to the conversation file to load
         path: String,
     },
+    /// Export the current conversation as a Markdown, HTML or JSON Lines transcript
+    Export {
+        /// Path where the transcript will be written
+        path: String,
+        /// Format of the transcript, guessed from the file extension if not given
+        #[arg(long, value_enum)]
+        format: Option<ExportFormat>,
+        /// Replace the outputs of tools with a placeholder
+        #[arg(long)]
+        redact_tool_outputs: bool,
+        #[arg(short, long)]
+        /// Force overwrite if file already exists
+        force: bool,
+    },
 }
 
 impl PersistSubcommand {
@@ -117,6 +135,54 @@ impl PersistSubcommand {
                     style::SetAttribute(Attribute::Reset)
                 )?;
             },
+            Self::Export {
+                path,
+                format,
+                redact_tool_outputs,
+                force,
+            } => {
+                let Some(format) = format.or_else(|| ExportFormat::from_path(&path)) else {
+                    execute!(
+                        session.stderr,
+                        style::SetForegroundColor(Color::Red),
+                        style::Print(format!(
+                            "\nCould not guess the format of {} from its extension, use --format\n\n",
+                            &path
+                        )),
+                        style::SetAttribute(Attribute::Reset)
+                    )?;
+                    return Ok(ChatState::PromptUser {
+                        skip_printing_tools: true,
+                    });
+                };
+                if os.fs.exists(&path) && !force {
+                    execute!(
+                        session.stderr,
+                        style::SetForegroundColor(Color::Red),
+                        style::Print(format!(
+                            "\nFile at {} already exists. To overwrite, use -f or --force\n\n",
+                            &path
+                        )),
+                        style::SetAttribute(Attribute::Reset)
+                    )?;
+                    return Ok(ChatState::PromptUser {
+                        skip_printing_tools: true,
+                    });
+                }
+                let contents = tri!(
+                    export(&session.conversation, format, redact_tool_outputs),
+                    "export to",
+                    &path
+                );
+                tri!(os.fs.write(&path, contents).await, "export to", &path);
+
+                execute!(
+                    session.stderr,
+                    style::SetForegroundColor(Color::Green),
+                    style::Print(format!("\n✔ Exported transcript to {}\n\n", &path)),
+                    style::SetAttribute(Attribute::Reset)
+                )?;
+            },
         }
 
         Ok(ChatState::PromptUser {
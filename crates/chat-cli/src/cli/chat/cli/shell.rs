@@ -0,0 +1,113 @@
+use clap::{
+    Args,
+    Subcommand,
+};
+use crossterm::execute;
+use crossterm::style::{
+    self,
+    Color,
+};
+
+use crate::cli::chat::{
+    ChatError,
+    ChatSession,
+    ChatState,
+};
+
+#[derive(Debug, PartialEq, Args)]
+pub struct ShellArgs {
+    #[command(subcommand)]
+    pub subcommand: Option<ShellSubcommand>,
+}
+
+/// Commands for the persistent shell session of execute_bash
+#[deny(missing_docs)]
+#[derive(Debug, PartialEq, Subcommand)]
+pub enum ShellSubcommand {
+    /// Show the working directory of the shell session and the variables changed since it started
+    Status,
+    /// Kill the shell session, the next command starts a new one
+    Reset,
+}
+
+impl ShellArgs {
+    pub fn subcommand_name(&self) -> Option<&'static str> {
+        match self.subcommand {
+            Some(ShellSubcommand::Status) => Some("status"),
+            Some(ShellSubcommand::Reset) => Some("reset"),
+            None => None,
+        }
+    }
+
+    pub async fn execute(self, session: &mut ChatSession) -> Result<ChatState, ChatError> {
+        match self.subcommand.unwrap_or(ShellSubcommand::Status) {
+            ShellSubcommand::Status => match session.shell_session.status() {
+                Some((state, changes)) => {
+                    execute!(
+                        session.stderr,
+                        style::Print("\nWorking directory: "),
+                        style::SetForegroundColor(Color::Green),
+                        style::Print(state.cwd.display()),
+                        style::SetForegroundColor(Color::Reset),
+                        style::Print("\n"),
+                    )?;
+                    if changes.is_empty() {
+                        execute!(
+                            session.stderr,
+                            style::SetForegroundColor(Color::DarkGrey),
+                            style::Print("No variables changed since the shell started.\n"),
+                            style::SetForegroundColor(Color::Reset),
+                        )?;
+                    } else {
+                        execute!(session.stderr, style::Print("Changed variables:\n"))?;
+                    }
+                    for change in changes {
+                        execute!(
+                            session.stderr,
+                            style::SetForegroundColor(Color::DarkGrey),
+                            style::Print(format!("    {change}\n")),
+                            style::SetForegroundColor(Color::Reset),
+                        )?;
+                    }
+                    if session.shell_session.overrides_commands() {
+                        execute!(
+                            session.stderr,
+                            style::SetForegroundColor(Color::Yellow),
+                            style::Print(
+                                "The shell defines functions, aliases or traps, or changed exported variables. Commands are not approved automatically until it is reset.\n"
+                            ),
+                            style::SetForegroundColor(Color::Reset),
+                        )?;
+                    }
+                    execute!(session.stderr, style::Print("\n"))?;
+                },
+                None => {
+                    execute!(
+                        session.stderr,
+                        style::SetForegroundColor(Color::DarkGrey),
+                        style::Print(
+                            "\nNo shell session is running. Enable one with `persistentShell` in the execute_bash tool settings of the agent.\n\n"
+                        ),
+                        style::SetForegroundColor(Color::Reset),
+                    )?;
+                },
+            },
+            ShellSubcommand::Reset => {
+                let message = match session.shell_session.reset() {
+                    true => "\nThe shell session was reset, the next command starts a new one.\n\n",
+                    false => "\nNo shell session is running.\n\n",
+                };
+                execute!(
+                    session.stderr,
+                    style::SetForegroundColor(Color::DarkGrey),
+                    style::Print(message),
+                    style::SetForegroundColor(Color::Reset),
+                )?;
+            },
+        }
+
+        Ok(ChatState::PromptUser {
+            skip_printing_tools: true,
+        })
+    }
+}
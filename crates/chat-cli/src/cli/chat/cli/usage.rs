@@ -11,8 +11,8 @@ use crossterm::{
 
 use super::model::context_window_tokens;
 use crate::cli::chat::token_counter::{
-    CharCount,
     TokenCount,
+    Tokenizer,
 };
 use crate::cli::chat::{
     ChatError,
@@ -57,13 +57,12 @@ impl UsageArgs {
             .filter_map(|s| serde_json::to_string(s).ok())
             .collect::<Vec<String>>()
             .join("");
-        let context_token_count: TokenCount = data.context_messages.into();
-        let assistant_token_count: TokenCount = data.assistant_messages.into();
-        let user_token_count: TokenCount = data.user_messages.into();
-        let tools_char_count: CharCount = tool_specs_json.len().into(); // usize → CharCount
-        let tools_token_count: TokenCount = tools_char_count.into(); // CharCount → TokenCount
-        let total_token_used: TokenCount =
-            (data.context_messages + data.user_messages + data.assistant_messages + tools_char_count).into();
+        let tokenizer = Tokenizer::for_model(session.conversation.model_info.as_ref());
+        let context_token_count = data.context_messages;
+        let assistant_token_count = data.assistant_messages;
+        let user_token_count = data.user_messages;
+        let tools_token_count = TokenCount::from(tokenizer.count(&tool_specs_json));
+        let total_token_used = data.total() + tools_token_count;
         let window_width = session.terminal_width();
         // set a max width for the progress bar for better aesthetic
         let progress_bar_width = std::cmp::min(window_width, 80);
@@ -0,0 +1,146 @@
+use chrono::{
+    DateTime,
+    Utc,
+};
+use eyre::Result;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::api_client::model::{
+    CodeReference,
+    Span,
+};
+use crate::os::Os;
+use crate::util::directories;
+
+/// Line of the workspace reference log, recording a code reference returned with a response.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReferenceLogEntry {
+    pub timestamp: DateTime<Utc>,
+    pub conversation_id: String,
+    /// Id of the assistant message that the reference was returned with.
+    pub message_id: Option<String>,
+    pub license_name: Option<String>,
+    pub repository: Option<String>,
+    pub url: Option<String>,
+    pub span: Option<Span>,
+}
+
+/// Appends `references` to the reference log of the current workspace.
+pub async fn log_code_references(
+    os: &Os,
+    conversation_id: &str,
+    message_id: Option<&str>,
+    references: &[CodeReference],
+) -> Result<()> {
+    if references.is_empty() {
+        return Ok(());
+    }
+
+    let path = directories::chat_code_reference_log_path(os)?;
+    if let Some(parent) = path.parent() {
+        os.fs.create_dir_all(parent).await?;
+    }
+
+    let timestamp = Utc::now();
+    let mut lines = String::new();
+    for reference in references {
+        let entry = ReferenceLogEntry {
+            timestamp,
+            conversation_id: conversation_id.to_string(),
+            message_id: message_id.map(str::to_string),
+            license_name: reference.license_name.clone(),
+            repository: reference.repository.clone(),
+            url: reference.url.clone(),
+            span: reference.recommendation_content_span,
+        };
+        lines.push_str(&serde_json::to_string(&entry)?);
+        lines.push('\n');
+    }
+    os.fs.append(path, lines).await?;
+
+    Ok(())
+}
+
+/// Describes a reference on a single line, e.g. `MIT license - aws/repo (https://..), characters 10 to 42`.
+pub fn describe_reference(reference: &CodeReference) -> String {
+    let mut description = format!("{} license", reference.license_name.as_deref().unwrap_or("Unknown"));
+    if let Some(repository) = &reference.repository {
+        description.push_str(&format!(" - {repository}"));
+    }
+    if let Some(url) = &reference.url {
+        description.push_str(&format!(" ({url})"));
+    }
+    if let Some(Span {
+        start: Some(start),
+        end: Some(end),
+    }) = reference.recommendation_content_span
+    {
+        description.push_str(&format!(", characters {start} to {end}"));
+    }
+    description
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reference(span: Option<Span>) -> CodeReference {
+        CodeReference {
+            license_name: Some("MIT".to_string()),
+            repository: Some("example/repo".to_string()),
+            url: Some("https://github.com/example/repo".to_string()),
+            recommendation_content_span: span,
+        }
+    }
+
+    #[test]
+    fn test_describe_reference() {
+        assert_eq!(
+            describe_reference(&reference(Some(Span {
+                start: Some(10),
+                end: Some(42)
+            }))),
+            "MIT license - example/repo (https://github.com/example/repo), characters 10 to 42"
+        );
+        assert_eq!(
+            describe_reference(&CodeReference {
+                license_name: None,
+                repository: None,
+                url: None,
+                recommendation_content_span: None,
+            }),
+            "Unknown license"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_log_code_references() {
+        let os = Os::new().await.unwrap();
+        let span = Some(Span {
+            start: Some(0),
+            end: Some(5),
+        });
+        log_code_references(&os, "conv", Some("msg-1"), &[reference(span)])
+            .await
+            .unwrap();
+        log_code_references(&os, "conv", None, &[]).await.unwrap();
+        log_code_references(&os, "conv", Some("msg-2"), &[reference(None), reference(None)])
+            .await
+            .unwrap();
+
+        let path = directories::chat_code_reference_log_path(&os).unwrap();
+        let content = os.fs.read_to_string(path).await.unwrap();
+        let entries = content
+            .lines()
+            .map(|line| serde_json::from_str::<ReferenceLogEntry>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].message_id.as_deref(), Some("msg-1"));
+        assert_eq!(entries[0].license_name.as_deref(), Some("MIT"));
+        assert_eq!(entries[0].span, span);
+        assert_eq!(entries[2].message_id.as_deref(), Some("msg-2"));
+    }
+}
@@ -13,6 +13,13 @@ pub const MAX_USER_MESSAGE_SIZE: usize = 400_000;
 
 pub const DUMMY_TOOL_NAME: &str = "dummy";
 
+/// Default number of read-only tool uses run concurrently, see `chat.maxConcurrentTools`.
+pub const DEFAULT_MAX_CONCURRENT_TOOLS: usize = 8;
+
+/// Number of times in a row stop hooks may make the assistant continue before they are ignored
+/// for the rest of the user turn, so that a hook that always blocks cannot loop forever.
+pub const MAX_STOP_HOOK_CONTINUATIONS: usize = 5;
+
 pub const MAX_NUMBER_OF_IMAGES_PER_REQUEST: usize = 10;
 
 /// In bytes - 10 MB
@@ -15,6 +15,7 @@ use serde::{
 };
 
 use super::cli::model::context_window_tokens;
+use super::token_counter::Tokenizer;
 use super::util::drop_matched_context_files;
 use crate::cli::agent::Agent;
 use crate::cli::agent::hook::{
@@ -22,8 +23,13 @@ use crate::cli::agent::hook::{
     HookTrigger,
 };
 use crate::cli::chat::ChatError;
-use crate::cli::chat::cli::hooks::HookExecutor;
+use crate::cli::chat::cli::hooks::{
+    HookExecutor,
+    HookOutput,
+    HookPayload,
+};
 use crate::cli::chat::cli::model::ModelInfo;
+use crate::mcp_client::resources::parse_resource_path;
 use crate::os::Os;
 
 #[derive(Debug, Clone)]
@@ -211,15 +217,18 @@ impl ContextManager {
         Ok(context_files)
     }
 
-    /// Collects context files and optionally drops files if the total size exceeds the limit.
+    /// Collects context files and optionally drops files if their total number of tokens, as
+    /// counted by `tokenizer`, exceeds the limit.
     /// Returns (files_to_use, dropped_files)
     pub async fn collect_context_files_with_limit(
         &self,
         os: &Os,
+        tokenizer: Tokenizer,
     ) -> Result<(Vec<(String, String)>, Vec<(String, String)>)> {
         let mut files = self.get_context_files(os).await?;
 
-        let dropped_files = drop_matched_context_files(&mut files, self.max_context_files_size).unwrap_or_default();
+        let dropped_files =
+            drop_matched_context_files(&mut files, self.max_context_files_size, tokenizer).unwrap_or_default();
 
         // remove dropped files from files
         files.retain(|file| !dropped_files.iter().any(|dropped| dropped.0 == file.0));
@@ -241,6 +250,8 @@ impl ContextManager {
     }
 
     /// Run all the currently enabled hooks from both the global and profile contexts.
+    ///
+    /// If `payload` refers to a tool, only hooks whose matcher matches the tool are run.
     /// # Returns
     /// A vector containing pairs of a [`Hook`] definition and its execution output
     pub async fn run_hooks(
@@ -248,10 +259,16 @@ impl ContextManager {
         trigger: HookTrigger,
         output: &mut impl Write,
         prompt: Option<&str>,
-    ) -> Result<Vec<((HookTrigger, Hook), String)>, ChatError> {
+        payload: Option<&HookPayload>,
+    ) -> Result<Vec<((HookTrigger, Hook), HookOutput)>, ChatError> {
         let mut hooks = self.hooks.clone();
         hooks.retain(|t, _| *t == trigger);
-        self.hook_executor.run_hooks(hooks, output, prompt).await
+        if let Some(tool_name) = payload.and_then(|p| p.tool_name.as_deref()) {
+            for hooks in hooks.values_mut() {
+                hooks.retain(|hook| hook.matches_tool(tool_name));
+            }
+        }
+        self.hook_executor.run_hooks(hooks, output, prompt, payload).await
     }
 }
 
@@ -283,6 +300,11 @@ async fn process_path(
     context_files: &mut Vec<(String, String)>,
     is_validation: bool,
 ) -> Result<()> {
+    // Mcp resources are not files, their contents are provided by the tool manager
+    if parse_resource_path(path).is_some() {
+        return Ok(());
+    }
+
     // Expand ~ to home directory
     let expanded_path = if path.starts_with('~') {
         if let Some(home_dir) = os.env.home() {
@@ -391,7 +413,10 @@ mod tests {
         os.fs.write("test/to-drop.md", "long content that exceed limit").await?;
         manager.add_paths(&os, vec!["test/*.md".to_string()], false).await?;
 
-        let (used, dropped) = manager.collect_context_files_with_limit(&os).await.unwrap();
+        let (used, dropped) = manager
+            .collect_context_files_with_limit(&os, Tokenizer::default())
+            .await
+            .unwrap();
 
         assert!(used.len() + dropped.len() == 2);
         assert!(used.len() == 1);
@@ -28,6 +28,15 @@ use tracing::{
     warn,
 };
 
+use super::branch::{
+    Branch,
+    BranchError,
+    BranchInfo,
+    BranchOrigin,
+    BranchStore,
+    user_turn_indices,
+};
+use super::checkpoint::CheckpointStore;
 use super::cli::compact::CompactStrategy;
 use super::cli::model::context_window_tokens;
 use super::consts::{
@@ -35,6 +44,7 @@ use super::consts::{
     MAX_CONVERSATION_STATE_HISTORY_LEN,
 };
 use super::context::{
+    ContextFilePath,
     ContextManager,
     calc_max_context_files_size,
 };
@@ -46,9 +56,9 @@ use super::message::{
 };
 use super::parser::RequestMetadata;
 use super::token_counter::{
-    CharCount,
-    CharCounter,
-    TokenCounter,
+    CountTokens,
+    TokenCount,
+    Tokenizer,
 };
 use super::tool_manager::ToolManager;
 use super::tools::{
@@ -73,22 +83,46 @@ use crate::cli::agent::hook::{
     HookTrigger,
 };
 use crate::cli::chat::ChatError;
+use crate::cli::chat::cli::hooks::HookOutput;
 use crate::cli::chat::cli::model::{
     ModelInfo,
     get_model_info,
 };
 use crate::cli::chat::tools::custom_tool::CustomToolConfig;
+use crate::mcp_client::resources::parse_resource_path;
+use crate::mcp_client::roots::collect_roots;
 use crate::os::Os;
 
 pub const CONTEXT_ENTRY_START_HEADER: &str = "--- CONTEXT ENTRY BEGIN ---\n";
 pub const CONTEXT_ENTRY_END_HEADER: &str = "--- CONTEXT ENTRY END ---\n\n";
 
+/// Maximum number of characters of the first prompt kept as the title of a conversation.
+const MAX_TITLE_LEN: usize = 60;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HistoryEntry {
     user: UserMessage,
     assistant: AssistantMessage,
     #[serde(default)]
     request_metadata: Option<RequestMetadata>,
+    /// Index in [ConversationState::transcript] of the first line of this entry, i.e. of the user
+    /// input that started it. Used to cut the transcript when the history is rewound.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    transcript_start: Option<usize>,
+}
+
+impl HistoryEntry {
+    pub fn user(&self) -> &UserMessage {
+        &self.user
+    }
+
+    pub fn assistant(&self) -> &AssistantMessage {
+        &self.assistant
+    }
+
+    pub fn request_metadata(&self) -> Option<&RequestMetadata> {
+        self.request_metadata.as_ref()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -123,6 +157,9 @@ pub struct ConversationState {
     context_message_length: Option<usize>,
     /// Stores the latest conversation summary created by /compact
     latest_summary: Option<(String, RequestMetadata)>,
+    /// Index in the transcript of the last user input, which starts the next history entry
+    #[serde(skip)]
+    next_transcript_start: Option<usize>,
     #[serde(skip)]
     pub agents: Agents,
     /// Unused, kept only to maintain deserialization backwards compatibility with <=v1.13.3
@@ -142,6 +179,15 @@ pub struct ConversationState {
     /// Tangent mode checkpoint - stores main conversation when in tangent mode
     #[serde(default, skip_serializing_if = "Option::is_none")]
     tangent_state: Option<ConversationCheckpoint>,
+    /// Named branches of the history
+    #[serde(default, skip_serializing_if = "BranchStore::is_default")]
+    branches: BranchStore,
+    /// Snapshots of the files written by the agent, taken at the start of each turn
+    #[serde(skip)]
+    pub checkpoints: CheckpointStore,
+    /// Unified diffs of the files written by `fs_write`, keyed by tool use id
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub file_diffs: HashMap<String, String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -198,12 +244,16 @@ impl ConversationState {
             tool_manager,
             context_message_length: None,
             latest_summary: None,
+            next_transcript_start: None,
             agents,
             model: None,
             model_info: model,
             file_line_tracker: HashMap::new(),
             mcp_enabled,
             tangent_state: None,
+            branches: BranchStore::default(),
+            checkpoints: CheckpointStore::default(),
+            file_diffs: HashMap::new(),
         }
     }
 
@@ -220,6 +270,26 @@ impl ConversationState {
         self.next_message = None;
         self.history.clear();
         self.latest_summary = None;
+        self.checkpoints.forget_history();
+    }
+
+    /// Starts a new file checkpoint for the turn started by the given user prompt.
+    pub fn begin_checkpoint(&mut self, prompt: &str) {
+        self.checkpoints.begin_turn(prompt, self.history.len());
+    }
+
+    /// Drops every history entry starting from `history_len`, returning the conversation to the
+    /// point right before the corresponding user message was sent. The transcript is cut back to
+    /// the same point. The summary is kept, as it only covers entries before the history.
+    pub fn rewind_history(&mut self, history_len: usize) {
+        if let Some(transcript_start) = self.history.get(history_len).and_then(|entry| entry.transcript_start) {
+            self.transcript.truncate(transcript_start);
+            self.next_transcript_start = None;
+        }
+        self.history.truncate(history_len);
+        self.next_message = None;
+        self.valid_history_range = (0, self.history.len());
+        self.checkpoints.truncate_history(self.history.len());
     }
 
     /// Check if currently in tangent mode
@@ -245,6 +315,7 @@ impl ConversationState {
         self.transcript = checkpoint.main_transcript;
         self.latest_summary = checkpoint.main_latest_summary;
         self.valid_history_range = (0, self.history.len());
+        self.checkpoints.truncate_history(self.history.len());
     }
 
     /// Enter tangent mode - creates checkpoint of current state
@@ -290,6 +361,155 @@ impl ConversationState {
         }
     }
 
+    /// Name of the branch the conversation is on.
+    pub fn current_branch(&self) -> &str {
+        &self.branches.current
+    }
+
+    /// Lists the branches of the conversation, sorted by name.
+    pub fn branches(&self) -> Vec<BranchInfo> {
+        let mut branches = self
+            .branches
+            .inactive
+            .iter()
+            .map(|(name, branch)| BranchInfo {
+                name: name.clone(),
+                current: false,
+                turns: user_turn_indices(&branch.history).len(),
+                origin: branch.origin.clone(),
+            })
+            .collect::<Vec<_>>();
+        branches.push(BranchInfo {
+            name: self.branches.current.clone(),
+            current: true,
+            turns: user_turn_indices(&self.history).len(),
+            origin: self.branches.current_origin.clone(),
+        });
+        branches.sort_by(|a, b| a.name.cmp(&b.name));
+        branches
+    }
+
+    /// Returns the history of the branch named `name`.
+    pub fn branch_history(&self, name: &str) -> Option<&VecDeque<HistoryEntry>> {
+        match name == self.branches.current {
+            true => Some(&self.history),
+            false => self.branches.inactive.get(name).map(|branch| &branch.history),
+        }
+    }
+
+    /// Creates the branch `name` and switches to it.
+    ///
+    /// The new branch starts with the history of the current branch up to, but excluding, the
+    /// user turn `at_turn` (1-based), or with the whole history if `at_turn` is [None].
+    pub fn fork_branch(&mut self, name: &str, at_turn: Option<usize>) -> Result<(), BranchError> {
+        if self.is_in_tangent_mode() {
+            return Err(BranchError::TangentMode);
+        }
+        self.branches.validate_new_name(name)?;
+
+        let turns = user_turn_indices(&self.history);
+        let (history_len, kept_turns) = match at_turn {
+            None => (self.history.len(), turns.len()),
+            Some(turn) if turn >= 1 && turn <= turns.len() => (turns[turn - 1], turn - 1),
+            Some(turn) => {
+                return Err(BranchError::InvalidTurn {
+                    turn,
+                    turns: turns.len(),
+                });
+            },
+        };
+
+        let parent = self.branches.current.clone();
+        self.branches.inactive.insert(parent.clone(), Branch {
+            history: self.history.clone(),
+            transcript: self.transcript.clone(),
+            latest_summary: self.latest_summary.clone(),
+            origin: self.branches.current_origin.take(),
+        });
+        self.branches.current = name.to_string();
+        self.branches.current_origin = Some(BranchOrigin {
+            branch: parent,
+            turns: kept_turns,
+            created_at: chrono::Utc::now(),
+        });
+        self.rewind_history(history_len);
+
+        Ok(())
+    }
+
+    /// Switches to the existing branch `name`, keeping the history of the current branch.
+    pub fn switch_branch(&mut self, name: &str) -> Result<(), BranchError> {
+        if self.is_in_tangent_mode() {
+            return Err(BranchError::TangentMode);
+        }
+        if name == self.branches.current {
+            return Err(BranchError::AlreadyCurrent(name.to_string()));
+        }
+        let Some(branch) = self.branches.inactive.remove(name) else {
+            return Err(BranchError::NotFound(name.to_string()));
+        };
+
+        let previous = Branch {
+            history: std::mem::replace(&mut self.history, branch.history),
+            transcript: std::mem::replace(&mut self.transcript, branch.transcript),
+            latest_summary: std::mem::replace(&mut self.latest_summary, branch.latest_summary),
+            origin: std::mem::replace(&mut self.branches.current_origin, branch.origin),
+        };
+        let previous_name = std::mem::replace(&mut self.branches.current, name.to_string());
+        self.branches.inactive.insert(previous_name, previous);
+
+        self.next_message = None;
+        self.valid_history_range = (0, self.history.len());
+        // Checkpoints refer to positions in the history of the branch they were taken on.
+        self.checkpoints.forget_history();
+
+        Ok(())
+    }
+
+    /// Renames the branch `from` to `to`.
+    pub fn rename_branch(&mut self, from: &str, to: &str) -> Result<(), BranchError> {
+        if self.is_in_tangent_mode() {
+            return Err(BranchError::TangentMode);
+        }
+        if from != self.branches.current && !self.branches.inactive.contains_key(from) {
+            return Err(BranchError::NotFound(from.to_string()));
+        }
+        self.branches.validate_new_name(to)?;
+
+        if from == self.branches.current {
+            self.branches.current = to.to_string();
+        } else if let Some(branch) = self.branches.inactive.remove(from) {
+            self.branches.inactive.insert(to.to_string(), branch);
+        }
+        for origin in self
+            .branches
+            .inactive
+            .values_mut()
+            .filter_map(|branch| branch.origin.as_mut())
+            .chain(self.branches.current_origin.as_mut())
+        {
+            if origin.branch == from {
+                origin.branch = to.to_string();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Deletes the branch `name`, which must not be the current branch.
+    pub fn delete_branch(&mut self, name: &str) -> Result<(), BranchError> {
+        if self.is_in_tangent_mode() {
+            return Err(BranchError::TangentMode);
+        }
+        if name == self.branches.current {
+            return Err(BranchError::DeleteCurrent(name.to_string()));
+        }
+        match self.branches.inactive.remove(name) {
+            Some(_) => Ok(()),
+            None => Err(BranchError::NotFound(name.to_string())),
+        }
+    }
+
     /// Appends a collection prompts into history and returns the last message in the collection.
     /// It asserts that the collection ends with a prompt that assumes the role of user.
     pub fn append_prompts(&mut self, mut prompts: VecDeque<PromptMessage>) -> Option<String> {
@@ -346,11 +566,13 @@ impl ConversationState {
             if candidate_asst.is_some() && candidate_user.is_some() {
                 let assistant = candidate_asst.take().unwrap();
                 let user = candidate_user.take().unwrap();
+                let transcript_start = self.transcript.len();
                 self.append_assistant_transcript(&assistant);
                 self.history.push_back(HistoryEntry {
                     user,
                     assistant,
                     request_metadata: None,
+                    transcript_start: Some(transcript_start),
                 });
             }
         }
@@ -393,15 +615,18 @@ impl ConversationState {
         debug_assert!(self.next_message.is_some(), "next_message should exist");
         let next_user_message = self.next_message.take().expect("next user message should exist");
 
+        // Prompts that the user did not type, e.g. from stop hooks, have no line of their own.
+        let transcript_start = self.next_transcript_start.take().unwrap_or(self.transcript.len());
         self.append_assistant_transcript(&message);
         self.history.push_back(HistoryEntry {
             user: next_user_message,
             assistant: message,
             request_metadata,
+            transcript_start: Some(transcript_start),
         });
 
         if let Ok(cwd) = std::env::current_dir() {
-            os.database.set_conversation_by_path(cwd, self).ok();
+            os.database.set_session_by_path(cwd, self).ok();
         }
     }
 
@@ -410,6 +635,17 @@ impl ConversationState {
         self.conversation_id.as_ref()
     }
 
+    /// Returns a title for the conversation, made from the first line of its first prompt.
+    pub fn title(&self) -> Option<String> {
+        let prompt = self.history.iter().find_map(|entry| entry.user.prompt())?;
+        let first_line = prompt.trim().lines().next().unwrap_or_default();
+        let mut title = first_line.chars().take(MAX_TITLE_LEN).collect::<String>();
+        if first_line.chars().count() > MAX_TITLE_LEN {
+            title.push('…');
+        }
+        Some(title)
+    }
+
     /// Returns the message id associated with the last assistant message, if present.
     ///
     /// This is equivalent to `utterance_id` in the Q API.
@@ -498,6 +734,8 @@ impl ConversationState {
         self.enforce_conversation_invariants();
         self.history.drain(self.valid_history_range.1..);
         self.history.drain(..self.valid_history_range.0);
+        self.checkpoints.truncate_history(self.valid_history_range.1);
+        self.checkpoints.shift_history(self.valid_history_range.0);
 
         let context = self.backend_conversation_state(os, run_perprompt_hooks, stderr).await?;
         if !context.dropped_context_files.is_empty() {
@@ -563,12 +801,12 @@ impl ConversationState {
         let mut agent_spawn_context = None;
         if let Some(cm) = self.context_manager.as_mut() {
             let user_prompt = self.next_message.as_ref().and_then(|m| m.prompt());
-            let agent_spawn = cm.run_hooks(HookTrigger::AgentSpawn, output, user_prompt).await?;
+            let agent_spawn = cm.run_hooks(HookTrigger::AgentSpawn, output, user_prompt, None).await?;
             agent_spawn_context = format_hook_context(&agent_spawn, HookTrigger::AgentSpawn);
 
             if let (true, Some(next_message)) = (run_perprompt_hooks, self.next_message.as_mut()) {
                 let per_prompt = cm
-                    .run_hooks(HookTrigger::UserPromptSubmit, output, next_message.prompt())
+                    .run_hooks(HookTrigger::UserPromptSubmit, output, next_message.prompt(), None)
                     .await?;
                 if let Some(ctx) = format_hook_context(&per_prompt, HookTrigger::UserPromptSubmit) {
                     next_message.additional_context = ctx;
@@ -707,6 +945,7 @@ impl ConversationState {
         self.history
             .drain(..(self.history.len().saturating_sub(strategy.messages_to_exclude)));
         self.latest_summary = Some((summary, request_metadata));
+        self.checkpoints.forget_history();
     }
 
     pub async fn create_agent_generation_request(
@@ -787,13 +1026,21 @@ Return only the JSON configuration, no additional text.",
         }
 
         // Add context files if available
+        let tokenizer = Tokenizer::for_model(self.model_info.as_ref());
         if let Some(context_manager) = self.context_manager.as_mut() {
-            match context_manager.collect_context_files_with_limit(os).await {
-                Ok((files_to_use, files_dropped)) => {
+            match context_manager.collect_context_files_with_limit(os, tokenizer).await {
+                Ok((mut files_to_use, files_dropped)) => {
                     if !files_dropped.is_empty() {
                         dropped_context_files.extend(files_dropped);
                     }
 
+                    // Mcp resources attached via /context add
+                    let resource_store = self.tool_manager.resource_store();
+                    files_to_use.extend(context_manager.paths.iter().filter_map(|path| {
+                        let path = path.get_path_as_str();
+                        Some((path.to_string(), resource_store.content_for_path(path)?))
+                    }));
+
                     if !files_to_use.is_empty() {
                         context_content.push_str(CONTEXT_ENTRY_START_HEADER);
                         for (filename, content) in files_to_use {
@@ -825,6 +1072,7 @@ Return only the JSON configuration, no additional text.",
                     user,
                     assistant,
                     request_metadata: None,
+                    transcript_start: None,
                 }]),
                 dropped_context_files,
             )
@@ -838,20 +1086,21 @@ Return only the JSON configuration, no additional text.",
         self.context_message_length
     }
 
-    /// Calculate the total character count in the conversation
-    pub async fn calculate_char_count(&mut self, os: &Os) -> Result<CharCount, ChatError> {
+    /// Calculate the total token count in the conversation
+    pub async fn calculate_token_count(&mut self, os: &Os) -> Result<TokenCount, ChatError> {
         Ok(self
             .backend_conversation_state(os, false, &mut vec![])
             .await?
-            .char_count())
+            .calculate_conversation_size()
+            .total())
     }
 
     /// Get the current token warning level
     pub async fn get_token_warning_level(&mut self, os: &Os) -> Result<TokenWarningLevel, ChatError> {
-        let total_chars = self.calculate_char_count(os).await?;
-        let max_chars = TokenCounter::token_to_chars(context_window_tokens(self.model_info.as_ref()));
+        let total_tokens = self.calculate_token_count(os).await?;
+        let max_tokens = context_window_tokens(self.model_info.as_ref());
 
-        Ok(if *total_chars >= max_chars {
+        Ok(if *total_tokens >= max_tokens {
             TokenWarningLevel::Critical
         } else {
             TokenWarningLevel::None
@@ -860,6 +1109,7 @@ Return only the JSON configuration, no additional text.",
 
     pub fn append_user_transcript(&mut self, message: &str) {
         self.append_transcript(format!("> {}", message.replace("\n", "> \n")));
+        self.next_transcript_start = Some(self.transcript.len() - 1);
     }
 
     pub fn append_assistant_transcript(&mut self, message: &AssistantMessage) {
@@ -872,6 +1122,10 @@ Return only the JSON configuration, no additional text.",
     pub fn append_transcript(&mut self, message: String) {
         if self.transcript.len() >= MAX_CONVERSATION_STATE_HISTORY_LEN {
             self.transcript.pop_front();
+            let starts = self.history.iter_mut().map(|entry| &mut entry.transcript_start);
+            for start in starts.chain([&mut self.next_transcript_start]).flatten() {
+                *start = start.saturating_sub(1);
+            }
         }
         self.transcript.push_back(message);
     }
@@ -900,6 +1154,18 @@ Return only the JSON configuration, no additional text.",
 
         Ok(())
     }
+
+    /// Recomputes the roots exposed to mcp servers from the active agent and the context paths,
+    /// notifying the servers if they changed.
+    pub fn update_mcp_roots(&self, os: &Os) {
+        let context_paths = self
+            .context_manager
+            .iter()
+            .flat_map(|cm| cm.paths.iter().map(ContextFilePath::get_path_as_str))
+            .filter(|path| parse_resource_path(path).is_none());
+        let roots = collect_roots(os, self.agents.get_active(), context_paths);
+        self.tool_manager.set_roots(roots);
+    }
 }
 
 pub fn format_tool_spec(tool_spec: HashMap<String, ToolSpec>) -> HashMap<ToolOrigin, Vec<Tool>> {
@@ -956,33 +1222,30 @@ impl BackendConversationStateImpl<'_, std::collections::vec_deque::Iter<'_, Hist
     }
 
     pub fn calculate_conversation_size(&self) -> ConversationSize {
-        let mut user_chars = 0;
-        let mut assistant_chars = 0;
-        let mut context_chars = 0;
+        let tokenizer = self.model_id.map_or_else(Tokenizer::default, Tokenizer::for_model_id);
+        let mut user_tokens = TokenCount::default();
+        let mut assistant_tokens = TokenCount::default();
 
-        // Count the chars used by the messages in the history.
+        // Count the tokens used by the messages in the history.
         // this clone is cheap
         let history = self.history.clone();
         for HistoryEntry { user, assistant, .. } in history {
-            user_chars += *user.char_count();
-            assistant_chars += *assistant.char_count();
+            user_tokens = user_tokens + user.token_count(tokenizer);
+            assistant_tokens = assistant_tokens + assistant.token_count(tokenizer);
         }
 
-        // Add any chars from context messages, if available.
-        context_chars += self
+        // Add any tokens from context messages, if available.
+        let context_tokens = self
             .context_messages
-            .as_ref()
-            .map(|v| {
-                v.iter().fold(0, |acc, HistoryEntry { user, assistant, .. }| {
-                    acc + *user.char_count() + *assistant.char_count()
-                })
-            })
-            .unwrap_or_default();
+            .iter()
+            .flatten()
+            .map(|HistoryEntry { user, assistant, .. }| user.token_count(tokenizer) + assistant.token_count(tokenizer))
+            .sum();
 
         ConversationSize {
-            context_messages: context_chars.into(),
-            user_messages: user_chars.into(),
-            assistant_messages: assistant_chars.into(),
+            context_messages: context_tokens,
+            user_messages: user_tokens,
+            assistant_messages: assistant_tokens,
         }
     }
 }
@@ -990,9 +1253,15 @@ impl BackendConversationStateImpl<'_, std::collections::vec_deque::Iter<'_, Hist
 /// Reflects a detailed accounting of the context window utilization for a given conversation.
 #[derive(Debug, Clone, Copy)]
 pub struct ConversationSize {
-    pub context_messages: CharCount,
-    pub user_messages: CharCount,
-    pub assistant_messages: CharCount,
+    pub context_messages: TokenCount,
+    pub user_messages: TokenCount,
+    pub assistant_messages: TokenCount,
+}
+
+impl ConversationSize {
+    pub fn total(&self) -> TokenCount {
+        self.context_messages + self.user_messages + self.assistant_messages
+    }
 }
 
 /// Converts a list of user/assistant message pairs into a flattened list of ChatMessage.
@@ -1030,8 +1299,8 @@ impl From<InputSchema> for ToolInputSchema {
 /// # Returns
 /// [Option::Some] if `hook_results` is not empty and at least one hook has content. Otherwise,
 /// [Option::None]
-fn format_hook_context(hook_results: &[((HookTrigger, Hook), String)], trigger: HookTrigger) -> Option<String> {
-    if hook_results.iter().all(|(_, content)| content.is_empty()) {
+fn format_hook_context(hook_results: &[((HookTrigger, Hook), HookOutput)], trigger: HookTrigger) -> Option<String> {
+    if hook_results.iter().all(|(_, output)| output.stdout.is_empty()) {
         return None;
     }
 
@@ -1045,7 +1314,7 @@ fn format_hook_context(hook_results: &[((HookTrigger, Hook), String)], trigger:
     context_content.push_str("\n\n");
 
     for (_, output) in hook_results.iter().filter(|((h_trigger, _), _)| *h_trigger == trigger) {
-        context_content.push_str(&format!("{output}\n\n"));
+        context_content.push_str(&format!("{}\n\n", output.stdout));
     }
     context_content.push_str(CONTEXT_ENTRY_END_HEADER);
     Some(context_content)
@@ -1213,6 +1482,10 @@ mod tests {
         Agent,
         Agents,
     };
+    use crate::cli::chat::branch::{
+        DEFAULT_BRANCH,
+        common_prefix_len,
+    };
     use crate::cli::chat::tool_manager::ToolManager;
 
     const AMAZONQ_FILENAME: &str = "AmazonQ.md";
@@ -1338,6 +1611,46 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_rewind_checkpoint_after_history_truncation() {
+        let mut os = Os::new().await.unwrap();
+        let agents = Agents::default();
+        let mut output = vec![];
+
+        let mut tool_manager = ToolManager::default();
+        let mut conversation = ConversationState::new(
+            "fake_conv_id",
+            agents,
+            tool_manager.load_tools(&mut os, &mut output).await.unwrap(),
+            tool_manager,
+            None,
+            &os,
+            false,
+        )
+        .await;
+
+        let turns = MAX_CONVERSATION_STATE_HISTORY_LEN + 10;
+        for i in 0..turns {
+            conversation.begin_checkpoint(&format!("prompt {i}"));
+            conversation.set_next_user_message(format!("prompt {i}")).await;
+            conversation
+                .as_sendable_conversation_state(&os, &mut vec![], true)
+                .await
+                .unwrap();
+            conversation.push_assistant_message(&mut os, AssistantMessage::new_response(None, i.to_string()), None);
+        }
+        assert!(conversation.history.len() < turns);
+        // The oldest turns were dropped, so their checkpoints cannot be rewound to
+        assert_eq!(conversation.checkpoints.get(1).unwrap().history_len, None);
+
+        let checkpoint = conversation.checkpoints.get(turns - 2).unwrap();
+        assert_eq!(checkpoint.label, format!("prompt {}", turns - 3));
+        conversation.rewind_history(checkpoint.history_len.unwrap());
+        let last = conversation.history.back().unwrap();
+        assert_eq!(last.user.prompt(), Some(format!("prompt {}", turns - 4).as_str()));
+        assert_eq!(last.assistant.content(), (turns - 4).to_string());
+    }
+
     #[tokio::test]
     async fn test_conversation_state_history_handling_with_tool_results() {
         let mut os = Os::new().await.unwrap();
@@ -1554,6 +1867,102 @@ mod tests {
         assert!(!conversation.is_in_tangent_mode());
     }
 
+    #[tokio::test]
+    async fn test_branches() {
+        let mut os = Os::new().await.unwrap();
+        let mut conversation = ConversationState::new(
+            "fake_conv_id",
+            Agents::default(),
+            HashMap::new(),
+            ToolManager::default(),
+            None,
+            &os,
+            false,
+        )
+        .await;
+        for prompt in ["first", "second", "third"] {
+            conversation.append_user_transcript(prompt);
+            conversation.set_next_user_message(prompt.to_string()).await;
+            conversation.push_assistant_message(
+                &mut os,
+                AssistantMessage::new_response(None, format!("{prompt} response")),
+                None,
+            );
+            conversation.append_transcript(format!("{prompt} error"));
+        }
+        assert_eq!(conversation.current_branch(), DEFAULT_BRANCH);
+
+        assert!(matches!(
+            conversation.fork_branch("retry", Some(4)),
+            Err(BranchError::InvalidTurn { turn: 4, turns: 3 })
+        ));
+        assert!(matches!(
+            conversation.fork_branch("main", None),
+            Err(BranchError::AlreadyExists(_))
+        ));
+        assert!(matches!(
+            conversation.fork_branch("two words", None),
+            Err(BranchError::InvalidName)
+        ));
+
+        // Forking before the second turn keeps the first one only.
+        conversation.fork_branch("retry", Some(2)).unwrap();
+        assert_eq!(conversation.current_branch(), "retry");
+        assert_eq!(conversation.history().len(), 1);
+        assert_eq!(conversation.transcript, [
+            "> first",
+            "first response\n[Tool uses: none]",
+            "first error"
+        ]);
+        conversation
+            .set_next_user_message("second, differently".to_string())
+            .await;
+        conversation.push_assistant_message(&mut os, AssistantMessage::new_response(None, "ok".to_string()), None);
+
+        let branches = conversation.branches();
+        assert_eq!(branches.len(), 2);
+        assert_eq!((branches[0].name.as_str(), branches[0].turns), ("main", 3));
+        assert_eq!((branches[1].name.as_str(), branches[1].turns), ("retry", 2));
+        assert!(branches[1].current);
+        assert_eq!(branches[1].origin.as_ref().unwrap().turns, 1);
+        assert_eq!(
+            common_prefix_len(
+                conversation.branch_history("main").unwrap(),
+                conversation.branch_history("retry").unwrap()
+            ),
+            1
+        );
+
+        // Branches are kept when the conversation is saved.
+        let mut conversation: ConversationState =
+            serde_json::from_str(&serde_json::to_string(&conversation).unwrap()).unwrap();
+        conversation.switch_branch("main").unwrap();
+        assert_eq!(conversation.history().len(), 3);
+        assert_eq!(conversation.transcript.len(), 9);
+        assert!(matches!(
+            conversation.switch_branch("main"),
+            Err(BranchError::AlreadyCurrent(_))
+        ));
+
+        conversation.rename_branch("retry", "alternative").unwrap();
+        assert_eq!(
+            conversation.branches()[0].origin.as_ref().map(|o| o.branch.as_str()),
+            Some("main")
+        );
+        assert!(matches!(
+            conversation.delete_branch("main"),
+            Err(BranchError::DeleteCurrent(_))
+        ));
+        conversation.delete_branch("alternative").unwrap();
+        assert_eq!(conversation.branches().len(), 1);
+
+        conversation.enter_tangent_mode();
+        assert!(matches!(
+            conversation.fork_branch("tangent", None),
+            Err(BranchError::TangentMode)
+        ));
+    }
+
     #[tokio::test]
     async fn test_tangent_mode_duration() {
         let mut os = Os::new().await.unwrap();
@@ -0,0 +1,712 @@
+//! Exporting conversations as transcripts that can be shared outside of the CLI.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::path::Path;
+
+use chrono::{
+    DateTime,
+    FixedOffset,
+    TimeZone,
+    Utc,
+};
+use clap::ValueEnum;
+use eyre::Result;
+use pulldown_cmark::{
+    Event,
+    Options,
+    Parser,
+    Tag,
+    TagEnd,
+};
+use serde::Serialize;
+use serde_json::Value;
+
+use super::conversation::ConversationState;
+use super::message::{
+    ToolUseResult,
+    ToolUseResultBlock,
+};
+use super::tools::fs_write::FsWrite;
+use crate::api_client::model::ToolResultStatus;
+
+/// Replaces the outputs of the tools when they are redacted.
+const REDACTED_OUTPUT: &str = "[redacted]";
+
+/// Number of unchanged lines shown around the changes of a diff.
+const DIFF_CONTEXT_LINES: usize = 3;
+
+/// Schemes of the links kept in HTML exports, links without a scheme are kept too.
+const SAFE_LINK_SCHEMES: &[&str] = &["http", "https", "mailto"];
+
+const HTML_STYLE: &str = "
+body { font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Helvetica, Arial, sans-serif; margin: 0; background: #f6f8fa; color: #1f2328; }
+main { max-width: 960px; margin: 0 auto; padding: 24px; }
+header .meta { color: #59636e; }
+.message { background: #fff; border: 1px solid #d1d9e0; border-radius: 6px; margin: 16px 0; padding: 8px 16px; }
+.message.user { border-left: 4px solid #0969da; }
+.message.assistant { border-left: 4px solid #1a7f37; }
+.role { font-weight: 600; margin: 8px 0; }
+.role time { font-weight: normal; color: #59636e; margin-left: 8px; }
+pre { background: #f6f8fa; border-radius: 6px; padding: 12px; overflow-x: auto; white-space: pre-wrap; }
+.message.user pre { background: none; padding: 0; font-family: inherit; }
+details.tool-use { background: #fff; border: 1px solid #d1d9e0; border-radius: 6px; margin: 8px 0 8px 24px; padding: 8px 16px; }
+details.tool-use.error summary { color: #d1242f; }
+summary { cursor: pointer; }
+.diff .add { color: #1a7f37; }
+.diff .del { color: #d1242f; }
+";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    /// Markdown, with the tool uses in collapsible sections
+    Markdown,
+    /// A self-contained HTML page
+    Html,
+    /// One JSON event per line
+    Jsonl,
+}
+
+impl ExportFormat {
+    /// Guesses the format from the extension of `path`.
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        match path.as_ref().extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "md" | "markdown" => Some(Self::Markdown),
+            "html" | "htm" => Some(Self::Html),
+            "jsonl" | "ndjson" => Some(Self::Jsonl),
+            _ => None,
+        }
+    }
+}
+
+/// Event of an exported transcript. JSON Lines exports contain one event per line.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TranscriptEvent {
+    /// Always the first event of a transcript.
+    Conversation {
+        conversation_id: String,
+        title: Option<String>,
+        model_id: Option<String>,
+        exported_at: DateTime<Utc>,
+    },
+    Prompt {
+        timestamp: Option<DateTime<FixedOffset>>,
+        text: String,
+    },
+    Response {
+        /// When the response finished streaming.
+        timestamp: Option<DateTime<Utc>>,
+        message_id: Option<String>,
+        /// Markdown text of the response.
+        text: String,
+    },
+    ToolUse {
+        id: String,
+        name: String,
+        input: Value,
+        /// Unified diff of the file written by an `fs_write`. Conversations saved before diffs were
+        /// recorded get one built from the input of the tool, without the surrounding lines of
+        /// inserted and appended text.
+        diff: Option<String>,
+        /// `success` or `error`, [None] if the tool was never run.
+        status: Option<&'static str>,
+        output: Option<String>,
+    },
+}
+
+/// Renders the transcript of `conversation` in the given format.
+pub fn export(conversation: &ConversationState, format: ExportFormat, redact_tool_outputs: bool) -> Result<String> {
+    let events = transcript(conversation, redact_tool_outputs);
+    Ok(match format {
+        ExportFormat::Markdown => render_markdown(&events),
+        ExportFormat::Html => render_html(&events),
+        ExportFormat::Jsonl => render_jsonl(&events)?,
+    })
+}
+
+/// Builds the transcript of a conversation, pairing each tool use with its result.
+pub fn transcript(conversation: &ConversationState, redact_tool_outputs: bool) -> Vec<TranscriptEvent> {
+    let results = conversation
+        .history()
+        .iter()
+        .filter_map(|entry| entry.user().tool_use_results())
+        .flatten()
+        .map(|result| (result.tool_use_id.as_str(), result))
+        .collect::<HashMap<_, _>>();
+
+    let mut events = vec![TranscriptEvent::Conversation {
+        conversation_id: conversation.conversation_id().to_string(),
+        title: conversation.title(),
+        model_id: conversation.model_info.as_ref().map(|m| m.model_id.clone()),
+        exported_at: Utc::now(),
+    }];
+    for entry in conversation.history() {
+        if let Some(prompt) = entry.user().prompt() {
+            events.push(TranscriptEvent::Prompt {
+                timestamp: entry.user().timestamp,
+                text: prompt.to_string(),
+            });
+        }
+
+        let assistant = entry.assistant();
+        if !assistant.content().trim().is_empty() {
+            events.push(TranscriptEvent::Response {
+                timestamp: entry
+                    .request_metadata()
+                    .and_then(|rm| DateTime::from_timestamp_millis(rm.stream_end_timestamp_ms as i64)),
+                message_id: assistant.message_id().map(str::to_string),
+                text: assistant.content().to_string(),
+            });
+        }
+
+        for tool_use in assistant.tool_uses().unwrap_or_default() {
+            let result = results.get(tool_use.id.as_str());
+            events.push(TranscriptEvent::ToolUse {
+                id: tool_use.id.clone(),
+                name: tool_use.name.clone(),
+                input: tool_use.args.clone(),
+                diff: match tool_use.name.as_str() {
+                    "fs_write" => conversation
+                        .file_diffs
+                        .get(&tool_use.id)
+                        .cloned()
+                        .or_else(|| fs_write_input_diff(&tool_use.args)),
+                    _ => None,
+                },
+                status: result.map(|r| match r.status {
+                    ToolResultStatus::Success => "success",
+                    ToolResultStatus::Error => "error",
+                }),
+                output: result.map(|r| match redact_tool_outputs {
+                    true => REDACTED_OUTPUT.to_string(),
+                    false => result_text(r),
+                }),
+            });
+        }
+    }
+
+    events
+}
+
+/// Unified diff of the file at `path` going from `before` to `after`. A missing `before` is a
+/// file being created.
+pub fn unified_diff(path: &str, before: Option<&str>, after: &str) -> String {
+    let path = path.trim_start_matches('/');
+    let old_header = match before {
+        Some(_) => format!("a/{path}"),
+        None => "/dev/null".to_string(),
+    };
+    similar::TextDiff::from_lines(before.unwrap_or_default(), after)
+        .unified_diff()
+        .context_radius(DIFF_CONTEXT_LINES)
+        .header(&old_header, &format!("b/{path}"))
+        .to_string()
+}
+
+/// Diff of an `fs_write` built from its input alone, for tool uses whose diff was not recorded,
+/// see [TranscriptEvent::ToolUse].
+fn fs_write_input_diff(args: &Value) -> Option<String> {
+    let diff = match serde_json::from_value::<FsWrite>(args.clone()).ok()? {
+        FsWrite::Create {
+            path,
+            file_text,
+            new_str,
+            ..
+        } => unified_diff(&path, None, file_text.or(new_str).as_deref().unwrap_or_default()),
+        FsWrite::StrReplace {
+            path, old_str, new_str, ..
+        } => unified_diff(&path, Some(&old_str), &new_str),
+        // The rest of the file is unknown, so the added lines are shown on their own
+        FsWrite::Insert {
+            path,
+            insert_line,
+            new_str,
+            ..
+        } => {
+            let diff = unified_diff(&path, Some(""), &new_str);
+            let hunk = format!(
+                "@@ -{insert_line},0 +{},{} @@",
+                insert_line + 1,
+                new_str.lines().count()
+            );
+            diff.lines()
+                .map(|line| if line.starts_with("@@ ") { hunk.as_str() } else { line })
+                .map(|line| format!("{line}\n"))
+                .collect()
+        },
+        FsWrite::Append { path, new_str, .. } => unified_diff(&path, Some(""), &new_str),
+    };
+    (!diff.is_empty()).then_some(diff)
+}
+
+fn result_text(result: &ToolUseResult) -> String {
+    result
+        .content
+        .iter()
+        .map(|block| match block {
+            ToolUseResultBlock::Text(text) => text.clone(),
+            ToolUseResultBlock::Json(value) => serde_json::to_string_pretty(value).unwrap_or_default(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn format_time<Tz: TimeZone>(time: &DateTime<Tz>) -> String
+where
+    Tz::Offset: Display,
+{
+    time.format("%Y-%m-%d %H:%M:%S %:z").to_string()
+}
+
+fn render_markdown(events: &[TranscriptEvent]) -> String {
+    let mut out = String::new();
+    for event in events {
+        match event {
+            TranscriptEvent::Conversation {
+                conversation_id,
+                title,
+                model_id,
+                exported_at,
+            } => {
+                out.push_str(&format!("# {}\n\n", title.as_deref().unwrap_or("Conversation")));
+                out.push_str(&format!("- Conversation: `{conversation_id}`\n"));
+                if let Some(model_id) = model_id {
+                    out.push_str(&format!("- Model: `{model_id}`\n"));
+                }
+                out.push_str(&format!("- Exported: {}\n", format_time(exported_at)));
+            },
+            TranscriptEvent::Prompt { timestamp, text } => {
+                let time = timestamp.as_ref().map(|t| format!(" ({})", format_time(t)));
+                out.push_str(&format!(
+                    "\n## User{}\n\n{}\n",
+                    time.unwrap_or_default(),
+                    text.trim_end()
+                ));
+            },
+            TranscriptEvent::Response { timestamp, text, .. } => {
+                let time = timestamp.as_ref().map(|t| format!(" ({})", format_time(t)));
+                out.push_str(&format!(
+                    "\n## Assistant{}\n\n{}\n",
+                    time.unwrap_or_default(),
+                    text.trim_end()
+                ));
+            },
+            TranscriptEvent::ToolUse {
+                name,
+                input,
+                diff,
+                status,
+                output,
+                ..
+            } => {
+                out.push_str(&format!(
+                    "\n<details>\n<summary>Tool use: {name} ({})</summary>\n\n",
+                    status.unwrap_or("not run")
+                ));
+                let input = serde_json::to_string_pretty(input).unwrap_or_default();
+                out.push_str(&format!("**Input**\n\n{}", fenced("json", &input)));
+                if let Some(diff) = diff {
+                    out.push_str(&format!("\n**Diff**\n\n{}", fenced("diff", diff)));
+                }
+                if let Some(output) = output {
+                    out.push_str(&format!("\n**Output**\n\n{}", fenced("text", output)));
+                }
+                out.push_str("\n</details>\n");
+            },
+        }
+    }
+    out
+}
+
+/// Wraps `content` in a code block whose fence is longer than any run of backticks it contains.
+fn fenced(language: &str, content: &str) -> String {
+    let mut longest_run = 0;
+    let mut run = 0;
+    for c in content.chars() {
+        run = if c == '`' { run + 1 } else { 0 };
+        longest_run = longest_run.max(run);
+    }
+    let fence = "`".repeat((longest_run + 1).max(3));
+    format!("{fence}{language}\n{}\n{fence}\n", content.trim_end_matches('\n'))
+}
+
+fn render_html(events: &[TranscriptEvent]) -> String {
+    let mut title = "Conversation".to_string();
+    let mut body = String::new();
+    for event in events {
+        match event {
+            TranscriptEvent::Conversation {
+                conversation_id,
+                title: conversation_title,
+                model_id,
+                exported_at,
+            } => {
+                if let Some(conversation_title) = conversation_title {
+                    title = conversation_title.clone();
+                }
+                let mut meta = format!("Conversation <code>{}</code>", escape_html(conversation_id));
+                if let Some(model_id) = model_id {
+                    meta.push_str(&format!(" · Model <code>{}</code>", escape_html(model_id)));
+                }
+                meta.push_str(&format!(" · Exported {}", format_time(exported_at)));
+                body.push_str(&format!(
+                    "<header>\n<h1>{}</h1>\n<p class=\"meta\">{meta}</p>\n</header>\n",
+                    escape_html(&title)
+                ));
+            },
+            TranscriptEvent::Prompt { timestamp, text } => {
+                body.push_str(&format!(
+                    "<section class=\"message user\">\n<div class=\"role\">User{}</div>\n<pre>{}</pre>\n</section>\n",
+                    html_time(timestamp.as_ref()),
+                    escape_html(text.trim_end())
+                ));
+            },
+            TranscriptEvent::Response { timestamp, text, .. } => {
+                body.push_str(&format!(
+                    "<section class=\"message assistant\">\n<div class=\"role\">Assistant{}</div>\n{}</section>\n",
+                    html_time(timestamp.as_ref()),
+                    markdown_to_html(text)
+                ));
+            },
+            TranscriptEvent::ToolUse {
+                name,
+                input,
+                diff,
+                status,
+                output,
+                ..
+            } => {
+                let status = status.unwrap_or("not run");
+                body.push_str(&format!(
+                    "<details class=\"tool-use {}\">\n<summary>Tool use: <code>{}</code> ({status})</summary>\n",
+                    status.replace(' ', "-"),
+                    escape_html(name)
+                ));
+                let input = serde_json::to_string_pretty(input).unwrap_or_default();
+                body.push_str(&format!("<h4>Input</h4>\n<pre>{}</pre>\n", escape_html(&input)));
+                if let Some(diff) = diff {
+                    body.push_str(&format!(
+                        "<h4>Diff</h4>\n<pre class=\"diff\">{}</pre>\n",
+                        diff_to_html(diff)
+                    ));
+                }
+                if let Some(output) = output {
+                    body.push_str(&format!("<h4>Output</h4>\n<pre>{}</pre>\n", escape_html(output)));
+                }
+                body.push_str("</details>\n");
+            },
+        }
+    }
+
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n<title>{}</title>\n<style>{HTML_STYLE}</style>\n</head>\n<body>\n<main>\n{body}</main>\n</body>\n</html>\n",
+        escape_html(&title)
+    )
+}
+
+fn html_time<Tz: TimeZone>(time: Option<&DateTime<Tz>>) -> String
+where
+    Tz::Offset: Display,
+{
+    time.map(|t| format!("<time datetime=\"{}\">{}</time>", t.to_rfc3339(), format_time(t)))
+        .unwrap_or_default()
+}
+
+/// Renders markdown to HTML. Raw HTML in the markdown is escaped rather than passed through.
+///
+/// Only links with a [safe scheme](SAFE_LINK_SCHEMES) are kept, other links are replaced by their
+/// text. Images are rendered as links to them, so that the page stays self-contained.
+fn markdown_to_html(markdown: &str) -> String {
+    let options = Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    // Whether each of the links and images being rendered was kept.
+    let mut kept_links = Vec::new();
+    let events = Parser::new_ext(markdown, options).filter_map(|event| match event {
+        Event::Html(html) | Event::InlineHtml(html) => Some(Event::Text(html)),
+        Event::Start(
+            Tag::Link {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }
+            | Tag::Image {
+                link_type,
+                dest_url,
+                title,
+                id,
+            },
+        ) => {
+            let safe = is_safe_link(&dest_url);
+            kept_links.push(safe);
+            safe.then_some(Event::Start(Tag::Link {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }))
+        },
+        Event::End(TagEnd::Link | TagEnd::Image) => {
+            kept_links.pop().unwrap_or_default().then_some(Event::End(TagEnd::Link))
+        },
+        event => Some(event),
+    });
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, events);
+    html
+}
+
+/// Whether a link can be kept in an HTML export, see [SAFE_LINK_SCHEMES].
+fn is_safe_link(url: &str) -> bool {
+    match url.find([':', '/', '?', '#']) {
+        Some(end) if url[end..].starts_with(':') => SAFE_LINK_SCHEMES
+            .iter()
+            .any(|scheme| url[..end].eq_ignore_ascii_case(scheme)),
+        _ => true,
+    }
+}
+
+fn diff_to_html(diff: &str) -> String {
+    diff.lines()
+        .map(|line| {
+            let class = match line.chars().next() {
+                Some('+') => Some("add"),
+                Some('-') => Some("del"),
+                _ => None,
+            };
+            match class {
+                Some(class) => format!("<span class=\"{class}\">{}</span>", escape_html(line)),
+                None => escape_html(line),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn render_jsonl(events: &[TranscriptEvent]) -> Result<String> {
+    let mut out = String::new();
+    for event in events {
+        out.push_str(&serde_json::to_string(event)?);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::agent::Agents;
+    use crate::cli::chat::message::{
+        AssistantMessage,
+        AssistantToolUse,
+    };
+    use crate::cli::chat::tool_manager::ToolManager;
+    use crate::os::Os;
+
+    async fn conversation(os: &mut Os) -> ConversationState {
+        let mut conversation = ConversationState::new(
+            "conv-1",
+            Agents::default(),
+            HashMap::new(),
+            ToolManager::default(),
+            None,
+            os,
+            false,
+        )
+        .await;
+
+        conversation
+            .set_next_user_message("Rename the greeting".to_string())
+            .await;
+        conversation.push_assistant_message(
+            os,
+            AssistantMessage::new_tool_use(None, "Updating main.rs".to_string(), vec![AssistantToolUse {
+                id: "tool-1".to_string(),
+                name: "fs_write".to_string(),
+                args: serde_json::json!({
+                    "command": "str_replace",
+                    "path": "src/main.rs",
+                    "old_str": "println!(\"hello\");",
+                    "new_str": "println!(\"hi\");",
+                }),
+                ..Default::default()
+            }]),
+            None,
+        );
+        conversation.add_tool_results(vec![ToolUseResult {
+            tool_use_id: "tool-1".to_string(),
+            content: vec![ToolUseResultBlock::Text("Replaced 1 occurrence".to_string())],
+            status: ToolResultStatus::Success,
+        }]);
+        conversation.push_assistant_message(
+            os,
+            AssistantMessage::new_response(
+                Some("msg-2".to_string()),
+                "Done, `main` now prints <script>hi</script>".to_string(),
+            ),
+            None,
+        );
+        conversation
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(ExportFormat::from_path("out/review.MD"), Some(ExportFormat::Markdown));
+        assert_eq!(ExportFormat::from_path("review.html"), Some(ExportFormat::Html));
+        assert_eq!(ExportFormat::from_path("review.jsonl"), Some(ExportFormat::Jsonl));
+        assert_eq!(ExportFormat::from_path("review.json"), None);
+        assert_eq!(ExportFormat::from_path("review"), None);
+    }
+
+    #[tokio::test]
+    async fn test_transcript() {
+        let mut os = Os::new().await.unwrap();
+        let conversation = conversation(&mut os).await;
+
+        let events = transcript(&conversation, false);
+        assert_eq!(events.len(), 5, "{events:#?}");
+        assert!(matches!(&events[1], TranscriptEvent::Prompt { text, .. } if text == "Rename the greeting"));
+        match &events[3] {
+            TranscriptEvent::ToolUse {
+                diff, status, output, ..
+            } => {
+                let diff = diff.as_deref().unwrap();
+                assert!(diff.starts_with("--- a/src/main.rs\n+++ b/src/main.rs\n"), "{diff}");
+                assert!(diff.contains("-println!(\"hello\");\n"), "{diff}");
+                assert!(diff.contains("+println!(\"hi\");\n"), "{diff}");
+                assert_eq!(*status, Some("success"));
+                assert_eq!(output.as_deref(), Some("Replaced 1 occurrence"));
+            },
+            other => panic!("expected a tool use, found {other:?}"),
+        }
+
+        let events = transcript(&conversation, true);
+        assert!(
+            matches!(&events[3], TranscriptEvent::ToolUse { output: Some(output), .. } if output == REDACTED_OUTPUT)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_export_formats() {
+        let mut os = Os::new().await.unwrap();
+        let conversation = conversation(&mut os).await;
+
+        let markdown = export(&conversation, ExportFormat::Markdown, false).unwrap();
+        assert!(markdown.starts_with("# Rename the greeting\n"), "{markdown}");
+        assert!(
+            markdown.contains("<summary>Tool use: fs_write (success)</summary>"),
+            "{markdown}"
+        );
+        assert!(markdown.contains("```diff\n"), "{markdown}");
+
+        let html = export(&conversation, ExportFormat::Html, false).unwrap();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<code>main</code>"), "{html}");
+        assert!(html.contains("&lt;script&gt;"), "{html}");
+        assert!(!html.contains("<script>"), "{html}");
+        assert!(html.contains("<span class=\"add\">"), "{html}");
+
+        let jsonl = export(&conversation, ExportFormat::Jsonl, true).unwrap();
+        let lines = jsonl
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[0]["type"], "conversation");
+        assert_eq!(lines[3]["type"], "tool_use");
+        assert_eq!(lines[3]["output"], REDACTED_OUTPUT);
+        assert_eq!(lines[4]["message_id"], "msg-2");
+    }
+
+    #[test]
+    fn test_fs_write_input_diff() {
+        let input_diff = |args| fs_write_input_diff(&args).unwrap();
+        assert_eq!(
+            input_diff(serde_json::json!({
+                "command": "str_replace",
+                "path": "a.txt",
+                "old_str": "one\ntwo\n",
+                "new_str": "one\nthree\n",
+            })),
+            "--- a/a.txt\n+++ b/a.txt\n@@ -1,2 +1,2 @@\n one\n-two\n+three\n"
+        );
+        assert_eq!(
+            input_diff(serde_json::json!({ "command": "create", "path": "a.txt", "file_text": "one\ntwo\n" })),
+            "--- /dev/null\n+++ b/a.txt\n@@ -0,0 +1,2 @@\n+one\n+two\n"
+        );
+        assert_eq!(
+            input_diff(
+                serde_json::json!({ "command": "insert", "path": "a.txt", "insert_line": 4, "new_str": "one\n" })
+            ),
+            "--- a/a.txt\n+++ b/a.txt\n@@ -4,0 +5,1 @@\n+one\n"
+        );
+        assert_eq!(
+            input_diff(serde_json::json!({ "command": "append", "path": "a.txt", "new_str": "one\n" })),
+            "--- a/a.txt\n+++ b/a.txt\n@@ -0,0 +1 @@\n+one\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_transcript_uses_recorded_diff() {
+        let mut os = Os::new().await.unwrap();
+        let mut conversation = conversation(&mut os).await;
+        let diff = unified_diff(
+            "src/main.rs",
+            Some("fn main() {\n    println!(\"hello\");\n}\n"),
+            "fn main() {\n    println!(\"hi\");\n}\n",
+        );
+        conversation.file_diffs.insert("tool-1".to_string(), diff.clone());
+
+        let events = transcript(&conversation, false);
+        assert!(
+            matches!(&events[3], TranscriptEvent::ToolUse { diff: Some(recorded), .. } if *recorded == diff),
+            "{events:#?}"
+        );
+        assert!(
+            diff.contains(" fn main() {\n-    println!(\"hello\");\n+    println!(\"hi\");\n }\n"),
+            "{diff}"
+        );
+    }
+
+    #[test]
+    fn test_markdown_to_html_links() {
+        let html = markdown_to_html(
+            "[docs](https://example.com/docs) [x](javascript:alert(1)) [y]( JavaScript:alert(1)) <javascript:alert(1)> [file](src/main.rs) [mail](mailto:a@example.com)",
+        );
+        assert!(html.contains("<a href=\"https://example.com/docs\">docs</a>"), "{html}");
+        assert!(html.contains("<a href=\"src/main.rs\">file</a>"), "{html}");
+        assert!(html.contains("<a href=\"mailto:a@example.com\">mail</a>"), "{html}");
+        // Unsafe links keep their text
+        assert!(!html.to_lowercase().contains("href=\"javascript"), "{html}");
+        assert!(html.contains(" x y javascript:alert(1) "), "{html}");
+
+        let html = markdown_to_html("![diagram](https://example.com/diagram.png) ![pixel](data:image/png;base64,AAAA)");
+        assert!(!html.contains("<img"), "{html}");
+        assert!(
+            html.contains("<a href=\"https://example.com/diagram.png\">diagram</a>"),
+            "{html}"
+        );
+        assert!(html.contains("pixel") && !html.contains("data:"), "{html}");
+    }
+
+    #[test]
+    fn test_fenced() {
+        assert_eq!(fenced("text", "a\n"), "```text\na\n```\n");
+        assert_eq!(fenced("", "```rust\n```"), "````\n```rust\n```\n````\n");
+    }
+}
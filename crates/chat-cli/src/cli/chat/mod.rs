@@ -1,19 +1,25 @@
+mod branch;
+mod checkpoint;
 pub mod cli;
+mod code_reference;
 mod consts;
 pub mod context;
 mod conversation;
+mod export;
 mod input_source;
 mod message;
 mod parse;
 use std::path::MAIN_SEPARATOR;
 mod line_tracker;
+mod output;
 mod parser;
 mod prompt;
 mod prompt_parser;
 pub mod server_messenger;
+mod sessions;
 #[cfg(unix)]
 mod skim_integration;
-mod token_counter;
+pub mod token_counter;
 pub mod tool_manager;
 pub mod tools;
 pub mod util;
@@ -29,6 +35,10 @@ use std::io::{
 };
 use std::process::ExitCode;
 use std::sync::Arc;
+use std::sync::atomic::{
+    AtomicUsize,
+    Ordering,
+};
 use std::time::{
     Duration,
     Instant,
@@ -39,6 +49,7 @@ use clap::{
     Args,
     CommandFactory,
     Parser,
+    Subcommand,
     ValueEnum,
 };
 use cli::compact::CompactStrategy;
@@ -47,6 +58,10 @@ use cli::model::{
     get_available_models,
     select_model,
 };
+use consts::{
+    DEFAULT_MAX_CONCURRENT_TOOLS,
+    MAX_STOP_HOOK_CONTINUATIONS,
+};
 pub use conversation::ConversationState;
 use conversation::TokenWarningLevel;
 use crossterm::style::{
@@ -67,6 +82,7 @@ use eyre::{
     bail,
     eyre,
 };
+use futures::StreamExt;
 use input_source::InputSource;
 use message::{
     AssistantMessage,
@@ -74,6 +90,10 @@ use message::{
     ToolUseResult,
     ToolUseResultBlock,
 };
+use output::{
+    ChatOutput,
+    JsonOutput,
+};
 use parse::{
     ParseState,
     interpret_markdown,
@@ -85,6 +105,7 @@ use parser::{
 };
 use regex::Regex;
 use rmcp::model::PromptMessage;
+pub use sessions::SessionsSubcommand;
 use spinners::{
     Spinner,
     Spinners,
@@ -103,8 +124,10 @@ use tool_manager::{
     ToolManager,
     ToolManagerBuilder,
 };
+use tools::execute::ShellSession;
 use tools::gh_issue::GhIssueContext;
 use tools::{
+    InvokeOutput,
     NATIVE_TOOLS,
     OutputKind,
     QueuedTool,
@@ -127,12 +150,18 @@ use util::{
 use winnow::Partial;
 use winnow::stream::Offset;
 
+use super::agent::hook::HookTrigger;
 use super::agent::{
     Agent,
     DEFAULT_AGENT_NAME,
     PermissionEvalResult,
 };
-use crate::api_client::model::ToolResultStatus;
+use crate::api_client::model::{
+    CodeReference,
+    FollowupPrompt,
+    SupplementaryWebLink,
+    ToolResultStatus,
+};
 use crate::api_client::{
     self,
     ApiClientError,
@@ -143,6 +172,11 @@ use crate::cli::TodoListState;
 use crate::cli::agent::Agents;
 use crate::cli::chat::cli::SlashCommand;
 use crate::cli::chat::cli::editor::open_editor;
+use crate::cli::chat::cli::hooks::{
+    HookDecision,
+    HookPayload,
+    combine_decisions,
+};
 use crate::cli::chat::cli::prompts::{
     GetPromptError,
     PromptsSubcommand,
@@ -164,6 +198,7 @@ use crate::telemetry::{
     TelemetryResult,
     get_error_reason,
 };
+use crate::util::knowledge_store::KnowledgeStore;
 use crate::util::{
     MCP_SERVER_TOOL_DELIMITER,
     directories,
@@ -201,11 +236,39 @@ pub enum WrapMode {
     Auto,
 }
 
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum ChatOutputFormat {
+    /// Outputs the rendered responses
+    #[default]
+    Plain,
+    /// Outputs the session as a JSON object once it ends
+    Json,
+    /// Outputs the session as a pretty printed JSON object once it ends
+    JsonPretty,
+    /// Outputs newline-delimited JSON events as they happen
+    StreamJson,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Subcommand)]
+pub enum ChatSubcommand {
+    /// Manage the saved chat sessions
+    #[command(subcommand)]
+    Sessions(SessionsSubcommand),
+}
+
+/// Value of `--resume` that picks the session to resume from a list.
+const RESUME_PICKER: &str = "pick";
+
 #[derive(Debug, Clone, PartialEq, Eq, Default, Args)]
 pub struct ChatArgs {
-    /// Resumes the previous conversation from this directory.
-    #[arg(short, long)]
-    pub resume: bool,
+    #[command(subcommand)]
+    pub subcommand: Option<ChatSubcommand>,
+    /// Resumes the previous conversation from this directory. `--resume <SESSION_ID>` resumes the
+    /// session with the given id, or unique id prefix, and `--resume=pick` picks the session of
+    /// this directory to resume from a list.
+    #[arg(short, long, value_name = "SESSION_ID", num_args = 0..=1, require_equals = true)]
+    #[allow(clippy::option_option)] // Tells clap that the value is optional.
+    pub resume: Option<Option<String>>,
     /// Context profile to use
     #[arg(long = "agent", alias = "profile")]
     pub agent: Option<String>,
@@ -227,10 +290,24 @@ pub struct ChatArgs {
     /// Control line wrapping behavior (default: auto-detect)
     #[arg(short = 'w', long, value_enum)]
     pub wrap: Option<WrapMode>,
+    /// Format of the output. Machine readable formats print the responses, tool calls and
+    /// request ids as JSON to stdout
+    #[arg(long, value_enum, requires = "no_interactive")]
+    pub output_format: Option<ChatOutputFormat>,
 }
 
 impl ChatArgs {
     pub async fn execute(mut self, os: &mut Os) -> Result<ExitCode> {
+        if let Some(ChatSubcommand::Sessions(subcommand)) = self.subcommand {
+            return subcommand.execute(os).await;
+        }
+
+        if self.resume == Some(None) {
+            if let Some(id) = sessions::take_session_id_from_input(os, &mut self.input)? {
+                self.resume = Some(Some(id));
+            }
+        }
+
         let mut input = self.input;
 
         if self.no_interactive && input.is_none() {
@@ -274,6 +351,21 @@ impl ChatArgs {
             )?;
         }
 
+        let resumed_conversation = match self.resume.take() {
+            Some(Some(id)) if id == RESUME_PICKER => {
+                if self.no_interactive {
+                    bail!("--resume={RESUME_PICKER} cannot be used in non-interactive mode");
+                }
+                sessions::pick_session(os)?
+            },
+            Some(Some(id)) => {
+                let session = sessions::find_session(os, &id)?;
+                Some(sessions::load_session(os, &session.id)?)
+            },
+            Some(None) => os.database.get_latest_session_by_path(os.env.current_dir()?)?,
+            None => None,
+        };
+
         let conversation_id = uuid::Uuid::new_v4().to_string();
         info!(?conversation_id, "Generated new conversation id");
 
@@ -422,7 +514,7 @@ impl ChatArgs {
             agents,
             input,
             InputSource::new(os, prompt_request_sender, prompt_response_receiver)?,
-            self.resume,
+            resumed_conversation,
             || terminal::window_size().map(|s| s.columns.into()).ok(),
             tool_manager,
             model_id,
@@ -430,6 +522,7 @@ impl ChatArgs {
             !self.no_interactive,
             mcp_enabled,
             self.wrap,
+            self.output_format.unwrap_or_default(),
         )
         .await?
         .spawn(os)
@@ -521,6 +614,22 @@ enum ToolUseStatus {
     RetryInProgress(String),
 }
 
+/// A tool use that was run ahead of time as part of a batch of concurrent read-only tool uses.
+enum PrefetchedToolUse {
+    /// The tool use was denied by a preToolUse hook
+    Denied(ToolUseResult),
+    /// The tool use was allowed by its preToolUse hooks, but a hook rewrote it into one that is not
+    /// read-only, so it is run on its own and ends the batch
+    Allowed,
+    Invoked {
+        /// Output written by the tool, shown once the preceding tool uses are done
+        output: Vec<u8>,
+        result: Result<InvokeOutput>,
+        start: Instant,
+        end: Instant,
+    },
+}
+
 #[derive(Debug, Error)]
 pub enum ChatError {
     #[error("{0}")]
@@ -609,7 +718,7 @@ impl From<parser::RecvError> for ChatError {
 
 pub struct ChatSession {
     /// For output read by humans and machine
-    pub stdout: std::io::Stdout,
+    pub stdout: ChatOutput,
     /// For display output, only read by humans
     pub stderr: std::io::Stderr,
     initial_input: Option<String>,
@@ -644,6 +753,18 @@ pub struct ChatSession {
     inner: Option<ChatState>,
     ctrlc_rx: broadcast::Receiver<()>,
     wrap: Option<WrapMode>,
+    /// Number of times in a row the assistant continued its response because a
+    /// [HookTrigger::Stop] hook blocked it from finishing.
+    stop_hook_continuations: usize,
+    /// Number of mcp tool calls in flight. Such calls handle ctrl+c themselves by cancelling the
+    /// request, so the tool turn must not be abandoned while any is running.
+    mcp_calls_in_flight: Arc<AtomicUsize>,
+    /// Collects the events printed as JSON when a machine readable [ChatOutputFormat] is selected.
+    json_output: Option<JsonOutput>,
+    /// Code references returned with the latest response.
+    response_code_references: Vec<CodeReference>,
+    /// Shell running the commands of execute_bash when the agent enables `persistentShell`.
+    shell_session: ShellSession,
 }
 
 impl ChatSession {
@@ -656,7 +777,7 @@ impl ChatSession {
         mut agents: Agents,
         mut input: Option<String>,
         input_source: InputSource,
-        resume_conversation: bool,
+        resumed_conversation: Option<ConversationState>,
         terminal_width_provider: fn() -> Option<usize>,
         tool_manager: ToolManager,
         model_id: Option<String>,
@@ -664,23 +785,13 @@ impl ChatSession {
         interactive: bool,
         mcp_enabled: bool,
         wrap: Option<WrapMode>,
+        output_format: ChatOutputFormat,
     ) -> Result<Self> {
-        // Reload prior conversation
-        let mut existing_conversation = false;
-        let previous_conversation = std::env::current_dir()
-            .ok()
-            .and_then(|cwd| os.database.get_conversation_by_path(cwd).ok())
-            .flatten();
-
         // Only restore conversations where there were actual messages.
         // Prevents edge case where user clears conversation then exits without chatting.
-        let conversation = match resume_conversation
-            && previous_conversation
-                .as_ref()
-                .is_some_and(|cs| !cs.history().is_empty())
-        {
-            true => {
-                let mut cs = previous_conversation.unwrap();
+        let mut existing_conversation = false;
+        let conversation = match resumed_conversation.filter(|cs| !cs.history().is_empty()) {
+            Some(mut cs) => {
                 existing_conversation = true;
                 input = Some(input.unwrap_or("In a few words, summarize our conversation so far.".to_owned()));
                 cs.tool_manager = tool_manager;
@@ -704,7 +815,7 @@ impl ChatSession {
                 cs.enforce_tool_use_history_invariants();
                 cs
             },
-            false => {
+            None => {
                 ConversationState::new(
                     conversation_id,
                     agents,
@@ -735,8 +846,12 @@ impl ChatSession {
             }
         });
 
+        let json_output = JsonOutput::new(output_format);
         Ok(Self {
-            stdout,
+            stdout: match json_output {
+                Some(_) => ChatOutput::Discard,
+                None => ChatOutput::Stdout(stdout),
+            },
             stderr,
             initial_input: input,
             existing_conversation,
@@ -756,6 +871,11 @@ impl ChatSession {
             inner: Some(ChatState::default()),
             ctrlc_rx,
             wrap,
+            stop_hook_continuations: 0,
+            mcp_calls_in_flight: Arc::new(AtomicUsize::new(0)),
+            json_output,
+            response_code_references: Vec::new(),
+            shell_session: ShellSession::default(),
         })
     }
 
@@ -795,9 +915,20 @@ impl ChatSession {
             },
             ChatState::ExecuteTools => {
                 let tool_uses_clone = self.tool_uses.clone();
-                tokio::select! {
-                    res = self.tool_use_execute(os) => res,
-                    Ok(_) = ctrl_c_stream.recv() => Err(ChatError::Interrupted { tool_uses: Some(tool_uses_clone) })
+                let mcp_calls_in_flight = Arc::clone(&self.mcp_calls_in_flight);
+                let execute = self.tool_use_execute(os);
+                tokio::pin!(execute);
+                loop {
+                    tokio::select! {
+                        res = &mut execute => break res,
+                        Ok(_) = ctrl_c_stream.recv() => {
+                            // The mcp calls in flight receive the same ctrl+c and cancel themselves
+                            if mcp_calls_in_flight.load(Ordering::SeqCst) > 0 {
+                                continue;
+                            }
+                            break Err(ChatError::Interrupted { tool_uses: Some(tool_uses_clone) });
+                        }
+                    }
                 }
             },
             ChatState::ValidateTools { tool_uses } => {
@@ -1067,6 +1198,7 @@ impl ChatSession {
 
                     return Ok(());
                 },
+                ApiClientError::Replay(_) => ("Failed to replay the recorded response", Report::from(err), true),
                 _ => (
                     "Amazon Q is having trouble responding right now",
                     Report::from(err),
@@ -1080,6 +1212,10 @@ impl ChatSession {
             ),
         };
 
+        if let Some(json_output) = self.json_output.as_mut() {
+            json_output.error(format!("{context}: {report}"));
+        }
+
         if display_err_message {
             // Remove non-ASCII and ANSI characters.
             let re = Regex::new(r"((\x9B|\x1B\[)[0-?]*[ -\/]*[@-~])|([^\x00-\x7F]+)").unwrap();
@@ -1214,6 +1350,12 @@ impl ChatSession {
         request_metadata_lock: Arc<Mutex<Option<RequestMetadata>>>,
         message_meta_tags: Option<Vec<MessageMetaTag>>,
     ) -> Result<SendMessageStream, ChatError> {
+        // Sampling requests from mcp servers are answered with the model in use by the conversation
+        self.conversation
+            .tool_manager
+            .set_sampling_model(conversation_state.user_input_message.model_id.clone());
+        self.conversation.update_mcp_roots(os);
+
         match SendMessageStream::send_message(&os.client, conversation_state, request_metadata_lock, message_meta_tags)
             .await
         {
@@ -1325,13 +1467,50 @@ impl ChatSession {
             self.inner = Some(ChatState::HandleInput { input: user_input });
         }
 
+        if let Some(json_output) = self.json_output.as_mut() {
+            json_output.init(
+                self.conversation.conversation_id(),
+                self.conversation.model_info.as_ref().map(|m| m.model_id.as_str()),
+            );
+        }
+
         while !matches!(self.inner, Some(ChatState::Exit)) {
-            self.next(os).await?;
+            if let Err(err) = self.next(os).await {
+                if let Some(json_output) = self.json_output.as_mut() {
+                    json_output.error(err.to_string());
+                }
+                self.finish_json_output(os).await;
+                return Err(err.into());
+            }
         }
 
+        self.finish_json_output(os).await;
+
         Ok(())
     }
 
+    /// Prints the final result of the session when a machine readable [ChatOutputFormat] is
+    /// selected.
+    async fn finish_json_output(&mut self, os: &Os) {
+        if self.json_output.is_none() {
+            return;
+        }
+        let conversation_size = match self
+            .conversation
+            .backend_conversation_state(os, false, &mut vec![])
+            .await
+        {
+            Ok(state) => state.calculate_conversation_size(),
+            Err(err) => {
+                error!(?err, "failed to calculate the conversation size");
+                return;
+            },
+        };
+        if let Some(json_output) = self.json_output.as_mut() {
+            json_output.finish(conversation_size);
+        }
+    }
+
     /// Compacts the conversation history using the strategy specified by [CompactStrategy],
     /// replacing the history with a summary generated by the model.
     ///
@@ -1920,7 +2099,11 @@ impl ChatSession {
             style::SetAttribute(Attribute::Reset)
         )?;
         let prompt = self.generate_tool_trust_prompt();
-        let user_input = match self.read_user_input(&prompt, false) {
+        // Watched knowledge contexts stop re-indexing if the user stays away from the prompt
+        KnowledgeStore::set_session_idle(true).await;
+        let user_input = self.read_user_input(&prompt, false);
+        KnowledgeStore::set_session_idle(false).await;
+        let user_input = match user_input {
             Some(input) => input,
             None => return Ok(ChatState::Exit),
         };
@@ -2135,8 +2318,10 @@ impl ChatSession {
                 } else {
                     user_input
                 };
+                self.conversation.begin_checkpoint(&user_input);
                 self.conversation.abandon_tool_use(&self.tool_uses, user_input);
             } else {
+                self.conversation.begin_checkpoint(&user_input);
                 self.conversation.set_next_user_message(user_input).await;
             }
 
@@ -2265,8 +2450,85 @@ impl ChatSession {
         let mut tool_results = vec![];
         let mut image_blocks: Vec<RichImageBlock> = Vec::new();
 
-        for tool in &self.tool_uses {
-            let tool_start = std::time::Instant::now();
+        // Consecutive read-only tool uses are run ahead of time, concurrently. Their outputs are
+        // buffered and shown in order below. Other tool uses are run one at a time so that
+        // mutations keep their ordering.
+        let mut prefetched = HashMap::<usize, PrefetchedToolUse>::new();
+
+        for i in 0..self.tool_uses.len() {
+            if !prefetched.contains_key(&i) {
+                let batch_end = (i..self.tool_uses.len())
+                    .find(|&j| !self.runs_concurrently(os, j))
+                    .unwrap_or(self.tool_uses.len());
+                if batch_end - i > 1 {
+                    prefetched.extend(self.invoke_concurrently(os, i..batch_end).await?);
+                }
+            }
+
+            let (invoke_result, tool_start, tool_end_time) = match prefetched.remove(&i) {
+                Some(PrefetchedToolUse::Denied(denied)) => {
+                    tool_results.push(denied);
+                    continue;
+                },
+                Some(PrefetchedToolUse::Invoked {
+                    output,
+                    result,
+                    start,
+                    end,
+                }) => {
+                    self.stdout.write_all(&output)?;
+                    (result, start, end)
+                },
+                hooks_ran @ (None | Some(PrefetchedToolUse::Allowed)) => {
+                    if hooks_ran.is_none() {
+                        if let Some(denied) = self.run_pre_tool_use_hooks(os, i).await? {
+                            tool_results.push(denied);
+                            continue;
+                        }
+                    }
+                    if let Some(denied) = self.recheck_shell_command(os, i).await? {
+                        tool_results.push(denied);
+                        continue;
+                    }
+
+                    let tool = &self.tool_uses[i];
+                    let mut before_write = None;
+                    if let Tool::FsWrite(fs_write) = &tool.tool {
+                        if let Err(err) = self.conversation.checkpoints.snapshot(os, fs_write.path(os)).await {
+                            warn!(?err, "failed to snapshot the file before writing to it");
+                        }
+                        before_write = Some(os.fs.read_to_string(fs_write.path(os)).await.ok());
+                    }
+                    let start = Instant::now();
+                    // Subscribed before the call counts as in flight, so that no ctrl+c ignored by
+                    // the session is missed by the call
+                    let ctrl_c = self.ctrlc_rx.resubscribe();
+                    let _mcp_call = McpCallGuard::new(&tool.tool, &self.mcp_calls_in_flight);
+                    let result = tool
+                        .tool
+                        .invoke(
+                            os,
+                            &mut self.stdout,
+                            &mut self.conversation.file_line_tracker,
+                            self.conversation.agents.get_active(),
+                            &mut self.shell_session,
+                            ctrl_c,
+                        )
+                        .await;
+                    let end = Instant::now();
+                    if let (Tool::FsWrite(fs_write), Some(before), Ok(_)) = (&tool.tool, before_write, &result) {
+                        match fs_write.diff(os, before.as_deref()).await {
+                            Ok(diff) => {
+                                self.conversation.file_diffs.insert(tool.id.clone(), diff);
+                            },
+                            Err(err) => warn!(?err, "failed to diff the file written"),
+                        }
+                    }
+                    (result, start, end)
+                },
+            };
+
+            let tool = &self.tool_uses[i];
             let mut tool_telemetry = self.tool_use_telemetry_events.entry(tool.id.clone());
             tool_telemetry = tool_telemetry.and_modify(|ev| {
                 ev.is_accepted = true;
@@ -2284,16 +2546,6 @@ impl ChatSession {
                 }
             }
 
-            let invoke_result = tool
-                .tool
-                .invoke(
-                    os,
-                    &mut self.stdout,
-                    &mut self.conversation.file_line_tracker,
-                    self.conversation.agents.get_active(),
-                )
-                .await;
-
             if self.spinner.is_some() {
                 queue!(
                     self.stderr,
@@ -2304,7 +2556,6 @@ impl ChatSession {
             }
             execute!(self.stdout, style::Print("\n"))?;
 
-            let tool_end_time = Instant::now();
             let tool_time = tool_end_time.duration_since(tool_start);
             tool_telemetry = tool_telemetry.and_modify(|ev| {
                 ev.execution_duration = Some(tool_time);
@@ -2424,6 +2675,14 @@ impl ChatSession {
                     }
                 },
             }
+
+            if let Some(result) = tool_results.last_mut() {
+                self.run_post_tool_use_hooks(i, result).await?;
+            }
+        }
+
+        if let Some(json_output) = self.json_output.as_mut() {
+            json_output.tool_results(&tool_results);
         }
 
         if !image_blocks.is_empty() {
@@ -2495,6 +2754,10 @@ impl ChatSession {
 
         let mut tool_uses = Vec::new();
         let mut tool_name_being_recvd: Option<String> = None;
+        let mut web_links = Vec::new();
+        let mut followup_prompts = Vec::new();
+        let mut message_id = None;
+        self.response_code_references.clear();
 
         if self.spinner.is_some() {
             drop(self.spinner.take());
@@ -2544,6 +2807,11 @@ impl ChatSession {
                             tool_uses.push(tool_use);
                             tool_name_being_recvd = None;
                         },
+                        parser::ResponseEvent::CodeReferences(references) => {
+                            self.response_code_references.extend(references);
+                        },
+                        parser::ResponseEvent::WebLinks(links) => web_links.extend(links),
+                        parser::ResponseEvent::FollowupPrompt(prompt) => followup_prompts.push(prompt),
                         parser::ResponseEvent::EndStream {
                             message,
                             request_metadata: rm,
@@ -2553,6 +2821,10 @@ impl ChatSession {
                             if message.content() == RESPONSE_TIMEOUT_CONTENT {
                                 error!(?request_id, ?message, "Encountered an unexpected model response");
                             }
+                            if let Some(json_output) = self.json_output.as_mut() {
+                                json_output.assistant_message(&message, &rm);
+                            }
+                            message_id = message.message_id().map(str::to_string);
                             self.conversation.push_assistant_message(os, message, Some(rm.clone()));
                             self.user_turn_request_metadata.push(rm);
                             ended = true;
@@ -2741,6 +3013,18 @@ impl ChatSession {
                     )?;
                 }
 
+                self.print_response_supplements(&web_links, &followup_prompts)?;
+                if let Err(err) = code_reference::log_code_references(
+                    os,
+                    self.conversation.conversation_id(),
+                    message_id.as_deref(),
+                    &self.response_code_references,
+                )
+                .await
+                {
+                    warn!(?err, "failed to write to the code reference log");
+                }
+
                 break;
             }
         }
@@ -2748,6 +3032,25 @@ impl ChatSession {
         if !tool_uses.is_empty() {
             Ok(ChatState::ValidateTools { tool_uses })
         } else {
+            if let Some(reason) = self.run_stop_hooks(buf.trim()).await? {
+                self.send_chat_telemetry(os, TelemetryResult::Succeeded, None, None, None, false)
+                    .await;
+                self.conversation
+                    .set_next_user_message(format!(
+                        "A stop hook asked you to continue before finishing your response: {reason}"
+                    ))
+                    .await;
+                if self.interactive {
+                    execute!(self.stderr, cursor::Hide)?;
+                    self.spinner = Some(Spinner::new(Spinners::Dots, "Thinking...".to_string()));
+                }
+                return Ok(ChatState::HandleResponseStream(
+                    self.conversation
+                        .as_sendable_conversation_state(os, &mut self.stderr, false)
+                        .await?,
+                ));
+            }
+
             self.tool_uses.clear();
             self.pending_tool_index = None;
             self.tool_turn_start_time = None;
@@ -2761,6 +3064,57 @@ impl ChatSession {
         }
     }
 
+    /// Prints the code references, related links and suggested follow-up prompts returned along
+    /// with the response.
+    fn print_response_supplements(
+        &mut self,
+        web_links: &[SupplementaryWebLink],
+        followup_prompts: &[FollowupPrompt],
+    ) -> Result<(), ChatError> {
+        if !self.response_code_references.is_empty() {
+            queue!(
+                self.stdout,
+                style::SetForegroundColor(Color::Yellow),
+                style::Print("\nThis response references licensed code:\n"),
+                style::SetForegroundColor(Color::DarkGrey),
+            )?;
+            for reference in &self.response_code_references {
+                queue!(
+                    self.stdout,
+                    style::Print(format!("  - {}\n", code_reference::describe_reference(reference)))
+                )?;
+            }
+            queue!(self.stdout, style::SetForegroundColor(Color::Reset))?;
+        }
+
+        if !web_links.is_empty() {
+            queue!(self.stdout, style::Print("\nSources:\n"))?;
+            for link in web_links {
+                queue!(
+                    self.stdout,
+                    style::Print(format!("  - {} ", link.title)),
+                    style::SetForegroundColor(Color::DarkGrey),
+                    style::Print(format!("{}\n", link.url)),
+                    style::SetForegroundColor(Color::Reset),
+                )?;
+            }
+        }
+
+        if !followup_prompts.is_empty() {
+            queue!(self.stdout, style::Print("\nSuggested follow-ups:\n"))?;
+            for prompt in followup_prompts {
+                queue!(
+                    self.stdout,
+                    style::SetForegroundColor(Color::DarkGrey),
+                    style::Print(format!("  - {}\n", prompt.content)),
+                    style::SetForegroundColor(Color::Reset),
+                )?;
+            }
+        }
+
+        Ok(self.stdout.flush()?)
+    }
+
     async fn validate_tools(&mut self, os: &Os, tool_uses: Vec<AssistantToolUse>) -> Result<ChatState, ChatError> {
         let conv_id = self.conversation.conversation_id().to_owned();
         debug!(?tool_uses, "Validating tool uses");
@@ -2770,6 +3124,7 @@ impl ChatSession {
         for tool_use in tool_uses {
             let tool_use_id = tool_use.id.clone();
             let tool_use_name = tool_use.name.clone();
+            let tool_use_input = tool_use.args.clone();
             let mut tool_telemetry = ToolUseEventBuilder::new(
                 conv_id.clone(),
                 tool_use.id.clone(),
@@ -2778,6 +3133,27 @@ impl ChatSession {
             .set_tool_use_id(tool_use_id.clone())
             .set_tool_name(tool_use.name.clone())
             .utterance_id(self.conversation.message_id().map(|s| s.to_string()));
+            if tool_use.name == "fs_write"
+                && !self.response_code_references.is_empty()
+                && os
+                    .database
+                    .settings
+                    .get_bool(Setting::ChatBlockReferencedCode)
+                    .unwrap_or(false)
+            {
+                tool_telemetry.is_valid = Some(false);
+                tool_results.push(ToolUseResult {
+                    tool_use_id: tool_use_id.clone(),
+                    content: vec![ToolUseResultBlock::Text(
+                        "The file was not written because the response contains code matching licensed code, which \
+                         chat.blockReferencedCode does not allow. Write an original implementation instead."
+                            .to_string(),
+                    )],
+                    status: ToolResultStatus::Error,
+                });
+                self.tool_use_telemetry_events.insert(tool_use_id, tool_telemetry);
+                continue;
+            }
             match self.conversation.tool_manager.get_tool_from_tool_use(tool_use).await {
                 Ok(mut tool) => {
                     // Apply non-Q-generated context to tools
@@ -2791,6 +3167,7 @@ impl ChatSession {
                                 name: tool_use_name,
                                 tool,
                                 accepted: false,
+                                input: tool_use_input,
                             });
                         },
                         Err(err) => {
@@ -2843,6 +3220,9 @@ impl ChatSession {
                 }
             }
 
+            if let Some(json_output) = self.json_output.as_mut() {
+                json_output.tool_results(&tool_results);
+            }
             self.conversation.add_tool_results(tool_results);
             self.send_chat_telemetry(os, TelemetryResult::Succeeded, None, None, None, false)
                 .await;
@@ -2896,6 +3276,367 @@ impl ChatSession {
         ))
     }
 
+    /// Runs the [HookTrigger::PreToolUse] hooks for the tool at `tool_index`. If a hook rewrites
+    /// the tool input, the queued tool is replaced with one built from the new input, which the
+    /// agent must allow or the user must approve again.
+    ///
+    /// Returns the result to send back to the model if the tool use should not be executed.
+    async fn run_pre_tool_use_hooks(&mut self, os: &Os, tool_index: usize) -> Result<Option<ToolUseResult>, ChatError> {
+        let Some(cm) = self.conversation.context_manager.as_mut() else {
+            return Ok(None);
+        };
+        if !cm.hooks.contains_key(&HookTrigger::PreToolUse) {
+            return Ok(None);
+        }
+
+        let tool = &self.tool_uses[tool_index];
+        let payload = HookPayload::tool_use(hook_tool_name(tool), tool.input.clone());
+        let results = cm
+            .run_hooks(HookTrigger::PreToolUse, &mut self.stderr, None, Some(&payload))
+            .await?;
+
+        let tool_use_id = tool.id.clone();
+        let denied_result = |reason: String| ToolUseResult {
+            tool_use_id: tool_use_id.clone(),
+            content: vec![ToolUseResultBlock::Text(reason)],
+            status: ToolResultStatus::Error,
+        };
+
+        match combine_decisions(&results) {
+            HookDecision::Allow => Ok(None),
+            HookDecision::Deny { reason } => {
+                execute!(
+                    self.stderr,
+                    style::SetForegroundColor(Color::Red),
+                    style::Print("Tool use "),
+                    style::SetForegroundColor(Color::Yellow),
+                    style::Print(&tool.name),
+                    style::SetForegroundColor(Color::Red),
+                    style::Print(" was denied by a preToolUse hook: "),
+                    style::SetForegroundColor(Color::Reset),
+                    style::Print(format!("{reason}\n\n")),
+                )?;
+                Ok(Some(denied_result(format!(
+                    "Tool use was denied by a preToolUse hook: {reason}"
+                ))))
+            },
+            HookDecision::Modify { tool_input } => {
+                let tool_use = AssistantToolUse {
+                    id: tool.id.clone(),
+                    name: tool.name.clone(),
+                    orig_name: tool.name.clone(),
+                    args: tool_input.clone(),
+                    orig_args: tool_input.clone(),
+                };
+                let mut new_tool = match self.conversation.tool_manager.get_tool_from_tool_use(tool_use).await {
+                    Ok(new_tool) => new_tool,
+                    Err(err) => return Ok(Some(err.into())),
+                };
+                self.contextualize_tool(&mut new_tool);
+                if let Err(err) = new_tool.validate(os).await {
+                    return Ok(Some(denied_result(format!(
+                        "The tool input rewritten by a preToolUse hook is invalid: {err}"
+                    ))));
+                }
+
+                execute!(
+                    self.stderr,
+                    style::SetForegroundColor(Color::DarkGrey),
+                    style::Print("Tool input was modified by a preToolUse hook\n"),
+                    style::SetForegroundColor(Color::Reset),
+                )?;
+                let queued = &mut self.tool_uses[tool_index];
+                queued.tool = new_tool;
+                queued.input = tool_input;
+
+                // Approving the original input does not approve the rewritten one
+                match self.tool_permission(os, tool_index) {
+                    PermissionEvalResult::Allow => Ok(None),
+                    PermissionEvalResult::Deny(rules) => {
+                        execute!(
+                            self.stderr,
+                            style::SetForegroundColor(Color::Red),
+                            style::Print(
+                                "The tool input rewritten by a preToolUse hook matches rules on the denied list: "
+                            ),
+                            style::SetForegroundColor(Color::Reset),
+                            style::Print(format!("{}\n\n", rules.join(", "))),
+                        )?;
+                        Ok(Some(denied_result(
+                            "The tool input rewritten by a preToolUse hook was rejected because it matches rules on the denied list".to_string(),
+                        )))
+                    },
+                    PermissionEvalResult::Ask => {
+                        if self
+                            .confirm_tool_use(os, tool_index, "Allow this modified action?")
+                            .await?
+                        {
+                            Ok(None)
+                        } else {
+                            Ok(Some(denied_result(
+                                "The user denied the tool input rewritten by a preToolUse hook".to_string(),
+                            )))
+                        }
+                    },
+                }
+            },
+        }
+    }
+
+    /// Evaluates whether the active agent lets the tool at `tool_index` run without asking the
+    /// user. Denied rules win over trusting all tools.
+    fn tool_permission(&self, os: &Os, tool_index: usize) -> PermissionEvalResult {
+        let tool = &self.tool_uses[tool_index].tool;
+        match self
+            .conversation
+            .agents
+            .get_active()
+            .map(|agent| tool.requires_acceptance(os, agent))
+        {
+            Some(PermissionEvalResult::Deny(rules)) => PermissionEvalResult::Deny(rules),
+            Some(PermissionEvalResult::Allow) => PermissionEvalResult::Allow,
+            _ if self.conversation.agents.trust_all_tools => PermissionEvalResult::Allow,
+            _ => PermissionEvalResult::Ask,
+        }
+    }
+
+    /// Asks the user to approve the tool at `tool_index` again while the tools are running, e.g.
+    /// after a preToolUse hook rewrote its input. Non-interactive sessions cannot approve it.
+    async fn confirm_tool_use(&mut self, os: &Os, tool_index: usize, question: &str) -> Result<bool, ChatError> {
+        if !self.interactive {
+            return Ok(false);
+        }
+
+        self.print_tool_description(os, tool_index, false).await?;
+        execute!(
+            self.stderr,
+            style::SetForegroundColor(Color::DarkGrey),
+            style::Print(format!("\n{question} [")),
+            style::SetForegroundColor(Color::Green),
+            style::Print("y"),
+            style::SetForegroundColor(Color::DarkGrey),
+            style::Print("/"),
+            style::SetForegroundColor(Color::Green),
+            style::Print("n"),
+            style::SetForegroundColor(Color::DarkGrey),
+            style::Print("]:\n\n"),
+            style::SetForegroundColor(Color::Reset),
+        )?;
+
+        let prompt = self.generate_tool_trust_prompt();
+        Ok(self
+            .read_user_input(&prompt, true)
+            .is_some_and(|input| ["y", "Y"].contains(&input.trim())))
+    }
+
+    /// Evaluates the permission of an `execute_bash` tool use again if the persistent shell started
+    /// overriding commands after it was evaluated, e.g. because an earlier command of the same
+    /// response defined a function. Returns the result to send instead of running it if the user
+    /// does not approve it.
+    async fn recheck_shell_command(&mut self, os: &Os, tool_index: usize) -> Result<Option<ToolUseResult>, ChatError> {
+        let overrides_commands = self.shell_session.overrides_commands();
+        let Tool::ExecuteCommand(execute_command) = &mut self.tool_uses[tool_index].tool else {
+            return Ok(None);
+        };
+        if execute_command.shell_overrides_commands || !overrides_commands {
+            return Ok(None);
+        }
+        execute_command.shell_overrides_commands = true;
+
+        let question = "The shell now overrides commands with functions, aliases or PATH. Allow this action?";
+        let approved = match self.tool_permission(os, tool_index) {
+            PermissionEvalResult::Allow => true,
+            PermissionEvalResult::Deny(_) => false,
+            PermissionEvalResult::Ask => self.confirm_tool_use(os, tool_index, question).await?,
+        };
+        Ok((!approved).then(|| ToolUseResult {
+            tool_use_id: self.tool_uses[tool_index].id.clone(),
+            content: vec![ToolUseResultBlock::Text(
+                "The command was not run because the user did not approve it after the shell started overriding commands"
+                    .to_string(),
+            )],
+            status: ToolResultStatus::Error,
+        }))
+    }
+
+    /// Whether a tool use can run concurrently with others, which is the case for read-only tools
+    /// that are allowed without prompting the user.
+    fn runs_concurrently(&self, os: &Os, tool_index: usize) -> bool {
+        let tool = &self.tool_uses[tool_index].tool;
+        tool.is_read_only()
+            && (self.conversation.agents.trust_all_tools
+                || self
+                    .conversation
+                    .agents
+                    .get_active()
+                    .is_some_and(|agent| matches!(tool.requires_acceptance(os, agent), PermissionEvalResult::Allow)))
+    }
+
+    /// Runs the preToolUse hooks of a batch of tool uses, then invokes the ones that were not
+    /// denied concurrently, up to `chat.maxConcurrentTools` at a time. Outputs are buffered so
+    /// they can be shown in order.
+    ///
+    /// The batch ends early at a tool use that a hook rewrote into one that is not read-only.
+    async fn invoke_concurrently(
+        &mut self,
+        os: &Os,
+        batch: std::ops::Range<usize>,
+    ) -> Result<Vec<(usize, PrefetchedToolUse)>, ChatError> {
+        let mut outcomes = Vec::new();
+        let mut to_invoke = Vec::new();
+        for i in batch {
+            match self.run_pre_tool_use_hooks(os, i).await? {
+                Some(denied) => outcomes.push((i, PrefetchedToolUse::Denied(denied))),
+                None if !self.tool_uses[i].tool.is_read_only() => {
+                    outcomes.push((i, PrefetchedToolUse::Allowed));
+                    break;
+                },
+                None => to_invoke.push(i),
+            }
+        }
+
+        let limit = max_concurrent_tools(os.database.settings.get_int(Setting::ChatMaxConcurrentTools));
+        let tool_uses = &self.tool_uses;
+        let agent = self.conversation.agents.get_active();
+        let mcp_calls_in_flight = &self.mcp_calls_in_flight;
+        let ctrlc_rx = &self.ctrlc_rx;
+        let stdout = &self.stdout;
+
+        let invoked = buffered_in_order(
+            to_invoke.into_iter().map(|i| async move {
+                let mut output = Vec::new();
+                let tool = &tool_uses[i].tool;
+                let start = Instant::now();
+                let ctrl_c = ctrlc_rx.resubscribe();
+                let _mcp_call = McpCallGuard::new(tool, mcp_calls_in_flight);
+                // Read-only tools do not track file lines or use the shell session. mcp tools only
+                // write their progress, which is shown live rather than once they are done.
+                let result = match tool {
+                    Tool::Custom(_) => {
+                        tool.invoke(
+                            os,
+                            &mut stdout.handle(),
+                            &mut HashMap::new(),
+                            agent,
+                            &mut ShellSession::default(),
+                            ctrl_c,
+                        )
+                        .await
+                    },
+                    _ => {
+                        tool.invoke(
+                            os,
+                            &mut output,
+                            &mut HashMap::new(),
+                            agent,
+                            &mut ShellSession::default(),
+                            ctrl_c,
+                        )
+                        .await
+                    },
+                };
+                (i, PrefetchedToolUse::Invoked {
+                    output,
+                    result,
+                    start,
+                    end: Instant::now(),
+                })
+            }),
+            limit,
+        )
+        .await;
+
+        outcomes.extend(invoked);
+        Ok(outcomes)
+    }
+
+    /// Runs the [HookTrigger::PostToolUse] hooks for the tool at `tool_index`. Reasons given by
+    /// hooks that deny the result are appended to it so that the model can act on them.
+    async fn run_post_tool_use_hooks(
+        &mut self,
+        tool_index: usize,
+        result: &mut ToolUseResult,
+    ) -> Result<(), ChatError> {
+        let Some(cm) = self.conversation.context_manager.as_mut() else {
+            return Ok(());
+        };
+        if !cm.hooks.contains_key(&HookTrigger::PostToolUse) {
+            return Ok(());
+        }
+
+        let tool = &self.tool_uses[tool_index];
+        let tool_response = result
+            .content
+            .iter()
+            .map(|block| match block {
+                ToolUseResultBlock::Text(text) => serde_json::Value::String(text.clone()),
+                ToolUseResultBlock::Json(json) => json.clone(),
+            })
+            .collect::<Vec<_>>();
+        let payload = HookPayload {
+            tool_response: Some(serde_json::json!({
+                "success": matches!(result.status, ToolResultStatus::Success),
+                "content": tool_response,
+            })),
+            ..HookPayload::tool_use(hook_tool_name(tool), tool.input.clone())
+        };
+        let results = cm
+            .run_hooks(HookTrigger::PostToolUse, &mut self.stderr, None, Some(&payload))
+            .await?;
+
+        if let HookDecision::Deny { reason } = combine_decisions(&results) {
+            result.content.push(ToolUseResultBlock::Text(format!(
+                "Feedback from a postToolUse hook: {reason}"
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Runs the [HookTrigger::Stop] hooks once the assistant has finished responding.
+    ///
+    /// Returns the reason a hook gave for the assistant to keep going, if any.
+    async fn run_stop_hooks(&mut self, assistant_response: &str) -> Result<Option<String>, ChatError> {
+        let Some(cm) = self.conversation.context_manager.as_mut() else {
+            return Ok(None);
+        };
+        if !cm.hooks.contains_key(&HookTrigger::Stop) {
+            return Ok(None);
+        }
+
+        let payload = HookPayload {
+            assistant_response: Some(assistant_response.to_string()),
+            stop_hook_active: self.stop_hook_continuations > 0,
+            ..Default::default()
+        };
+        let results = cm
+            .run_hooks(HookTrigger::Stop, &mut self.stderr, None, Some(&payload))
+            .await?;
+
+        match combine_decisions(&results) {
+            HookDecision::Deny { .. } if self.stop_hook_continuations >= MAX_STOP_HOOK_CONTINUATIONS => {
+                execute!(
+                    self.stderr,
+                    style::SetForegroundColor(Color::Yellow),
+                    style::Print(format!(
+                        "Stop hooks blocked the response {MAX_STOP_HOOK_CONTINUATIONS} times in a row, finishing anyway\n"
+                    )),
+                    style::SetForegroundColor(Color::Reset),
+                )?;
+                self.stop_hook_continuations = 0;
+                Ok(None)
+            },
+            HookDecision::Deny { reason } => {
+                self.stop_hook_continuations += 1;
+                Ok(Some(reason))
+            },
+            HookDecision::Allow | HookDecision::Modify { .. } => {
+                self.stop_hook_continuations = 0;
+                Ok(None)
+            },
+        }
+    }
+
     /// Apply program context to tools that Q may not have.
     // We cannot attach this any other way because Tools are constructed by deserializing
     // output from Amazon Q.
@@ -2918,6 +3659,9 @@ impl ChatSession {
                 tool_permissions: allowed_tools,
             });
         }
+        if let Tool::ExecuteCommand(execute_command) = tool {
+            execute_command.shell_overrides_commands = self.shell_session.overrides_commands();
+        }
     }
 
     async fn print_tool_description(&mut self, os: &Os, tool_index: usize, trusted: bool) -> Result<(), ChatError> {
@@ -3054,6 +3798,7 @@ impl ChatSession {
     fn reset_user_turn(&mut self) {
         info!(?self.user_turn_request_metadata, "Resetting the current user turn");
         self.user_turn_request_metadata.clear();
+        self.stop_hook_continuations = 0;
     }
 
     /// Sends an "codewhispererterminal_addChatMessage" telemetry event.
@@ -3230,6 +3975,15 @@ impl ChatSession {
     }
 }
 
+/// The name of a queued tool as used in hook matchers, e.g. `fs_write` or `@server/tool` for MCP
+/// tools.
+fn hook_tool_name(tool: &QueuedTool) -> String {
+    match &tool.tool {
+        Tool::Custom(ct) => format!("@{}{MCP_SERVER_TOOL_DELIMITER}{}", ct.server_name, ct.name),
+        _ => tool.name.clone(),
+    }
+}
+
 /// Replaces amzn_codewhisperer_client::types::SubscriptionStatus with a more descriptive type.
 /// See response expectations in [`get_subscription_status`] for reasoning.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -3310,6 +4064,49 @@ where
     result
 }
 
+/// Counts an mcp tool call as in flight until dropped, does nothing for other tools.
+struct McpCallGuard<'a>(Option<&'a AtomicUsize>);
+
+impl<'a> McpCallGuard<'a> {
+    fn new(tool: &Tool, in_flight: &'a AtomicUsize) -> Self {
+        if !matches!(tool, Tool::Custom(_)) {
+            return Self(None);
+        }
+        in_flight.fetch_add(1, Ordering::SeqCst);
+        Self(Some(in_flight))
+    }
+}
+
+impl Drop for McpCallGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(in_flight) = self.0 {
+            in_flight.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+/// The number of tool uses to run at a time for a `chat.maxConcurrentTools` setting. Values below
+/// 1 would never run a tool, so they run tools one at a time instead.
+fn max_concurrent_tools(setting: Option<i64>) -> usize {
+    match setting {
+        None => DEFAULT_MAX_CONCURRENT_TOOLS,
+        Some(value) if value >= 1 => usize::try_from(value).unwrap_or(usize::MAX),
+        Some(value) => {
+            warn!(
+                value,
+                "chat.maxConcurrentTools must be at least 1, running tools one at a time"
+            );
+            1
+        },
+    }
+}
+
+/// Runs `futures` with at most `limit` of them in flight at a time, returning their outputs in
+/// the order the futures were given.
+async fn buffered_in_order<F: Future>(futures: impl IntoIterator<Item = F>, limit: usize) -> Vec<F::Output> {
+    futures::stream::iter(futures).buffered(limit.max(1)).collect().await
+}
+
 /// Checks if an input may be referencing a file and should not be handled as a typical slash
 /// command. If true, then return [Option::Some<ChatState>], otherwise [Option::None].
 fn does_input_reference_file(input: &str) -> Option<ChatState> {
@@ -3401,7 +4198,7 @@ mod tests {
                 "y".to_string(),
                 "exit".to_string(),
             ]),
-            false,
+            None,
             || Some(80),
             tool_manager,
             None,
@@ -3409,6 +4206,7 @@ mod tests {
             true,
             false,
             None,
+            ChatOutputFormat::Plain,
         )
         .await
         .unwrap()
@@ -3544,7 +4342,7 @@ mod tests {
                 "n".to_string(),             // cancel
                 "exit".to_string(),
             ]),
-            false,
+            None,
             || Some(80),
             tool_manager,
             None,
@@ -3552,6 +4350,7 @@ mod tests {
             true,
             false,
             None,
+            ChatOutputFormat::Plain,
         )
         .await
         .unwrap()
@@ -3567,6 +4366,449 @@ mod tests {
         // assert!(!ctx.fs.exists("/file6.txt"));
     }
 
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_flow_modified_tool_input_requires_permission() {
+        use crate::cli::agent::ToolSettingTarget;
+        use crate::cli::agent::hook::{
+            Hook,
+            HookTrigger,
+            Source,
+        };
+
+        let mut os = Os::new().await.unwrap();
+        let write_file = |path: &str| {
+            serde_json::json!([
+                "Ok",
+                {
+                    "tool_use_id": "1",
+                    "name": "fs_write",
+                    "args": {
+                        "command": "create",
+                        "file_text": "Hello, world!",
+                        "path": path,
+                    }
+                }
+            ])
+        };
+        os.client.set_mock_output(serde_json::json!([
+            write_file("/allowed.txt"),
+            ["Done"],
+            write_file("/allowed.txt"),
+            ["Done"],
+        ]));
+
+        // Writing to /allowed.txt is allowed without prompting, but the hook redirects the write
+        let mut agents = get_test_agents(&os).await;
+        let agent = agents.get_active_mut().unwrap();
+        agent.tools_settings.insert(
+            ToolSettingTarget("fs_write".to_string()),
+            serde_json::json!({ "allowedPaths": ["/allowed.txt"] }),
+        );
+        agent.hooks.insert(HookTrigger::PreToolUse, vec![Hook::new(
+            r#"echo '{"updated_input": {"command": "create", "file_text": "Hello, world!", "path": "/redirected.txt"}}'"#
+                .to_string(),
+            Source::Agent,
+        )]);
+
+        let tool_manager = ToolManager::default();
+        let tool_config = serde_json::from_str::<HashMap<String, ToolSpec>>(include_str!("tools/tool_index.json"))
+            .expect("Tools failed to load");
+        ChatSession::new(
+            &mut os,
+            std::io::stdout(),
+            std::io::stderr(),
+            "fake_conv_id",
+            agents,
+            None,
+            InputSource::new_mock(vec![
+                "create a file".to_string(),
+                "n".to_string(), // deny the rewritten input
+                "create a file".to_string(),
+                "y".to_string(), // approve the rewritten input
+                "exit".to_string(),
+            ]),
+            None,
+            || Some(80),
+            tool_manager,
+            None,
+            tool_config,
+            true,
+            false,
+            None,
+            ChatOutputFormat::Plain,
+        )
+        .await
+        .unwrap()
+        .spawn(&mut os)
+        .await
+        .unwrap();
+
+        assert!(!os.fs.exists("/allowed.txt"));
+        assert_eq!(
+            os.fs.read_to_string("/redirected.txt").await.unwrap(),
+            "Hello, world!\n"
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_flow_stop_hook_continuations_are_capped() {
+        use crate::cli::agent::hook::{
+            Hook,
+            HookTrigger,
+            Source,
+        };
+
+        let mut os = Os::new().await.unwrap();
+        os.client.set_mock_output(serde_json::json!([["Done"]]));
+
+        // The hook blocks every time, and records how often it ran
+        let log_dir = tempfile::tempdir().unwrap();
+        let log = log_dir.path().join("stop.log");
+        let mut agents = get_test_agents(&os).await;
+        agents
+            .get_active_mut()
+            .unwrap()
+            .hooks
+            .insert(HookTrigger::Stop, vec![Hook::new(
+                format!("echo run >> '{}'; echo 'keep going' >&2; exit 2", log.display()),
+                Source::Agent,
+            )]);
+
+        let tool_manager = ToolManager::default();
+        let tool_config = serde_json::from_str::<HashMap<String, ToolSpec>>(include_str!("tools/tool_index.json"))
+            .expect("Tools failed to load");
+        ChatSession::new(
+            &mut os,
+            std::io::stdout(),
+            std::io::stderr(),
+            "fake_conv_id",
+            agents,
+            None,
+            InputSource::new_mock(vec!["hello".to_string(), "/quit".to_string()]),
+            None,
+            || Some(80),
+            tool_manager,
+            None,
+            tool_config,
+            true,
+            false,
+            None,
+            ChatOutputFormat::Plain,
+        )
+        .await
+        .unwrap()
+        .spawn(&mut os)
+        .await
+        .unwrap();
+
+        let runs = std::fs::read_to_string(&log).unwrap().lines().count();
+        assert_eq!(runs, MAX_STOP_HOOK_CONTINUATIONS + 1);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_flow_concurrent_tools() {
+        use crate::cli::agent::hook::{
+            Hook,
+            HookTrigger,
+            Source,
+        };
+
+        let mut os = Os::new().await.unwrap();
+        for name in ["a", "b", "c", "d"] {
+            os.fs.write(format!("/{name}.txt"), name).await.unwrap();
+        }
+        let read_file = |id: &str, name: &str| {
+            serde_json::json!({
+                "tool_use_id": id,
+                "name": "fs_read",
+                "args": { "operations": [{ "mode": "Line", "path": format!("/{name}.txt") }] }
+            })
+        };
+        os.client.set_mock_output(serde_json::json!([
+            [
+                "Reading the files",
+                read_file("1", "a"),
+                read_file("2", "b"),
+                read_file("3", "c"),
+                {
+                    "tool_use_id": "4",
+                    "name": "fs_write",
+                    "args": { "command": "create", "file_text": "w", "path": "/w.txt" }
+                },
+                read_file("5", "d"),
+            ],
+            ["Done"],
+        ]));
+
+        // The hooks record the order in which they run for each file, and reading b.txt is denied
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("hooks.log");
+        let script = dir.path().join("hook.sh");
+        std::fs::write(
+            &script,
+            format!(
+                r#"input=$(cat)
+for name in a b c d w; do
+    case "$input" in *"/$name.txt"*) file=$name ;; esac
+done
+echo "$1 $file" >> '{}'
+if [ "$1" = pre ] && [ "$file" = b ]; then
+    echo "b.txt is secret" >&2
+    exit 2
+fi
+"#,
+                log.display()
+            ),
+        )
+        .unwrap();
+        let mut agents = get_test_agents(&os).await;
+        let agent = agents.get_active_mut().unwrap();
+        agent.allowed_tools.insert("fs_write".to_string());
+        for (trigger, arg) in [(HookTrigger::PreToolUse, "pre"), (HookTrigger::PostToolUse, "post")] {
+            agent.hooks.insert(trigger, vec![Hook::new(
+                format!("sh '{}' {arg}", script.display()),
+                Source::Agent,
+            )]);
+        }
+
+        let tool_manager = ToolManager::default();
+        let tool_config = serde_json::from_str::<HashMap<String, ToolSpec>>(include_str!("tools/tool_index.json"))
+            .expect("Tools failed to load");
+        let mut session = ChatSession::new(
+            &mut os,
+            std::io::stdout(),
+            std::io::stderr(),
+            "fake_conv_id",
+            agents,
+            None,
+            InputSource::new_mock(vec!["read the files".to_string(), "/quit".to_string()]),
+            None,
+            || Some(80),
+            tool_manager,
+            None,
+            tool_config,
+            true,
+            false,
+            None,
+            ChatOutputFormat::Plain,
+        )
+        .await
+        .unwrap();
+        session.spawn(&mut os).await.unwrap();
+
+        // The allowed read-only tools before fs_write form a batch whose preToolUse hooks run
+        // before any of them completes, the remaining tools run one at a time
+        let runs = std::fs::read_to_string(&log).unwrap();
+        assert_eq!(runs.lines().collect::<Vec<_>>(), vec![
+            "pre a", "pre b", "pre c", "post a", "post c", "pre w", "post w", "pre d", "post d",
+        ]);
+        assert_eq!(os.fs.read_to_string("/w.txt").await.unwrap(), "w\n");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_flow_concurrent_tools_rewritten_to_mutate() {
+        use crate::cli::agent::hook::{
+            Hook,
+            HookTrigger,
+            Source,
+        };
+
+        let mut os = Os::new().await.unwrap();
+        for name in ["a", "c"] {
+            os.fs.write(format!("/{name}.txt"), name).await.unwrap();
+        }
+        let read_file = |id: &str, name: &str| {
+            serde_json::json!({
+                "tool_use_id": id,
+                "name": "fs_read",
+                "args": { "operations": [{ "mode": "Line", "path": format!("/{name}.txt") }] }
+            })
+        };
+        os.client.set_mock_output(serde_json::json!([
+            [
+                "Reading the knowledge base",
+                read_file("1", "a"),
+                { "tool_use_id": "2", "name": "knowledge", "args": { "command": "show" } },
+                read_file("3", "c"),
+            ],
+            ["Done"],
+        ]));
+
+        // The preToolUse hook rewrites showing the knowledge base into removing from it
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("hooks.log");
+        let script = dir.path().join("hook.sh");
+        std::fs::write(
+            &script,
+            format!(
+                r#"input=$(cat)
+for name in a c show remove; do
+    case "$input" in *"/$name.txt"*|*"\"$name\""*) op=$name ;; esac
+done
+echo "$1 $op" >> '{}'
+if [ "$1" = pre ] && [ "$op" = show ]; then
+    echo '{{"updated_input": {{"command": "remove", "name": "notes"}}}}'
+fi
+"#,
+                log.display()
+            ),
+        )
+        .unwrap();
+        let mut agents = get_test_agents(&os).await;
+        agents.trust_all_tools = true;
+        let agent = agents.get_active_mut().unwrap();
+        for (trigger, arg) in [(HookTrigger::PreToolUse, "pre"), (HookTrigger::PostToolUse, "post")] {
+            agent.hooks.insert(trigger, vec![Hook::new(
+                format!("sh '{}' {arg}", script.display()),
+                Source::Agent,
+            )]);
+        }
+
+        let tool_manager = ToolManager::default();
+        let tool_config = serde_json::from_str::<HashMap<String, ToolSpec>>(include_str!("tools/tool_index.json"))
+            .expect("Tools failed to load");
+        ChatSession::new(
+            &mut os,
+            std::io::stdout(),
+            std::io::stderr(),
+            "fake_conv_id",
+            agents,
+            None,
+            InputSource::new_mock(vec!["show the knowledge base".to_string(), "/quit".to_string()]),
+            None,
+            || Some(80),
+            tool_manager,
+            None,
+            tool_config,
+            true,
+            false,
+            None,
+            ChatOutputFormat::Plain,
+        )
+        .await
+        .unwrap()
+        .spawn(&mut os)
+        .await
+        .unwrap();
+
+        // The rewritten tool use ends the batch and runs once the tool uses before it are done, and
+        // its preToolUse hook is not run again
+        let runs = std::fs::read_to_string(&log).unwrap();
+        assert_eq!(runs.lines().collect::<Vec<_>>(), vec![
+            "pre a",
+            "pre show",
+            "post a",
+            "post remove",
+            "pre c",
+            "post c",
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_buffered_in_order() {
+        let in_flight = AtomicUsize::new(0);
+        let max_in_flight = AtomicUsize::new(0);
+        let run = |i: u64| {
+            let (in_flight, max_in_flight) = (&in_flight, &max_in_flight);
+            async move {
+                let running = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                max_in_flight.fetch_max(running, Ordering::SeqCst);
+                // Later futures finish first
+                tokio::time::sleep(std::time::Duration::from_millis(50 - i * 10)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                i
+            }
+        };
+
+        assert_eq!(buffered_in_order((0..5).map(run), 2).await, vec![0, 1, 2, 3, 4]);
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 2);
+
+        // A limit of 0 still makes progress, one future at a time
+        max_in_flight.store(0, Ordering::SeqCst);
+        assert_eq!(buffered_in_order((0..3).map(run), 0).await, vec![0, 1, 2]);
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_max_concurrent_tools() {
+        assert_eq!(max_concurrent_tools(None), DEFAULT_MAX_CONCURRENT_TOOLS);
+        assert_eq!(max_concurrent_tools(Some(3)), 3);
+        assert_eq!(max_concurrent_tools(Some(1)), 1);
+        assert_eq!(max_concurrent_tools(Some(0)), 1);
+        assert_eq!(max_concurrent_tools(Some(-1)), 1);
+        assert_eq!(max_concurrent_tools(Some(i64::MIN)), 1);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_flow_shell_overriding_commands_requires_permission() {
+        use crate::cli::agent::ToolSettingTarget;
+
+        let mut os = Os::new().await.unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let marker = dir.path().join("overridden");
+        let run_command = |id: &str, command: String| {
+            serde_json::json!({
+                "tool_use_id": id,
+                "name": "execute_bash",
+                "args": { "command": command }
+            })
+        };
+        // `echo` is read-only, so it is allowed until the first command overrides it
+        os.client.set_mock_output(serde_json::json!([
+            [
+                "Running",
+                run_command("1", format!("echo() {{ touch '{}'; }}", marker.display())),
+                run_command("2", "echo hello".to_string()),
+            ],
+            ["Done"],
+        ]));
+
+        let mut agents = get_test_agents(&os).await;
+        agents.get_active_mut().unwrap().tools_settings.insert(
+            ToolSettingTarget("execute_bash".to_string()),
+            serde_json::json!({ "persistentShell": { "enabled": true } }),
+        );
+
+        let tool_manager = ToolManager::default();
+        let tool_config = serde_json::from_str::<HashMap<String, ToolSpec>>(include_str!("tools/tool_index.json"))
+            .expect("Tools failed to load");
+        ChatSession::new(
+            &mut os,
+            std::io::stdout(),
+            std::io::stderr(),
+            "fake_conv_id",
+            agents,
+            None,
+            InputSource::new_mock(vec![
+                "run the commands".to_string(),
+                "y".to_string(), // approve defining the function
+                "n".to_string(), // deny the overridden command
+                "/quit".to_string(),
+            ]),
+            None,
+            || Some(80),
+            tool_manager,
+            None,
+            tool_config,
+            true,
+            false,
+            None,
+            ChatOutputFormat::Plain,
+        )
+        .await
+        .unwrap()
+        .spawn(&mut os)
+        .await
+        .unwrap();
+
+        assert!(!marker.exists());
+    }
+
     #[tokio::test]
     async fn test_flow_multiple_tools() {
         // let _ = tracing_subscriber::fmt::try_init();
@@ -3642,7 +4884,7 @@ mod tests {
                 "y".to_string(),
                 "exit".to_string(),
             ]),
-            false,
+            None,
             || Some(80),
             tool_manager,
             None,
@@ -3650,6 +4892,7 @@ mod tests {
             true,
             false,
             None,
+            ChatOutputFormat::Plain,
         )
         .await
         .unwrap()
@@ -3718,7 +4961,7 @@ mod tests {
                 "create a new file".to_string(),
                 "exit".to_string(),
             ]),
-            false,
+            None,
             || Some(80),
             tool_manager,
             None,
@@ -3726,6 +4969,7 @@ mod tests {
             true,
             false,
             None,
+            ChatOutputFormat::Plain,
         )
         .await
         .unwrap()
@@ -3770,7 +5014,7 @@ mod tests {
             agents,
             None,
             InputSource::new_mock(vec!["/subscribe".to_string(), "y".to_string(), "/quit".to_string()]),
-            false,
+            None,
             || Some(80),
             tool_manager,
             None,
@@ -3778,6 +5022,7 @@ mod tests {
             true,
             false,
             None,
+            ChatOutputFormat::Plain,
         )
         .await
         .unwrap()
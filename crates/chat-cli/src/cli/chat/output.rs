@@ -0,0 +1,364 @@
+use std::io::Write;
+
+use serde::Serialize;
+use tracing::error;
+
+use super::ChatOutputFormat;
+use super::conversation::ConversationSize;
+use super::message::{
+    AssistantMessage,
+    ToolUseResult,
+    ToolUseResultBlock,
+};
+use super::parser::RequestMetadata;
+use super::token_counter::{
+    CountTokens,
+    Tokenizer,
+};
+use crate::api_client::model::ToolResultStatus;
+
+/// Destination of [super::ChatSession::stdout].
+///
+/// When a machine readable [ChatOutputFormat] is selected, stdout is reserved for JSON and the
+/// output meant for humans is discarded.
+pub enum ChatOutput {
+    Stdout(std::io::Stdout),
+    Discard,
+}
+
+impl ChatOutput {
+    /// Another handle to the same destination, for output written while `self` is borrowed.
+    pub fn handle(&self) -> Self {
+        match self {
+            ChatOutput::Stdout(_) => ChatOutput::Stdout(std::io::stdout()),
+            ChatOutput::Discard => ChatOutput::Discard,
+        }
+    }
+}
+
+impl Write for ChatOutput {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            ChatOutput::Stdout(stdout) => stdout.write(buf),
+            ChatOutput::Discard => Ok(buf.len()),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            ChatOutput::Stdout(stdout) => stdout.flush(),
+            ChatOutput::Discard => Ok(()),
+        }
+    }
+}
+
+/// A tool use requested by the model, along with its result once executed.
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolCall {
+    pub id: String,
+    pub name: String,
+    pub input: serde_json::Value,
+    /// [None] if the tool was never executed.
+    pub status: Option<ToolCallStatus>,
+    pub result: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ToolCallStatus {
+    Success,
+    Error,
+}
+
+/// Number of tokens, counted the same way as `/usage`.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct TokenEstimates {
+    /// Size of the conversation sent with the last request, including context files
+    pub context: usize,
+    /// Size of the responses generated during this run
+    pub output: usize,
+}
+
+/// Object printed at the end of a non-interactive chat with `--output-format json`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ChatResult {
+    pub conversation_id: String,
+    /// Text of the last response from the model
+    pub text: String,
+    pub tool_calls: Vec<ToolCall>,
+    pub request_ids: Vec<String>,
+    pub token_estimates: TokenEstimates,
+    /// Set if the chat ended because of an error
+    pub error: Option<String>,
+}
+
+/// Event printed as a single line with `--output-format stream-json`.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum StreamEvent<'a> {
+    Init {
+        conversation_id: &'a str,
+        model_id: Option<&'a str>,
+    },
+    AssistantMessage {
+        message_id: Option<&'a str>,
+        request_id: Option<&'a str>,
+        text: &'a str,
+        tool_uses: &'a [ToolCall],
+    },
+    ToolResult(&'a ToolCall),
+    Error {
+        message: &'a str,
+    },
+    Result(&'a ChatResult),
+}
+
+/// Collects what happens during a non-interactive chat session and prints it as JSON according
+/// to the selected [ChatOutputFormat].
+pub struct JsonOutput<W: Write = std::io::Stdout> {
+    format: ChatOutputFormat,
+    out: W,
+    result: ChatResult,
+    tokenizer: Tokenizer,
+    output_tokens: usize,
+}
+
+impl JsonOutput {
+    /// Returns [None] for [ChatOutputFormat::Plain] since there is nothing to collect.
+    pub fn new(format: ChatOutputFormat) -> Option<Self> {
+        (format != ChatOutputFormat::Plain).then(|| Self::with_writer(format, std::io::stdout()))
+    }
+}
+
+impl<W: Write> JsonOutput<W> {
+    pub fn with_writer(format: ChatOutputFormat, out: W) -> Self {
+        Self {
+            format,
+            out,
+            result: ChatResult::default(),
+            tokenizer: Tokenizer::default(),
+            output_tokens: 0,
+        }
+    }
+
+    pub fn init(&mut self, conversation_id: &str, model_id: Option<&str>) {
+        self.result.conversation_id = conversation_id.to_string();
+        self.tokenizer = model_id.map_or_else(Tokenizer::default, Tokenizer::for_model_id);
+        self.emit(&StreamEvent::Init {
+            conversation_id,
+            model_id,
+        });
+    }
+
+    pub fn assistant_message(&mut self, message: &AssistantMessage, request_metadata: &RequestMetadata) {
+        let tool_uses = message
+            .tool_uses()
+            .unwrap_or_default()
+            .iter()
+            .map(|tool_use| ToolCall {
+                id: tool_use.id.clone(),
+                name: tool_use.name.clone(),
+                input: tool_use.args.clone(),
+                status: None,
+                result: None,
+            })
+            .collect::<Vec<_>>();
+
+        self.output_tokens += message.token_count(self.tokenizer).value();
+        if let Some(request_id) = &request_metadata.request_id {
+            self.result.request_ids.push(request_id.clone());
+        }
+        self.result.text = message.content().to_string();
+
+        self.emit(&StreamEvent::AssistantMessage {
+            message_id: message.message_id(),
+            request_id: request_metadata.request_id.as_deref(),
+            text: message.content(),
+            tool_uses: &tool_uses,
+        });
+        self.result.tool_calls.extend(tool_uses);
+    }
+
+    pub fn tool_results(&mut self, results: &[ToolUseResult]) {
+        for result in results {
+            let Some(index) = self.result.tool_calls.iter().position(|c| c.id == result.tool_use_id) else {
+                continue;
+            };
+            let call = &mut self.result.tool_calls[index];
+            call.status = Some(match result.status {
+                ToolResultStatus::Success => ToolCallStatus::Success,
+                ToolResultStatus::Error => ToolCallStatus::Error,
+            });
+            call.result = Some(result_to_json(&result.content));
+
+            let call = call.clone();
+            self.emit(&StreamEvent::ToolResult(&call));
+        }
+    }
+
+    /// Records the error that ended the session. Errors following the first one are a consequence
+    /// of it and are not reported.
+    pub fn error(&mut self, message: String) {
+        if self.result.error.is_some() {
+            return;
+        }
+        self.emit(&StreamEvent::Error { message: &message });
+        self.result.error = Some(message);
+    }
+
+    /// Prints the final [ChatResult].
+    pub fn finish(&mut self, conversation_size: ConversationSize) {
+        self.result.token_estimates = TokenEstimates {
+            context: conversation_size.total().value(),
+            output: self.output_tokens,
+        };
+
+        let result = std::mem::take(&mut self.result);
+        let printed = match self.format {
+            ChatOutputFormat::Plain => return,
+            ChatOutputFormat::Json => serde_json::to_string(&result),
+            ChatOutputFormat::JsonPretty => serde_json::to_string_pretty(&result),
+            ChatOutputFormat::StreamJson => serde_json::to_string(&StreamEvent::Result(&result)),
+        };
+        match printed {
+            Ok(printed) => self.write_line(&printed),
+            Err(err) => error!(?err, "failed to serialize the chat result"),
+        }
+    }
+
+    /// Prints `event` if streaming events.
+    fn emit(&mut self, event: &StreamEvent<'_>) {
+        if self.format != ChatOutputFormat::StreamJson {
+            return;
+        }
+        match serde_json::to_string(event) {
+            Ok(line) => self.write_line(&line),
+            Err(err) => error!(?err, "failed to serialize the chat event"),
+        }
+    }
+
+    fn write_line(&mut self, line: &str) {
+        if let Err(err) = writeln!(self.out, "{line}").and_then(|_| self.out.flush()) {
+            error!(?err, "failed to write the chat output");
+        }
+    }
+}
+
+/// Converts the content of a tool result into a single JSON value.
+fn result_to_json(content: &[ToolUseResultBlock]) -> serde_json::Value {
+    let mut values = content
+        .iter()
+        .map(|block| match block {
+            ToolUseResultBlock::Json(value) => value.clone(),
+            ToolUseResultBlock::Text(text) => serde_json::Value::String(text.clone()),
+        })
+        .collect::<Vec<_>>();
+    match values.len() {
+        1 => values.remove(0),
+        _ => serde_json::Value::Array(values),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::cli::chat::message::AssistantToolUse;
+    use crate::cli::chat::token_counter::TokenCount;
+
+    fn request_metadata(request_id: &str) -> RequestMetadata {
+        RequestMetadata {
+            request_id: Some(request_id.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn conversation_size() -> ConversationSize {
+        ConversationSize {
+            context_messages: TokenCount::from(10),
+            user_messages: TokenCount::from(10),
+            assistant_messages: TokenCount::from(0),
+        }
+    }
+
+    fn run(format: ChatOutputFormat) -> Vec<serde_json::Value> {
+        let mut output = JsonOutput::with_writer(format, Vec::new());
+        output.init("conv", Some("model"));
+        output.assistant_message(
+            &AssistantMessage::new_tool_use(Some("m1".to_string()), "Reading".to_string(), vec![AssistantToolUse {
+                id: "t1".to_string(),
+                name: "fs_read".to_string(),
+                args: json!({ "path": "a.txt" }),
+                ..Default::default()
+            }]),
+            &request_metadata("r1"),
+        );
+        output.tool_results(&[ToolUseResult {
+            tool_use_id: "t1".to_string(),
+            content: vec![ToolUseResultBlock::Text("hello".to_string())],
+            status: ToolResultStatus::Success,
+        }]);
+        output.assistant_message(
+            &AssistantMessage::new_response(Some("m2".to_string()), "Done".to_string()),
+            &request_metadata("r2"),
+        );
+        output.finish(conversation_size());
+
+        String::from_utf8(output.out)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_json_output() {
+        let lines = run(ChatOutputFormat::Json);
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0]["conversation_id"], "conv");
+        assert_eq!(lines[0]["text"], "Done");
+        assert_eq!(lines[0]["request_ids"], json!(["r1", "r2"]));
+        assert_eq!(
+            lines[0]["tool_calls"],
+            json!([{ "id": "t1", "name": "fs_read", "input": { "path": "a.txt" }, "status": "success", "result": "hello" }])
+        );
+        assert_eq!(lines[0]["token_estimates"]["context"], 20);
+        assert_eq!(lines[0]["error"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn test_stream_json_output() {
+        let lines = run(ChatOutputFormat::StreamJson);
+        let types = lines.iter().map(|l| l["type"].as_str().unwrap()).collect::<Vec<_>>();
+        assert_eq!(types, vec![
+            "init",
+            "assistant_message",
+            "tool_result",
+            "assistant_message",
+            "result"
+        ]);
+        assert_eq!(lines[1]["tool_uses"][0]["name"], "fs_read");
+        assert_eq!(lines[2]["status"], "success");
+        assert_eq!(lines[4]["text"], "Done");
+    }
+
+    #[test]
+    fn test_error_is_reported_once() {
+        let mut output = JsonOutput::with_writer(ChatOutputFormat::StreamJson, Vec::new());
+        output.init("conv", None);
+        output.error("first".to_string());
+        output.error("second".to_string());
+        output.finish(conversation_size());
+
+        let lines = String::from_utf8(output.out)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<_>>();
+        let errors = lines.iter().filter(|l| l["type"] == "error").collect::<Vec<_>>();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0]["message"], "first");
+        assert_eq!(lines.last().unwrap()["error"], "first");
+    }
+}
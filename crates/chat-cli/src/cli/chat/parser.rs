@@ -31,7 +31,10 @@ use super::message::{
 };
 use crate::api_client::model::{
     ChatResponseStream,
+    CodeReference,
     ConversationState,
+    FollowupPrompt,
+    SupplementaryWebLink,
 };
 use crate::api_client::send_message_output::SendMessageOutput;
 use crate::api_client::{
@@ -389,7 +392,7 @@ impl ResponseParser {
             let content = content.clone();
             self.next().await?;
             match self.peek().await? {
-                Some(ChatResponseStream::CodeReferenceEvent(_)) => (),
+                Some(ChatResponseStream::CodeReferenceEvent { .. }) => (),
                 _ => {
                     self.assistant_text.push_str(&content);
                     return Ok(ResponseEvent::AssistantText(content));
@@ -421,6 +424,19 @@ impl ResponseParser {
                         self.parsing_tool_use = Some((tool_use_id.clone(), name.clone()));
                         return Ok(ResponseEvent::ToolUseStart { name });
                     },
+                    ChatResponseStream::CodeReferenceEvent { references } if !references.is_empty() => {
+                        return Ok(ResponseEvent::CodeReferences(references));
+                    },
+                    ChatResponseStream::SupplementaryWebLinksEvent {
+                        supplementary_web_links,
+                    } if !supplementary_web_links.is_empty() => {
+                        return Ok(ResponseEvent::WebLinks(supplementary_web_links));
+                    },
+                    ChatResponseStream::FollowupPromptEvent {
+                        followup_prompt: Some(prompt),
+                    } => {
+                        return Ok(ResponseEvent::FollowupPrompt(prompt));
+                    },
                     _ => {},
                 },
                 Ok(None) => {
@@ -628,6 +644,13 @@ pub enum ResponseEvent {
     /// A tool use requested by the assistant. This should be displayed to the user as it is
     /// received.
     ToolUse(AssistantToolUse),
+    /// Licensed code that the preceding assistant text was found to be similar to. The text
+    /// itself is not returned.
+    CodeReferences(Vec<CodeReference>),
+    /// Web pages related to the response.
+    WebLinks(Vec<SupplementaryWebLink>),
+    /// A prompt suggested to continue the conversation.
+    FollowupPrompt(FollowupPrompt),
     /// Represents the end of the response. No more events will be returned.
     EndStream {
         /// The completed message containing all of the assistant text and tool use events
@@ -702,7 +725,7 @@ mod tests {
             ChatResponseStream::AssistantResponseEvent {
                 content: content_to_ignore.to_string(),
             },
-            ChatResponseStream::CodeReferenceEvent(()),
+            ChatResponseStream::CodeReferenceEvent { references: vec![] },
             ChatResponseStream::ToolUseEvent {
                 tool_use_id: tool_use_id.clone(),
                 name: tool_name.clone(),
@@ -753,4 +776,50 @@ mod tests {
             "assistant text preceding a code reference should be ignored as this indicates licensed code is being returned"
         );
     }
+
+    #[tokio::test]
+    async fn test_response_parser_returns_supplementary_events() {
+        let reference = CodeReference {
+            license_name: Some("MIT".to_string()),
+            repository: Some("example/repo".to_string()),
+            url: None,
+            recommendation_content_span: None,
+        };
+        let mut events = vec![
+            ChatResponseStream::AssistantResponseEvent {
+                content: "hi".to_string(),
+            },
+            ChatResponseStream::CodeReferenceEvent {
+                references: vec![reference.clone()],
+            },
+            ChatResponseStream::SupplementaryWebLinksEvent {
+                supplementary_web_links: vec![],
+            },
+            ChatResponseStream::FollowupPromptEvent {
+                followup_prompt: Some(FollowupPrompt {
+                    content: "and then?".to_string(),
+                }),
+            },
+        ];
+        events.reverse();
+        let mut parser = ResponseParser::new(
+            SendMessageOutput::Mock(events),
+            "".to_string(),
+            None,
+            1,
+            vec![],
+            mpsc::channel(32).0,
+            Instant::now(),
+            SystemTime::now(),
+            CancellationToken::new(),
+            Arc::new(Mutex::new(None)),
+        );
+
+        assert!(matches!(parser.recv().await.unwrap(), ResponseEvent::CodeReferences(r) if r == vec![reference]));
+        assert!(matches!(parser.recv().await.unwrap(), ResponseEvent::FollowupPrompt(p) if p.content == "and then?"));
+        match parser.recv().await.unwrap() {
+            ResponseEvent::EndStream { message, .. } => assert_eq!(message.content(), ""),
+            other => panic!("expected the end of the stream, found {other:?}"),
+        }
+    }
 }
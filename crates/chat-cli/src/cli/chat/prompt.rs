@@ -62,6 +62,7 @@ pub const COMMANDS: &[&str] = &[
     "/tools trust-all",
     "/tools reset",
     "/mcp",
+    "/mcp resources",
     "/model",
     "/experiment",
     "/agent",
@@ -91,10 +92,26 @@ pub const COMMANDS: &[&str] = &[
     "/hooks disable-all",
     "/compact",
     "/compact help",
+    "/checkpoint",
+    "/checkpoint list",
+    "/checkpoint diff",
+    "/checkpoint restore",
+    "/shell",
+    "/shell status",
+    "/shell reset",
+    "/branch",
+    "/branch list",
+    "/branch turns",
+    "/branch fork",
+    "/branch switch",
+    "/branch rename",
+    "/branch delete",
+    "/branch compare",
     "/usage",
     "/changelog",
     "/save",
     "/load",
+    "/export",
     "/subscribe",
     "/todos",
     "/todos resume",
@@ -0,0 +1,349 @@
+use std::process::ExitCode;
+
+use chrono::{
+    DateTime,
+    Local,
+};
+use clap::Subcommand;
+use eyre::{
+    Result,
+    bail,
+};
+use serde_json::json;
+
+use super::ConversationState;
+use super::export::{
+    ExportFormat,
+    export,
+};
+use crate::cli::OutputFormat;
+use crate::database::SessionInfo;
+use crate::os::Os;
+
+/// Number of characters of the session ids shown in listings. Any unique prefix of an id can be
+/// used to refer to a session.
+const SHORT_ID_LEN: usize = 8;
+
+#[derive(Debug, Clone, PartialEq, Eq, Subcommand)]
+pub enum SessionsSubcommand {
+    /// List the saved sessions of the current directory
+    List {
+        /// List the sessions of every directory
+        #[arg(long, short)]
+        all: bool,
+        /// Output format to use
+        #[arg(long, short, value_enum, default_value_t)]
+        format: OutputFormat,
+    },
+    /// Show the transcript of a session
+    Show {
+        /// Id of the session, or a unique prefix of it
+        id: String,
+        /// Output format to use
+        #[arg(long, short, value_enum, default_value_t)]
+        format: OutputFormat,
+    },
+    /// Delete a session
+    Delete {
+        /// Id of the session, or a unique prefix of it
+        id: String,
+    },
+    /// Rename a session
+    Rename {
+        /// Id of the session, or a unique prefix of it
+        id: String,
+        /// New title of the session
+        title: String,
+    },
+    /// Export a session as a Markdown, HTML or JSON Lines transcript
+    Export {
+        /// Id of the session, or a unique prefix of it
+        id: String,
+        /// Path where the transcript will be written
+        path: String,
+        /// Format of the transcript, guessed from the file extension if not given
+        #[arg(long, value_enum)]
+        format: Option<ExportFormat>,
+        /// Replace the outputs of tools with a placeholder
+        #[arg(long)]
+        redact_tool_outputs: bool,
+        /// Overwrite the file if it already exists
+        #[arg(long, short)]
+        force: bool,
+    },
+}
+
+impl SessionsSubcommand {
+    pub async fn execute(self, os: &mut Os) -> Result<ExitCode> {
+        match self {
+            Self::List { all, format } => {
+                let cwd = os.env.current_dir()?;
+                let sessions = os.database.list_sessions((!all).then_some(cwd.as_path()))?;
+                format.print(
+                    || match sessions.is_empty() {
+                        true => "No saved sessions".to_string(),
+                        false => sessions
+                            .iter()
+                            .map(|session| describe_session(session, all))
+                            .collect::<Vec<_>>()
+                            .join("\n"),
+                    },
+                    || &sessions,
+                );
+            },
+            Self::Show { id, format } => {
+                let session = find_session(os, &id)?;
+                let conversation = load_session(os, &session.id)?;
+                format.print(
+                    || {
+                        let mut text = describe_session(&session, true);
+                        for entry in &conversation.transcript {
+                            text.push_str(&format!("\n\n{entry}"));
+                        }
+                        text
+                    },
+                    || json!({ "session": &session, "transcript": &conversation.transcript }),
+                );
+            },
+            Self::Delete { id } => {
+                let session = find_session(os, &id)?;
+                os.database.delete_session(&session.id)?;
+                eprintln!("Deleted session {}", session.id);
+            },
+            Self::Rename { id, title } => {
+                let session = find_session(os, &id)?;
+                os.database.rename_session(&session.id, &title)?;
+                eprintln!("Renamed session {} to {title}", session.id);
+            },
+            Self::Export {
+                id,
+                path,
+                format,
+                redact_tool_outputs,
+                force,
+            } => {
+                let Some(format) = format.or_else(|| ExportFormat::from_path(&path)) else {
+                    bail!("Could not guess the format of {path} from its extension, use --format");
+                };
+                if os.fs.exists(&path) && !force {
+                    bail!("File at {path} already exists. To overwrite, use -f or --force");
+                }
+                let session = find_session(os, &id)?;
+                let conversation = load_session(os, &session.id)?;
+                os.fs
+                    .write(&path, export(&conversation, format, redact_tool_outputs)?)
+                    .await?;
+                eprintln!("Exported session {} to {path}", session.id);
+            },
+        }
+
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+/// Finds the saved session whose id is `id` or starts with `id`.
+pub fn find_session(os: &Os, id: &str) -> Result<SessionInfo> {
+    let sessions = os.database.list_sessions(None)?;
+    if let Some(session) = sessions.iter().find(|s| s.id == id) {
+        return Ok(session.clone());
+    }
+
+    let mut matches = sessions.into_iter().filter(|s| s.id.starts_with(id));
+    match (matches.next(), matches.next()) {
+        (Some(session), None) => Ok(session),
+        (Some(_), Some(_)) => bail!("More than one session starts with '{id}', use a longer id"),
+        (None, _) => bail!("No session found with id '{id}'"),
+    }
+}
+
+/// Handles `q chat --resume <SESSION_ID>`, which is parsed as resuming the last session with
+/// `<SESSION_ID>` as the first question. A first question that looks like a session id is taken
+/// out of `input` and resolved to the session to resume instead.
+pub fn take_session_id_from_input(os: &Os, input: &mut Option<String>) -> Result<Option<String>> {
+    let Some(id) = input.as_deref().filter(|input| looks_like_session_id(input)) else {
+        return Ok(None);
+    };
+    let session = find_session(os, id)?;
+    *input = None;
+    Ok(Some(session.id))
+}
+
+/// Whether `text` looks like a session id, or a prefix of one as shown in listings.
+fn looks_like_session_id(text: &str) -> bool {
+    (8..=36).contains(&text.len())
+        && text.chars().all(|c| c.is_ascii_hexdigit() || c == '-')
+        && text.chars().any(|c| c.is_ascii_digit())
+}
+
+/// Loads the conversation of the saved session with the given id.
+pub fn load_session(os: &Os, id: &str) -> Result<ConversationState> {
+    match os.database.get_session(id)? {
+        Some(conversation) => Ok(conversation),
+        None => bail!("No session found with id '{id}'"),
+    }
+}
+
+/// Asks the user to pick one of the saved sessions of the current directory and loads it.
+///
+/// Returns [None] if there are no saved sessions or the user cancelled.
+pub fn pick_session(os: &Os) -> Result<Option<ConversationState>> {
+    let cwd = os.env.current_dir()?;
+    let sessions = os.database.list_sessions(Some(&cwd))?;
+    if sessions.is_empty() {
+        return Ok(None);
+    }
+
+    let items = sessions
+        .iter()
+        .map(|session| describe_session(session, false))
+        .collect::<Vec<_>>();
+    match crate::util::choose("Select a session to resume", &items)? {
+        Some(i) => Ok(Some(load_session(os, &sessions[i].id)?)),
+        None => Ok(None),
+    }
+}
+
+/// Describes a session on a single line, e.g. `1b9c2f3a  2025-06-01 14:02  Fix the build  (default,
+/// claude-sonnet-4)`.
+fn describe_session(session: &SessionInfo, with_directory: bool) -> String {
+    let short_id = session.id.chars().take(SHORT_ID_LEN).collect::<String>();
+    let updated_at = DateTime::from_timestamp_millis(session.updated_at)
+        .map(|time| time.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default();
+    let mut description = format!(
+        "{short_id}  {updated_at}  {}",
+        session.title.as_deref().unwrap_or("(untitled)")
+    );
+
+    let details = [session.agent.as_deref(), session.model_id.as_deref()]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+    if !details.is_empty() {
+        description.push_str(&format!("  ({})", details.join(", ")));
+    }
+    if with_directory {
+        description.push_str(&format!("  {}", session.directory));
+    }
+
+    description
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::cli::agent::Agents;
+    use crate::cli::chat::message::AssistantMessage;
+    use crate::cli::chat::tool_manager::ToolManager;
+
+    fn session(id: &str) -> SessionInfo {
+        SessionInfo {
+            id: id.to_string(),
+            directory: "/workspace".to_string(),
+            title: Some("Fix the build".to_string()),
+            agent: Some("default".to_string()),
+            model_id: None,
+            created_at: 0,
+            updated_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_describe_session() {
+        let description = describe_session(&session("1b9c2f3a-0000-0000-0000-000000000000"), false);
+        assert!(description.starts_with("1b9c2f3a  "), "{description}");
+        assert!(description.ends_with("  Fix the build  (default)"), "{description}");
+
+        let description = describe_session(
+            &SessionInfo {
+                title: None,
+                agent: None,
+                ..session("abc")
+            },
+            true,
+        );
+        assert!(description.ends_with("  (untitled)  /workspace"), "{description}");
+    }
+
+    #[tokio::test]
+    async fn test_save_and_find_sessions() {
+        let mut os = Os::new().await.unwrap();
+        for id in ["aaaa-1", "aaaa-2"] {
+            let mut conversation = ConversationState::new(
+                id,
+                Agents::default(),
+                HashMap::new(),
+                ToolManager::default(),
+                None,
+                &os,
+                false,
+            )
+            .await;
+            conversation
+                .set_next_user_message(format!("prompt of {id}\nsecond line"))
+                .await;
+            conversation.push_assistant_message(&mut os, AssistantMessage::new_response(None, "ok".into()), None);
+            os.database.set_session_by_path("/workspace", &conversation).unwrap();
+        }
+
+        let sessions = os.database.list_sessions(Some("/workspace".as_ref())).unwrap();
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].id, "aaaa-2");
+        assert_eq!(sessions[0].title.as_deref(), Some("prompt of aaaa-2"));
+        let latest = os.database.get_latest_session_by_path("/workspace").unwrap().unwrap();
+        assert_eq!(latest.conversation_id(), "aaaa-2");
+
+        assert!(
+            find_session(&os, "aaaa")
+                .unwrap_err()
+                .to_string()
+                .contains("More than one")
+        );
+        assert!(find_session(&os, "zzzz").is_err());
+        let session = find_session(&os, "aaaa-1").unwrap();
+
+        // Renaming sticks when the session is saved again.
+        assert!(os.database.rename_session(&session.id, "Renamed").unwrap());
+        let conversation = load_session(&os, &session.id).unwrap();
+        os.database.set_session_by_path("/workspace", &conversation).unwrap();
+        assert_eq!(find_session(&os, "aaaa-1").unwrap().title.as_deref(), Some("Renamed"));
+
+        assert!(os.database.delete_session("aaaa-2").unwrap());
+        assert!(!os.database.delete_session("aaaa-2").unwrap());
+        assert!(os.database.get_session("aaaa-2").unwrap().is_none());
+        assert_eq!(find_session(&os, "aaaa").unwrap().id, "aaaa-1");
+    }
+
+    #[tokio::test]
+    async fn test_take_session_id_from_input() {
+        let mut os = Os::new().await.unwrap();
+        let id = "1b9c2f3a-0000-0000-0000-000000000000";
+        let conversation = ConversationState::new(
+            id,
+            Agents::default(),
+            HashMap::new(),
+            ToolManager::default(),
+            None,
+            &os,
+            false,
+        )
+        .await;
+        os.database.set_session_by_path("/workspace", &conversation).unwrap();
+
+        let mut input = Some("1b9c2f3a".to_string());
+        assert_eq!(
+            take_session_id_from_input(&os, &mut input).unwrap().as_deref(),
+            Some(id)
+        );
+        assert_eq!(input, None);
+
+        // Questions are left alone, and ids of no session are an error rather than a question
+        let mut input = Some("Hello".to_string());
+        assert_eq!(take_session_id_from_input(&os, &mut input).unwrap(), None);
+        assert_eq!(input.as_deref(), Some("Hello"));
+        let mut input = Some("99999999".to_string());
+        assert!(take_session_id_from_input(&os, &mut input).is_err());
+    }
+}
@@ -1,4 +1,16 @@
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{
+    Hash,
+    Hasher,
+};
 use std::ops::Deref;
+use std::sync::{
+    LazyLock,
+    Mutex,
+};
+
+use tiktoken_rs::CoreBPE;
 
 use super::message::{
     AssistantMessage,
@@ -7,21 +19,32 @@ use super::message::{
     UserMessage,
     UserMessageContent,
 };
-use crate::cli::chat::conversation::{
-    BackendConversationState,
-    ConversationSize,
-};
+use crate::cli::chat::cli::model::ModelInfo;
+
+/// Texts are encoded in chunks of at most this many bytes, cut right before a whitespace where
+/// possible. Byte pair encoding is quadratic in the length of each piece of text it merges, so
+/// this keeps inputs without whitespace, such as minified code or base64 blobs, from being slow.
+const MAX_CHUNK_LEN: usize = 256;
 
-#[derive(Debug, Clone, Copy)]
-pub struct CharCount(usize);
+/// Counts of texts at least this long are cached, since the same messages are counted on every
+/// turn.
+const MIN_CACHED_LEN: usize = 1024;
 
-impl CharCount {
+/// Maximum number of cached counts, after which the cache is cleared.
+const MAX_CACHE_ENTRIES: usize = 4096;
+
+static COUNT_CACHE: LazyLock<Mutex<HashMap<(Tokenizer, u64), usize>>> = LazyLock::new(Default::default);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TokenCount(usize);
+
+impl TokenCount {
     pub fn value(&self) -> usize {
         self.0
     }
 }
 
-impl Deref for CharCount {
+impl Deref for TokenCount {
     type Target = usize;
 
     fn deref(&self) -> &Self::Target {
@@ -29,223 +52,301 @@ impl Deref for CharCount {
     }
 }
 
-impl From<usize> for CharCount {
+impl From<usize> for TokenCount {
     fn from(value: usize) -> Self {
         Self(value)
     }
 }
 
-impl std::ops::Add for CharCount {
-    type Output = CharCount;
+impl std::ops::Add for TokenCount {
+    type Output = TokenCount;
 
     fn add(self, rhs: Self) -> Self::Output {
         Self(self.value() + rhs.value())
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-pub struct TokenCount(usize);
+impl std::iter::Sum for TokenCount {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), |acc, v| acc + v)
+    }
+}
 
-impl TokenCount {
-    pub fn value(&self) -> usize {
-        self.0
+impl std::fmt::Display for TokenCount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
     }
 }
 
-impl Deref for TokenCount {
-    type Target = usize;
+/// Byte pair encodings bundled with the CLI.
+///
+/// The tokenizers of the Claude models are not published, so they are counted with
+/// `cl100k_base`, which gives much closer counts than a fixed number of bytes per token.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Tokenizer {
+    #[default]
+    Cl100kBase,
+    O200kBase,
+}
 
-    fn deref(&self) -> &Self::Target {
-        &self.0
+impl Tokenizer {
+    /// Returns the tokenizer to use for the given model, defaulting to [Tokenizer::Cl100kBase].
+    pub fn for_model(model: Option<&ModelInfo>) -> Self {
+        model.map_or_else(Self::default, |model| Self::for_model_id(&model.model_id))
     }
-}
 
-impl From<CharCount> for TokenCount {
-    fn from(value: CharCount) -> Self {
-        Self(TokenCounter::count_tokens_char_count(value.value()))
+    pub fn for_model_id(model_id: &str) -> Self {
+        // Models of OpenAI-compatible endpoints are prefixed, e.g. `openai:gpt-4o`, and some
+        // servers add their own prefix, e.g. `openai/gpt-4o`.
+        let model_id = model_id.rsplit([':', '/']).next().unwrap_or(model_id);
+        match tiktoken_rs::tokenizer::get_tokenizer(model_id) {
+            Some(tiktoken_rs::tokenizer::Tokenizer::O200kBase) => Self::O200kBase,
+            Some(_) => Self::Cl100kBase,
+            None if model_id.starts_with("gpt-5") => Self::O200kBase,
+            None => Self::Cl100kBase,
+        }
     }
-}
 
-impl std::fmt::Display for TokenCount {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.0)
+    fn bpe(self) -> &'static CoreBPE {
+        match self {
+            Self::Cl100kBase => tiktoken_rs::cl100k_base_singleton(),
+            Self::O200kBase => tiktoken_rs::o200k_base_singleton(),
+        }
     }
-}
 
-pub struct TokenCounter;
+    /// Counts the tokens in `text`.
+    pub fn count(self, text: &str) -> usize {
+        if text.len() < MIN_CACHED_LEN {
+            return self.count_uncached(text);
+        }
 
-impl TokenCounter {
-    pub const TOKEN_TO_CHAR_RATIO: usize = 4;
+        let mut hasher = DefaultHasher::new();
+        text.hash(&mut hasher);
+        let key = (self, hasher.finish());
+        if let Some(count) = COUNT_CACHE.lock().ok().and_then(|cache| cache.get(&key).copied()) {
+            return count;
+        }
 
-    /// Estimates the number of tokens in the input content.
-    /// Currently uses a simple heuristic: content length / TOKEN_TO_CHAR_RATIO
-    ///
-    /// Rounds up to the nearest multiple of 10 to avoid giving users a false sense of precision.
-    pub fn count_tokens(content: &str) -> usize {
-        Self::count_tokens_char_count(content.len())
+        let count = self.count_uncached(text);
+        if let Ok(mut cache) = COUNT_CACHE.lock() {
+            if cache.len() >= MAX_CACHE_ENTRIES {
+                cache.clear();
+            }
+            cache.insert(key, count);
+        }
+        count
+    }
+
+    fn count_uncached(self, text: &str) -> usize {
+        let bpe = self.bpe();
+        chunks(text).map(|chunk| bpe.encode_ordinary(chunk).len()).sum()
     }
 
-    fn count_tokens_char_count(count: usize) -> usize {
-        (count / Self::TOKEN_TO_CHAR_RATIO + 5) / 10 * 10
+    /// Truncates `text` to its first `max_tokens` tokens, returning whether it was truncated.
+    pub fn truncate(self, text: &mut String, max_tokens: usize) -> bool {
+        match self.truncated_len(text, max_tokens) {
+            Some(len) => {
+                text.truncate(len);
+                true
+            },
+            None => false,
+        }
     }
 
-    pub const fn token_to_chars(token: usize) -> usize {
-        token * Self::TOKEN_TO_CHAR_RATIO
+    /// Length in bytes of the first `max_tokens` tokens of `text`, or [None] if it has no more
+    /// tokens than that.
+    fn truncated_len(self, text: &str, max_tokens: usize) -> Option<usize> {
+        let bpe = self.bpe();
+        let mut tokens = 0;
+        let mut len = 0;
+        for chunk in chunks(text) {
+            let encoded = bpe.encode_ordinary(chunk);
+            if tokens + encoded.len() <= max_tokens {
+                tokens += encoded.len();
+                len += chunk.len();
+                continue;
+            }
+
+            let kept = bpe
+                ._decode_native_and_split(encoded[..max_tokens - tokens].to_vec())
+                .map(|bytes| bytes.len())
+                .sum::<usize>();
+            let mut end = len + kept;
+            while !text.is_char_boundary(end) {
+                end -= 1;
+            }
+            return Some(end);
+        }
+        None
     }
 }
 
-/// A trait for types that represent some number of characters (aka bytes). For use in calculating
-/// context window size utilization.
-pub trait CharCounter {
-    /// Returns the number of characters contained within this type.
-    ///
-    /// One "character" is essentially the same as one "byte"
-    fn char_count(&self) -> CharCount;
+/// Splits `text` into chunks of at most [MAX_CHUNK_LEN] bytes, cutting right before a whitespace
+/// character where possible so that the pieces of text the encoder sees are left unchanged.
+fn chunks(text: &str) -> impl Iterator<Item = &str> {
+    let mut rest = text;
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        if rest.len() <= MAX_CHUNK_LEN {
+            return Some(std::mem::take(&mut rest));
+        }
+
+        let mut end = MAX_CHUNK_LEN;
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        if let Some(whitespace) = rest[..end].rfind(char::is_whitespace).filter(|&i| i > 0) {
+            end = whitespace;
+        }
+        let (chunk, remainder) = rest.split_at(end);
+        rest = remainder;
+        Some(chunk)
+    })
 }
 
-impl CharCounter for BackendConversationState<'_> {
-    fn char_count(&self) -> CharCount {
-        self.calculate_conversation_size().char_count()
+pub struct TokenCounter;
+
+impl TokenCounter {
+    /// Counts the tokens in the input content with the default [Tokenizer].
+    pub fn count_tokens(content: &str) -> usize {
+        Tokenizer::default().count(content)
     }
 }
 
-impl CharCounter for ConversationSize {
-    fn char_count(&self) -> CharCount {
-        self.user_messages + self.assistant_messages + self.context_messages
-    }
+/// A trait for types that hold some number of tokens. For use in calculating context window size
+/// utilization.
+pub trait CountTokens {
+    /// Returns the number of tokens contained within this type, as encoded by `tokenizer`.
+    fn token_count(&self, tokenizer: Tokenizer) -> TokenCount;
 }
 
-impl CharCounter for UserMessage {
-    fn char_count(&self) -> CharCount {
-        let mut total_chars = 0;
-        total_chars += self.additional_context().len();
+impl CountTokens for UserMessage {
+    fn token_count(&self, tokenizer: Tokenizer) -> TokenCount {
+        let mut total_tokens = tokenizer.count(self.additional_context());
         match self.content() {
             UserMessageContent::Prompt { prompt } => {
-                total_chars += prompt.len();
+                total_tokens += tokenizer.count(prompt);
             },
             UserMessageContent::CancelledToolUses {
                 prompt,
                 tool_use_results,
             } => {
-                total_chars += prompt.as_ref().map_or(0, String::len);
-                total_chars += tool_use_results.as_slice().char_count().0;
+                total_tokens += prompt.as_deref().map_or(0, |prompt| tokenizer.count(prompt));
+                total_tokens += *tool_use_results.as_slice().token_count(tokenizer);
             },
             UserMessageContent::ToolUseResults { tool_use_results } => {
-                total_chars += tool_use_results.as_slice().char_count().0;
+                total_tokens += *tool_use_results.as_slice().token_count(tokenizer);
             },
         }
-        total_chars.into()
+        total_tokens.into()
     }
 }
 
-impl CharCounter for AssistantMessage {
-    fn char_count(&self) -> CharCount {
-        let mut total_chars = 0;
-        total_chars += self.content().len();
+impl CountTokens for AssistantMessage {
+    fn token_count(&self, tokenizer: Tokenizer) -> TokenCount {
+        let mut total_tokens = tokenizer.count(self.content());
         if let Some(tool_uses) = self.tool_uses() {
-            total_chars += tool_uses
+            total_tokens += tool_uses
                 .iter()
-                .map(|v| calculate_value_char_count(&v.args))
-                .reduce(|acc, e| acc + e)
-                .unwrap_or_default();
+                .map(|v| count_value_tokens(&v.args, tokenizer))
+                .sum::<usize>();
         }
-        total_chars.into()
+        total_tokens.into()
     }
 }
 
-impl CharCounter for &[ToolUseResult] {
-    fn char_count(&self) -> CharCount {
+impl CountTokens for &[ToolUseResult] {
+    fn token_count(&self, tokenizer: Tokenizer) -> TokenCount {
         self.iter()
             .flat_map(|v| &v.content)
-            .fold(0, |acc, v| {
-                acc + match v {
-                    ToolUseResultBlock::Json(v) => calculate_value_char_count(v),
-                    ToolUseResultBlock::Text(s) => s.len(),
-                }
+            .map(|v| match v {
+                ToolUseResultBlock::Json(v) => count_value_tokens(v, tokenizer),
+                ToolUseResultBlock::Text(s) => tokenizer.count(s),
             })
+            .sum::<usize>()
             .into()
     }
 }
 
-fn calculate_value_char_count(document: &serde_json::Value) -> usize {
-    match document {
-        serde_json::Value::Null => 1,
-        serde_json::Value::Bool(_) => 1,
-        serde_json::Value::Number(_) => 1,
-        serde_json::Value::String(s) => s.len(),
-        serde_json::Value::Array(vec) => vec.iter().fold(0, |acc, v| acc + calculate_value_char_count(v)),
-        serde_json::Value::Object(map) => map.values().fold(0, |acc, v| acc + calculate_value_char_count(v)),
+/// Counts the tokens of a JSON value as it is sent to the model.
+fn count_value_tokens(value: &serde_json::Value, tokenizer: Tokenizer) -> usize {
+    match value {
+        serde_json::Value::String(s) => tokenizer.count(s),
+        value => tokenizer.count(&value.to_string()),
     }
 }
 
 #[cfg(test)]
 mod tests {
-
     use super::*;
 
     #[test]
     fn test_token_count() {
-        let text = "This is a test sentence.";
-        let count = TokenCounter::count_tokens(text);
-        assert_eq!(count, (text.len() / 3 + 5) / 10 * 10);
+        assert_eq!(TokenCounter::count_tokens(""), 0);
+        assert_eq!(TokenCounter::count_tokens("This is a test sentence."), 6);
+        assert_eq!(Tokenizer::O200kBase.count("This is a test sentence."), 6);
     }
 
     #[test]
-    fn test_calculate_value_char_count() {
-        // Test simple types
-        assert_eq!(
-            calculate_value_char_count(&serde_json::Value::String("hello".to_string())),
-            5
+    fn test_tokenizer_for_model() {
+        assert_eq!(Tokenizer::for_model_id("claude-sonnet-4"), Tokenizer::Cl100kBase);
+        assert_eq!(Tokenizer::for_model_id("gpt-4o-mini"), Tokenizer::O200kBase);
+        assert_eq!(Tokenizer::for_model_id("openai:gpt-4.1-mini"), Tokenizer::O200kBase);
+        assert_eq!(Tokenizer::for_model_id("openai:openai/gpt-5"), Tokenizer::O200kBase);
+        assert_eq!(Tokenizer::for_model_id("gpt-3.5-turbo"), Tokenizer::Cl100kBase);
+        assert_eq!(Tokenizer::for_model(None), Tokenizer::Cl100kBase);
+    }
+
+    #[test]
+    fn test_chunks() {
+        let mut text = "fn main() {\n    println!(\"héllo wörld\");\n}\n".repeat(50);
+        text.push_str(&"é".repeat(500));
+        let chunks = chunks(&text).collect::<Vec<_>>();
+        assert!(
+            chunks
+                .iter()
+                .all(|chunk| !chunk.is_empty() && chunk.len() <= MAX_CHUNK_LEN)
         );
+        assert_eq!(chunks.concat(), text);
+
+        // Cutting right before whitespace leaves the pieces seen by the encoder unchanged.
+        let bpe = Tokenizer::Cl100kBase.bpe();
+        let prose = "The quick brown fox jumps over the lazy dog, then naps. ".repeat(200);
+        assert_eq!(Tokenizer::Cl100kBase.count(&prose), bpe.encode_ordinary(&prose).len());
+    }
+
+    #[test]
+    fn test_count_cache() {
+        let text = "cached text ".repeat(200);
+        assert_eq!(Tokenizer::Cl100kBase.count(&text), Tokenizer::Cl100kBase.count(&text));
+        assert_ne!(Tokenizer::Cl100kBase.count(&text), 0);
+    }
+
+    #[test]
+    fn test_truncate() {
+        let mut text = "The quick brown fox jumps over the lazy dog".to_string();
+        assert!(Tokenizer::default().truncate(&mut text, 3));
+        assert_eq!(text, "The quick brown");
+        assert!(!Tokenizer::default().truncate(&mut text, 3));
+
+        let mut text = "日本語のテキスト".to_string();
+        assert!(Tokenizer::default().truncate(&mut text, 1));
+        assert!(text.len() < "日本語のテキスト".len());
+    }
+
+    #[test]
+    fn test_count_value_tokens() {
+        let tokenizer = Tokenizer::default();
         assert_eq!(
-            calculate_value_char_count(&serde_json::Value::Number(serde_json::Number::from(123))),
+            count_value_tokens(&serde_json::Value::String("hello".to_string()), tokenizer),
             1
         );
-        assert_eq!(calculate_value_char_count(&serde_json::Value::Bool(true)), 1);
-        assert_eq!(calculate_value_char_count(&serde_json::Value::Null), 1);
-
-        // Test array
-        let array = serde_json::Value::Array(vec![
-            serde_json::Value::String("test".to_string()),
-            serde_json::Value::Number(serde_json::Number::from(42)),
-            serde_json::Value::Bool(false),
-        ]);
-        assert_eq!(calculate_value_char_count(&array), 6); // "test" (4) + Number (1) + Bool (1)
-
-        // Test object
-        let mut obj = serde_json::Map::new();
-        obj.insert("key1".to_string(), serde_json::Value::String("value1".to_string()));
-        obj.insert(
-            "key2".to_string(),
-            serde_json::Value::Number(serde_json::Number::from(99)),
-        );
-        let object = serde_json::Value::Object(obj);
-        assert_eq!(calculate_value_char_count(&object), 7); // "value1" (6) + Number (1)
-
-        // Test nested structure
-        let mut nested_obj = serde_json::Map::new();
-        let mut inner_obj = serde_json::Map::new();
-        inner_obj.insert(
-            "inner_key".to_string(),
-            serde_json::Value::String("inner_value".to_string()),
-        );
-        nested_obj.insert("outer_key".to_string(), serde_json::Value::Object(inner_obj));
-        nested_obj.insert(
-            "array_key".to_string(),
-            serde_json::Value::Array(vec![
-                serde_json::Value::String("item1".to_string()),
-                serde_json::Value::String("item2".to_string()),
-            ]),
-        );
-
-        let complex = serde_json::Value::Object(nested_obj);
-        assert_eq!(calculate_value_char_count(&complex), 21); // "inner_value" (11) + "item1" (5) + "item2" (5)
-
-        // Test empty structures
-        assert_eq!(calculate_value_char_count(&serde_json::Value::Array(vec![])), 0);
+        let value = serde_json::json!({ "path": "src/main.rs", "command": "view" });
         assert_eq!(
-            calculate_value_char_count(&serde_json::Value::Object(serde_json::Map::new())),
-            0
+            count_value_tokens(&value, tokenizer),
+            tokenizer.count(&value.to_string())
         );
     }
 }
@@ -39,6 +39,9 @@ use rmcp::model::{
     GetPromptRequestParam,
     GetPromptResult,
     Prompt,
+    ReadResourceRequestParam,
+    SubscribeRequestParam,
+    UnsubscribeRequestParam,
 };
 use tokio::signal::ctrl_c;
 use tokio::sync::{
@@ -70,6 +73,7 @@ use crate::cli::chat::server_messenger::{
     ServerMessengerBuilder,
     UpdateEventMessage,
 };
+use crate::cli::chat::tools::code_search::CodeSearch;
 use crate::cli::chat::tools::custom_tool::CustomTool;
 use crate::cli::chat::tools::execute::ExecuteCommand;
 use crate::cli::chat::tools::fs_read::FsRead;
@@ -77,6 +81,10 @@ use crate::cli::chat::tools::fs_write::FsWrite;
 use crate::cli::chat::tools::gh_issue::GhIssue;
 use crate::cli::chat::tools::introspect::Introspect;
 use crate::cli::chat::tools::knowledge::Knowledge;
+use crate::cli::chat::tools::mcp_resources::{
+    McpResources,
+    McpResourcesOperation,
+};
 use crate::cli::chat::tools::thinking::Thinking;
 use crate::cli::chat::tools::todo::TodoList;
 use crate::cli::chat::tools::use_aws::UseAws;
@@ -87,11 +95,19 @@ use crate::cli::chat::tools::{
 };
 use crate::database::Database;
 use crate::database::settings::Setting;
+use crate::mcp_client::elicitation::ElicitationHandler;
 use crate::mcp_client::messenger::Messenger;
+use crate::mcp_client::resources::ResourceStore;
+use crate::mcp_client::roots::{
+    RootsHandler,
+    collect_roots,
+};
+use crate::mcp_client::sampling::SamplingHandler;
 use crate::mcp_client::{
     InitializedMcpClient,
     InnerService,
     McpClientService,
+    RunningService,
 };
 use crate::os::Os;
 use crate::telemetry::TelemetryThread;
@@ -168,6 +184,10 @@ pub struct ToolManagerBuilder {
     pending_clients: Option<Arc<RwLock<HashSet<String>>>>,
     is_first_launch: bool,
     agent: Option<Arc<Mutex<Agent>>>,
+    sampling_handler: Option<SamplingHandler>,
+    roots_handler: Option<RootsHandler>,
+    elicitation_handler: Option<ElicitationHandler>,
+    resource_store: Option<ResourceStore>,
 }
 
 impl Default for ToolManagerBuilder {
@@ -185,6 +205,10 @@ impl Default for ToolManagerBuilder {
             pending_clients: Default::default(),
             is_first_launch: true,
             agent: Default::default(),
+            sampling_handler: Default::default(),
+            roots_handler: Default::default(),
+            elicitation_handler: Default::default(),
+            resource_store: Default::default(),
         }
     }
 }
@@ -204,6 +228,10 @@ impl From<&mut ToolManager> for ToolManagerBuilder {
             mcp_load_record: value.mcp_load_record.clone(),
             new_tool_specs: value.new_tool_specs.clone(),
             pending_clients: Some(value.pending_clients.clone()),
+            sampling_handler: value.sampling_handler.clone(),
+            roots_handler: Some(value.roots_handler.clone()),
+            elicitation_handler: value.elicitation_handler.clone(),
+            resource_store: Some(value.resource_store.clone()),
             // if we are getting a builder from an instantiated tool manager this field would be
             // false
             is_first_launch: false,
@@ -264,6 +292,20 @@ impl ToolManagerBuilder {
             Some(agent) => agent.lock().await.mcp_servers.clone(),
             None => Default::default(),
         };
+        // Roots are initially derived from the agent alone. Paths added through /context are
+        // accounted for once the conversation starts.
+        let roots_handler = self.roots_handler.take().unwrap_or_default();
+        match &self.agent {
+            Some(agent) => {
+                let agent = agent.lock().await;
+                let context_paths = agent
+                    .resources
+                    .iter()
+                    .filter_map(|resource| resource.strip_prefix("file://"));
+                roots_handler.set_roots(collect_roots(os, Some(&agent), context_paths));
+            },
+            None => roots_handler.set_roots(collect_roots(os, None, [])),
+        }
         debug_assert!(self.conversation_id.is_some());
         let conversation_id = self.conversation_id.ok_or(eyre::eyre!("Missing conversation id"))?;
 
@@ -315,6 +357,15 @@ impl ToolManagerBuilder {
         let agent = self.agent.unwrap_or_default();
         let database = os.database.clone();
         let mut messenger_builder = self.messenger_builder.take();
+        let sampling_handler = self
+            .sampling_handler
+            .take()
+            .unwrap_or_else(|| SamplingHandler::new(os.client.clone(), interactive));
+        let elicitation_handler = self
+            .elicitation_handler
+            .take()
+            .unwrap_or_else(|| ElicitationHandler::new(interactive));
+        let resource_store = self.resource_store.take().unwrap_or_default();
 
         let mut loading_servers = HashMap::<String, Instant>::new();
         for (server_name, _) in &pre_initialized {
@@ -364,6 +415,7 @@ impl ToolManagerBuilder {
                 new_tool_specs,
                 total,
                 conv_id,
+                resource_store.clone(),
             );
         }
 
@@ -378,6 +430,10 @@ impl ToolManagerBuilder {
                         server_name.clone(),
                         server_config,
                         messenger_builder.build_with_name(server_name),
+                        Some(sampling_handler.clone()),
+                        roots_handler.clone(),
+                        elicitation_handler.clone(),
+                        resource_store.clone(),
                     ),
                 )
             })
@@ -440,6 +496,10 @@ impl ToolManagerBuilder {
             },
             messenger_builder: Some(messenger_builder),
             is_first_launch: self.is_first_launch,
+            sampling_handler: Some(sampling_handler),
+            roots_handler,
+            elicitation_handler: Some(elicitation_handler),
+            resource_store,
             ..Default::default()
         })
     }
@@ -483,6 +543,8 @@ enum OutOfSpecName {
 pub struct ToolInfo {
     pub server_name: String,
     pub host_tool_name: HostToolName,
+    /// Whether the server annotated the tool with `readOnlyHint`
+    pub read_only: bool,
 }
 
 impl Borrow<HostToolName> for ToolInfo {
@@ -598,6 +660,19 @@ pub struct ToolManager {
     pub agent: Arc<Mutex<Agent>>,
 
     is_first_launch: bool,
+
+    /// Services sampling requests from mcp servers. This is shared by all of the clients spawned
+    /// and is carried over when the agent is swapped so that token usage persists.
+    sampling_handler: Option<SamplingHandler>,
+
+    /// Provides the roots exposed to mcp servers and notifies them when those change
+    roots_handler: RootsHandler,
+
+    /// Prompts the user for information requested by mcp servers
+    elicitation_handler: Option<ElicitationHandler>,
+
+    /// Resources offered by mcp servers and the contents of those attached to the context
+    resource_store: ResourceStore,
 }
 
 impl Clone for ToolManager {
@@ -617,6 +692,70 @@ impl Clone for ToolManager {
 }
 
 impl ToolManager {
+    /// Sets the model used to answer sampling requests from mcp servers.
+    pub fn set_sampling_model(&self, model_id: Option<String>) {
+        if let Some(sampling_handler) = &self.sampling_handler {
+            sampling_handler.set_model_id(model_id);
+        }
+    }
+
+    /// Updates the roots exposed to mcp servers, notifying them if they changed.
+    pub fn set_roots(&self, roots: Vec<PathBuf>) {
+        self.roots_handler.set_roots(roots);
+    }
+
+    /// The resources offered by mcp servers and the contents of those attached to the context.
+    pub fn resource_store(&self) -> &ResourceStore {
+        &self.resource_store
+    }
+
+    async fn running_service(&mut self, server_name: &str) -> eyre::Result<RunningService> {
+        let client = self
+            .clients
+            .get_mut(server_name)
+            .ok_or(eyre::eyre!("No mcp server named {server_name} is loaded"))?;
+        Ok(client.get_running_service().await?.clone())
+    }
+
+    /// Reads a resource and attaches its contents to the context. If the server supports it, the
+    /// resource is also subscribed to so that its contents are refreshed when it changes.
+    pub async fn attach_resource(&mut self, server_name: &str, uri: &str) -> eyre::Result<()> {
+        let running_service = self.running_service(server_name).await?;
+        let result = running_service
+            .read_resource(ReadResourceRequestParam { uri: uri.to_string() })
+            .await?;
+        self.resource_store.attach(server_name, uri, &result);
+
+        if running_service.supports_resource_subscription() {
+            if let Err(e) = running_service
+                .subscribe(SubscribeRequestParam { uri: uri.to_string() })
+                .await
+            {
+                warn!("Failed to subscribe to resource {uri} from {server_name}: {e}");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Removes a resource from the context, unsubscribing from it if applicable.
+    pub async fn detach_resource(&mut self, server_name: &str, uri: &str) {
+        if !self.resource_store.detach(server_name, uri) {
+            return;
+        }
+
+        if let Ok(running_service) = self.running_service(server_name).await {
+            if running_service.supports_resource_subscription() {
+                if let Err(e) = running_service
+                    .unsubscribe(UnsubscribeRequestParam { uri: uri.to_string() })
+                    .await
+                {
+                    warn!("Failed to unsubscribe from resource {uri} from {server_name}: {e}");
+                }
+            }
+        }
+    }
+
     /// Swapping agent involves the following:
     /// - Dropping all of the clients first to avoid resource contention
     /// - Clearing fields that are already referenced by background tasks. We can't simply spawn new
@@ -710,6 +849,9 @@ impl ToolManager {
             if !crate::cli::chat::tools::todo::TodoList::is_enabled(os) {
                 tool_specs.remove("todo_list");
             }
+            if self.clients.is_empty() {
+                tool_specs.remove("mcp_resources");
+            }
 
             #[cfg(windows)]
             {
@@ -736,6 +878,7 @@ impl ToolManager {
                     },
                         "required": ["command"]})),
                     tool_origin: ToolOrigin::Native,
+                    read_only: false,
                 });
             }
 
@@ -840,6 +983,7 @@ impl ToolManager {
 
         Ok(match value.name.as_str() {
             "fs_read" => Tool::FsRead(serde_json::from_value::<FsRead>(value.args).map_err(map_err)?),
+            "code_search" => Tool::CodeSearch(serde_json::from_value::<CodeSearch>(value.args).map_err(map_err)?),
             "fs_write" => Tool::FsWrite(serde_json::from_value::<FsWrite>(value.args).map_err(map_err)?),
             #[cfg(windows)]
             "execute_cmd" => {
@@ -855,6 +999,24 @@ impl ToolManager {
             "thinking" => Tool::Thinking(serde_json::from_value::<Thinking>(value.args).map_err(map_err)?),
             "knowledge" => Tool::Knowledge(serde_json::from_value::<Knowledge>(value.args).map_err(map_err)?),
             "todo_list" => Tool::Todo(serde_json::from_value::<TodoList>(value.args).map_err(map_err)?),
+            "mcp_resources" => {
+                let operation = serde_json::from_value::<McpResourcesOperation>(value.args).map_err(map_err)?;
+                let client = match &operation {
+                    McpResourcesOperation::Read { server_name, .. } => {
+                        Some(self.running_service(server_name).await.map_err(|e| ToolResult {
+                            tool_use_id: value.id.clone(),
+                            content: vec![ToolResultContentBlock::Text(e.to_string())],
+                            status: ToolResultStatus::Error,
+                        })?)
+                    },
+                    McpResourcesOperation::List { .. } => None,
+                };
+                Tool::McpResources(McpResources {
+                    operation,
+                    store: self.resource_store.clone(),
+                    client,
+                })
+            },
             // Note that this name is namespaced with server_name{DELIMITER}tool_name
             name => {
                 // Note: tn_map also has tools that underwent no transformation. In otherwords, if
@@ -862,6 +1024,7 @@ impl ToolManager {
                 let ToolInfo {
                     server_name,
                     host_tool_name: tool_name,
+                    read_only,
                 } = match self.tn_map.get(name) {
                     Some(tool_info) => Ok::<&ToolInfo, ToolResult>(tool_info),
                     None => {
@@ -896,6 +1059,7 @@ impl ToolManager {
                     server_name: server_name.to_owned(),
                     client: running_service.clone(),
                     params: value.args.as_object().cloned(),
+                    read_only: *read_only,
                 })
             },
         })
@@ -1228,6 +1392,7 @@ fn spawn_display_task(
 /// - Listens for prompt list request and serve them. Unlike tools, we do *not* cache prompts on the
 ///   conversation state. This is because prompts do not need to be sent to the model every turn.
 ///   Instead, the prompts are cached in a hashmap that is owned by the orchestrator task.
+/// - Records the resources and resource templates offered by servers in the [ResourceStore].
 ///
 /// Note that there should be exactly one instance of this task running per session. Should there
 /// be any need to instantiate a new [ToolManager] (e.g. swapping agents), see
@@ -1250,6 +1415,7 @@ fn spawn_orchestrator_task(
     new_tool_specs: NewToolSpecs,
     total: usize,
     conv_id: String,
+    resource_store: ResourceStore,
 ) {
     tokio::spawn(async move {
         use tokio::sync::broadcast::Sender as BroadcastSender;
@@ -1339,6 +1505,7 @@ fn spawn_orchestrator_task(
             notify_weak: &std::sync::Weak<Notify>,
             initialized: &mut HashSet<String>,
             prompts: &mut HashMap<String, Vec<PromptBundle>>,
+            resource_store: &ResourceStore,
             total: usize,
         ) {
             record_temp_buf.clear();
@@ -1437,6 +1604,11 @@ fn spawn_orchestrator_task(
                                     description: v.description.as_ref().map(|d| d.to_string()).unwrap_or_default(),
                                     input_schema: crate::cli::chat::tools::InputSchema(v.schema_as_json_value()),
                                     tool_origin: ToolOrigin::Native,
+                                    read_only: v
+                                        .annotations
+                                        .as_ref()
+                                        .and_then(|annotations| annotations.read_only_hint)
+                                        .unwrap_or(false),
                                 })
                                 .filter(|spec| tool_filter.should_include(&spec.name))
                                 .collect::<Vec<_>>();
@@ -1617,8 +1789,18 @@ fn spawn_orchestrator_task(
                             .or_insert(vec![record]);
                     },
                 },
-                UpdateEventMessage::ListResourcesResult { .. } => {},
-                UpdateEventMessage::ResourceTemplatesListResult { .. } => {},
+                UpdateEventMessage::ListResourcesResult {
+                    server_name, result, ..
+                } => match result {
+                    Ok(result) => resource_store.set_resources(&server_name, result.resources),
+                    Err(e) => error!("Error fetching resources from server {server_name}: {:?}", e),
+                },
+                UpdateEventMessage::ResourceTemplatesListResult {
+                    server_name, result, ..
+                } => match result {
+                    Ok(result) => resource_store.set_templates(&server_name, result.resource_templates),
+                    Err(e) => error!("Error fetching resource templates from server {server_name}: {:?}", e),
+                },
                 UpdateEventMessage::OauthLink { server_name, link } => {
                     let mut buf_writer = BufWriter::new(&mut *record_temp_buf);
                     let msg = eyre::eyre!(link);
@@ -1653,13 +1835,14 @@ fn spawn_orchestrator_task(
                     loading_servers.insert(server_name, std::time::Instant::now());
                 },
                 UpdateEventMessage::Deinit { server_name, .. } => {
-                    // Only prompts are stored here so we'll just be clearing that
+                    // Only prompts and resources are stored here so we'll just be clearing those
                     // In the future if we are also storing tools, we need to make sure that
                     // the tools are also pruned.
                     for (_prompt_name, bundles) in prompts.iter_mut() {
                         bundles.retain(|bundle| bundle.server_name != server_name);
                     }
                     prompts.retain(|_, bundles| !bundles.is_empty());
+                    resource_store.remove_server(&server_name);
                     has_new_stuff.store(true, Ordering::Release);
                 },
             }
@@ -1688,6 +1871,7 @@ fn spawn_orchestrator_task(
                             &notify_weak,
                             &mut initialized,
                             &mut prompts,
+                            &resource_store,
                             total
                         ).await;
                 },
@@ -1754,6 +1938,7 @@ async fn process_tool_specs(
         tn_map.insert(model_tool_name.clone(), ToolInfo {
             server_name: server_name.to_string(),
             host_tool_name: spec.name.clone(),
+            read_only: spec.read_only,
         });
         spec.name = model_tool_name;
         spec.tool_origin = ToolOrigin::McpServer(server_name.to_string());
@@ -0,0 +1,557 @@
+use std::io::Write;
+use std::path::{
+    Path,
+    PathBuf,
+};
+
+use crossterm::queue;
+use crossterm::style::{
+    self,
+    Color,
+};
+use eyre::{
+    Result,
+    bail,
+};
+use globset::{
+    Glob,
+    GlobSet,
+    GlobSetBuilder,
+};
+use ignore::WalkBuilder;
+use regex::{
+    Regex,
+    RegexBuilder,
+};
+use serde::Deserialize;
+use tracing::debug;
+
+use super::fs_read::{
+    FsDirectory,
+    FsRead,
+    FsReadOperation,
+    denied_paths,
+};
+use super::{
+    InvokeOutput,
+    MAX_TOOL_RESPONSE_SIZE,
+    OutputKind,
+    display_purpose,
+    expand_path_tool_arg,
+};
+use crate::cli::agent::{
+    Agent,
+    PermissionEvalResult,
+};
+use crate::cli::chat::sanitize_unicode_tags;
+use crate::cli::chat::util::truncate_safe;
+use crate::os::Os;
+use crate::util::pattern_matching::matches_any_pattern;
+
+/// Recursively searches the files under a directory for a regular expression, skipping the files
+/// ignored by git.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CodeSearch {
+    /// Regular expression to search for
+    pub pattern: String,
+    /// Directory or file to search, defaults to the current working directory
+    #[serde(default)]
+    pub path: Option<String>,
+    /// Only search files matching one of these globs
+    #[serde(default)]
+    pub include: Vec<String>,
+    /// Skip files matching one of these globs
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    #[serde(default)]
+    pub ignore_case: bool,
+    /// Number of lines shown before and after each match
+    pub context_lines: Option<usize>,
+    /// Maximum number of matching lines returned
+    pub max_matches: Option<usize>,
+    /// Number of matching lines to skip, used to continue a truncated search
+    #[serde(default)]
+    pub offset: usize,
+    pub summary: Option<String>,
+}
+
+impl CodeSearch {
+    const DEFAULT_MAX_MATCHES: usize = 100;
+    const MAX_CONTEXT_LINES: usize = 10;
+    /// Files larger than this are not searched, they are most likely generated.
+    const MAX_FILE_SIZE: u64 = 2 * 1024 * 1024;
+    /// Lines longer than this many bytes are cut when shown, e.g. the lines of minified files.
+    const MAX_LINE_LENGTH: usize = 500;
+
+    pub async fn validate(&mut self, os: &Os) -> Result<()> {
+        if self.pattern.is_empty() {
+            bail!("Search pattern cannot be empty");
+        }
+        self.regex()?;
+        build_globset(&self.include)?;
+        build_globset(&self.exclude)?;
+
+        let path = self.search_root(os)?;
+        if !os.fs.exists(&path) {
+            bail!("Path not found: {}", self.path.as_deref().unwrap_or("."));
+        }
+        Ok(())
+    }
+
+    pub fn queue_description(&self, updates: &mut impl Write) -> Result<()> {
+        queue!(
+            updates,
+            style::Print("Searching for: "),
+            style::SetForegroundColor(Color::Green),
+            style::Print(&self.pattern),
+            style::ResetColor,
+            style::Print(" in "),
+            style::SetForegroundColor(Color::Green),
+            style::Print(self.path.as_deref().unwrap_or(".")),
+            style::ResetColor,
+        )?;
+        if !self.include.is_empty() {
+            queue!(
+                updates,
+                style::Print(format!(" (including {})", self.include.join(", ")))
+            )?;
+        }
+        if !self.exclude.is_empty() {
+            queue!(
+                updates,
+                style::Print(format!(" (excluding {})", self.exclude.join(", ")))
+            )?;
+        }
+        queue!(updates, style::Print("\n"))?;
+        display_purpose(self.summary.as_ref(), updates)
+    }
+
+    pub async fn invoke(&self, os: &Os, updates: &mut impl Write, agent: Option<&Agent>) -> Result<InvokeOutput> {
+        // The files are walked on a blocking thread, outside of [crate::os::Fs]
+        let root = os.fs.chroot_path(self.search_root(os)?);
+        debug!(?root, pattern = self.pattern, "Searching files");
+
+        let searcher = Searcher {
+            display_root: PathBuf::from(self.path.as_deref().unwrap_or("")),
+            regex: self.regex()?,
+            include: build_globset(&self.include)?,
+            exclude: build_globset(&self.exclude)?,
+            denied: agent.and_then(|agent| denied_paths(os, agent)),
+            context_lines: self.context_lines.unwrap_or_default().min(Self::MAX_CONTEXT_LINES),
+            offset: self.offset,
+            max_matches: self.max_matches.unwrap_or(Self::DEFAULT_MAX_MATCHES).max(1),
+            output_limit: MAX_TOOL_RESPONSE_SIZE,
+            ..Default::default()
+        };
+        let results = tokio::task::spawn_blocking(move || searcher.run(&root)).await?;
+
+        super::queue_function_result(
+            &format!(
+                "Found {} matches in {} files{}",
+                results.matches,
+                results.files,
+                if results.truncated { " (truncated)" } else { "" }
+            ),
+            updates,
+            false,
+            false,
+        )?;
+
+        let mut output = if results.matches == 0 && results.skipped == 0 && !results.truncated {
+            "No matches found".to_string()
+        } else if results.truncated {
+            format!(
+                "{}\n[Results truncated after {} matches. Call code_search again with \"offset\": {} to see more, or narrow the search with a more specific pattern, path or include globs.]",
+                results.output,
+                results.matches,
+                self.offset + results.matches + results.skipped
+            )
+        } else {
+            results.output
+        };
+        if results.skipped > 0 {
+            output.push_str(&format!(
+                "\n[{} matches were left out because the lines around them are too large to show. Use fewer context lines or a more specific path.]",
+                results.skipped
+            ));
+        }
+
+        Ok(InvokeOutput {
+            output: OutputKind::Text(output),
+        })
+    }
+
+    /// Searching is subject to the same `allowedPaths` and `deniedPaths` settings as reading the
+    /// searched directory with fs_read.
+    pub fn eval_perm(&self, os: &Os, agent: &Agent) -> PermissionEvalResult {
+        let fs_read = FsRead {
+            operations: vec![FsReadOperation::Directory(FsDirectory {
+                path: self.path.clone().unwrap_or_else(|| ".".to_string()),
+                depth: None,
+            })],
+            summary: None,
+        };
+
+        match fs_read.eval_perm(os, agent) {
+            PermissionEvalResult::Ask if matches_any_pattern(&agent.allowed_tools, "code_search") => {
+                PermissionEvalResult::Allow
+            },
+            result => result,
+        }
+    }
+
+    fn regex(&self) -> Result<Regex> {
+        Ok(RegexBuilder::new(&self.pattern)
+            .case_insensitive(self.ignore_case)
+            .build()?)
+    }
+
+    fn search_root(&self, os: &Os) -> Result<PathBuf> {
+        Ok(match &self.path {
+            Some(path) => expand_path_tool_arg(os, path),
+            None => os.env.current_dir()?,
+        })
+    }
+}
+
+fn build_globset(globs: &[String]) -> Result<Option<GlobSet>> {
+    if globs.is_empty() {
+        return Ok(None);
+    }
+    let mut builder = GlobSetBuilder::new();
+    for glob in globs {
+        builder.add(Glob::new(glob)?);
+    }
+    Ok(Some(builder.build()?))
+}
+
+#[derive(Debug, Default)]
+struct SearchResults {
+    output: String,
+    matches: usize,
+    files: usize,
+    /// Number of matches left out because the lines around them could never fit in the output
+    skipped: usize,
+    truncated: bool,
+}
+
+#[derive(Debug)]
+struct Searcher {
+    /// Prefix used for the paths shown in the results, i.e. the path as given by the model
+    display_root: PathBuf,
+    regex: Regex,
+    include: Option<GlobSet>,
+    exclude: Option<GlobSet>,
+    denied: Option<GlobSet>,
+    context_lines: usize,
+    offset: usize,
+    max_matches: usize,
+    output_limit: usize,
+    /// Number of matches seen so far, including the ones skipped because of the offset
+    seen: usize,
+    results: SearchResults,
+}
+
+impl Default for Searcher {
+    fn default() -> Self {
+        Self {
+            display_root: PathBuf::new(),
+            regex: Regex::new("").expect("empty regex is valid"),
+            include: None,
+            exclude: None,
+            denied: None,
+            context_lines: 0,
+            offset: 0,
+            max_matches: CodeSearch::DEFAULT_MAX_MATCHES,
+            output_limit: MAX_TOOL_RESPONSE_SIZE,
+            seen: 0,
+            results: SearchResults::default(),
+        }
+    }
+}
+
+impl Searcher {
+    fn run(mut self, root: &Path) -> SearchResults {
+        // Files ignored by git are skipped the way git does, including the rules of enclosing
+        // directories, .git/info/exclude and core.excludesFile
+        let denied = self.denied.clone();
+        let walker = WalkBuilder::new(root)
+            .hidden(false)
+            .ignore(false)
+            .require_git(false)
+            // Sorted so that a truncated search can be continued with an offset
+            .sort_by_file_name(|a, b| a.cmp(b))
+            .filter_entry(move |entry| {
+                entry.file_name() != ".git" && !denied.as_ref().is_some_and(|denied| denied.is_match(entry.path()))
+            })
+            .build();
+
+        for entry in walker {
+            if self.results.truncated {
+                break;
+            }
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(err) => {
+                    debug!(?err, "Failed to read directory entry");
+                    continue;
+                },
+            };
+            if !entry.file_type().is_some_and(|file_type| file_type.is_file()) {
+                continue;
+            }
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if metadata.len() > CodeSearch::MAX_FILE_SIZE {
+                continue;
+            }
+
+            let path = entry.path();
+            // A file given as the search root is searched regardless of the globs
+            if entry.depth() == 0 {
+                let display = match self.display_root.as_os_str().is_empty() {
+                    true => path.to_path_buf(),
+                    false => self.display_root.clone(),
+                };
+                self.search_file(path, &display);
+                continue;
+            }
+
+            let relative = path.strip_prefix(root).unwrap_or(path).to_path_buf();
+            if self
+                .include
+                .as_ref()
+                .is_some_and(|include| !include.is_match(&relative))
+                || self.exclude.as_ref().is_some_and(|exclude| exclude.is_match(&relative))
+            {
+                continue;
+            }
+            self.search_file(path, &self.display_root.join(relative));
+        }
+
+        self.results
+    }
+
+    /// Searches a single file, `display` being the path shown in the results.
+    fn search_file(&mut self, path: &Path, display: &Path) {
+        let Ok(bytes) = std::fs::read(path) else {
+            return;
+        };
+        // Skip binary files
+        if bytes.iter().take(8000).any(|b| *b == 0) {
+            return;
+        }
+
+        let content = sanitize_unicode_tags(&String::from_utf8_lossy(&bytes));
+        let lines = content.lines().collect::<Vec<_>>();
+        let mut matching = Vec::new();
+        for (index, line) in lines.iter().enumerate() {
+            if !self.regex.is_match(line) {
+                continue;
+            }
+            self.seen += 1;
+            if self.seen <= self.offset {
+                continue;
+            }
+            if self.results.matches + matching.len() == self.max_matches {
+                self.results.truncated = true;
+                break;
+            }
+            matching.push(index);
+        }
+        if matching.is_empty() {
+            return;
+        }
+
+        let mut file_output = format!("{}\n", display.display());
+        let mut shown = 0;
+        for group in group_matches(&matching, self.context_lines, lines.len()) {
+            let mut group_output = String::new();
+            if shown > 0 {
+                group_output.push_str("--\n");
+            }
+            for index in group.clone() {
+                let separator = if matching.binary_search(&index).is_ok() {
+                    ':'
+                } else {
+                    '-'
+                };
+                let line = lines[index];
+                let line = match line.len() > CodeSearch::MAX_LINE_LENGTH {
+                    true => format!("{}…", truncate_safe(line, CodeSearch::MAX_LINE_LENGTH)),
+                    false => line.to_string(),
+                };
+                group_output.push_str(&format!("{}{}{}\n", index + 1, separator, line));
+            }
+
+            let group_matches = matching.iter().filter(|index| group.contains(index)).count();
+            if self.results.output.len() + file_output.len() + group_output.len() > self.output_limit {
+                // A group that would not fit even on its own is skipped, as continuing the search
+                // later would stop at it again
+                if display.as_os_str().len() + 1 + group_output.len() > self.output_limit {
+                    self.results.skipped += group_matches;
+                    continue;
+                }
+                self.results.truncated = true;
+                break;
+            }
+            file_output.push_str(&group_output);
+            shown += group_matches;
+        }
+
+        if shown > 0 {
+            self.results.output.push_str(&file_output);
+            self.results.output.push('\n');
+            self.results.matches += shown;
+            self.results.files += 1;
+        }
+    }
+}
+
+/// Merges the ranges of lines shown around each match when they overlap or touch.
+fn group_matches(matching: &[usize], context_lines: usize, line_count: usize) -> Vec<std::ops::Range<usize>> {
+    let mut groups: Vec<std::ops::Range<usize>> = Vec::new();
+    for &index in matching {
+        let start = index.saturating_sub(context_lines);
+        let end = (index + context_lines + 1).min(line_count);
+        match groups.last_mut() {
+            Some(last) if start <= last.end => last.end = end.max(last.end),
+            _ => groups.push(start..end),
+        }
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(root: &Path, path: &str, content: &str) {
+        let path = root.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    fn searcher(pattern: &str) -> Searcher {
+        Searcher {
+            regex: Regex::new(pattern).unwrap(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_search_honors_gitignore_and_globs() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(root, ".gitignore", "target/\n");
+        write(root, "src/main.rs", "fn main() {\n    todo!()\n}\n");
+        write(root, "src/lib.rs", "// todo: document\n");
+        write(root, "README.md", "todo list\n");
+        write(root, "target/debug/out.rs", "todo\n");
+
+        let results = searcher("todo").run(root);
+        assert_eq!(results.matches, 3);
+        assert_eq!(results.files, 3);
+        assert!(!results.output.contains("target"));
+
+        let results = Searcher {
+            include: build_globset(&["*.rs".to_string()]).unwrap(),
+            exclude: build_globset(&["src/lib.rs".to_string()]).unwrap(),
+            context_lines: 1,
+            ..searcher("todo")
+        }
+        .run(root);
+        assert_eq!(results.output, "src/main.rs\n1-fn main() {\n2:    todo!()\n3-}\n\n");
+    }
+
+    #[test]
+    fn test_search_honors_git_excludes() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(root, ".git/info/exclude", "local.txt\n");
+        write(root, ".gitignore", "*.log\n");
+        write(root, "src/local.txt", "todo\n");
+        write(root, "src/out.log", "todo\n");
+        write(root, "src/lib.rs", "// todo\n");
+
+        // Rules of the enclosing repository also apply when searching a subdirectory
+        let results = Searcher {
+            display_root: PathBuf::from("src"),
+            ..searcher("todo")
+        }
+        .run(&root.join("src"));
+        assert_eq!(results.output, "src/lib.rs\n1:// todo\n\n");
+    }
+
+    #[test]
+    fn test_search_truncation_and_offset() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(root, "a.txt", "match 1\nmatch 2\n");
+        write(root, "b.txt", "match 3\n");
+
+        let results = Searcher {
+            max_matches: 2,
+            ..searcher("match")
+        }
+        .run(root);
+        assert!(results.truncated);
+        assert_eq!(results.matches, 2);
+        assert_eq!(results.output, "a.txt\n1:match 1\n2:match 2\n\n");
+
+        let results = Searcher {
+            max_matches: 2,
+            offset: 2,
+            ..searcher("match")
+        }
+        .run(root);
+        assert!(!results.truncated);
+        assert_eq!(results.output, "b.txt\n1:match 3\n\n");
+
+        let results = Searcher {
+            output_limit: 30,
+            ..searcher("match")
+        }
+        .run(root);
+        assert!(results.truncated);
+        assert_eq!(results.matches, 2);
+    }
+
+    #[test]
+    fn test_search_long_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(
+            root,
+            "bundle.min.js",
+            &format!("var match={};\n", "x".repeat(MAX_TOOL_RESPONSE_SIZE)),
+        );
+        write(root, "lib.js", "// match\n");
+
+        // Long lines are cut rather than filling the output
+        let results = searcher("match").run(root);
+        assert!(!results.truncated);
+        assert_eq!(results.matches, 2);
+        let line = results.output.lines().nth(1).unwrap();
+        assert!(line.starts_with("1:var match=xxx"));
+        assert!(line.ends_with('…'));
+        assert_eq!(line.len(), "1:".len() + CodeSearch::MAX_LINE_LENGTH + '…'.len_utf8());
+
+        // Lines that cannot fit at all are skipped without ending the search
+        let results = Searcher {
+            output_limit: 100,
+            ..searcher("match")
+        }
+        .run(root);
+        assert!(!results.truncated);
+        assert_eq!(results.skipped, 1);
+        assert_eq!(results.output, "lib.js\n1:// match\n\n");
+    }
+
+    #[test]
+    fn test_group_matches() {
+        assert_eq!(group_matches(&[1, 3, 10], 1, 12), vec![0..5, 9..12]);
+        assert_eq!(group_matches(&[0], 0, 1), vec![0..1]);
+    }
+}
@@ -3,16 +3,27 @@ use std::collections::HashMap;
 use std::io::Write;
 
 use crossterm::{
+    cursor,
     queue,
     style,
+    terminal,
+};
+use eyre::{
+    Result,
+    bail,
+};
+use futures::StreamExt;
+use rmcp::model::{
+    CallToolRequestParam,
+    CallToolResult,
+    ProgressNotificationParam,
 };
-use eyre::Result;
-use rmcp::model::CallToolRequestParam;
 use schemars::JsonSchema;
 use serde::{
     Deserialize,
     Serialize,
 };
+use tokio::sync::broadcast;
 use tracing::warn;
 
 use super::InvokeOutput;
@@ -23,6 +34,7 @@ use crate::cli::agent::{
 use crate::cli::chat::CONTINUATION_LINE;
 use crate::cli::chat::token_counter::TokenCounter;
 use crate::mcp_client::RunningService;
+use crate::mcp_client::sampling::SamplingConfig;
 use crate::os::Os;
 use crate::util::MCP_SERVER_TOOL_DELIMITER;
 use crate::util::pattern_matching::matches_any_pattern;
@@ -68,6 +80,9 @@ pub struct CustomToolConfig {
     /// A boolean flag to denote whether or not to load this mcp server
     #[serde(default)]
     pub disabled: bool,
+    /// Settings for sampling requests (model completions) initiated by this mcp server
+    #[serde(default, skip_serializing_if = "SamplingConfig::is_default")]
+    pub sampling: SamplingConfig,
     /// A flag to denote whether this is a server from the legacy mcp.json
     #[serde(skip)]
     pub is_from_legacy_mcp_json: bool,
@@ -92,16 +107,36 @@ pub struct CustomTool {
     /// Optional parameters to pass to the tool when invoking the method.
     /// Structured as a JSON value to accommodate various parameter types and structures.
     pub params: Option<serde_json::Map<String, serde_json::Value>>,
+    /// Whether the server annotated the tool with `readOnlyHint`
+    pub read_only: bool,
 }
 
+/// Width in characters of the progress bar shown for mcp tool calls that report a total.
+const PROGRESS_BAR_WIDTH: usize = 30;
+
 impl CustomTool {
-    pub async fn invoke(&self, _os: &Os, _updates: &mut impl Write) -> Result<InvokeOutput> {
+    pub async fn invoke(
+        &self,
+        _os: &Os,
+        updates: &mut impl Write,
+        mut ctrl_c: broadcast::Receiver<()>,
+    ) -> Result<InvokeOutput> {
         let params = CallToolRequestParam {
             name: Cow::from(self.name.clone()),
             arguments: self.params.clone(),
         };
 
-        let resp = self.client.call_tool(params.clone()).await?;
+        let resp = match self.call_with_progress(params.clone(), updates, &mut ctrl_c).await {
+            Err(CallOutcome::Failed(_)) if self.client.refresh_auth().await => {
+                self.call_with_progress(params, updates, &mut ctrl_c).await
+            },
+            result => result,
+        };
+        let resp = match resp {
+            Ok(resp) => resp,
+            Err(CallOutcome::Failed(err)) => return Err(err.into()),
+            Err(CallOutcome::Cancelled) => bail!("The user cancelled the tool call"),
+        };
 
         if resp.is_error.is_none_or(|v| !v) {
             Ok(InvokeOutput {
@@ -115,6 +150,68 @@ impl CustomTool {
         }
     }
 
+    /// Calls the tool while rendering the progress reported by the server. A ctrl+c received from
+    /// the chat session cancels the call by sending notifications/cancelled to the server.
+    async fn call_with_progress(
+        &self,
+        params: CallToolRequestParam,
+        updates: &mut impl Write,
+        ctrl_c: &mut broadcast::Receiver<()>,
+    ) -> Result<CallToolResult, CallOutcome> {
+        let (handle, mut progress) = self
+            .client
+            .call_tool_with_progress(params)
+            .await
+            .map_err(CallOutcome::Failed)?;
+
+        let id = handle.id.clone();
+        let peer = handle.peer.clone();
+        let response = RunningService::await_tool_result(handle);
+        tokio::pin!(response);
+        let mut progress_shown = false;
+
+        let outcome = loop {
+            tokio::select! {
+                result = &mut response => break result.map_err(CallOutcome::Failed),
+                Some(notification) = progress.next() => {
+                    let _ = queue!(
+                        updates,
+                        terminal::Clear(terminal::ClearType::CurrentLine),
+                        cursor::MoveToColumn(0),
+                        style::Print(format_progress(&notification)),
+                    );
+                    let _ = updates.flush();
+                    progress_shown = true;
+                },
+                Ok(_) = ctrl_c.recv() => {
+                    let notification = rmcp::model::CancelledNotification {
+                        params: rmcp::model::CancelledNotificationParam {
+                            request_id: id,
+                            reason: Some("Cancelled by the user".to_string()),
+                        },
+                        method: Default::default(),
+                        extensions: Default::default(),
+                    };
+                    if let Err(err) = peer.send_notification(notification.into()).await {
+                        warn!(?err, "Failed to cancel the tool call for {}", self.name);
+                    }
+                    break Err(CallOutcome::Cancelled);
+                },
+            }
+        };
+
+        if progress_shown {
+            let _ = queue!(
+                updates,
+                terminal::Clear(terminal::ClearType::CurrentLine),
+                cursor::MoveToColumn(0),
+            );
+            let _ = updates.flush();
+        }
+
+        outcome
+    }
+
     pub fn queue_description(&self, output: &mut impl Write) -> Result<()> {
         queue!(
             output,
@@ -172,3 +269,78 @@ impl CustomTool {
         PermissionEvalResult::Ask
     }
 }
+
+/// The ways in which a tool call can end without a result.
+enum CallOutcome {
+    Failed(rmcp::ServiceError),
+    Cancelled,
+}
+
+/// Renders a progress notification as a single line: a bar when the server reports a total, the
+/// raw progress otherwise.
+fn format_progress(notification: &ProgressNotificationParam) -> String {
+    let ProgressNotificationParam {
+        progress,
+        total,
+        message,
+        ..
+    } = notification;
+
+    let mut line = match total {
+        Some(total) if *total > 0.0 => {
+            let ratio = (progress / total).clamp(0.0, 1.0);
+            let filled = (ratio * PROGRESS_BAR_WIDTH as f64).round() as usize;
+            format!(
+                "[{}{}] {:>3}%",
+                "█".repeat(filled),
+                " ".repeat(PROGRESS_BAR_WIDTH - filled),
+                (ratio * 100.0).round() as usize
+            )
+        },
+        _ => format!("Progress: {progress}"),
+    };
+    if let Some(message) = message.as_deref().filter(|m| !m.is_empty()) {
+        line.push(' ');
+        line.push_str(message);
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use rmcp::model::{
+        NumberOrString,
+        ProgressToken,
+    };
+
+    use super::*;
+
+    fn notification(progress: f64, total: Option<f64>, message: Option<&str>) -> ProgressNotificationParam {
+        ProgressNotificationParam {
+            progress_token: ProgressToken(NumberOrString::Number(1)),
+            progress,
+            total,
+            message: message.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_format_progress() {
+        assert_eq!(
+            format_progress(&notification(15.0, Some(30.0), Some("Compiling"))),
+            format!("[{}{}]  50% Compiling", "█".repeat(15), " ".repeat(15))
+        );
+        assert_eq!(
+            format_progress(&notification(40.0, Some(30.0), None)),
+            format!("[{}] 100%", "█".repeat(30))
+        );
+        assert_eq!(
+            format_progress(&notification(3.0, None, Some("step 3"))),
+            "Progress: 3 step 3"
+        );
+        assert_eq!(
+            format_progress(&notification(2.5, Some(0.0), Some(""))),
+            "Progress: 2.5"
+        );
+    }
+}
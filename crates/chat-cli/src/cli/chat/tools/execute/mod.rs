@@ -1,4 +1,5 @@
 use std::io::Write;
+use std::time::Duration;
 
 use crossterm::queue;
 use crossterm::style::{
@@ -6,7 +7,6 @@ use crossterm::style::{
     Color,
 };
 use eyre::Result;
-use regex::Regex;
 use serde::Deserialize;
 use tracing::error;
 
@@ -36,6 +36,25 @@ mod unix;
 #[cfg(not(windows))]
 pub use unix::*;
 
+mod rules;
+mod sandbox;
+mod session;
+mod shell;
+pub use rules::CommandRule;
+use rules::Denial;
+pub use sandbox::{
+    Sandbox,
+    SandboxSettings,
+};
+pub use session::{
+    SessionOutput,
+    ShellSession,
+    ShellSessionSettings,
+};
+
+/// Name of the tool in `allowedTools` and `toolsSettings`.
+const TOOL_NAME: &str = if cfg!(windows) { "execute_cmd" } else { "execute_bash" };
+
 // Common readonly commands that are safe to execute without user confirmation
 pub const READONLY_COMMANDS: &[&str] = &[
     "ls", "cat", "echo", "pwd", "which", "head", "tail", "find", "grep", "dir", "type",
@@ -45,111 +64,79 @@ pub const READONLY_COMMANDS: &[&str] = &[
 pub struct ExecuteCommand {
     pub command: String,
     pub summary: Option<String>,
+    /// Set when the persistent shell session overrides commands, see
+    /// [ShellSession::overrides_commands]. A command name may then run something else, so
+    /// commands are not allowed by their name.
+    #[serde(skip)]
+    pub shell_overrides_commands: bool,
 }
 
 impl ExecuteCommand {
-    pub fn requires_acceptance(&self, allowed_commands: Option<&Vec<String>>, allow_read_only: bool) -> bool {
-        // Always require acceptance for multi-line commands.
-        if self.command.contains("\n") || self.command.contains("\r") {
+    pub fn requires_acceptance(&self, allowed_commands: Option<&[CommandRule]>, allow_read_only: bool) -> bool {
+        // Control characters such as `\r` can hide part of the command when it is displayed, and
+        // are not handled the same way by the parser and by the shell.
+        if self.command.chars().any(|c| c.is_control() && c != '\n' && c != '\t') {
             return true;
         }
 
-        let default_arr = vec![];
-        let allowed_commands = allowed_commands.unwrap_or(&default_arr);
+        let parsed = shell::parse(&self.command);
+        !rules::is_allowed(
+            &self.command,
+            &parsed,
+            allowed_commands.unwrap_or_default(),
+            allow_read_only,
+        )
+    }
 
-        let has_regex_match = allowed_commands
-            .iter()
-            .map(|cmd| Regex::new(&format!(r"\A{}\z", cmd)))
-            .filter(Result::is_ok)
+    pub async fn invoke(
+        &self,
+        os: &Os,
+        output: &mut impl Write,
+        agent: Option<&Agent>,
+        shell: &mut ShellSession,
+    ) -> Result<InvokeOutput> {
+        let sandbox = match agent.map(SandboxSettings::from_agent).transpose()?.flatten() {
+            Some(settings) if settings.enabled => Some(Sandbox::new(os, &settings).await?),
+            _ => None,
+        };
+        let session_settings = agent
+            .map(ShellSessionSettings::from_agent)
+            .transpose()?
             .flatten()
-            .any(|regex| regex.is_match(&self.command));
-        if has_regex_match {
-            return false;
-        }
+            .filter(|settings| settings.enabled && !cfg!(windows));
 
-        let Some(args) = shlex::split(&self.command) else {
-            return true;
+        let (result, cwd) = match session_settings {
+            Some(settings) => {
+                let session_output = shell
+                    .run(
+                        os,
+                        &self.command,
+                        MAX_TOOL_RESPONSE_SIZE / 3,
+                        Some(&mut *output),
+                        sandbox,
+                        Duration::from_secs(settings.timeout_seconds),
+                    )
+                    .await?;
+                queue_session_changes(output, &session_output)?;
+                (session_output.result, session_output.state.map(|state| state.cwd))
+            },
+            None => (
+                run_command(os, &self.command, MAX_TOOL_RESPONSE_SIZE / 3, Some(output), sandbox).await?,
+                None,
+            ),
         };
-        const DANGEROUS_PATTERNS: &[&str] = &["<(", "$(", "`", ">", "&&", "||", "&", ";", "$", "\n", "\r", "IFS"];
-
-        if args
-            .iter()
-            .any(|arg| DANGEROUS_PATTERNS.iter().any(|p| arg.contains(p)))
-        {
-            return true;
-        }
-
-        // Split commands by pipe and check each one
-        let mut current_cmd = Vec::new();
-        let mut all_commands = Vec::new();
+        let clean_stdout = sanitize_unicode_tags(&result.stdout);
+        let clean_stderr = sanitize_unicode_tags(&result.stderr);
 
-        for arg in args {
-            if arg == "|" {
-                if !current_cmd.is_empty() {
-                    all_commands.push(current_cmd);
-                }
-                current_cmd = Vec::new();
-            } else if arg.contains("|") {
-                // if pipe appears without spacing e.g. `echo myimportantfile|args rm` it won't get
-                // parsed out, in this case - we want to verify before running
-                return true;
-            } else {
-                current_cmd.push(arg);
-            }
-        }
-        if !current_cmd.is_empty() {
-            all_commands.push(current_cmd);
-        }
-
-        // Check if each command in the pipe chain starts with a safe command
-        for cmd_args in all_commands {
-            match cmd_args.first() {
-                // Special casing for `find` so that we support most cases while safeguarding
-                // against unwanted mutations
-                Some(cmd)
-                    if cmd == "find"
-                        && cmd_args.iter().any(|arg| {
-                            arg.contains("-exec") // includes -execdir
-                                || arg.contains("-delete")
-                                || arg.contains("-ok") // includes -okdir
-                                || arg.contains("-fprint") // includes -fprint0 and -fprintf
-                        }) =>
-                {
-                    return true;
-                },
-                Some(cmd) => {
-                    // Special casing for `grep`. -P flag for perl regexp has RCE issues, apparently
-                    // should not be supported within grep but is flagged as a possibility since this is perl
-                    // regexp.
-                    if cmd == "grep"
-                        && cmd_args
-                            .iter()
-                            .any(|arg| arg.contains("-P") || arg.contains("--perl-regexp"))
-                    {
-                        return true;
-                    }
-                    let is_cmd_read_only = READONLY_COMMANDS.contains(&cmd.as_str());
-                    if !allow_read_only || !is_cmd_read_only {
-                        return true;
-                    }
-                },
-                None => return true,
-            }
-        }
-
-        false
-    }
-
-    pub async fn invoke(&self, os: &Os, output: &mut impl Write) -> Result<InvokeOutput> {
-        let output = run_command(os, &self.command, MAX_TOOL_RESPONSE_SIZE / 3, Some(output)).await?;
-        let clean_stdout = sanitize_unicode_tags(&output.stdout);
-        let clean_stderr = sanitize_unicode_tags(&output.stderr);
-
-        let result = serde_json::json!({
-            "exit_status": output.exit_status.unwrap_or(0).to_string(),
+        let mut result = serde_json::json!({
+            "exit_status": result.exit_status.unwrap_or(0).to_string(),
             "stdout": clean_stdout,
             "stderr": clean_stderr,
         });
+        // Lets the model know where the next command of the session runs.
+        if let Some(cwd) = cwd {
+            result["cwd"] = serde_json::Value::String(cwd.to_string_lossy().into_owned());
+        }
 
         Ok(InvokeOutput {
             output: OutputKind::Json(result),
@@ -192,11 +179,13 @@ impl ExecuteCommand {
         #[serde(rename_all = "camelCase")]
         struct Settings {
             #[serde(default)]
-            allowed_commands: Vec<String>,
+            allowed_commands: Vec<CommandRule>,
             #[serde(default)]
-            denied_commands: Vec<String>,
+            denied_commands: Vec<CommandRule>,
             #[serde(default = "default_allow_read_only")]
             allow_read_only: bool,
+            #[serde(default)]
+            sandbox: SandboxSettings,
         }
 
         fn default_allow_read_only() -> bool {
@@ -204,14 +193,14 @@ impl ExecuteCommand {
         }
 
         let Self { command, .. } = self;
-        let tool_name = if cfg!(windows) { "execute_cmd" } else { "execute_bash" };
-        let is_in_allowlist = matches_any_pattern(&agent.allowed_tools, tool_name);
-        match agent.tools_settings.get(tool_name) {
+        let is_in_allowlist = matches_any_pattern(&agent.allowed_tools, TOOL_NAME);
+        match agent.tools_settings.get(TOOL_NAME) {
             Some(settings) => {
                 let Settings {
                     allowed_commands,
                     denied_commands,
                     allow_read_only,
+                    sandbox,
                 } = match serde_json::from_value::<Settings>(settings.clone()) {
                     Ok(settings) => settings,
                     Err(e) => {
@@ -220,20 +209,19 @@ impl ExecuteCommand {
                     },
                 };
 
-                let denied_match_set = denied_commands
-                    .iter()
-                    .filter_map(|dc| Regex::new(&format!(r"\A{dc}\z")).ok())
-                    .filter(|r| r.is_match(command))
-                    .map(|r| r.to_string())
-                    .collect::<Vec<_>>();
-
-                if !denied_match_set.is_empty() {
-                    return PermissionEvalResult::Deny(denied_match_set);
+                // Commands that may match a denied rule always require acceptance.
+                match rules::check_denied(command, &shell::parse(command), &denied_commands) {
+                    Denial::Denied(rules) => return PermissionEvalResult::Deny(rules),
+                    Denial::Possible => return PermissionEvalResult::Ask,
+                    Denial::None => {},
                 }
 
-                if is_in_allowlist {
+                // Sandboxed commands can only write to the paths allowed by the agent.
+                if is_in_allowlist || sandbox.auto_approves() {
                     PermissionEvalResult::Allow
-                } else if self.requires_acceptance(Some(&allowed_commands), allow_read_only) {
+                } else if self.shell_overrides_commands
+                    || self.requires_acceptance(Some(&allowed_commands), allow_read_only)
+                {
                     PermissionEvalResult::Ask
                 } else {
                     PermissionEvalResult::Allow
@@ -241,7 +229,7 @@ impl ExecuteCommand {
             },
             None if is_in_allowlist => PermissionEvalResult::Allow,
             _ => {
-                if self.requires_acceptance(None, default_allow_read_only()) {
+                if self.shell_overrides_commands || self.requires_acceptance(None, default_allow_read_only()) {
                     PermissionEvalResult::Ask
                 } else {
                     PermissionEvalResult::Allow
@@ -251,6 +239,32 @@ impl ExecuteCommand {
     }
 }
 
+/// Shows the changes a command made to the working directory and variables of the
+/// [ShellSession].
+fn queue_session_changes(output: &mut impl Write, session_output: &SessionOutput) -> Result<()> {
+    let Some(state) = &session_output.state else {
+        return Ok(());
+    };
+    if session_output.cwd_changed {
+        queue!(
+            output,
+            style::SetForegroundColor(Color::DarkGrey),
+            style::Print(format!("Working directory: {}\n", state.cwd.display())),
+            style::ResetColor,
+        )?;
+    }
+    for change in &session_output.var_changes {
+        queue!(
+            output,
+            style::SetForegroundColor(Color::DarkGrey),
+            style::Print(format!("{change}\n")),
+            style::ResetColor,
+        )?;
+    }
+    Ok(())
+}
+
+#[derive(Debug)]
 pub struct CommandResult {
     pub exit_status: Option<i32>,
     /// Truncated stdout
@@ -388,12 +402,13 @@ mod tests {
 
     #[test]
     fn test_requires_acceptance_allowed_commands() {
-        let allowed_cmds: &[String] = &[
-            String::from("git status"),
-            String::from("root"),
-            String::from("command subcommand a=[0-9]{10} b=[0-9]{10}"),
-            String::from("command subcommand && command subcommand"),
-        ];
+        let allowed_cmds = [
+            "git status",
+            "root",
+            "command subcommand a=[0-9]{10} b=[0-9]{10}",
+            "command subcommand && command subcommand",
+        ]
+        .map(|cmd| CommandRule::Regex(cmd.to_string()));
         let cmds = &[
             // Command first argument 'root' allowed (allows all subcommands)
             ("root", false),
@@ -413,7 +428,7 @@ mod tests {
             }))
             .unwrap();
             assert_eq!(
-                tool.requires_acceptance(Option::from(&allowed_cmds.to_vec()), true),
+                tool.requires_acceptance(Some(&allowed_cmds), true),
                 *expected,
                 "expected command: `{}` to have requires_acceptance: `{}`",
                 cmd,
@@ -489,6 +504,68 @@ mod tests {
         assert!(matches!(res, PermissionEvalResult::Deny(ref rules) if rules.contains(&"\\Agit .*\\z".to_string())));
     }
 
+    #[tokio::test]
+    async fn test_eval_perm_argv_rules() {
+        let agent = Agent {
+            name: "test_agent".to_string(),
+            tools_settings: {
+                let mut map = HashMap::<ToolSettingTarget, serde_json::Value>::new();
+                map.insert(
+                    ToolSettingTarget(TOOL_NAME.to_string()),
+                    serde_json::json!({
+                        "allowedCommands": [["git", "status"], ["git", "diff", "*"]],
+                        "deniedCommands": [["git", "push", "*"], "rm -rf .*"]
+                    }),
+                );
+                map
+            },
+            ..Default::default()
+        };
+        let os = Os::new().await.unwrap();
+
+        let eval = |command: &str| {
+            serde_json::from_value::<ExecuteCommand>(serde_json::json!({ "command": command }))
+                .unwrap()
+                .eval_perm(&os, &agent)
+        };
+
+        assert!(matches!(eval("git status && git diff"), PermissionEvalResult::Allow));
+        assert!(matches!(
+            eval("git diff --stat | head -n 20"),
+            PermissionEvalResult::Allow
+        ));
+        assert!(matches!(
+            eval("git status && git commit -m x"),
+            PermissionEvalResult::Ask
+        ));
+        assert!(matches!(eval("git diff > out.patch"), PermissionEvalResult::Ask));
+        assert!(matches!(eval("git $CMD origin"), PermissionEvalResult::Ask));
+        assert!(matches!(
+            eval("git status && git push origin main"),
+            PermissionEvalResult::Deny(ref rules)
+                if rules == &[r#"["git","push","*"] (matched by `git push origin main`)"#.to_string()]
+        ));
+        assert!(matches!(
+            eval("cd build; sudo rm -rf target"),
+            PermissionEvalResult::Deny(ref rules)
+                if rules == &[r"\Arm -rf .*\z (matched by `rm -rf target`)".to_string()]
+        ));
+
+        // Command names cannot be trusted once the persistent shell may override them
+        let mut tool =
+            serde_json::from_value::<ExecuteCommand>(serde_json::json!({ "command": "git status" })).unwrap();
+        tool.shell_overrides_commands = true;
+        assert!(matches!(tool.eval_perm(&os, &agent), PermissionEvalResult::Ask));
+        tool.command = "git push origin main".to_string();
+        assert!(matches!(tool.eval_perm(&os, &agent), PermissionEvalResult::Deny(_)));
+        let mut tool = serde_json::from_value::<ExecuteCommand>(serde_json::json!({ "command": "ls" })).unwrap();
+        tool.shell_overrides_commands = true;
+        assert!(matches!(
+            tool.eval_perm(&os, &Agent::default()),
+            PermissionEvalResult::Ask
+        ));
+    }
+
     #[tokio::test]
     async fn test_cloudtrail_tracking() {
         use crate::cli::chat::consts::{
@@ -0,0 +1,495 @@
+use std::fmt;
+
+use globset::Glob;
+use regex::Regex;
+use serde::Deserialize;
+
+use super::READONLY_COMMANDS;
+use super::shell::{
+    ParsedCommand,
+    SimpleCommand,
+    Word,
+};
+
+/// Arguments of `find` that write to files.
+const FIND_WRITE_ARGS: &[&str] = &["-delete", "-fprint", "-fls"];
+
+/// Rule of `allowedCommands` or `deniedCommands`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum CommandRule {
+    /// Regular expression, anchored with `\A` and `\z`, matched against the source text of each
+    /// command in the command line, and against the whole command line when denying or when the
+    /// rule is written for the whole command line
+    Regex(String),
+    /// Patterns matched against the name and arguments of each command in the command line, e.g.
+    /// `["git", "log", "*"]`
+    Argv(Vec<String>),
+}
+
+impl fmt::Display for CommandRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommandRule::Regex(regex) => write!(f, r"\A{regex}\z"),
+            CommandRule::Argv(patterns) => write!(f, "{}", serde_json::json!(patterns)),
+        }
+    }
+}
+
+/// Whether a rule matches a command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Match {
+    Yes,
+    No,
+    /// The rule matches for some values of the dynamic words of the command
+    Maybe,
+}
+
+impl CommandRule {
+    fn regex(&self) -> Option<Regex> {
+        match self {
+            CommandRule::Regex(regex) => Regex::new(&format!(r"\A{regex}\z")).ok(),
+            CommandRule::Argv(_) => None,
+        }
+    }
+
+    /// Whether the rule matches the whole command line. Only regex rules can.
+    pub fn matches_command_line(&self, command_line: &str) -> bool {
+        self.regex().is_some_and(|regex| regex.is_match(command_line))
+    }
+
+    /// Whether the rule allows running `command` without prompting. Argv rules do not allow
+    /// commands with variable assignments, as variables like `LD_PRELOAD` can change what they run.
+    pub fn allows(&self, command: &SimpleCommand) -> bool {
+        if matches!(self, CommandRule::Argv(_)) && command.has_assignments {
+            return false;
+        }
+        self.matches(command, false) == Match::Yes
+    }
+
+    /// Whether the rule denies running `command`. Unlike [CommandRule::allows], the name of the
+    /// command also matches when it is given as an absolute path, e.g. `/bin/rm` matches `rm`.
+    pub fn denies(&self, command: &SimpleCommand) -> Match {
+        self.matches(command, true)
+    }
+
+    /// Whether the rule allows the command line as a whole, rather than each command in it. The
+    /// command line must not have substitutions or redirections, and a list of commands is only
+    /// matched by a rule written for the whole list, which matches none of its commands on their
+    /// own, as `git .*` would otherwise allow `git status; rm -rf ~`. A single command may still
+    /// run other commands, like `sudo` or `bash -c` do, which the rule has seen.
+    fn allows_command_line(&self, command_line: &str, parsed: &ParsedCommand) -> bool {
+        if !parsed.unsupported.is_empty()
+            || !parsed.written_files.is_empty()
+            || parsed
+                .commands
+                .iter()
+                .flat_map(|command| &command.argv)
+                .any(|word| matches!(word, Word::Dynamic(text) if text.contains(['(', '`'])))
+            || !self.matches_command_line(command_line)
+        {
+            return false;
+        }
+        match parsed.commands.as_slice() {
+            [] => false,
+            [first, ..] if first.text == command_line.trim() => true,
+            commands => commands.iter().all(|command| self.matches(command, false) == Match::No),
+        }
+    }
+
+    fn matches(&self, command: &SimpleCommand, match_basename: bool) -> Match {
+        match self {
+            CommandRule::Regex(_) => match self.regex().is_some_and(|regex| regex.is_match(&command.text)) {
+                true => Match::Yes,
+                false => Match::No,
+            },
+            CommandRule::Argv(patterns) => match_argv(patterns, &command.argv, match_basename),
+        }
+    }
+}
+
+/// Matches each pattern against the argument at the same position, with `*` and `?` wildcards. A
+/// final `*` pattern matches any number of remaining arguments, including none.
+fn match_argv(patterns: &[String], argv: &[Word], match_basename: bool) -> Match {
+    if patterns.is_empty() || argv.is_empty() {
+        return Match::No;
+    }
+    let (patterns, any_rest) = match patterns.split_last() {
+        Some((last, init)) if last == "*" => (init, true),
+        _ => (patterns, false),
+    };
+
+    let has_dynamic = argv.iter().any(|word| matches!(word, Word::Dynamic(_)));
+    let length_matches = match any_rest {
+        true => argv.len() >= patterns.len(),
+        false => argv.len() == patterns.len(),
+    };
+    if !length_matches {
+        // An unquoted expansion can expand to any number of arguments.
+        return if has_dynamic { Match::Maybe } else { Match::No };
+    }
+
+    let mut result = Match::Yes;
+    for (i, (pattern, word)) in patterns.iter().zip(argv).enumerate() {
+        match word {
+            Word::Static(value) => {
+                let matches = match_word(pattern, value)
+                    || (i == 0
+                        && match_basename
+                        && !pattern.contains('/')
+                        && value.starts_with('/')
+                        && value.rsplit('/').next().is_some_and(|name| match_word(pattern, name)));
+                if !matches {
+                    return Match::No;
+                }
+            },
+            Word::Dynamic(_) => result = Match::Maybe,
+        }
+    }
+    result
+}
+
+fn match_word(pattern: &str, value: &str) -> bool {
+    if pattern == value {
+        return true;
+    }
+    if pattern.contains(['*', '?', '[']) {
+        if let Ok(glob) = Glob::new(pattern) {
+            return glob.compile_matcher().is_match(value);
+        }
+    }
+    false
+}
+
+/// Result of checking a command line against `deniedCommands`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Denial {
+    None,
+    /// A rule may match, depending on the value of variables or the files in the working directory
+    Possible,
+    /// The rules that matched, with the commands that they matched
+    Denied(Vec<String>),
+}
+
+/// Checks the whole command line and each command in it against the `denied` rules.
+pub fn check_denied(command_line: &str, parsed: &ParsedCommand, denied: &[CommandRule]) -> Denial {
+    let matches = denied
+        .iter()
+        .filter(|rule| rule.matches_command_line(command_line))
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    if !matches.is_empty() {
+        return Denial::Denied(matches);
+    }
+
+    let mut matches = Vec::new();
+    let mut possible = !denied.is_empty() && !parsed.unsupported.is_empty();
+    for command in &parsed.commands {
+        for rule in denied {
+            match rule.denies(command) {
+                Match::Yes => matches.push(format!("{rule} (matched by `{}`)", command.text)),
+                Match::Maybe => possible = true,
+                Match::No => {},
+            }
+        }
+    }
+
+    match (matches.is_empty(), possible) {
+        (false, _) => Denial::Denied(matches),
+        (true, true) => Denial::Possible,
+        (true, false) => Denial::None,
+    }
+}
+
+/// Whether the command line can run without prompting: one of the `allowed` regex rules allows it
+/// as a whole, or every command in it is allowed by a rule or read-only and it does not write to
+/// files.
+pub fn is_allowed(command_line: &str, parsed: &ParsedCommand, allowed: &[CommandRule], allow_read_only: bool) -> bool {
+    if allowed
+        .iter()
+        .any(|rule| rule.allows_command_line(command_line, parsed))
+    {
+        return true;
+    }
+    if !parsed.unsupported.is_empty() || !parsed.written_files.is_empty() {
+        return false;
+    }
+
+    parsed
+        .commands
+        .iter()
+        .all(|command| allowed.iter().any(|rule| rule.allows(command)) || (allow_read_only && is_read_only(command)))
+}
+
+/// Whether `command` is one of [READONLY_COMMANDS] and does not use one of their options that
+/// write to files or run code.
+pub fn is_read_only(command: &SimpleCommand) -> bool {
+    if command.has_assignments {
+        return false;
+    }
+    let Some(name) = command.argv.first().and_then(Word::as_static) else {
+        return false;
+    };
+    if !READONLY_COMMANDS.contains(&name) {
+        return false;
+    }
+
+    let args = &command.argv[1..];
+    match name {
+        // Commands run by `-exec` and friends are checked on their own.
+        "find" => args.iter().all(|arg| {
+            arg.as_static()
+                .is_some_and(|arg| !FIND_WRITE_ARGS.iter().any(|write_arg| arg.starts_with(write_arg)))
+        }),
+        // Perl regular expressions can run code.
+        "grep" => args.iter().all(|arg| {
+            arg.as_static().is_some_and(|arg| {
+                arg != "--perl-regexp" && !(arg.starts_with('-') && !arg.starts_with("--") && arg.contains('P'))
+            })
+        }),
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::shell::parse;
+    use super::*;
+
+    fn rules(value: serde_json::Value) -> Vec<CommandRule> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn allowed(command_line: &str, allowed: &[CommandRule]) -> bool {
+        is_allowed(command_line, &parse(command_line), allowed, true)
+    }
+
+    fn denied(command_line: &str, denied: &[CommandRule]) -> Denial {
+        check_denied(command_line, &parse(command_line), denied)
+    }
+
+    #[test]
+    fn test_deserialize_rules() {
+        assert_eq!(rules(serde_json::json!(["git status", ["git", "log", "*"]])), vec![
+            CommandRule::Regex("git status".to_string()),
+            CommandRule::Argv(vec!["git".to_string(), "log".to_string(), "*".to_string()]),
+        ]);
+        assert_eq!(CommandRule::Regex("git .*".to_string()).to_string(), r"\Agit .*\z");
+        assert_eq!(
+            CommandRule::Argv(vec!["git".to_string(), "*".to_string()]).to_string(),
+            r#"["git","*"]"#
+        );
+    }
+
+    #[test]
+    fn test_allowed_rules() {
+        let allowed_rules = rules(serde_json::json!([
+            ["git", "status"],
+            ["git", "log", "*"],
+            ["git", "diff", "*"],
+            ["cargo", "test", "*"],
+            ["npm", "run", "lint", "*"],
+            ["kubectl", "get", "pods", "-n", "*"],
+            ["make", "test-*"],
+            ["docker", "ps", "*"],
+            "gh pr view [0-9]+",
+        ]));
+        let cases = [
+            ("git status", true),
+            ("git status --short", false),
+            ("git log", true),
+            ("git log --oneline -n 5", true),
+            ("git status && git diff", true),
+            ("git status; git log | head", true),
+            ("git diff HEAD~1 | grep foo | wc -l", false),
+            ("git diff HEAD~1 | grep foo | head", true),
+            ("git push", false),
+            ("git status && git push", false),
+            ("git log $(git rev-parse HEAD)", false),
+            ("git log $(echo HEAD)", true),
+            ("git log \"$BRANCH\"", true),
+            ("git log > log.txt", false),
+            ("git log 2>/dev/null", true),
+            ("(git status; git log) | head", true),
+            ("if git diff --quiet; then echo clean; fi", true),
+            ("'git' \"status\"", true),
+            ("/usr/bin/git status", false),
+            ("$GIT status", false),
+            ("GIT_PAGER=cat git log", false),
+            ("cargo test --workspace", true),
+            ("cargo test && cargo publish", false),
+            ("npm run lint", true),
+            ("npm run lint -- --fix", true),
+            ("npm run build", false),
+            ("kubectl get pods -n default", true),
+            ("kubectl get pods -n", true),
+            ("kubectl get pods", false),
+            ("kubectl get pods -n a b", true),
+            ("kubectl get svc -n default", false),
+            ("make test-unit", true),
+            ("make test-unit install", false),
+            ("make install", false),
+            ("docker ps -a", true),
+            ("gh pr view 12", true),
+            ("gh pr view 12 && rm -rf /", false),
+            ("gh pr view abc", false),
+            ("sudo git status", false),
+            ("xargs git log", false),
+            ("bash -c 'git status'", false),
+            ("git log; rm -rf ~", false),
+            ("git log\nrm -rf ~", false),
+            ("git log &\nrm -rf ~", false),
+            ("git log `rm -rf ~`", false),
+            ("git log \"$(rm -rf ~)\"", false),
+            ("git log <(rm -rf ~)", false),
+            ("git log | tee out.txt", false),
+            ("git log 'unterminated", false),
+            ("FOO=1", false),
+            ("", true),
+        ];
+        for (command, expected) in cases {
+            assert_eq!(allowed(command, &allowed_rules), expected, "{command}");
+        }
+    }
+
+    #[test]
+    fn test_allowed_regex_rules() {
+        let allowed_rules = rules(serde_json::json!(["git .*", "npm run [a-z]+", "sudo apt update"]));
+        let cases = [
+            ("git status", true),
+            ("sudo apt update", true),
+            ("sudo apt update && rm -rf ~", false),
+            ("git status && git log --oneline", true),
+            ("npm run lint | head", true),
+            ("git status; rm -rf ~", false),
+            ("git status && rm -rf ~", false),
+            ("git log $(rm -rf ~)", false),
+            ("git log \"$(rm -rf ~)\"", false),
+            ("git log <(rm -rf ~)", false),
+            ("git log > log.txt", false),
+            ("npm run lint; npm publish", false),
+        ];
+        for (command, expected) in cases {
+            assert_eq!(allowed(command, &allowed_rules), expected, "{command}");
+        }
+
+        // Rules written for a whole list of commands allow it
+        let allowed_rules = rules(serde_json::json!(["cd [a-z]+ && make", "make .*"]));
+        let cases = [
+            ("cd build && make", true),
+            ("cd build && make; rm -rf ~", false),
+            ("cd build && make > out.txt", false),
+            ("make all && rm -rf ~", false),
+        ];
+        for (command, expected) in cases {
+            assert_eq!(allowed(command, &allowed_rules), expected, "{command}");
+        }
+    }
+
+    #[test]
+    fn test_read_only_commands() {
+        let cases = [
+            ("ls -la", true),
+            ("ls $HOME ~ *.rs", true),
+            ("cat a b | grep -n x | head -n 3 | tail -n 1", true),
+            ("echo $(pwd)", true),
+            ("echo \"$(rm x)\"", false),
+            ("find . -name '*.rs' -type f", true),
+            ("find . -name *.rs", false),
+            ("find . -delete", false),
+            ("find . -fprint out", false),
+            ("find . -fls out", false),
+            ("find . -exec cat {} \\;", true),
+            ("find . -exec rm {} \\;", false),
+            ("find . -exec cat {} \\; -exec rm {} +", false),
+            ("find . -${t}exec touch x +", false),
+            ("grep -rn foo .", true),
+            ("grep -rnP foo .", false),
+            ("grep --perl-regexp foo", false),
+            ("grep $FLAGS foo", false),
+            ("sudo ls", false),
+            ("command ls", false),
+            ("LC_ALL=C ls", false),
+            ("ls > out", false),
+            ("ls 2>&1 | head", true),
+            ("which ls && pwd", true),
+            ("echo hi; touch x", false),
+            ("rm -rf x", false),
+            ("[ -d x ] && ls x", true),
+            ("[[ -d x ]] && ls x", true),
+            ("for f in *; do echo \"$f\"; done", true),
+            ("for f in *; do rm \"$f\"; done", false),
+            ("echo x | xargs rm", false),
+            ("echo x | xargs echo", false),
+        ];
+        for (command, expected) in cases {
+            assert_eq!(allowed(command, &[]), expected, "{command}");
+        }
+
+        assert!(!is_allowed("ls", &parse("ls"), &[], false));
+    }
+
+    #[test]
+    fn test_denied_rules() {
+        let denied_rules = rules(serde_json::json!([
+            ["git", "push", "*"],
+            ["rm", "-rf", "*"],
+            ["curl", "*"],
+            "npm publish.*",
+        ]));
+        let cases: &[(&str, Denial)] = &[
+            ("git status", Denial::None),
+            (
+                "git push",
+                Denial::Denied(vec![r#"["git","push","*"] (matched by `git push`)"#.to_string()]),
+            ),
+            (
+                "git status && git push origin main",
+                Denial::Denied(vec![
+                    r#"["git","push","*"] (matched by `git push origin main`)"#.to_string(),
+                ]),
+            ),
+            (
+                "echo $(curl -s x) | sh",
+                Denial::Denied(vec![r#"["curl","*"] (matched by `curl -s x`)"#.to_string()]),
+            ),
+            (
+                "/usr/bin/curl x",
+                Denial::Denied(vec![r#"["curl","*"] (matched by `/usr/bin/curl x`)"#.to_string()]),
+            ),
+            (
+                "sudo rm -rf /",
+                Denial::Denied(vec![r#"["rm","-rf","*"] (matched by `rm -rf /`)"#.to_string()]),
+            ),
+            (
+                "bash -c 'git push'",
+                Denial::Denied(vec![r#"["git","push","*"] (matched by `git push`)"#.to_string()]),
+            ),
+            (
+                "find . -exec curl {} \\;",
+                Denial::Denied(vec![r#"["curl","*"] (matched by `curl '{}'`)"#.to_string()]),
+            ),
+            (
+                "npm publish --dry-run",
+                Denial::Denied(vec![r"\Anpm publish.*\z".to_string()]),
+            ),
+            (
+                "npm test && npm publish",
+                Denial::Denied(vec![r"\Anpm publish.*\z (matched by `npm publish`)".to_string()]),
+            ),
+            ("git $CMD", Denial::Possible),
+            ("$CMD push", Denial::Possible),
+            ("rm $FLAGS /", Denial::Possible),
+            ("rm -r -f /", Denial::None),
+            ("eval \"$CMD\"", Denial::Possible),
+            ("echo 'unterminated", Denial::Possible),
+            ("echo 'git push'", Denial::None),
+            ("git log # git push", Denial::None),
+        ];
+        for (command, expected) in cases {
+            assert_eq!(&denied(command, &denied_rules), expected, "{command}");
+        }
+
+        assert_eq!(denied("eval \"$CMD\"", &[]), Denial::None);
+    }
+}
@@ -0,0 +1,625 @@
+use std::io;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use thiserror::Error;
+use tracing::warn;
+
+use super::TOOL_NAME;
+use crate::cli::agent::Agent;
+use crate::os::Os;
+use crate::util::directories;
+
+/// Device files that sandboxed commands can always write to.
+const WRITABLE_DEVICES: &[&str] = &["/dev/null", "/dev/zero", "/dev/full", "/dev/tty"];
+
+#[derive(Debug, Error)]
+pub enum SandboxError {
+    #[cfg(not(target_os = "linux"))]
+    #[error("The execute_bash sandbox is only supported on Linux")]
+    UnsupportedPlatform,
+    #[cfg(target_os = "linux")]
+    #[error(
+        "The execute_bash sandbox needs Landlock, which is not enabled in this kernel. Landlock requires Linux 5.13 or later with `landlock` listed in the `lsm=` boot parameter"
+    )]
+    LandlockUnavailable,
+    #[cfg(target_os = "linux")]
+    #[error(
+        "The execute_bash sandbox needs unprivileged user namespaces, which are not available: {0}. Enable them (e.g. `sysctl kernel.unprivileged_userns_clone=1`)"
+    )]
+    UserNamespacesUnavailable(io::Error),
+    #[cfg(target_os = "linux")]
+    #[error("Sandboxed commands cannot run as root, start Q as a regular user or disable the sandbox")]
+    Root,
+    #[cfg(target_os = "linux")]
+    #[error("Failed to set up the Landlock ruleset: {0}")]
+    Landlock(#[from] landlock::RulesetError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("Invalid sandbox settings: {0}")]
+    Settings(#[from] serde_json::Error),
+}
+
+/// Settings of the sandbox, under `toolsSettings.execute_bash.sandbox` in an agent config.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SandboxSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Paths that commands can write to, on top of the workspace and the temporary directory
+    #[serde(default)]
+    pub allowed_paths: Vec<String>,
+    #[serde(default = "default_allow_network")]
+    pub allow_network: bool,
+    /// Whether to run commands without prompting, unless they match `deniedCommands`, see
+    /// [Self::auto_approves]
+    #[serde(default)]
+    pub auto_approve: bool,
+}
+
+fn default_allow_network() -> bool {
+    true
+}
+
+impl Default for SandboxSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            allowed_paths: Vec::new(),
+            allow_network: default_allow_network(),
+            auto_approve: false,
+        }
+    }
+}
+
+impl SandboxSettings {
+    /// Reads the sandbox settings of the agent, if it has any.
+    pub fn from_agent(agent: &Agent) -> Result<Option<Self>, SandboxError> {
+        #[derive(Debug, Deserialize)]
+        struct Settings {
+            sandbox: Option<SandboxSettings>,
+        }
+
+        match agent.tools_settings.get(TOOL_NAME) {
+            Some(settings) => Ok(serde_json::from_value::<Settings>(settings.clone())?.sandbox),
+            None => Ok(None),
+        }
+    }
+
+    /// Paths that sandboxed commands can write to: the workspace, the temporary directory, the
+    /// configured `allowedPaths` and a few device files. Relative paths are resolved against the
+    /// workspace.
+    pub fn writable_paths(&self, os: &Os) -> Result<Vec<PathBuf>, SandboxError> {
+        let cwd = os.env.current_dir()?;
+        let mut paths = vec![cwd.clone(), std::env::temp_dir()];
+        for path in &self.allowed_paths {
+            match directories::canonicalizes_path(os, path) {
+                Ok(path) => paths.push(cwd.join(path)),
+                Err(e) => warn!("Failed to expand sandbox path {path}: {e}. Ignoring."),
+            }
+        }
+        paths.extend(WRITABLE_DEVICES.iter().map(PathBuf::from));
+        Ok(paths)
+    }
+
+    /// Paths that stay read-only even when they are below a writable path, because changing them
+    /// would let commands run outside the sandbox later: the agent and MCP configs of the workspace
+    /// and of the user, and the git repository of the workspace with its hooks and config. The
+    /// whole `.git` is read-only, a writable one could be moved away and replaced with new hooks.
+    /// Only existing paths are returned.
+    pub fn read_only_paths(os: &Os) -> Result<Vec<PathBuf>, SandboxError> {
+        let cwd = os.env.current_dir()?;
+        let mut paths = Vec::new();
+        for agent_dir in [
+            directories::chat_local_agent_dir(os),
+            directories::chat_global_agent_path(os),
+        ] {
+            match agent_dir {
+                Ok(dir) => paths.extend(dir.parent().map(PathBuf::from)),
+                Err(e) => warn!("Failed to find the agent directory: {e}. Ignoring."),
+            }
+        }
+        paths.push(cwd.join(".git"));
+        paths.retain(|path| os.fs.exists(path));
+        Ok(paths)
+    }
+
+    /// Whether commands run without prompting. Sandboxed commands can read the whole filesystem,
+    /// so this needs network access to be blocked.
+    pub fn auto_approves(&self) -> bool {
+        self.enabled && self.auto_approve && !self.allow_network
+    }
+}
+
+/// Restrictions applied to a command right before it starts, see [SandboxSettings].
+///
+/// Writes are limited to [SandboxSettings::writable_paths] with Landlock, and the command cannot
+/// gain privileges, e.g. through `sudo`. The command runs in its own user and mount namespaces,
+/// keeping the user and group ids of the current process, where
+/// [SandboxSettings::read_only_paths] are mounted read-only. When network access is blocked, it
+/// also gets its own network namespace.
+pub struct Sandbox {
+    #[cfg(target_os = "linux")]
+    ruleset: landlock::RulesetCreated,
+    #[cfg(target_os = "linux")]
+    namespaces: linux::Namespaces,
+}
+
+#[cfg(target_os = "linux")]
+impl Sandbox {
+    pub async fn new(os: &Os, settings: &SandboxSettings) -> Result<Self, SandboxError> {
+        // Agents could otherwise be added to the workspace by creating the directory.
+        if let Ok(agent_dir) = directories::chat_local_agent_dir(os) {
+            os.fs.create_dir_all(&agent_dir).await?;
+        }
+        Self::build(
+            &settings.writable_paths(os)?,
+            &SandboxSettings::read_only_paths(os)?,
+            settings.allow_network,
+        )
+    }
+
+    fn build(
+        writable_paths: &[PathBuf],
+        read_only_paths: &[PathBuf],
+        allow_network: bool,
+    ) -> Result<Self, SandboxError> {
+        use landlock::{
+            Access,
+            AccessFs,
+            Ruleset,
+            RulesetAttr,
+            RulesetCreatedAttr,
+            path_beneath_rules,
+        };
+
+        if nix::unistd::geteuid().is_root() {
+            return Err(SandboxError::Root);
+        }
+
+        let abi = linux::landlock_abi().ok_or(SandboxError::LandlockUnavailable)?;
+        let ruleset = Ruleset::default()
+            .handle_access(AccessFs::from_all(abi))?
+            .create()?
+            .add_rules(path_beneath_rules(["/"], AccessFs::from_read(abi)))?
+            .add_rules(path_beneath_rules(writable_paths, AccessFs::from_all(abi)))?;
+
+        let namespaces = linux::Namespaces::new(read_only_paths, allow_network)?;
+        namespaces.check()?;
+
+        Ok(Self { ruleset, namespaces })
+    }
+
+    /// Applies the sandbox to `command` when it is spawned.
+    pub fn apply(self, command: &mut tokio::process::Command) {
+        let Self { ruleset, namespaces } = self;
+        let mut ruleset = Some(ruleset);
+        // SAFETY: The closure runs in the child between fork and exec. It only makes
+        // async-signal-safe system calls and does not allocate.
+        unsafe {
+            command.pre_exec(move || {
+                namespaces.enter()?;
+                let Some(ruleset) = ruleset.take() else {
+                    return Err(io::Error::from_raw_os_error(libc::EINVAL));
+                };
+                match ruleset.restrict_self() {
+                    Ok(status) if status.ruleset != landlock::RulesetStatus::NotEnforced && status.no_new_privs => {
+                        Ok(())
+                    },
+                    Ok(_) => Err(io::Error::from_raw_os_error(libc::ENOSYS)),
+                    Err(_) => Err(io::Error::last_os_error()),
+                }
+            });
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+impl Sandbox {
+    pub async fn new(_os: &Os, _settings: &SandboxSettings) -> Result<Self, SandboxError> {
+        Err(SandboxError::UnsupportedPlatform)
+    }
+
+    /// Applies the sandbox to `command` when it is spawned.
+    pub fn apply(self, _command: &mut tokio::process::Command) {}
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::ffi::{
+        CStr,
+        CString,
+    };
+    use std::io;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::PathBuf;
+    use std::sync::OnceLock;
+
+    use landlock::ABI;
+    use nix::sys::statvfs::{
+        FsFlags,
+        statvfs,
+    };
+    use nix::sys::wait::{
+        WaitStatus,
+        waitpid,
+    };
+    use nix::unistd::{
+        ForkResult,
+        fork,
+    };
+
+    use super::SandboxError;
+
+    /// Flag of `landlock_create_ruleset` that returns the highest supported ABI version.
+    const LANDLOCK_CREATE_RULESET_VERSION: libc::c_uint = 1;
+
+    /// Returns the Landlock ABI supported by the kernel, or [None] if Landlock is not enabled.
+    pub fn landlock_abi() -> Option<ABI> {
+        // SAFETY: With a null attribute and the version flag, the call only returns the version.
+        let version = unsafe {
+            libc::syscall(
+                libc::SYS_landlock_create_ruleset,
+                std::ptr::null::<libc::c_void>(),
+                0usize,
+                LANDLOCK_CREATE_RULESET_VERSION,
+            )
+        };
+        (version > 0).then(|| ABI::from(version as i32))
+    }
+
+    /// Contents of `/proc/self/uid_map` and `/proc/self/gid_map`, mapping the ids of the current
+    /// process to themselves.
+    struct IdMaps {
+        uid_map: Vec<u8>,
+        gid_map: Vec<u8>,
+    }
+
+    impl IdMaps {
+        fn current() -> Self {
+            let uid = nix::unistd::geteuid();
+            let gid = nix::unistd::getegid();
+            Self {
+                uid_map: format!("{uid} {uid} 1").into_bytes(),
+                gid_map: format!("{gid} {gid} 1").into_bytes(),
+            }
+        }
+    }
+
+    /// Mount flags that a user namespace cannot clear, so they must be kept when remounting.
+    const LOCKED_MOUNT_FLAGS: [(FsFlags, libc::c_ulong); 6] = [
+        (FsFlags::ST_NOSUID, libc::MS_NOSUID),
+        (FsFlags::ST_NODEV, libc::MS_NODEV),
+        (FsFlags::ST_NOEXEC, libc::MS_NOEXEC),
+        (FsFlags::ST_NOATIME, libc::MS_NOATIME),
+        (FsFlags::ST_NODIRATIME, libc::MS_NODIRATIME),
+        (FsFlags::ST_RELATIME, libc::MS_RELATIME),
+    ];
+
+    /// A path to bind mount read-only onto itself.
+    struct ReadOnlyMount {
+        path: CString,
+        /// Flags of the mount containing the path
+        flags: libc::c_ulong,
+    }
+
+    /// Namespaces that a sandboxed command runs in.
+    pub struct Namespaces {
+        id_maps: IdMaps,
+        network: bool,
+        read_only: Vec<ReadOnlyMount>,
+    }
+
+    impl Namespaces {
+        pub fn new(read_only_paths: &[PathBuf], network: bool) -> io::Result<Self> {
+            let read_only = read_only_paths
+                .iter()
+                .map(|path| {
+                    let stat = statvfs(path)?;
+                    let flags = LOCKED_MOUNT_FLAGS
+                        .iter()
+                        .filter(|(fs_flag, _)| stat.flags().contains(*fs_flag))
+                        .fold(0, |flags, (_, mount_flag)| flags | mount_flag);
+                    let path = CString::new(path.as_os_str().as_bytes())
+                        .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+                    Ok(ReadOnlyMount { path, flags })
+                })
+                .collect::<io::Result<_>>()?;
+            Ok(Self {
+                id_maps: IdMaps::current(),
+                network,
+                read_only,
+            })
+        }
+
+        /// Moves the calling process to new user and mount namespaces, and a new network
+        /// namespace unless network access is allowed, keeping its user and group ids. Then
+        /// mounts the read-only paths.
+        ///
+        /// Called between fork and exec, so it must only make async-signal-safe calls.
+        pub fn enter(&self) -> io::Result<()> {
+            let flags = match self.network {
+                true => libc::CLONE_NEWUSER | libc::CLONE_NEWNS,
+                false => libc::CLONE_NEWUSER | libc::CLONE_NEWNS | libc::CLONE_NEWNET,
+            };
+            enter_user_namespace(&self.id_maps, flags)?;
+
+            // Keeps the mounts below from propagating back to the parent namespace.
+            mount(None, c"/", libc::MS_REC | libc::MS_PRIVATE)?;
+            for ReadOnlyMount { path, flags } in &self.read_only {
+                mount(Some(path), path, libc::MS_BIND | libc::MS_REC)?;
+                mount(None, path, libc::MS_BIND | libc::MS_REMOUNT | libc::MS_RDONLY | flags)?;
+            }
+            Ok(())
+        }
+
+        /// Checks that commands can enter their own namespaces, by trying it once in a forked
+        /// child.
+        pub fn check(&self) -> Result<(), SandboxError> {
+            check_namespaces(&self.id_maps)
+        }
+    }
+
+    fn enter_user_namespace(id_maps: &IdMaps, flags: libc::c_int) -> io::Result<()> {
+        // SAFETY: unshare takes no pointers.
+        if unsafe { libc::unshare(flags) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        write_proc_file(c"/proc/self/uid_map", &id_maps.uid_map)?;
+        write_proc_file(c"/proc/self/setgroups", b"deny")?;
+        write_proc_file(c"/proc/self/gid_map", &id_maps.gid_map)
+    }
+
+    fn mount(source: Option<&CStr>, target: &CStr, flags: libc::c_ulong) -> io::Result<()> {
+        // SAFETY: The strings are null terminated, and the file system type and data may be null.
+        let result = unsafe {
+            libc::mount(
+                source.map_or(std::ptr::null(), CStr::as_ptr),
+                target.as_ptr(),
+                std::ptr::null(),
+                flags,
+                std::ptr::null(),
+            )
+        };
+        match result {
+            0 => Ok(()),
+            _ => Err(io::Error::last_os_error()),
+        }
+    }
+
+    fn write_proc_file(path: &CStr, contents: &[u8]) -> io::Result<()> {
+        // SAFETY: `path` is null terminated and `contents` outlives the calls.
+        unsafe {
+            let fd = libc::open(path.as_ptr(), libc::O_WRONLY | libc::O_CLOEXEC);
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let written = libc::write(fd, contents.as_ptr().cast(), contents.len());
+            let result = match usize::try_from(written) {
+                Ok(written) if written == contents.len() => Ok(()),
+                _ => Err(io::Error::last_os_error()),
+            };
+            libc::close(fd);
+            result
+        }
+    }
+
+    fn check_namespaces(id_maps: &IdMaps) -> Result<(), SandboxError> {
+        static ERRNO: OnceLock<Option<i32>> = OnceLock::new();
+
+        let errno = *ERRNO.get_or_init(|| {
+            // SAFETY: The child only makes async-signal-safe calls before exiting.
+            match unsafe { fork() } {
+                Ok(ForkResult::Child) => {
+                    let flags = libc::CLONE_NEWUSER | libc::CLONE_NEWNS | libc::CLONE_NEWNET;
+                    let code = match enter_user_namespace(id_maps, flags) {
+                        Ok(()) => 0,
+                        Err(e) => e.raw_os_error().unwrap_or(libc::EPERM),
+                    };
+                    // SAFETY: Exits the child without running the destructors of the parent.
+                    unsafe { libc::_exit(code) }
+                },
+                Ok(ForkResult::Parent { child }) => match waitpid(child, None) {
+                    Ok(WaitStatus::Exited(_, 0)) => None,
+                    Ok(WaitStatus::Exited(_, code)) => Some(code),
+                    Ok(_) => Some(libc::ECHILD),
+                    Err(e) => Some(e as i32),
+                },
+                Err(e) => Some(e as i32),
+            }
+        });
+
+        match errno {
+            Some(errno) => Err(SandboxError::UserNamespacesUnavailable(io::Error::from_raw_os_error(
+                errno,
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::cli::agent::ToolSettingTarget;
+
+    fn agent(settings: serde_json::Value) -> Agent {
+        let mut tools_settings = HashMap::new();
+        tools_settings.insert(ToolSettingTarget(TOOL_NAME.to_string()), settings);
+        Agent {
+            name: "test_agent".to_string(),
+            tools_settings,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_settings_from_agent() {
+        assert_eq!(SandboxSettings::from_agent(&Agent::default()).unwrap(), None);
+        assert_eq!(
+            SandboxSettings::from_agent(&agent(serde_json::json!({ "allowReadOnly": true }))).unwrap(),
+            None
+        );
+
+        let settings = SandboxSettings::from_agent(&agent(serde_json::json!({
+            "sandbox": { "enabled": true, "allowedPaths": ["~/.cargo"] }
+        })))
+        .unwrap()
+        .unwrap();
+        assert_eq!(settings, SandboxSettings {
+            enabled: true,
+            allowed_paths: vec!["~/.cargo".to_string()],
+            ..Default::default()
+        });
+        assert!(settings.allow_network);
+        assert!(!settings.auto_approve);
+
+        // Auto-approval needs network access to be blocked
+        let settings = |value| SandboxSettings::from_agent(&agent(value)).unwrap().unwrap();
+        assert!(!settings(serde_json::json!({ "sandbox": { "enabled": true, "autoApprove": true } })).auto_approves());
+        assert!(
+            settings(serde_json::json!({ "sandbox": { "enabled": true, "autoApprove": true, "allowNetwork": false } }))
+                .auto_approves()
+        );
+        assert!(
+            !settings(
+                serde_json::json!({ "sandbox": { "enabled": false, "autoApprove": true, "allowNetwork": false } })
+            )
+            .auto_approves()
+        );
+
+        assert!(SandboxSettings::from_agent(&agent(serde_json::json!({ "sandbox": { "enabled": "yes" } }))).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_writable_paths() {
+        let os = Os::new().await.unwrap();
+        unsafe {
+            os.env.set_var("HOME", "/home/user");
+        }
+        let settings = SandboxSettings {
+            enabled: true,
+            allowed_paths: vec!["~/.cargo".to_string(), "target".to_string(), "/opt/cache".to_string()],
+            ..Default::default()
+        };
+        let cwd = os.env.current_dir().unwrap();
+        let paths = settings.writable_paths(&os).unwrap();
+        assert_eq!(paths[0], cwd);
+        assert_eq!(paths[1], std::env::temp_dir());
+        assert!(paths.contains(&PathBuf::from("/home/user/.cargo")));
+        assert!(paths.contains(&cwd.join("target")));
+        assert!(paths.contains(&PathBuf::from("/opt/cache")));
+        assert!(paths.contains(&PathBuf::from("/dev/null")));
+    }
+
+    #[tokio::test]
+    async fn test_read_only_paths() {
+        let os = Os::new().await.unwrap();
+        let cwd = os.env.current_dir().unwrap();
+        assert!(SandboxSettings::read_only_paths(&os).unwrap().is_empty());
+
+        os.fs.create_dir_all(cwd.join(".amazonq/cli-agents")).await.unwrap();
+        os.fs.create_dir_all(cwd.join(".git/hooks")).await.unwrap();
+        os.fs.write(cwd.join(".git/config"), "").await.unwrap();
+        let global_config_dir = directories::chat_global_agent_path(&os)
+            .unwrap()
+            .parent()
+            .unwrap()
+            .to_path_buf();
+        os.fs.create_dir_all(&global_config_dir).await.unwrap();
+        assert_eq!(SandboxSettings::read_only_paths(&os).unwrap(), vec![
+            cwd.join(".amazonq"),
+            global_config_dir,
+            cwd.join(".git"),
+        ]);
+    }
+
+    #[cfg(target_os = "linux")]
+    fn build_sandbox(writable_paths: &[PathBuf], read_only_paths: &[PathBuf], allow_network: bool) -> Option<Sandbox> {
+        match Sandbox::build(writable_paths, read_only_paths, allow_network) {
+            Ok(sandbox) => Some(sandbox),
+            Err(
+                e @ (SandboxError::Root
+                | SandboxError::LandlockUnavailable
+                | SandboxError::UserNamespacesUnavailable(_)),
+            ) => {
+                eprintln!("Skipping sandbox test: {e}");
+                None
+            },
+            Err(e) => panic!("{e}"),
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_sandboxed_command() {
+        let workspace = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir_in(std::env::current_dir().unwrap()).unwrap();
+        let Some(sandbox) = build_sandbox(&[workspace.path().to_path_buf()], &[], false) else {
+            return;
+        };
+
+        let script = format!(
+            "echo ok > {0}/inside; echo no > {1}/outside; echo $?; grep -c : /proc/net/dev",
+            workspace.path().display(),
+            outside.path().display()
+        );
+        let mut command = tokio::process::Command::new("bash");
+        command.arg("-c").arg(script);
+        sandbox.apply(&mut command);
+        let output = command.output().await.unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout);
+
+        assert!(workspace.path().join("inside").exists());
+        assert!(!outside.path().join("outside").exists());
+        // The failed redirection, then only the loopback interface.
+        assert_eq!(stdout.lines().collect::<Vec<_>>(), ["1", "1"], "{stdout}");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_sandboxed_command_cannot_change_read_only_paths() {
+        let workspace = tempfile::tempdir().unwrap();
+        let agents = workspace.path().join(".amazonq/cli-agents");
+        let git = workspace.path().join(".git");
+        let hooks = git.join("hooks");
+        std::fs::create_dir_all(&agents).unwrap();
+        std::fs::create_dir_all(&hooks).unwrap();
+        std::fs::write(agents.join("agent.json"), "{}").unwrap();
+        std::fs::write(git.join("config"), "").unwrap();
+        let read_only_paths = [workspace.path().join(".amazonq"), git.clone()];
+        let Some(sandbox) = build_sandbox(&[workspace.path().to_path_buf()], &read_only_paths, true) else {
+            return;
+        };
+
+        let script = "echo '{\"tools\":[\"*\"]}' > .amazonq/cli-agents/agent.json; \
+            echo '{}' > .amazonq/cli-agents/other.json; \
+            echo '#!/bin/sh' > .git/hooks/pre-commit; \
+            echo '[core]' >> .git/config; \
+            mv .amazonq moved; rm -rf .git/hooks; \
+            mv .git moved-git; mkdir -p .git/hooks && echo '#!/bin/sh' > .git/hooks/post-checkout; \
+            echo ok > inside";
+        let mut command = tokio::process::Command::new("bash");
+        command.arg("-c").arg(script).current_dir(workspace.path());
+        sandbox.apply(&mut command);
+        let output = command.output().await.unwrap();
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        assert_eq!(
+            std::fs::read_to_string(agents.join("agent.json")).unwrap(),
+            "{}",
+            "{stderr}"
+        );
+        assert!(!agents.join("other.json").exists());
+        assert!(!hooks.join("pre-commit").exists());
+        assert!(!hooks.join("post-checkout").exists());
+        assert!(hooks.exists());
+        assert_eq!(std::fs::read_to_string(git.join("config")).unwrap(), "");
+        assert!(!workspace.path().join("moved").exists());
+        assert!(!workspace.path().join("moved-git").exists());
+        // The rest of the workspace stays writable
+        assert!(workspace.path().join("inside").exists(), "{stderr}");
+    }
+}
@@ -0,0 +1,735 @@
+use std::collections::{
+    BTreeMap,
+    BTreeSet,
+    VecDeque,
+};
+use std::fmt;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+
+use eyre::{
+    Context as EyreContext,
+    Result,
+    eyre,
+};
+use serde::Deserialize;
+use tempfile::NamedTempFile;
+use tokio::io::{
+    AsyncBufReadExt,
+    AsyncWriteExt,
+    BufReader,
+};
+use tokio::process::{
+    Child,
+    ChildStderr,
+    ChildStdin,
+    ChildStdout,
+};
+use tokio::select;
+
+use super::{
+    CommandResult,
+    Sandbox,
+    TOOL_NAME,
+    env_vars_with_user_agent,
+    format_output,
+};
+use crate::cli::agent::Agent;
+use crate::os::Os;
+
+/// Variables that bash updates on its own, which are not reported as changes.
+const IGNORED_VARS: &[&str] = &["_", "PWD", "OLDPWD", "SHLVL"];
+
+/// Number of lines of each output stream kept for the tool result.
+const LINE_COUNT: usize = 1024;
+
+/// Number of characters of a variable shown when it changes.
+const MAX_DISPLAYED_VALUE_LEN: usize = 60;
+
+/// Defines the readonly `__q_state` function, which writes the state read by [ShellState::parse]
+/// to a file and then prints the sentinel of [ShellProcess::exec]. The state file is written with
+/// `>|` in case a command set `noclobber`. It is defined before any command runs, so that commands
+/// cannot redefine it or shadow the words it uses with aliases. Commands can still shadow builtins
+/// with functions, so it runs in a subshell in POSIX mode, where `set`, `unset` and `export` are
+/// always the builtins, and removes the functions shadowing the other builtins it uses after
+/// recording them. Traps are not visible in functions, so they are listed by the caller.
+const STATE_FUNCTION: &str = r#"__q_state() (
+    {
+        POSIXLY_CORRECT=y
+        if [[ :$SHELLOPTS: == *:posix:* ]]; then
+            set +u -f
+            IFS=$'\n'
+            __q_shadowed=()
+            for __q_name in builtin compgen printf; do
+                export -f "$__q_name" 2>/dev/null && __q_shadowed+=("$__q_name")
+            done
+            unset -f builtin compgen printf
+            builtin printf '%s\0%s\0' "$1" "$PWD"
+            for __q_name in $(builtin compgen -e); do builtin printf '%s=%s\0' "$__q_name" "${!__q_name}"; done
+            for __q_name in $(builtin compgen -A function) "${__q_shadowed[@]}"; do builtin printf 'function %s\0' "$__q_name"; done
+            for __q_name in $(builtin compgen -a); do builtin printf 'alias %s\0' "$__q_name"; done
+            for __q_name in $4; do
+                [[ $__q_name == "trap -- - "* ]] || builtin printf 'trap %s\0' "$__q_name"
+            done
+        fi
+    } >|"$2"
+    builtin printf '%s\n' "$3"
+    builtin printf '%s\n' "$3" >&2
+)
+readonly -f __q_state
+"#;
+
+/// Settings of the persistent shell session, under `persistentShell` in the tool settings.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShellSessionSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Seconds after which a command is killed, along with the session
+    #[serde(default = "default_timeout_seconds")]
+    pub timeout_seconds: u64,
+}
+
+fn default_timeout_seconds() -> u64 {
+    300
+}
+
+impl Default for ShellSessionSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            timeout_seconds: default_timeout_seconds(),
+        }
+    }
+}
+
+impl ShellSessionSettings {
+    /// Reads the persistent shell settings of the agent, if it has any.
+    pub fn from_agent(agent: &Agent) -> Result<Option<Self>, serde_json::Error> {
+        #[derive(Debug, Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Settings {
+            persistent_shell: Option<ShellSessionSettings>,
+        }
+
+        match agent.tools_settings.get(TOOL_NAME) {
+            Some(settings) => Ok(serde_json::from_value::<Settings>(settings.clone())?.persistent_shell),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Working directory, exported variables, functions, aliases and traps of the shell.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShellState {
+    pub cwd: PathBuf,
+    pub env: BTreeMap<String, String>,
+    pub functions: BTreeSet<String>,
+    pub aliases: BTreeSet<String>,
+    /// Lines of `trap -p` for the traps that are set
+    pub traps: BTreeSet<String>,
+}
+
+/// Change of an exported variable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VarChange {
+    pub name: String,
+    /// New value, or [None] if the variable was unset
+    pub value: Option<String>,
+}
+
+impl fmt::Display for VarChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.value {
+            Some(value) if value.chars().count() > MAX_DISPLAYED_VALUE_LEN => {
+                let value = value.chars().take(MAX_DISPLAYED_VALUE_LEN).collect::<String>();
+                write!(f, "{}={value}...", self.name)
+            },
+            Some(value) => write!(f, "{}={value}", self.name),
+            None => write!(f, "unset {}", self.name),
+        }
+    }
+}
+
+impl ShellState {
+    /// Parses the exit status and state written by [STATE_FUNCTION]: NUL separated fields holding
+    /// the status, the working directory, `NAME=value` for each exported variable, and
+    /// `function NAME`, `alias NAME` or `trap LINE` for each function, alias and trap.
+    fn parse(bytes: &[u8]) -> Option<(i32, Self)> {
+        let text = String::from_utf8_lossy(bytes);
+        let mut fields = text.split('\0');
+        let status = fields.next()?.parse().ok()?;
+        let mut state = Self {
+            cwd: PathBuf::from(fields.next()?),
+            ..Default::default()
+        };
+        for field in fields {
+            if let Some(name) = field.strip_prefix("function ") {
+                state.functions.insert(name.to_string());
+            } else if let Some(name) = field.strip_prefix("alias ") {
+                state.aliases.insert(name.to_string());
+            } else if let Some(line) = field.strip_prefix("trap ") {
+                state.traps.insert(line.to_string());
+            } else if let Some((name, value)) = field.split_once('=') {
+                state.env.insert(name.to_string(), value.to_string());
+            }
+        }
+        Some((status, state))
+    }
+
+    /// Whether functions, aliases, traps or exported variables changed since `initial`, in which
+    /// case a command name may no longer run the program that allow rules and read-only checks
+    /// expect. Besides `PATH`, many variables change what programs do, e.g. `LD_PRELOAD`,
+    /// `BASH_ENV` or `GIT_PAGER`.
+    pub fn overrides_commands(&self, initial: &ShellState) -> bool {
+        self.functions != initial.functions
+            || self.aliases != initial.aliases
+            || self.traps != initial.traps
+            || !self.changes_since(initial).is_empty()
+    }
+
+    /// Variables that changed since `previous`, ignoring the ones bash updates on its own.
+    pub fn changes_since(&self, previous: &ShellState) -> Vec<VarChange> {
+        let set = self
+            .env
+            .iter()
+            .filter(|(name, value)| previous.env.get(*name) != Some(*value))
+            .map(|(name, value)| VarChange {
+                name: name.clone(),
+                value: Some(value.clone()),
+            });
+        let unset = previous
+            .env
+            .keys()
+            .filter(|name| !self.env.contains_key(*name))
+            .map(|name| VarChange {
+                name: name.clone(),
+                value: None,
+            });
+        let mut changes = set
+            .chain(unset)
+            .filter(|change| !IGNORED_VARS.contains(&change.name.as_str()))
+            .collect::<Vec<_>>();
+        changes.sort_by(|a, b| a.name.cmp(&b.name));
+        changes
+    }
+}
+
+/// Output of a command run in the [ShellSession].
+#[derive(Debug)]
+pub struct SessionOutput {
+    pub result: CommandResult,
+    /// State of the shell after the command, or [None] if the session ended
+    pub state: Option<ShellState>,
+    /// Variables changed by the command
+    pub var_changes: Vec<VarChange>,
+    /// Whether the command changed the working directory
+    pub cwd_changed: bool,
+}
+
+/// Long-lived bash process running the commands of a chat session, so that the working directory
+/// and exported variables carry over from one command to the next.
+///
+/// Each command is passed to `eval` with its standard input redirected from `/dev/null`, and
+/// followed by a random sentinel on both output streams to find where its output ends. The exit
+/// status and the state of the shell are written to a temporary file.
+#[derive(Debug, Default)]
+pub struct ShellSession {
+    process: Option<ShellProcess>,
+}
+
+impl ShellSession {
+    /// Runs `command` in the session, starting a new shell if none is running. The shell is
+    /// killed if the command takes longer than `timeout`.
+    pub async fn run<W: Write>(
+        &mut self,
+        os: &Os,
+        command: &str,
+        max_result_size: usize,
+        mut updates: Option<W>,
+        sandbox: Option<Sandbox>,
+        timeout: Duration,
+    ) -> Result<SessionOutput> {
+        // A command whose tool use was cancelled may still be running.
+        if self
+            .process
+            .as_ref()
+            .is_some_and(|process| process.busy || process.sandboxed != sandbox.is_some())
+        {
+            self.reset();
+        }
+        let process = match &mut self.process {
+            Some(process) => process,
+            None => self.process.insert(ShellProcess::spawn(os, sandbox).await?),
+        };
+
+        let mut stdout_buf = VecDeque::with_capacity(LINE_COUNT);
+        let mut stderr_buf = VecDeque::with_capacity(LINE_COUNT);
+        let exec = process.exec(command, updates.as_mut(), &mut stdout_buf, &mut stderr_buf);
+        let (exit_status, state, note) = match tokio::time::timeout(timeout, exec).await {
+            Ok(Ok(true)) => match process.read_state() {
+                Ok((status, state)) => (Some(status), Some(state), None),
+                // The command kept the state from being written, e.g. by keeping bash out of POSIX mode.
+                Err(_) => {
+                    self.reset();
+                    (
+                        None,
+                        None,
+                        Some(
+                            "The state of the shell could not be read, a new one will be started for the next command",
+                        ),
+                    )
+                },
+            },
+            Ok(Ok(false)) => {
+                let status = process.child.wait().await.ok().and_then(|status| status.code());
+                self.process = None;
+                (
+                    status,
+                    None,
+                    Some("The shell exited, a new one will be started for the next command"),
+                )
+            },
+            Ok(Err(err)) => {
+                self.reset();
+                return Err(err);
+            },
+            Err(_elapsed) => {
+                self.reset();
+                (
+                    None,
+                    None,
+                    Some(
+                        "The command timed out and the shell was killed, a new one will be started for the next command",
+                    ),
+                )
+            },
+        };
+        if let Some(u) = updates.as_mut() {
+            u.flush()?;
+        }
+
+        let mut stderr = stderr_buf.into_iter().collect::<Vec<_>>().join("\n");
+        if let Some(note) = note {
+            if !stderr.is_empty() {
+                stderr.push('\n');
+            }
+            stderr.push_str(note);
+        }
+        let result = CommandResult {
+            exit_status,
+            stdout: format_output(&stdout_buf.into_iter().collect::<Vec<_>>().join("\n"), max_result_size),
+            stderr: format_output(&stderr, max_result_size),
+        };
+
+        match (state, self.process.as_mut()) {
+            (Some(state), Some(process)) => {
+                let previous = std::mem::replace(&mut process.state, state.clone());
+                Ok(SessionOutput {
+                    result,
+                    var_changes: state.changes_since(&previous),
+                    cwd_changed: state.cwd != previous.cwd,
+                    state: Some(state),
+                })
+            },
+            _ => Ok(SessionOutput {
+                result,
+                state: None,
+                var_changes: Vec::new(),
+                cwd_changed: false,
+            }),
+        }
+    }
+
+    /// Kills the shell, if one is running. The next command starts a new one.
+    pub fn reset(&mut self) -> bool {
+        self.process.take().is_some()
+    }
+
+    /// Current state of the shell and the variables changed since it started, if one is running.
+    pub fn status(&self) -> Option<(&ShellState, Vec<VarChange>)> {
+        self.process
+            .as_ref()
+            .map(|process| (&process.state, process.state.changes_since(&process.initial_state)))
+    }
+
+    /// Whether the running shell overrides commands, see [ShellState::overrides_commands].
+    pub fn overrides_commands(&self) -> bool {
+        self.process
+            .as_ref()
+            .is_some_and(|process| process.state.overrides_commands(&process.initial_state))
+    }
+}
+
+#[derive(Debug)]
+struct ShellProcess {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    stderr: BufReader<ChildStderr>,
+    /// Printed on both output streams after each command
+    sentinel: String,
+    /// File the shell writes its exit status and state to after each command
+    state_file: NamedTempFile,
+    sandboxed: bool,
+    /// Whether a command was sent and its sentinel was not read yet
+    busy: bool,
+    initial_state: ShellState,
+    state: ShellState,
+}
+
+impl ShellProcess {
+    async fn spawn(os: &Os, sandbox: Option<Sandbox>) -> Result<Self> {
+        let mut cmd = tokio::process::Command::new("bash");
+        cmd.args(["--noprofile", "--norc"])
+            .envs(env_vars_with_user_agent(os))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        // Put the shell in its own process group, so that killing it also kills what it runs.
+        #[cfg(unix)]
+        cmd.process_group(0);
+        let sandboxed = sandbox.is_some();
+        if let Some(sandbox) = sandbox {
+            sandbox.apply(&mut cmd);
+        }
+        let mut child = cmd.spawn().wrap_err("Unable to start the shell session")?;
+
+        let (Some(mut stdin), Some(stdout), Some(stderr)) =
+            (child.stdin.take(), child.stdout.take(), child.stderr.take())
+        else {
+            return Err(eyre!("The shell session has no standard streams"));
+        };
+        stdin.write_all(STATE_FUNCTION.as_bytes()).await?;
+        let mut process = Self {
+            child,
+            stdin,
+            stdout: BufReader::new(stdout),
+            stderr: BufReader::new(stderr),
+            sentinel: format!("__Q_SHELL_SESSION_{}__", uuid::Uuid::new_v4().simple()),
+            state_file: NamedTempFile::new()?,
+            sandboxed,
+            busy: false,
+            initial_state: ShellState::default(),
+            state: ShellState::default(),
+        };
+
+        let (mut stdout_buf, mut stderr_buf) = (VecDeque::new(), VecDeque::new());
+        if !process
+            .exec::<std::io::Sink>("", None, &mut stdout_buf, &mut stderr_buf)
+            .await?
+        {
+            return Err(eyre!(
+                "The shell session exited on startup: {}",
+                Vec::from(stderr_buf).join("\n")
+            ));
+        }
+        let (_, state) = process.read_state()?;
+        process.initial_state = state.clone();
+        process.state = state;
+        Ok(process)
+    }
+
+    /// Runs `command` and collects its output until the sentinels. Returns false if the shell
+    /// exited before printing them.
+    ///
+    /// Assigning `PATH` clears the locations of commands that bash remembers, which `hash -p` could
+    /// have pointed to other programs. If `__q_state` fails, the state file is left empty, so that
+    /// the shell is reset.
+    async fn exec<W: Write>(
+        &mut self,
+        command: &str,
+        mut updates: Option<&mut W>,
+        stdout_buf: &mut VecDeque<String>,
+        stderr_buf: &mut VecDeque<String>,
+    ) -> Result<bool> {
+        let quote = |text: &str| shlex::try_quote(text).map(|quoted| quoted.into_owned());
+        let command = quote(command).wrap_err("The command contains a NUL byte")?;
+        let state_file = quote(&self.state_file.path().to_string_lossy()).wrap_err("Invalid state file path")?;
+        let sentinel = &self.sentinel;
+        let script = format!(
+            "PATH=$PATH\n\
+             eval {command} </dev/null\n\
+             \\__q_state $? {state_file} {sentinel} \"$(POSIXLY_CORRECT=y; trap -p)\" || \
+             {{ >|{state_file}; printf '%s\\n' {sentinel}; printf '%s\\n' {sentinel} >&2; }}\n"
+        );
+
+        self.busy = true;
+        self.stdin.write_all(script.as_bytes()).await?;
+        self.stdin.flush().await?;
+
+        let (mut stdout_line, mut stderr_line) = (Vec::new(), Vec::new());
+        let (mut stdout_done, mut stderr_done) = (false, false);
+        while !stdout_done || !stderr_done {
+            // `read_until` keeps what it read in the buffer when the other branch completes first.
+            select! {
+                read = self.stdout.read_until(b'\n', &mut stdout_line), if !stdout_done => {
+                    if read? == 0 {
+                        return Ok(false);
+                    }
+                    stdout_done = take_line(&mut stdout_line, sentinel, updates.as_deref_mut(), stdout_buf)?;
+                },
+                read = self.stderr.read_until(b'\n', &mut stderr_line), if !stderr_done => {
+                    if read? == 0 {
+                        return Ok(false);
+                    }
+                    stderr_done = take_line(&mut stderr_line, sentinel, updates.as_deref_mut(), stderr_buf)?;
+                },
+            }
+        }
+        self.busy = false;
+        Ok(true)
+    }
+
+    fn read_state(&self) -> Result<(i32, ShellState)> {
+        let bytes = std::fs::read(self.state_file.path())?;
+        ShellState::parse(&bytes).ok_or_else(|| eyre!("Failed to read the state of the shell session"))
+    }
+}
+
+impl Drop for ShellProcess {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if let Some(pid) = self.child.id() {
+            use nix::sys::signal::{
+                Signal,
+                killpg,
+            };
+            use nix::unistd::Pid;
+
+            let _ = killpg(Pid::from_raw(pid as i32), Signal::SIGKILL);
+        }
+    }
+}
+
+/// Handles a line read from one of the output streams, and returns whether it holds the sentinel.
+/// The sentinel follows the last line of output directly when that line has no trailing newline.
+fn take_line<W: Write>(
+    line: &mut Vec<u8>,
+    sentinel: &str,
+    updates: Option<&mut W>,
+    buf: &mut VecDeque<String>,
+) -> Result<bool> {
+    let text = String::from_utf8_lossy(line).into_owned();
+    line.clear();
+    let text = text.strip_suffix('\n').unwrap_or(&text);
+    let (text, done) = match text.find(sentinel) {
+        Some(i) => (&text[..i], true),
+        None => (text, false),
+    };
+    if done && text.is_empty() {
+        return Ok(true);
+    }
+
+    if let Some(u) = updates {
+        writeln!(u, "{text}")?;
+    }
+    if buf.len() >= LINE_COUNT {
+        buf.pop_front();
+    }
+    buf.push_back(text.to_string());
+    Ok(done)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn run(session: &mut ShellSession, os: &Os, command: &str) -> SessionOutput {
+        session
+            .run::<std::io::Sink>(os, command, 10_000, None, None, Duration::from_secs(10))
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn test_state_changes() {
+        let (status, state) = ShellState::parse(b"2\0/tmp\0A=1\0B=x=y\0_=/bin/ls\0").unwrap();
+        assert_eq!(status, 2);
+        assert_eq!(state.cwd, PathBuf::from("/tmp"));
+        assert_eq!(state.env.get("B").unwrap(), "x=y");
+        assert!(state.functions.is_empty());
+
+        let (_, newer) =
+            ShellState::parse(b"0\0/\0B=z\0C=\0_=/bin/cat\0function ls\0trap trap -- 'ls' DEBUG\0").unwrap();
+        assert!(newer.functions.contains("ls"));
+        assert!(newer.traps.contains("trap -- 'ls' DEBUG"));
+        assert_eq!(newer.changes_since(&state), vec![
+            VarChange {
+                name: "A".to_string(),
+                value: None
+            },
+            VarChange {
+                name: "B".to_string(),
+                value: Some("z".to_string())
+            },
+            VarChange {
+                name: "C".to_string(),
+                value: Some(String::new())
+            },
+        ]);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_session_keeps_state() {
+        let os = Os::new().await.unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let dir_path = dir.path().canonicalize().unwrap();
+        let mut session = ShellSession::default();
+
+        let output = run(
+            &mut session,
+            &os,
+            &format!("cd '{}' && export Q_TEST_VAR=1", dir_path.display()),
+        )
+        .await;
+        assert_eq!(output.result.exit_status, Some(0));
+        assert!(output.cwd_changed);
+        assert_eq!(output.state.unwrap().cwd, dir_path);
+        assert_eq!(output.var_changes, vec![VarChange {
+            name: "Q_TEST_VAR".to_string(),
+            value: Some("1".to_string())
+        }]);
+
+        let output = run(
+            &mut session,
+            &os,
+            "pwd; echo $Q_TEST_VAR; printf partial; echo err >&2; false",
+        )
+        .await;
+        assert_eq!(output.result.exit_status, Some(1));
+        assert_eq!(output.result.stdout, format!("{}\n1\npartial", dir_path.display()));
+        assert_eq!(output.result.stderr, "err");
+        assert!(!output.cwd_changed);
+        assert!(output.var_changes.is_empty());
+
+        // Commands do not read from the pipe the session is driven through.
+        let output = run(&mut session, &os, "cat; echo 'unterminated").await;
+        assert_eq!(output.result.exit_status, Some(2));
+        let output = run(&mut session, &os, "unset Q_TEST_VAR").await;
+        assert_eq!(output.var_changes, vec![VarChange {
+            name: "Q_TEST_VAR".to_string(),
+            value: None
+        }]);
+
+        let (state, changes) = session.status().unwrap();
+        assert_eq!(state.cwd, dir_path);
+        assert!(changes.iter().all(|change| change.name != "Q_TEST_VAR"));
+
+        let output = run(&mut session, &os, "exit 3").await;
+        assert_eq!(output.result.exit_status, Some(3));
+        assert!(output.state.is_none());
+        assert!(session.status().is_none());
+
+        let output = run(&mut session, &os, "echo $Q_TEST_VAR").await;
+        assert_eq!(output.result.stdout, "");
+        assert!(session.reset());
+        assert!(!session.reset());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_session_timeout() {
+        let os = Os::new().await.unwrap();
+        let mut session = ShellSession::default();
+        let output = session
+            .run::<std::io::Sink>(
+                &os,
+                "echo started; sleep 30",
+                10_000,
+                None,
+                None,
+                Duration::from_millis(500),
+            )
+            .await
+            .unwrap();
+        assert_eq!(output.result.exit_status, None);
+        assert_eq!(output.result.stdout, "started");
+        assert!(output.result.stderr.contains("timed out"));
+        assert!(session.status().is_none());
+
+        let output = run(&mut session, &os, "echo again").await;
+        assert_eq!(output.result.stdout, "again");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_session_overrides_commands() {
+        let os = Os::new().await.unwrap();
+        let mut session = ShellSession::default();
+        assert!(!session.overrides_commands());
+
+        run(&mut session, &os, "Q_TEST_VAR=1; cd /").await;
+        assert!(!session.overrides_commands());
+
+        let output = run(&mut session, &os, "ls() { echo shadowed; }").await;
+        assert!(output.state.unwrap().functions.contains("ls"));
+        assert!(session.overrides_commands());
+        run(&mut session, &os, "unset -f ls").await;
+        assert!(!session.overrides_commands());
+
+        run(&mut session, &os, "alias ls='echo shadowed'").await;
+        assert!(session.overrides_commands());
+        run(&mut session, &os, "unalias ls").await;
+        assert!(!session.overrides_commands());
+
+        run(&mut session, &os, "export PATH=\"/tmp:$PATH\"").await;
+        assert!(session.overrides_commands());
+        assert!(session.reset());
+        assert!(!session.overrides_commands());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_session_overrides_commands_with_any_exported_variable() {
+        let os = Os::new().await.unwrap();
+        for command in [
+            "export Q_TEST_VAR=1",
+            "export LD_PRELOAD=/tmp/preload.so",
+            "export BASH_ENV=/tmp/env.sh",
+            "export GIT_PAGER=/tmp/pager",
+            "unset HOME",
+        ] {
+            let mut session = ShellSession::default();
+            run(&mut session, &os, command).await;
+            assert!(session.overrides_commands(), "{command}");
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_session_overrides_cannot_be_hidden() {
+        let os = Os::new().await.unwrap();
+        for command in [
+            // Shadowing the builtins that report the state of the shell
+            "compgen() { :; }; ls() { echo shadowed; }",
+            "builtin() { :; }; printf() { :; }; ls() { echo shadowed; }",
+            "unset() { :; }; export() { :; }; set() { :; }; ls() { echo shadowed; }",
+            "shopt -s expand_aliases; alias __q_state=:; ls() { echo shadowed; }",
+            "set -C -u; ls() { echo shadowed; }",
+            // Removing the function before each state is written
+            "trap 'unset -f ls 2>/dev/null' DEBUG",
+            "compgen() { :; }",
+        ] {
+            let mut session = ShellSession::default();
+            run(&mut session, &os, command).await;
+            assert!(session.overrides_commands(), "{command}");
+        }
+
+        // The state is not written when the builtins cannot be trusted, so a new shell is started
+        let mut session = ShellSession::default();
+        let output = run(&mut session, &os, "readonly POSIXLY_CORRECT=; ls() { echo shadowed; }").await;
+        assert!(output.result.stderr.contains("could not be read"), "{output:?}");
+        assert!(session.status().is_none());
+
+        // Commands remembered by bash are looked up again
+        let mut session = ShellSession::default();
+        run(&mut session, &os, "hash -p /bin/echo ls").await;
+        let output = run(&mut session, &os, "type ls").await;
+        assert!(!output.result.stdout.contains("hashed"), "{output:?}");
+    }
+}
@@ -0,0 +1,789 @@
+use tree_sitter::{
+    Node,
+    Parser,
+};
+
+/// Maximum depth of nested scripts, e.g. `bash -c "bash -c '...'"`, that are parsed.
+const MAX_DEPTH: usize = 8;
+
+/// Shells whose `-c` script is parsed as part of the command.
+const SHELLS: &[&str] = &["bash", "sh", "zsh", "dash", "ksh"];
+
+/// Commands that run the command given in their arguments, with the options that take a value
+/// and the number of arguments between the options and the command.
+const WRAPPERS: &[(&str, &[&str], usize)] = &[
+    ("builtin", &[], 0),
+    ("command", &[], 0),
+    ("doas", &["-u", "-C"], 0),
+    ("env", &["-u", "-C", "--unset", "--chdir"], 0),
+    ("exec", &["-a"], 0),
+    ("nice", &["-n", "--adjustment"], 0),
+    ("nohup", &[], 0),
+    ("stdbuf", &["-i", "-o", "-e", "--input", "--output", "--error"], 0),
+    (
+        "sudo",
+        &[
+            "-u",
+            "-g",
+            "-C",
+            "-D",
+            "-h",
+            "-p",
+            "-R",
+            "-r",
+            "-t",
+            "-T",
+            "-U",
+            "--user",
+            "--group",
+            "--close-from",
+            "--chdir",
+            "--host",
+            "--prompt",
+            "--chroot",
+            "--role",
+            "--type",
+            "--command-timeout",
+            "--other-user",
+        ],
+        0,
+    ),
+    ("time", &["-f", "-o", "--format", "--output"], 0),
+    ("timeout", &["-s", "-k", "--signal", "--kill-after"], 1),
+    (
+        "xargs",
+        &[
+            "-a",
+            "-d",
+            "-E",
+            "-I",
+            "-L",
+            "-n",
+            "-P",
+            "-s",
+            "--arg-file",
+            "--delimiter",
+            "--max-args",
+            "--max-procs",
+            "--max-lines",
+            "--max-chars",
+            "--process-slot-var",
+        ],
+        0,
+    ),
+];
+
+/// Arguments of `find` that run the command that follows them, up to a `;` or `+` argument.
+const FIND_EXEC_ARGS: &[&str] = &["-exec", "-execdir", "-ok", "-okdir"];
+
+/// Word of a command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Word {
+    /// Value of a word that has no expansions, after quote removal
+    Static(String),
+    /// Source text of a word whose value is only known when the command runs, e.g. `$HOME/bin`,
+    /// `$(date)` or `*.rs`
+    Dynamic(String),
+}
+
+impl Word {
+    pub fn as_static(&self) -> Option<&str> {
+        match self {
+            Word::Static(value) => Some(value),
+            Word::Dynamic(_) => None,
+        }
+    }
+}
+
+/// Command without control operators, e.g. `git log` in `git log | head`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SimpleCommand {
+    /// Source text of the command, without its redirections
+    pub text: String,
+    /// Name and arguments of the command, empty for commands that only set variables
+    pub argv: Vec<Word>,
+    /// Whether the command sets variables, e.g. `FOO=1 make` or `export FOO=1`
+    pub has_assignments: bool,
+}
+
+/// Commands of a command line, as run by bash.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParsedCommand {
+    /// Every simple command, including the commands in pipelines, lists, subshells, compound
+    /// commands, command substitutions, scripts given to `bash -c` or `eval` and commands run by
+    /// wrappers like `sudo`, `xargs` or `find -exec`
+    pub commands: Vec<SimpleCommand>,
+    /// Files written by output redirections, e.g. `out.txt` in `echo hi > out.txt`
+    pub written_files: Vec<Word>,
+    /// Parts of the command line that cannot be checked, e.g. syntax errors or a script given to
+    /// `eval` that depends on variables
+    pub unsupported: Vec<String>,
+}
+
+/// Parses a command line with the bash grammar.
+pub fn parse(command: &str) -> ParsedCommand {
+    let mut parsed = ParsedCommand::default();
+    parse_into(command, 0, &mut parsed);
+    parsed
+}
+
+fn parse_into(source: &str, depth: usize, parsed: &mut ParsedCommand) {
+    if depth > MAX_DEPTH {
+        parsed.unsupported.push("scripts nested too deeply".to_string());
+        return;
+    }
+
+    let mut parser = Parser::new();
+    let tree = match parser.set_language(&tree_sitter_bash::LANGUAGE.into()) {
+        Ok(()) => parser.parse(source, None),
+        Err(_) => None,
+    };
+    let Some(tree) = tree else {
+        parsed.unsupported.push("the bash parser failed".to_string());
+        return;
+    };
+
+    let root = tree.root_node();
+    if root.has_error() {
+        parsed.unsupported.push(format!("syntax error in `{source}`"));
+        return;
+    }
+    Walker { source, depth, parsed }.walk(root);
+}
+
+struct Walker<'a> {
+    source: &'a str,
+    depth: usize,
+    parsed: &'a mut ParsedCommand,
+}
+
+impl Walker<'_> {
+    fn text(&self, node: Node<'_>) -> &str {
+        &self.source[node.byte_range()]
+    }
+
+    fn walk(&mut self, node: Node<'_>) {
+        match node.kind() {
+            "command" => self.command(node),
+            "declaration_command" | "unset_command" => {
+                let argv = named_children(node)
+                    .into_iter()
+                    .map(|child| decode_word(self.text(child)))
+                    .collect::<Vec<_>>();
+                let mut argv_with_name = vec![Word::Static(first_token(self.text(node)).to_string())];
+                argv_with_name.extend(argv);
+                self.push(SimpleCommand {
+                    text: self.text(node).to_string(),
+                    argv: argv_with_name,
+                    has_assignments: node.kind() == "declaration_command",
+                });
+            },
+            "variable_assignment" | "variable_assignments"
+                if !node.parent().is_some_and(|parent| {
+                    matches!(
+                        parent.kind(),
+                        "command" | "declaration_command" | "variable_assignments"
+                    )
+                }) =>
+            {
+                self.parsed.commands.push(SimpleCommand {
+                    text: self.text(node).to_string(),
+                    argv: Vec::new(),
+                    has_assignments: true,
+                });
+            },
+            "file_redirect" => self.redirect(node),
+            "comment" => return,
+            _ => {},
+        }
+
+        for child in named_children(node) {
+            self.walk(child);
+        }
+    }
+
+    fn command(&mut self, node: Node<'_>) {
+        let mut argv = Vec::new();
+        let mut has_assignments = false;
+        let mut end = node.start_byte();
+        let mut cursor = node.walk();
+        for child in node.named_children(&mut cursor) {
+            if child.kind() == "variable_assignment" {
+                has_assignments = true;
+                end = child.end_byte();
+            }
+        }
+        for child in node
+            .children_by_field_name("name", &mut cursor)
+            .chain(node.children_by_field_name("argument", &mut node.walk()))
+        {
+            argv.push(decode_word(self.text(child)));
+            end = end.max(child.end_byte());
+        }
+
+        self.push(SimpleCommand {
+            text: self.source[node.start_byte()..end].to_string(),
+            argv,
+            has_assignments,
+        });
+    }
+
+    /// Records a command along with the commands that it runs.
+    fn push(&mut self, command: SimpleCommand) {
+        self.parsed.commands.push(command.clone());
+        for inner in self.inner_commands(&command) {
+            self.push(inner);
+        }
+    }
+
+    /// Commands run by `command` itself, e.g. `rm {}` in `find . -exec rm {} ;`. Scripts given to
+    /// shells and `eval` are parsed right away.
+    fn inner_commands(&mut self, command: &SimpleCommand) -> Vec<SimpleCommand> {
+        let Some(name) = command.argv.first().and_then(Word::as_static) else {
+            return Vec::new();
+        };
+        let name = name.rsplit('/').next().unwrap_or(name);
+        let args = &command.argv[1..];
+
+        if SHELLS.contains(&name) {
+            let mut args = args.iter();
+            while let Some(arg) = args.next() {
+                match arg.as_static() {
+                    Some(flags) if flags.starts_with('-') && !flags.starts_with("--") && flags.contains('c') => {
+                        self.script(args.next());
+                        break;
+                    },
+                    Some("-o" | "+o" | "-O" | "+O") => {
+                        args.next();
+                    },
+                    Some(flags) if flags.starts_with('-') || flags.starts_with('+') => {},
+                    _ => break,
+                }
+            }
+            return Vec::new();
+        }
+
+        match name {
+            "eval" => {
+                let script = args
+                    .iter()
+                    .map(|arg| arg.as_static().map(str::to_string))
+                    .collect::<Option<Vec<_>>>()
+                    .map(|args| Word::Static(args.join(" ")));
+                match script {
+                    Some(script) => self.script(Some(&script)),
+                    None => self
+                        .parsed
+                        .unsupported
+                        .push(format!("dynamic script in `{}`", command.text)),
+                }
+                Vec::new()
+            },
+            "find" => {
+                let mut inner = Vec::new();
+                let mut args = args.iter();
+                while let Some(arg) = args.next() {
+                    if !arg.as_static().is_some_and(|arg| FIND_EXEC_ARGS.contains(&arg)) {
+                        continue;
+                    }
+                    let argv = args
+                        .by_ref()
+                        .take_while(|arg| !matches!(arg.as_static(), Some(";" | "+")))
+                        .cloned()
+                        .collect::<Vec<_>>();
+                    inner.push(wrapped_command(argv));
+                }
+                inner
+            },
+            "command" if args.iter().any(|arg| matches!(arg.as_static(), Some("-v" | "-V"))) => Vec::new(),
+            "env"
+                if args.iter().any(|arg| {
+                    arg.as_static()
+                        .is_some_and(|arg| arg == "-S" || arg.starts_with("--split-string"))
+                }) =>
+            {
+                self.parsed
+                    .unsupported
+                    .push(format!("split string in `{}`", command.text));
+                Vec::new()
+            },
+            _ => match WRAPPERS.iter().find(|(wrapper, ..)| *wrapper == name) {
+                Some((_, value_options, positional)) => {
+                    let argv = wrapped_argv(args, value_options, *positional);
+                    match argv.is_empty() {
+                        true => Vec::new(),
+                        false => vec![wrapped_command(argv)],
+                    }
+                },
+                None => Vec::new(),
+            },
+        }
+    }
+
+    /// Parses a script given to a shell or `eval`.
+    fn script(&mut self, script: Option<&Word>) {
+        match script {
+            Some(Word::Static(script)) => parse_into(script, self.depth + 1, self.parsed),
+            Some(Word::Dynamic(script)) => self.parsed.unsupported.push(format!("dynamic script {script}")),
+            None => {},
+        }
+    }
+
+    fn redirect(&mut self, node: Node<'_>) {
+        let mut cursor = node.walk();
+        let is_output = node
+            .children(&mut cursor)
+            .any(|child| !child.is_named() && child.kind().contains('>'));
+        if !is_output {
+            return;
+        }
+
+        let Some(destination) = node.child_by_field_name("destination") else {
+            // Closes a file descriptor, e.g. `2>&-`.
+            return;
+        };
+        if destination.kind() == "number" {
+            // Duplicates a file descriptor, e.g. `2>&1`.
+            return;
+        }
+        let word = decode_word(self.text(destination));
+        if !matches!(
+            word.as_static(),
+            Some("/dev/null" | "/dev/stdout" | "/dev/stderr" | "-")
+        ) {
+            self.parsed.written_files.push(word);
+        }
+    }
+}
+
+fn named_children(node: Node<'_>) -> Vec<Node<'_>> {
+    let mut cursor = node.walk();
+    node.named_children(&mut cursor).collect()
+}
+
+fn first_token(text: &str) -> &str {
+    text.split_whitespace().next().unwrap_or_default()
+}
+
+/// Arguments of a wrapper like `sudo` that make up the command it runs.
+fn wrapped_argv(args: &[Word], value_options: &[&str], positional: usize) -> Vec<Word> {
+    let mut args = args.iter().peekable();
+    while let Some(arg) = args.peek() {
+        match arg.as_static() {
+            Some("--") => {
+                args.next();
+                break;
+            },
+            Some(option) if value_options.contains(&option) => {
+                args.next();
+                args.next();
+            },
+            Some(option) if option.starts_with('-') && option.len() > 1 => {
+                args.next();
+            },
+            Some(assignment) if is_assignment(assignment) => {
+                args.next();
+            },
+            _ => break,
+        }
+    }
+    args.skip(positional).cloned().collect()
+}
+
+fn wrapped_command(argv: Vec<Word>) -> SimpleCommand {
+    let text = argv
+        .iter()
+        .map(|word| match word {
+            Word::Static(value) => shlex::try_quote(value).map_or_else(|_err| value.clone(), |v| v.to_string()),
+            Word::Dynamic(text) => text.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    SimpleCommand {
+        text,
+        argv,
+        has_assignments: false,
+    }
+}
+
+/// Whether `word` is a variable assignment like `FOO=bar`.
+fn is_assignment(word: &str) -> bool {
+    word.split_once('=').is_some_and(|(name, _)| {
+        !name.is_empty()
+            && !name.starts_with(|c: char| c.is_ascii_digit())
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    })
+}
+
+/// Performs quote removal on the source text of a word. The word is [Word::Dynamic] if it has any
+/// expansion: parameters, commands, arithmetic, globs, braces or a leading tilde.
+pub fn decode_word(text: &str) -> Word {
+    match decode(text) {
+        Some(value) => Word::Static(value),
+        None => Word::Dynamic(text.to_string()),
+    }
+}
+
+fn decode(text: &str) -> Option<String> {
+    let mut value = String::new();
+    let mut chars = text.chars().peekable();
+    let mut unquoted = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('\n') => {},
+                Some(c) => value.push(c),
+                None => value.push('\\'),
+            },
+            '\'' => loop {
+                match chars.next()? {
+                    '\'' => break,
+                    c => value.push(c),
+                }
+            },
+            '"' => loop {
+                match chars.next()? {
+                    '"' => break,
+                    '$' | '`' => return None,
+                    '\\' => match chars.peek() {
+                        Some('$' | '`' | '"' | '\\') => value.push(chars.next()?),
+                        Some('\n') => {
+                            chars.next();
+                        },
+                        _ => value.push('\\'),
+                    },
+                    c => value.push(c),
+                }
+            },
+            '$' | '`' | '*' | '?' => return None,
+            // Process substitution, e.g. `<(ls)`.
+            '<' | '>' if chars.peek() == Some(&'(') => return None,
+            '~' if value.is_empty() => return None,
+            '[' if text.len() > 1 => return None,
+            c => {
+                unquoted.push(c);
+                value.push(c);
+            },
+        }
+    }
+
+    // Brace expansion, e.g. `{a,b}` or `{1..3}`.
+    if let Some((_, rest)) = unquoted.split_once('{') {
+        if rest
+            .split_once('}')
+            .is_some_and(|(inner, _)| inner.contains(',') || inner.contains(".."))
+        {
+            return None;
+        }
+    }
+
+    Some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn s(value: &str) -> Word {
+        Word::Static(value.to_string())
+    }
+
+    fn d(text: &str) -> Word {
+        Word::Dynamic(text.to_string())
+    }
+
+    fn argvs(command: &str) -> Vec<Vec<Word>> {
+        parse(command).commands.into_iter().map(|c| c.argv).collect()
+    }
+
+    #[test]
+    fn test_decode_word() {
+        let cases = [
+            ("ls", s("ls")),
+            ("'a b'", s("a b")),
+            ("\"a b\"", s("a b")),
+            ("a\"b\"'c'", s("abc")),
+            (r"\;", s(";")),
+            (r"\{\}", s("{}")),
+            ("{}", s("{}")),
+            (r#""a \"b\" \$c \d""#, s(r#"a "b" $c \d"#)),
+            ("'$HOME'", s("$HOME")),
+            ("[", s("[")),
+            ("a=b", s("a=b")),
+            ("foo~", s("foo~")),
+            ("'*.rs'", s("*.rs")),
+            (r"\*", s("*")),
+            ("$HOME", d("$HOME")),
+            ("\"$HOME\"", d("\"$HOME\"")),
+            ("${x:-y}", d("${x:-y}")),
+            ("$(id)", d("$(id)")),
+            ("`id`", d("`id`")),
+            ("$'\\x41'", d("$'\\x41'")),
+            ("*.rs", d("*.rs")),
+            ("file?", d("file?")),
+            ("[ab]", d("[ab]")),
+            ("~/x", d("~/x")),
+            ("{a,b}", d("{a,b}")),
+            ("x{1..3}", d("x{1..3}")),
+            ("-exe$9c", d("-exe$9c")),
+            ("'unterminated", d("'unterminated")),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(decode_word(text), expected, "{text}");
+        }
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        let cases: &[(&str, Vec<Vec<Word>>)] = &[
+            ("ls", vec![vec![s("ls")]]),
+            ("ls -la ~", vec![vec![s("ls"), s("-la"), d("~")]]),
+            ("git status && git diff", vec![vec![s("git"), s("status")], vec![
+                s("git"),
+                s("diff"),
+            ]]),
+            ("a; b || c & d", vec![vec![s("a")], vec![s("b")], vec![s("c")], vec![
+                s("d"),
+            ]]),
+            ("a\nb", vec![vec![s("a")], vec![s("b")]]),
+            ("cat f | grep x | head -n 5", vec![
+                vec![s("cat"), s("f")],
+                vec![s("grep"), s("x")],
+                vec![s("head"), s("-n"), s("5")],
+            ]),
+            ("(cd x; ls) | wc -l", vec![vec![s("cd"), s("x")], vec![s("ls")], vec![
+                s("wc"),
+                s("-l"),
+            ]]),
+            ("{ a; b; }", vec![vec![s("a")], vec![s("b")]]),
+            ("echo $(rm x)", vec![vec![s("echo"), d("$(rm x)")], vec![
+                s("rm"),
+                s("x"),
+            ]]),
+            ("echo `rm x`", vec![vec![s("echo"), d("`rm x`")], vec![s("rm"), s("x")]]),
+            ("echo \"a $(rm x)\"", vec![vec![s("echo"), d("\"a $(rm x)\"")], vec![
+                s("rm"),
+                s("x"),
+            ]]),
+            ("echo ${x:-$(rm x)}", vec![vec![s("echo"), d("${x:-$(rm x)}")], vec![
+                s("rm"),
+                s("x"),
+            ]]),
+            ("diff <(ls a) >(tee b)", vec![
+                vec![s("diff"), d("<(ls a)"), d(">(tee b)")],
+                vec![s("ls"), s("a")],
+                vec![s("tee"), s("b")],
+            ]),
+            ("if test -f x; then rm x; else touch x; fi", vec![
+                vec![s("test"), s("-f"), s("x")],
+                vec![s("rm"), s("x")],
+                vec![s("touch"), s("x")],
+            ]),
+            ("while true; do sleep 1; done", vec![vec![s("true")], vec![
+                s("sleep"),
+                s("1"),
+            ]]),
+            ("for f in *.rs; do rm \"$f\"; done", vec![vec![s("rm"), d("\"$f\"")]]),
+            ("case $x in a) rm x;; esac", vec![vec![s("rm"), s("x")]]),
+            ("f() { rm x; }; f", vec![vec![s("rm"), s("x")], vec![s("f")]]),
+            ("! grep -q x y", vec![vec![s("grep"), s("-q"), s("x"), s("y")]]),
+            ("[[ -f $(rm x) ]]", vec![vec![s("rm"), s("x")]]),
+            ("[ -d y ]", vec![]),
+            ("[ -f $(rm x) ]", vec![vec![s("rm"), s("x")]]),
+            ("echo $((1 + $(rm x)))", vec![
+                vec![s("echo"), d("$((1 + $(rm x)))")],
+                vec![s("rm"), s("x")],
+            ]),
+            ("# just a comment", vec![]),
+            ("ls # rm x", vec![vec![s("ls")]]),
+            ("$CMD -x", vec![vec![d("$CMD"), s("-x")]]),
+            ("/bin/rm x", vec![vec![s("/bin/rm"), s("x")]]),
+            ("'r'm x", vec![vec![s("rm"), s("x")]]),
+            (r"r\m x", vec![vec![s("rm"), s("x")]]),
+        ];
+        for (command, expected) in cases {
+            assert_eq!(&argvs(command), expected, "{command}");
+        }
+    }
+
+    #[test]
+    fn test_parse_assignments() {
+        let parsed = parse("FOO=1 BAR=$(rm x) make -j4");
+        assert_eq!(parsed.commands.len(), 2);
+        assert_eq!(parsed.commands[0].argv, vec![s("make"), s("-j4")]);
+        assert_eq!(parsed.commands[0].text, "FOO=1 BAR=$(rm x) make -j4");
+        assert!(parsed.commands[0].has_assignments);
+        assert_eq!(parsed.commands[1].argv, vec![s("rm"), s("x")]);
+
+        for command in ["FOO=1", "A=1 B=2", "IFS=';'"] {
+            let parsed = parse(command);
+            assert_eq!(parsed.commands.len(), 1, "{command}");
+            assert!(parsed.commands[0].argv.is_empty(), "{command}");
+            assert!(parsed.commands[0].has_assignments, "{command}");
+        }
+
+        let parsed = parse("export PATH=/tmp:$PATH");
+        assert_eq!(parsed.commands[0].argv, vec![s("export"), d("PATH=/tmp:$PATH")]);
+        assert!(parsed.commands[0].has_assignments);
+
+        let parsed = parse("unset FOO");
+        assert_eq!(parsed.commands[0].argv, vec![s("unset"), s("FOO")]);
+        assert!(!parsed.commands[0].has_assignments);
+    }
+
+    #[test]
+    fn test_parse_redirections() {
+        let cases: &[(&str, Vec<Word>)] = &[
+            ("echo hi > out", vec![s("out")]),
+            ("echo hi >> 'my file'", vec![s("my file")]),
+            ("echo hi &> out", vec![s("out")]),
+            ("echo hi >| out", vec![s("out")]),
+            ("echo hi 2> err", vec![s("err")]),
+            ("echo hi > $FILE", vec![d("$FILE")]),
+            ("cat <<EOF > out\nhello\nEOF", vec![s("out")]),
+            ("(echo hi) > out", vec![s("out")]),
+            ("{ echo hi; } > out", vec![s("out")]),
+            ("for i in 1 2; do echo $i; done > out", vec![s("out")]),
+            ("echo hi > /dev/null 2>&1", vec![]),
+            ("echo hi 2>/dev/null >&2", vec![]),
+            ("echo hi 2>&-", vec![]),
+            ("cat < in", vec![]),
+            ("cat <<< hi", vec![]),
+            ("cat <<EOF\n> not a redirect\nEOF", vec![]),
+            ("echo '>' \">\" \\>", vec![]),
+        ];
+        for (command, expected) in cases {
+            let parsed = parse(command);
+            assert_eq!(&parsed.written_files, expected, "{command}");
+            assert!(parsed.unsupported.is_empty(), "{command}");
+        }
+
+        let parsed = parse("cat <<EOF\n$(rm x)\nEOF");
+        assert_eq!(parsed.commands[1].argv, vec![s("rm"), s("x")]);
+        let parsed = parse("cat <<'EOF'\n$(rm x)\nEOF");
+        assert_eq!(parsed.commands.len(), 1);
+        let parsed = parse("cat <<< \"$(rm x)\"");
+        assert_eq!(parsed.commands[1].argv, vec![s("rm"), s("x")]);
+        let parsed = parse("echo hi > $(rm x)");
+        assert_eq!(parsed.commands[1].argv, vec![s("rm"), s("x")]);
+    }
+
+    #[test]
+    fn test_parse_inner_commands() {
+        let cases: &[(&str, Vec<Vec<Word>>)] = &[
+            ("bash -c 'rm -rf x'", vec![
+                vec![s("bash"), s("-c"), s("rm -rf x")],
+                vec![s("rm"), s("-rf"), s("x")],
+            ]),
+            ("sh -ec \"ls; rm x\"", vec![
+                vec![s("sh"), s("-ec"), s("ls; rm x")],
+                vec![s("ls")],
+                vec![s("rm"), s("x")],
+            ]),
+            ("bash script.sh", vec![vec![s("bash"), s("script.sh")]]),
+            ("eval 'rm x'", vec![vec![s("eval"), s("rm x")], vec![s("rm"), s("x")]]),
+            ("eval rm x", vec![vec![s("eval"), s("rm"), s("x")], vec![
+                s("rm"),
+                s("x"),
+            ]]),
+            ("sudo rm -rf /", vec![vec![s("sudo"), s("rm"), s("-rf"), s("/")], vec![
+                s("rm"),
+                s("-rf"),
+                s("/"),
+            ]]),
+            ("sudo -u bob -E rm x", vec![
+                vec![s("sudo"), s("-u"), s("bob"), s("-E"), s("rm"), s("x")],
+                vec![s("rm"), s("x")],
+            ]),
+            ("env -i FOO=1 rm x", vec![
+                vec![s("env"), s("-i"), s("FOO=1"), s("rm"), s("x")],
+                vec![s("rm"), s("x")],
+            ]),
+            ("env", vec![vec![s("env")]]),
+            ("timeout -s KILL 5 rm x", vec![
+                vec![s("timeout"), s("-s"), s("KILL"), s("5"), s("rm"), s("x")],
+                vec![s("rm"), s("x")],
+            ]),
+            ("nice -n 10 nohup rm x", vec![
+                vec![s("nice"), s("-n"), s("10"), s("nohup"), s("rm"), s("x")],
+                vec![s("nohup"), s("rm"), s("x")],
+                vec![s("rm"), s("x")],
+            ]),
+            ("echo x | xargs -n 1 rm", vec![
+                vec![s("echo"), s("x")],
+                vec![s("xargs"), s("-n"), s("1"), s("rm")],
+                vec![s("rm")],
+            ]),
+            ("command -v rm", vec![vec![s("command"), s("-v"), s("rm")]]),
+            ("command rm x", vec![vec![s("command"), s("rm"), s("x")], vec![
+                s("rm"),
+                s("x"),
+            ]]),
+            ("find . -name '*.rs' -exec rm {} \\; -print", vec![
+                vec![
+                    s("find"),
+                    s("."),
+                    s("-name"),
+                    s("*.rs"),
+                    s("-exec"),
+                    s("rm"),
+                    s("{}"),
+                    s(";"),
+                    s("-print"),
+                ],
+                vec![s("rm"), s("{}")],
+            ]),
+            ("find . -execdir cat {} + -ok rm {} ';'", vec![
+                vec![
+                    s("find"),
+                    s("."),
+                    s("-execdir"),
+                    s("cat"),
+                    s("{}"),
+                    s("+"),
+                    s("-ok"),
+                    s("rm"),
+                    s("{}"),
+                    s(";"),
+                ],
+                vec![s("cat"), s("{}")],
+                vec![s("rm"), s("{}")],
+            ]),
+        ];
+        for (command, expected) in cases {
+            let parsed = parse(command);
+            assert_eq!(
+                &parsed.commands.into_iter().map(|c| c.argv).collect::<Vec<_>>(),
+                expected,
+                "{command}"
+            );
+            assert!(parsed.unsupported.is_empty(), "{command}");
+        }
+
+        let parsed = parse("sudo rm x");
+        assert_eq!(parsed.commands[1].text, "rm x");
+    }
+
+    #[test]
+    fn test_parse_unsupported() {
+        for command in [
+            "echo \"unterminated",
+            "echo $(",
+            "if true; then",
+            "ls |",
+            "bash -c \"$SCRIPT\"",
+            "eval \"$CMD\"",
+            "eval $(cat x)",
+            "env -S 'rm x'",
+            "bash -c 'echo \"'",
+            "cat <> file",
+        ] {
+            assert!(!parse(command).unsupported.is_empty(), "{command}");
+        }
+
+        let nested = (0..=MAX_DEPTH).fold("ls".to_string(), |script, _| {
+            format!("bash -c {}", shlex::try_quote(&script).unwrap())
+        });
+        assert!(!parse(&nested).unsupported.is_empty());
+    }
+}
@@ -12,6 +12,7 @@ use tracing::error;
 
 use super::{
     CommandResult,
+    Sandbox,
     env_vars_with_user_agent,
     format_output,
 };
@@ -22,6 +23,7 @@ use crate::os::Os;
 /// * `command` - The command to run
 /// * `max_result_size` - max size of output streams, truncating if required
 /// * `updates` - output stream to push informational messages about the progress
+/// * `sandbox` - restrictions to apply to the command, if any
 /// # Returns
 /// A [`CommandResult`]
 pub async fn run_command<W: Write>(
@@ -29,6 +31,7 @@ pub async fn run_command<W: Write>(
     command: &str,
     max_result_size: usize,
     mut updates: Option<W>,
+    sandbox: Option<Sandbox>,
 ) -> Result<CommandResult> {
     let shell = std::env::var("AMAZON_Q_CHAT_SHELL").unwrap_or("bash".to_string());
 
@@ -36,13 +39,17 @@ pub async fn run_command<W: Write>(
     let env_vars = env_vars_with_user_agent(os);
 
     // We need to maintain a handle on stderr and stdout, but pipe it to the terminal as well
-    let mut child = tokio::process::Command::new(shell)
-        .arg("-c")
+    let mut cmd = tokio::process::Command::new(shell);
+    cmd.arg("-c")
         .arg(command)
         .envs(env_vars)
         .stdin(Stdio::inherit())
         .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Some(sandbox) = sandbox {
+        sandbox.apply(&mut cmd);
+    }
+    let mut child = cmd
         .spawn()
         .wrap_err_with(|| format!("Unable to spawn command '{}'", command))?;
 
@@ -128,7 +135,10 @@ pub async fn run_command<W: Write>(
 #[cfg(test)]
 mod tests {
     use crate::cli::chat::tools::OutputKind;
-    use crate::cli::chat::tools::execute::ExecuteCommand;
+    use crate::cli::chat::tools::execute::{
+        ExecuteCommand,
+        ShellSession,
+    };
     use crate::os::Os;
 
     #[ignore = "todo: fix failing on musl for some reason"]
@@ -143,7 +153,7 @@ mod tests {
         });
         let out = serde_json::from_value::<ExecuteCommand>(v)
             .unwrap()
-            .invoke(&os, &mut stdout)
+            .invoke(&os, &mut stdout, None, &mut ShellSession::default())
             .await
             .unwrap();
 
@@ -161,7 +171,7 @@ mod tests {
         });
         let out = serde_json::from_value::<ExecuteCommand>(v)
             .unwrap()
-            .invoke(&os, &mut stdout)
+            .invoke(&os, &mut stdout, None, &mut ShellSession::default())
             .await
             .unwrap();
 
@@ -179,7 +189,7 @@ mod tests {
         });
         let out = serde_json::from_value::<ExecuteCommand>(v)
             .unwrap()
-            .invoke(&os, &mut stdout)
+            .invoke(&os, &mut stdout, None, &mut ShellSession::default())
             .await
             .unwrap();
         if let OutputKind::Json(json) = out.output {
@@ -11,7 +11,10 @@ use eyre::{
     Result,
     bail,
 };
-use globset::GlobSetBuilder;
+use globset::{
+    GlobSet,
+    GlobSetBuilder,
+};
 use serde::{
     Deserialize,
     Serialize,
@@ -822,6 +825,21 @@ struct SearchMatch {
     context: String,
 }
 
+/// Builds the set of paths denied by the `deniedPaths` setting of fs_read, if any.
+pub fn denied_paths(os: &Os, agent: &Agent) -> Option<GlobSet> {
+    let denied_paths = agent.tools_settings.get("fs_read")?.get("deniedPaths")?.as_array()?;
+    let mut builder = GlobSetBuilder::new();
+    for path in denied_paths.iter().filter_map(|path| path.as_str()) {
+        let Ok(path) = directories::canonicalizes_path(os, path) else {
+            continue;
+        };
+        if let Err(e) = directories::add_gitignore_globs(&mut builder, path.as_str()) {
+            warn!("Failed to create glob from path given: {path}: {e}. Ignoring.");
+        }
+    }
+    builder.build().ok()
+}
+
 fn format_ftype(md: &Metadata) -> char {
     if md.is_symlink() {
         'l'
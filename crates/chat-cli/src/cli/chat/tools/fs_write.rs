@@ -42,6 +42,7 @@ use crate::cli::agent::{
     Agent,
     PermissionEvalResult,
 };
+use crate::cli::chat::export::unified_diff;
 use crate::cli::chat::line_tracker::FileLineTracker;
 use crate::os::Os;
 use crate::util::directories;
@@ -86,12 +87,23 @@ pub enum FsWrite {
 
 impl FsWrite {
     pub fn path(&self, os: &Os) -> PathBuf {
-        sanitize_path_tool_arg(os, match self {
+        sanitize_path_tool_arg(os, self.input_path())
+    }
+
+    /// The path as given in the input of the tool.
+    fn input_path(&self) -> &str {
+        match self {
             FsWrite::Create { path, .. } => path.as_str(),
             FsWrite::StrReplace { path, .. } => path.as_str(),
             FsWrite::Insert { path, .. } => path.as_str(),
             FsWrite::Append { path, .. } => path.as_str(),
-        })
+        }
+    }
+
+    /// Unified diff of the file since it contained `before`, [None] meaning it did not exist.
+    pub async fn diff(&self, os: &Os, before: Option<&str>) -> Result<String> {
+        let after = os.fs.read_to_string(self.path(os)).await?;
+        Ok(unified_diff(self.input_path(), before, &after))
     }
 
     pub async fn invoke(
@@ -1065,6 +1077,41 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_fs_write_diff() {
+        let os = setup_test_directory().await;
+        let diff = |args: serde_json::Value| {
+            let os = &os;
+            async move {
+                let fs_write = serde_json::from_value::<FsWrite>(args).unwrap();
+                let before = os.fs.read_to_string(fs_write.path(os)).await.ok();
+                fs_write
+                    .invoke(os, &mut std::io::sink(), &mut HashMap::new())
+                    .await
+                    .unwrap();
+                fs_write.diff(os, before.as_deref()).await.unwrap()
+            }
+        };
+
+        assert_eq!(
+            diff(serde_json::json!({ "path": "/new.txt", "command": "create", "file_text": "one\ntwo" })).await,
+            "--- /dev/null\n+++ b/new.txt\n@@ -0,0 +1,2 @@\n+one\n+two\n"
+        );
+        // Inserted and appended lines come with the lines around them
+        assert_eq!(
+            diff(serde_json::json!({ "path": TEST_FILE_PATH, "command": "insert", "insert_line": 2, "new_str": "inserted\n" })).await,
+            "--- a/test_file.txt\n+++ b/test_file.txt\n@@ -1,4 +1,5 @@\n 1: Hello world!\n 2: This is line 2\n+inserted\n 3: asdf\n 4: Hello world!\n"
+        );
+        assert_eq!(
+            diff(serde_json::json!({ "path": TEST_FILE_PATH, "command": "append", "new_str": "appended\n" })).await,
+            "--- a/test_file.txt\n+++ b/test_file.txt\n@@ -3,3 +3,4 @@\n inserted\n 3: asdf\n 4: Hello world!\n+appended\n"
+        );
+        assert_eq!(
+            diff(serde_json::json!({ "path": TEST_FILE_PATH, "command": "str_replace", "old_str": "asdf", "new_str": "qwer" })).await,
+            "--- a/test_file.txt\n+++ b/test_file.txt\n@@ -1,6 +1,6 @@\n 1: Hello world!\n 2: This is line 2\n inserted\n-3: asdf\n+3: qwer\n 4: Hello world!\n appended\n"
+        );
+    }
+
     #[tokio::test]
     async fn test_fs_write_tool_insert_at_beginning() {
         let os = setup_test_directory().await;
@@ -6,6 +6,7 @@ use crossterm::style::{
     Color,
 };
 use eyre::Result;
+use semantic_search_client::SearchFilter;
 use serde::Deserialize;
 use tracing::warn;
 
@@ -67,6 +68,25 @@ pub struct KnowledgeClear {
 pub struct KnowledgeSearch {
     pub query: String,
     pub context_id: Option<String>,
+    /// Glob the context name must match
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Glob the path of the source file must match
+    #[serde(default)]
+    pub path_glob: Option<String>,
+    /// File type or extension of the source file
+    #[serde(default)]
+    pub file_type: Option<String>,
+}
+
+impl KnowledgeSearch {
+    fn filter(&self) -> SearchFilter {
+        SearchFilter {
+            path: self.path_glob.clone(),
+            file_type: self.file_type.clone(),
+            context_name: self.name.clone(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -86,6 +106,11 @@ pub struct KnowledgeCancel {
 }
 
 impl Knowledge {
+    /// Whether the operation leaves the knowledge base untouched
+    pub fn is_read_only(&self) -> bool {
+        matches!(self, Knowledge::Search(_) | Knowledge::Show | Knowledge::Status)
+    }
+
     /// Checks if the knowledge feature is enabled in settings
     pub fn is_enabled(os: &Os) -> bool {
         os.database
@@ -292,9 +317,29 @@ impl Knowledge {
                         style::Print(context_id),
                         style::ResetColor,
                     )?;
+                } else if let Some(name) = &search.name {
+                    queue!(
+                        updates,
+                        style::Print(" in contexts matching: "),
+                        style::SetForegroundColor(Color::Green),
+                        style::Print(name),
+                        style::ResetColor,
+                    )?;
                 } else {
                     queue!(updates, style::Print(" across all contexts"),)?;
                 }
+
+                for (label, value) in [("path", &search.path_glob), ("file type", &search.file_type)] {
+                    if let Some(value) = value {
+                        queue!(
+                            updates,
+                            style::Print(format!(", {label}: ")),
+                            style::SetForegroundColor(Color::Green),
+                            style::Print(value),
+                            style::ResetColor,
+                        )?;
+                    }
+                }
             },
             Knowledge::Show => {
                 queue!(updates, style::Print("Showing all knowledge base entries"),)?;
@@ -428,7 +473,9 @@ impl Knowledge {
                 .await
                 .unwrap_or_else(|e| format!("Failed to clear knowledge base: {}", e)),
             Knowledge::Search(search) => {
-                let results = store.search(&search.query, search.context_id.as_deref()).await;
+                let results = store
+                    .search(&search.query, search.context_id.as_deref(), &search.filter())
+                    .await;
                 match results {
                     Ok(results) => {
                         if results.is_empty() {
@@ -437,6 +484,18 @@ impl Knowledge {
                             let mut output = format!("Search results for \"{}\":\n\n", search.query);
                             for result in results {
                                 if let Some(text) = result.text() {
+                                    if let Some(path) = result.path() {
+                                        output.push_str(path);
+                                        if let Some((start, end)) = result.line_range() {
+                                            output.push_str(&format!(":{}-{}", start, end));
+                                        } else if let Some((kind, number)) = result.section() {
+                                            output.push_str(&format!(" ({} {})", kind, number));
+                                        }
+                                        if let Some(symbol) = result.symbol() {
+                                            output.push_str(&format!(" ({})", symbol));
+                                        }
+                                        output.push('\n');
+                                    }
                                     output.push_str(&format!("{}\n\n", text));
                                 }
                             }
@@ -0,0 +1,211 @@
+use std::io::Write;
+
+use crossterm::queue;
+use crossterm::style::{
+    self,
+    Color,
+};
+use eyre::{
+    Result,
+    bail,
+};
+use rmcp::model::ReadResourceRequestParam;
+use serde::Deserialize;
+use serde_json::json;
+
+use super::{
+    InvokeOutput,
+    OutputKind,
+};
+use crate::cli::agent::{
+    Agent,
+    PermissionEvalResult,
+};
+use crate::mcp_client::RunningService;
+use crate::mcp_client::resources::{
+    ResourceStore,
+    contents_to_text,
+};
+use crate::os::Os;
+use crate::util::pattern_matching::matches_any_pattern;
+
+/// Lets the model discover and read the resources offered by mcp servers.
+#[derive(Debug, Clone)]
+pub struct McpResources {
+    pub operation: McpResourcesOperation,
+    pub store: ResourceStore,
+    /// Client of the server the resource is read from. Only present for
+    /// [McpResourcesOperation::Read].
+    pub client: Option<RunningService>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "mode")]
+pub enum McpResourcesOperation {
+    /// Lists the resources and resource templates offered by all servers, or by the given one
+    List {
+        #[serde(default)]
+        server_name: Option<String>,
+    },
+    /// Reads the contents of a resource
+    Read { server_name: String, uri: String },
+}
+
+impl McpResources {
+    pub async fn invoke(&self, _os: &Os, _updates: impl Write) -> Result<InvokeOutput> {
+        match &self.operation {
+            McpResourcesOperation::List { server_name } => {
+                let filter = |name: &String| server_name.as_ref().is_none_or(|server_name| server_name == name);
+                let mut servers = serde_json::Map::new();
+                for (name, resources) in self.store.resources().into_iter().filter(|(name, _)| filter(name)) {
+                    servers.insert(name, json!({ "resources": resources }));
+                }
+                for (name, templates) in self.store.templates().into_iter().filter(|(name, _)| filter(name)) {
+                    let entry = servers.entry(name).or_insert_with(|| json!({}));
+                    entry["resourceTemplates"] = json!(templates);
+                }
+
+                Ok(InvokeOutput {
+                    output: OutputKind::Json(serde_json::Value::Object(servers)),
+                })
+            },
+            McpResourcesOperation::Read { server_name, uri } => {
+                let Some(client) = &self.client else {
+                    bail!("The mcp server {server_name} is not available");
+                };
+                let result = client
+                    .read_resource(ReadResourceRequestParam { uri: uri.clone() })
+                    .await?;
+
+                Ok(InvokeOutput {
+                    output: OutputKind::Text(contents_to_text(&result)),
+                })
+            },
+        }
+    }
+
+    pub fn queue_description(&self, output: &mut impl Write) -> Result<()> {
+        match &self.operation {
+            McpResourcesOperation::List { server_name: None } => {
+                queue!(output, style::Print("Listing resources offered by mcp servers\n"))?;
+            },
+            McpResourcesOperation::List {
+                server_name: Some(server_name),
+            } => {
+                queue!(
+                    output,
+                    style::Print("Listing resources offered by "),
+                    style::SetForegroundColor(Color::Green),
+                    style::Print(server_name),
+                    style::ResetColor,
+                    style::Print("\n"),
+                )?;
+            },
+            McpResourcesOperation::Read { server_name, uri } => {
+                queue!(
+                    output,
+                    style::Print("Reading resource "),
+                    style::SetForegroundColor(Color::Green),
+                    style::Print(uri),
+                    style::ResetColor,
+                    style::Print(" from "),
+                    style::SetForegroundColor(Color::Green),
+                    style::Print(server_name),
+                    style::ResetColor,
+                    style::Print("\n"),
+                )?;
+            },
+        }
+        Ok(())
+    }
+
+    pub async fn validate(&mut self, _os: &Os) -> Result<()> {
+        if let McpResourcesOperation::Read { uri, .. } = &self.operation {
+            if uri.trim().is_empty() {
+                bail!("The resource uri must not be empty");
+            }
+        }
+        Ok(())
+    }
+
+    /// Listing only shows what servers already advertised, whereas reading a resource contacts the
+    /// server and is allowed the same way as the server's tools.
+    pub fn eval_perm(&self, _os: &Os, agent: &Agent) -> PermissionEvalResult {
+        let McpResourcesOperation::Read { server_name, .. } = &self.operation else {
+            return PermissionEvalResult::Allow;
+        };
+
+        if agent.allowed_tools.contains(&format!("@{server_name}"))
+            || matches_any_pattern(&agent.allowed_tools, "mcp_resources")
+        {
+            return PermissionEvalResult::Allow;
+        }
+
+        PermissionEvalResult::Ask
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rmcp::model::{
+        AnnotateAble,
+        RawResource,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_list_resources() {
+        let os = Os::new().await.unwrap();
+        let store = ResourceStore::default();
+        store.set_resources("db", vec![RawResource::new("db://schema", "schema").no_annotation()]);
+        store.set_resources("notes", vec![]);
+
+        let tool = McpResources {
+            operation: serde_json::from_value(json!({ "mode": "List", "server_name": "db" })).unwrap(),
+            store,
+            client: None,
+        };
+        let OutputKind::Json(output) = tool.invoke(&os, std::io::sink()).await.unwrap().output else {
+            panic!("Expected json output");
+        };
+        assert_eq!(
+            output,
+            json!({ "db": { "resources": [{ "uri": "db://schema", "name": "schema" }] } })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_requires_client() {
+        let os = Os::new().await.unwrap();
+        let tool = McpResources {
+            operation: serde_json::from_value(json!({ "mode": "Read", "server_name": "db", "uri": "db://schema" }))
+                .unwrap(),
+            store: ResourceStore::default(),
+            client: None,
+        };
+        assert!(tool.invoke(&os, std::io::sink()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_eval_perm() {
+        let os = Os::new().await.unwrap();
+        let tool = |operation| McpResources {
+            operation: serde_json::from_value(operation).unwrap(),
+            store: ResourceStore::default(),
+            client: None,
+        };
+        let list = tool(json!({ "mode": "List" }));
+        let read = tool(json!({ "mode": "Read", "server_name": "db", "uri": "db://schema" }));
+
+        let mut agent = Agent::default();
+        assert!(matches!(list.eval_perm(&os, &agent), PermissionEvalResult::Allow));
+        assert!(matches!(read.eval_perm(&os, &agent), PermissionEvalResult::Ask));
+
+        agent.allowed_tools.insert("@notes".to_string());
+        assert!(matches!(read.eval_perm(&os, &agent), PermissionEvalResult::Ask));
+
+        agent.allowed_tools.insert("@db".to_string());
+        assert!(matches!(read.eval_perm(&os, &agent), PermissionEvalResult::Allow));
+    }
+}
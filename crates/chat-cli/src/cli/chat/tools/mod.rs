@@ -1,3 +1,4 @@
+pub mod code_search;
 pub mod custom_tool;
 pub mod execute;
 pub mod fs_read;
@@ -5,6 +6,7 @@ pub mod fs_write;
 pub mod gh_issue;
 pub mod introspect;
 pub mod knowledge;
+pub mod mcp_resources;
 pub mod thinking;
 pub mod todo;
 pub mod use_aws;
@@ -20,19 +22,24 @@ use std::path::{
     PathBuf,
 };
 
+use code_search::CodeSearch;
 use crossterm::queue;
 use crossterm::style::{
     self,
     Color,
 };
 use custom_tool::CustomTool;
-use execute::ExecuteCommand;
+use execute::{
+    ExecuteCommand,
+    ShellSession,
+};
 use eyre::Result;
 use fs_read::FsRead;
 use fs_write::FsWrite;
 use gh_issue::GhIssue;
 use introspect::Introspect;
 use knowledge::Knowledge;
+use mcp_resources::McpResources;
 use serde::{
     Deserialize,
     Serialize,
@@ -58,8 +65,9 @@ use crate::cli::chat::line_tracker::FileLineTracker;
 use crate::os::Os;
 
 pub const DEFAULT_APPROVE: [&str; 1] = ["fs_read"];
-pub const NATIVE_TOOLS: [&str; 8] = [
+pub const NATIVE_TOOLS: [&str; 10] = [
     "fs_read",
+    "code_search",
     "fs_write",
     #[cfg(windows)]
     "execute_cmd",
@@ -68,6 +76,7 @@ pub const NATIVE_TOOLS: [&str; 8] = [
     "use_aws",
     "gh_issue",
     "knowledge",
+    "mcp_resources",
     "thinking",
     "todo_list",
 ];
@@ -86,6 +95,8 @@ pub enum Tool {
     Knowledge(Knowledge),
     Thinking(Thinking),
     Todo(TodoList),
+    McpResources(McpResources),
+    CodeSearch(CodeSearch),
 }
 
 impl Tool {
@@ -105,6 +116,8 @@ impl Tool {
             Tool::Knowledge(_) => "knowledge",
             Tool::Thinking(_) => "thinking (prerelease)",
             Tool::Todo(_) => "todo_list",
+            Tool::McpResources(_) => "mcp_resources",
+            Tool::CodeSearch(_) => "code_search",
         }
         .to_owned()
     }
@@ -121,29 +134,50 @@ impl Tool {
             Tool::Introspect(_) => PermissionEvalResult::Allow,
             Tool::Thinking(_) => PermissionEvalResult::Allow,
             Tool::Todo(_) => PermissionEvalResult::Allow,
+            Tool::McpResources(mcp_resources) => mcp_resources.eval_perm(os, agent),
+            Tool::CodeSearch(code_search) => code_search.eval_perm(os, agent),
             Tool::Knowledge(knowledge) => knowledge.eval_perm(os, agent),
         }
     }
 
+    /// Whether the tool only reads state, in which case it can run concurrently with other
+    /// read-only tools.
+    pub fn is_read_only(&self) -> bool {
+        match self {
+            Tool::FsRead(_) | Tool::Introspect(_) | Tool::McpResources(_) | Tool::CodeSearch(_) => true,
+            Tool::UseAws(use_aws) => !use_aws.requires_acceptance(),
+            Tool::Knowledge(knowledge) => knowledge.is_read_only(),
+            Tool::Custom(custom_tool) => custom_tool.read_only,
+            Tool::FsWrite(_) | Tool::ExecuteCommand(_) | Tool::GhIssue(_) | Tool::Thinking(_) | Tool::Todo(_) => false,
+        }
+    }
+
     /// Invokes the tool asynchronously
+    ///
+    /// `ctrl_c` receives the ctrl+c presses of the chat session, which mcp tools handle by
+    /// cancelling the call.
     pub async fn invoke(
         &self,
         os: &Os,
         stdout: &mut impl Write,
         line_tracker: &mut HashMap<String, FileLineTracker>,
         agent: Option<&crate::cli::agent::Agent>,
+        shell: &mut ShellSession,
+        ctrl_c: tokio::sync::broadcast::Receiver<()>,
     ) -> Result<InvokeOutput> {
         match self {
             Tool::FsRead(fs_read) => fs_read.invoke(os, stdout).await,
             Tool::FsWrite(fs_write) => fs_write.invoke(os, stdout, line_tracker).await,
-            Tool::ExecuteCommand(execute_command) => execute_command.invoke(os, stdout).await,
+            Tool::ExecuteCommand(execute_command) => execute_command.invoke(os, stdout, agent, shell).await,
             Tool::UseAws(use_aws) => use_aws.invoke(os, stdout).await,
-            Tool::Custom(custom_tool) => custom_tool.invoke(os, stdout).await,
+            Tool::Custom(custom_tool) => custom_tool.invoke(os, stdout, ctrl_c).await,
             Tool::GhIssue(gh_issue) => gh_issue.invoke(os, stdout).await,
             Tool::Introspect(introspect) => introspect.invoke(os, stdout).await,
             Tool::Knowledge(knowledge) => knowledge.invoke(os, stdout, agent).await,
             Tool::Thinking(think) => think.invoke(stdout).await,
             Tool::Todo(todo) => todo.invoke(os, stdout).await,
+            Tool::McpResources(mcp_resources) => mcp_resources.invoke(os, stdout).await,
+            Tool::CodeSearch(code_search) => code_search.invoke(os, stdout, agent).await,
         }
     }
 
@@ -160,6 +194,8 @@ impl Tool {
             Tool::Knowledge(knowledge) => knowledge.queue_description(os, output).await,
             Tool::Thinking(thinking) => thinking.queue_description(output),
             Tool::Todo(_) => Ok(()),
+            Tool::McpResources(mcp_resources) => mcp_resources.queue_description(output),
+            Tool::CodeSearch(code_search) => code_search.queue_description(output),
         }
     }
 
@@ -176,6 +212,8 @@ impl Tool {
             Tool::Knowledge(knowledge) => knowledge.validate(os).await,
             Tool::Thinking(think) => think.validate(os).await,
             Tool::Todo(todo) => todo.validate(os).await,
+            Tool::McpResources(mcp_resources) => mcp_resources.validate(os).await,
+            Tool::CodeSearch(code_search) => code_search.validate(os).await,
         }
     }
 
@@ -199,6 +237,9 @@ pub struct ToolSpec {
     pub input_schema: InputSchema,
     #[serde(skip_serializing, default = "tool_origin")]
     pub tool_origin: ToolOrigin,
+    /// Whether an mcp tool is annotated with `readOnlyHint`
+    #[serde(skip)]
+    pub read_only: bool,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -271,6 +312,8 @@ pub struct QueuedTool {
     pub name: String,
     pub accepted: bool,
     pub tool: Tool,
+    /// The input the model supplied for the tool use.
+    pub input: serde_json::Value,
 }
 
 /// The schema specification describing a tool's fields.
@@ -318,6 +361,14 @@ impl Default for OutputKind {
 /// Required since path arguments are defined by the model.
 #[allow(dead_code)]
 pub fn sanitize_path_tool_arg(os: &Os, path: impl AsRef<Path>) -> PathBuf {
+    // For testing scenarios, we need to make sure paths are appropriately handled in chroot test
+    // file systems since they are passed directly from the model.
+    os.fs.chroot_path(expand_path_tool_arg(os, path))
+}
+
+/// Performs tilde expansion of a tool use path argument, for paths that are accessed through
+/// [crate::os::Fs].
+pub fn expand_path_tool_arg(os: &Os, path: impl AsRef<Path>) -> PathBuf {
     let mut res = PathBuf::new();
     // Expand `~` only if it is the first part.
     let mut path = path.as_ref().components();
@@ -331,9 +382,7 @@ pub fn sanitize_path_tool_arg(os: &Os, path: impl AsRef<Path>) -> PathBuf {
     for p in path {
         res.push(p);
     }
-    // For testing scenarios, we need to make sure paths are appropriately handled in chroot test
-    // file systems since they are passed directly from the model.
-    os.fs.chroot_path(res)
+    res
 }
 
 /// Converts `path` to a relative path according to the current working directory `cwd`.
@@ -366,7 +415,7 @@ fn absolute_to_relative(cwd: impl AsRef<Path>, path: impl AsRef<Path>) -> Result
 }
 
 /// Small helper for formatting the path as a relative path, if able.
-fn format_path(cwd: impl AsRef<Path>, path: impl AsRef<Path>) -> String {
+pub fn format_path(cwd: impl AsRef<Path>, path: impl AsRef<Path>) -> String {
     absolute_to_relative(cwd, path.as_ref())
         .map(|p| p.to_string_lossy().to_string())
         // If we have three consecutive ".." then it should probably just stay as an absolute path.
@@ -486,6 +535,42 @@ mod tests {
     use super::*;
     use crate::os::ACTIVE_USER_HOME;
 
+    #[test]
+    fn test_is_read_only() {
+        let fs_read = serde_json::from_value::<FsRead>(serde_json::json!({
+            "operations": [{ "path": "/test_file.txt", "mode": "Line" }]
+        }))
+        .unwrap();
+        assert!(Tool::FsRead(fs_read).is_read_only());
+
+        let fs_write = serde_json::from_value::<FsWrite>(serde_json::json!({
+            "command": "create", "path": "/test_file.txt", "file_text": "hello"
+        }))
+        .unwrap();
+        assert!(!Tool::FsWrite(fs_write).is_read_only());
+
+        let use_aws = |operation_name: &str| {
+            serde_json::from_value::<UseAws>(serde_json::json!({
+                "service_name": "s3",
+                "operation_name": operation_name,
+                "region": "us-west-2",
+                "label": ""
+            }))
+            .unwrap()
+        };
+        assert!(Tool::UseAws(use_aws("list-buckets")).is_read_only());
+        assert!(!Tool::UseAws(use_aws("delete-bucket")).is_read_only());
+
+        let knowledge = |value: serde_json::Value| serde_json::from_value::<Knowledge>(value).unwrap();
+        assert!(Tool::Knowledge(knowledge(serde_json::json!({ "command": "search", "query": "q" }))).is_read_only());
+        assert!(
+            !Tool::Knowledge(knowledge(
+                serde_json::json!({ "command": "add", "name": "n", "value": "/tmp" })
+            ))
+            .is_read_only()
+        );
+    }
+
     #[tokio::test]
     async fn test_tilde_path_expansion() {
         let os = Os::new().await.unwrap();
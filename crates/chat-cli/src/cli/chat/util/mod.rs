@@ -14,7 +14,7 @@ use aws_smithy_types::{
 use eyre::Result;
 
 use super::ChatError;
-use super::token_counter::TokenCounter;
+use super::token_counter::Tokenizer;
 
 pub fn truncate_safe(s: &str, max_bytes: usize) -> &str {
     if s.len() <= max_bytes {
@@ -162,14 +162,20 @@ fn should_play_bell() -> bool {
 /// * `files` - A mutable reference to a vector of tuples: (filename, content). This file will be
 ///   sorted but the content will not be changed.
 ///
+/// * `limit` - Maximum number of tokens, as counted by `tokenizer`, of the files that are kept.
+///
 /// Returns the dropped files
-pub fn drop_matched_context_files(files: &mut [(String, String)], limit: usize) -> Result<Vec<(String, String)>> {
-    files.sort_by(|a, b| TokenCounter::count_tokens(&b.1).cmp(&TokenCounter::count_tokens(&a.1)));
+pub fn drop_matched_context_files(
+    files: &mut [(String, String)],
+    limit: usize,
+    tokenizer: Tokenizer,
+) -> Result<Vec<(String, String)>> {
+    files.sort_by_cached_key(|(_, content)| std::cmp::Reverse(tokenizer.count(content)));
     let mut total_size = 0;
     let mut dropped_files = Vec::new();
 
     for (filename, content) in files.iter() {
-        let size = TokenCounter::count_tokens(content);
+        let size = tokenizer.count(content);
         if total_size + size > limit {
             dropped_files.push((filename.clone(), content.clone()));
         } else {
@@ -281,7 +287,7 @@ mod tests {
         ];
         let limit = 9;
 
-        let dropped_files = drop_matched_context_files(&mut files, limit).unwrap();
+        let dropped_files = drop_matched_context_files(&mut files, limit, Tokenizer::default()).unwrap();
         assert_eq!(dropped_files.len(), 1);
         assert_eq!(dropped_files[0].0, "file3");
         assert_eq!(files.len(), 2);
@@ -48,6 +48,7 @@ use crate::cli::user::{
     LoginArgs,
     WhoamiArgs,
 };
+use crate::database::settings::Setting;
 use crate::logging::{
     LogArgs,
     initialize_logging,
@@ -131,13 +132,41 @@ impl RootSubcommand {
         matches!(self, Self::Chat(_) | Self::Login(_) | Self::Profile | Self::Issue(_))
     }
 
-    pub fn requires_auth(&self) -> bool {
-        matches!(self, Self::Chat(_) | Self::Profile)
+    pub async fn requires_auth(&self, os: &Os) -> bool {
+        match self {
+            // Models of an OpenAI-compatible endpoint can be used without logging in, as long as
+            // they are selected before the chat starts, in the same order of priority as the chat:
+            // --model, the model of the agent and then chat.defaultModel
+            Self::Chat(ChatArgs {
+                subcommand: None,
+                model,
+                agent,
+                ..
+            }) => {
+                let mut model_id = model.clone();
+                if model_id.is_none() {
+                    let agent_names = [
+                        agent.clone(),
+                        os.database.settings.get_string(Setting::ChatDefaultAgent),
+                    ];
+                    for agent_name in agent_names.into_iter().flatten() {
+                        if let Ok((agent, _)) = Agent::get_agent_by_name(os, &agent_name).await {
+                            model_id = agent.model;
+                            break;
+                        }
+                    }
+                }
+                let model_id = model_id.or_else(|| os.database.settings.get_string(Setting::ChatDefaultModel));
+                os.client.requires_auth(model_id.as_deref())
+            },
+            Self::Profile => true,
+            _ => false,
+        }
     }
 
     pub async fn execute(self, os: &mut Os) -> Result<ExitCode> {
         // Check for auth on subcommands that require it.
-        if self.requires_auth() && !crate::auth::is_logged_in(&mut os.database).await {
+        if self.requires_auth(os).await && !crate::auth::is_logged_in(&mut os.database).await {
             bail!(
                 "You are not logged in, please log in with {}",
                 format!("{CLI_BINARY_NAME} login").bold()
@@ -336,6 +365,7 @@ impl Cli {
 
 #[cfg(test)]
 mod test {
+    use chat::ChatOutputFormat;
     use chat::WrapMode::{
         Always,
         Auto,
@@ -374,7 +404,8 @@ mod test {
 
         assert_eq!(Cli::parse_from([CHAT_BINARY_NAME, "chat", "-vv"]), Cli {
             subcommand: Some(RootSubcommand::Chat(ChatArgs {
-                resume: false,
+                subcommand: None,
+                resume: None,
                 input: None,
                 agent: None,
                 model: None,
@@ -382,6 +413,7 @@ mod test {
                 trust_tools: None,
                 no_interactive: false,
                 wrap: None,
+                output_format: None,
             })),
             verbose: 2,
             help_all: false,
@@ -414,7 +446,8 @@ mod test {
         assert_parse!(
             ["chat", "--profile", "my-profile"],
             RootSubcommand::Chat(ChatArgs {
-                resume: false,
+                subcommand: None,
+                resume: None,
                 input: None,
                 agent: Some("my-profile".to_string()),
                 model: None,
@@ -422,6 +455,7 @@ mod test {
                 trust_tools: None,
                 no_interactive: false,
                 wrap: None,
+                output_format: None,
             })
         );
     }
@@ -431,7 +465,8 @@ mod test {
         assert_parse!(
             ["chat", "--profile", "my-profile", "Hello"],
             RootSubcommand::Chat(ChatArgs {
-                resume: false,
+                subcommand: None,
+                resume: None,
                 input: Some("Hello".to_string()),
                 agent: Some("my-profile".to_string()),
                 model: None,
@@ -439,6 +474,7 @@ mod test {
                 trust_tools: None,
                 no_interactive: false,
                 wrap: None,
+                output_format: None,
             })
         );
     }
@@ -448,7 +484,8 @@ mod test {
         assert_parse!(
             ["chat", "--profile", "my-profile", "--trust-all-tools"],
             RootSubcommand::Chat(ChatArgs {
-                resume: false,
+                subcommand: None,
+                resume: None,
                 input: None,
                 agent: Some("my-profile".to_string()),
                 model: None,
@@ -456,6 +493,7 @@ mod test {
                 trust_tools: None,
                 no_interactive: false,
                 wrap: None,
+                output_format: None,
             })
         );
     }
@@ -465,7 +503,8 @@ mod test {
         assert_parse!(
             ["chat", "--no-interactive", "--resume"],
             RootSubcommand::Chat(ChatArgs {
-                resume: true,
+                subcommand: None,
+                resume: Some(None),
                 input: None,
                 agent: None,
                 model: None,
@@ -473,12 +512,14 @@ mod test {
                 trust_tools: None,
                 no_interactive: true,
                 wrap: None,
+                output_format: None,
             })
         );
         assert_parse!(
             ["chat", "--non-interactive", "-r"],
             RootSubcommand::Chat(ChatArgs {
-                resume: true,
+                subcommand: None,
+                resume: Some(None),
                 input: None,
                 agent: None,
                 model: None,
@@ -486,6 +527,86 @@ mod test {
                 trust_tools: None,
                 no_interactive: true,
                 wrap: None,
+                output_format: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_chat_with_resume_session() {
+        assert_parse!(
+            ["chat", "--resume=1b9c2f3a", "Hello"],
+            RootSubcommand::Chat(ChatArgs {
+                subcommand: None,
+                resume: Some(Some("1b9c2f3a".to_string())),
+                input: Some("Hello".to_string()),
+                agent: None,
+                model: None,
+                trust_all_tools: false,
+                trust_tools: None,
+                no_interactive: false,
+                wrap: None,
+                output_format: None,
+            })
+        );
+        assert_parse!(
+            ["chat", "-r", "continue the refactor"],
+            RootSubcommand::Chat(ChatArgs {
+                resume: Some(None),
+                input: Some("continue the refactor".to_string()),
+                ..Default::default()
+            })
+        );
+        assert_parse!(
+            ["chat", "-r=pick"],
+            RootSubcommand::Chat(ChatArgs {
+                resume: Some(Some("pick".to_string())),
+                ..Default::default()
+            })
+        );
+        assert!(Cli::try_parse_from([CHAT_BINARY_NAME, "chat", "--resume=1b9c2f3a", "--resume"]).is_err());
+    }
+
+    #[test]
+    fn test_chat_sessions() {
+        assert_parse!(
+            ["chat", "sessions", "list", "--all", "--format", "json"],
+            RootSubcommand::Chat(ChatArgs {
+                subcommand: Some(chat::ChatSubcommand::Sessions(chat::SessionsSubcommand::List {
+                    all: true,
+                    format: OutputFormat::Json,
+                })),
+                ..Default::default()
+            })
+        );
+        assert_parse!(
+            ["chat", "sessions", "rename", "1b9c", "New title"],
+            RootSubcommand::Chat(ChatArgs {
+                subcommand: Some(chat::ChatSubcommand::Sessions(chat::SessionsSubcommand::Rename {
+                    id: "1b9c".to_string(),
+                    title: "New title".to_string(),
+                })),
+                ..Default::default()
+            })
+        );
+        assert_parse!(
+            [
+                "chat",
+                "sessions",
+                "export",
+                "1b9c",
+                "review.md",
+                "--redact-tool-outputs"
+            ],
+            RootSubcommand::Chat(ChatArgs {
+                subcommand: Some(chat::ChatSubcommand::Sessions(chat::SessionsSubcommand::Export {
+                    id: "1b9c".to_string(),
+                    path: "review.md".to_string(),
+                    format: None,
+                    redact_tool_outputs: true,
+                    force: false,
+                })),
+                ..Default::default()
             })
         );
     }
@@ -495,7 +616,8 @@ mod test {
         assert_parse!(
             ["chat", "--trust-all-tools"],
             RootSubcommand::Chat(ChatArgs {
-                resume: false,
+                subcommand: None,
+                resume: None,
                 input: None,
                 agent: None,
                 model: None,
@@ -503,6 +625,7 @@ mod test {
                 trust_tools: None,
                 no_interactive: false,
                 wrap: None,
+                output_format: None,
             })
         );
     }
@@ -512,7 +635,8 @@ mod test {
         assert_parse!(
             ["chat", "--trust-tools="],
             RootSubcommand::Chat(ChatArgs {
-                resume: false,
+                subcommand: None,
+                resume: None,
                 input: None,
                 agent: None,
                 model: None,
@@ -520,6 +644,7 @@ mod test {
                 trust_tools: Some(vec!["".to_string()]),
                 no_interactive: false,
                 wrap: None,
+                output_format: None,
             })
         );
     }
@@ -529,7 +654,8 @@ mod test {
         assert_parse!(
             ["chat", "--trust-tools=fs_read,fs_write"],
             RootSubcommand::Chat(ChatArgs {
-                resume: false,
+                subcommand: None,
+                resume: None,
                 input: None,
                 agent: None,
                 model: None,
@@ -537,6 +663,7 @@ mod test {
                 trust_tools: Some(vec!["fs_read".to_string(), "fs_write".to_string()]),
                 no_interactive: false,
                 wrap: None,
+                output_format: None,
             })
         );
     }
@@ -546,7 +673,8 @@ mod test {
         assert_parse!(
             ["chat", "-w", "never"],
             RootSubcommand::Chat(ChatArgs {
-                resume: false,
+                subcommand: None,
+                resume: None,
                 input: None,
                 agent: None,
                 model: None,
@@ -554,12 +682,14 @@ mod test {
                 trust_tools: None,
                 no_interactive: false,
                 wrap: Some(Never),
+                output_format: None,
             })
         );
         assert_parse!(
             ["chat", "--wrap", "always"],
             RootSubcommand::Chat(ChatArgs {
-                resume: false,
+                subcommand: None,
+                resume: None,
                 input: None,
                 agent: None,
                 model: None,
@@ -567,12 +697,14 @@ mod test {
                 trust_tools: None,
                 no_interactive: false,
                 wrap: Some(Always),
+                output_format: None,
             })
         );
         assert_parse!(
             ["chat", "--wrap", "auto"],
             RootSubcommand::Chat(ChatArgs {
-                resume: false,
+                subcommand: None,
+                resume: None,
                 input: None,
                 agent: None,
                 model: None,
@@ -580,7 +712,76 @@ mod test {
                 trust_tools: None,
                 no_interactive: false,
                 wrap: Some(Auto),
+                output_format: None,
             })
         );
     }
+
+    #[test]
+    fn test_chat_with_output_format() {
+        assert_parse!(
+            ["chat", "--no-interactive", "--output-format", "stream-json", "hello"],
+            RootSubcommand::Chat(ChatArgs {
+                subcommand: None,
+                resume: None,
+                input: Some("hello".to_string()),
+                agent: None,
+                model: None,
+                trust_all_tools: false,
+                trust_tools: None,
+                no_interactive: true,
+                wrap: None,
+                output_format: Some(ChatOutputFormat::StreamJson),
+            })
+        );
+        assert!(Cli::try_parse_from([CHAT_BINARY_NAME, "chat", "--output-format", "json"]).is_err());
+        // Streaming is specific to chat
+        assert!(Cli::try_parse_from([CHAT_BINARY_NAME, "diagnostic", "--format", "stream-json"]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_chat_requires_auth_with_agent_model() {
+        let mut os = Os::new().await.unwrap();
+        os.database
+            .settings
+            .set(Setting::ApiOpenAiEndpoint, "http://localhost:8000/v1")
+            .await
+            .unwrap();
+        os.client = crate::api_client::ApiClient::new(&os.env, &os.fs, &mut os.database, None)
+            .await
+            .unwrap();
+
+        let agent_dir = crate::util::directories::chat_global_agent_path(&os).unwrap();
+        os.fs.create_dir_all(&agent_dir).await.unwrap();
+        let agent = Agent {
+            model: Some("openai:llama".to_string()),
+            ..Default::default()
+        };
+        os.fs
+            .write(agent_dir.join("local.json"), agent.to_str_pretty().unwrap())
+            .await
+            .unwrap();
+
+        let chat = |agent: Option<&str>| {
+            RootSubcommand::Chat(ChatArgs {
+                agent: agent.map(str::to_string),
+                ..Default::default()
+            })
+        };
+        assert!(chat(None).requires_auth(&os).await);
+        assert!(!chat(Some("local")).requires_auth(&os).await);
+
+        // The default agent is used without --agent, and --model takes priority over the agent
+        os.database
+            .settings
+            .set(Setting::ChatDefaultAgent, "local")
+            .await
+            .unwrap();
+        assert!(!chat(None).requires_auth(&os).await);
+        let with_model = RootSubcommand::Chat(ChatArgs {
+            model: Some("claude-sonnet-4".to_string()),
+            ..Default::default()
+        });
+        assert!(with_model.requires_auth(&os).await);
+    }
 }
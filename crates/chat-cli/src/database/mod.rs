@@ -71,7 +71,8 @@ const MIGRATIONS: &[Migration] = migrations![
     "004_state_table",
     "005_auth_table",
     "006_make_state_blob",
-    "007_conversations_table"
+    "007_conversations_table",
+    "008_sessions_table"
 ];
 
 #[derive(Debug, serde::Deserialize, serde::Serialize)]
@@ -97,6 +98,22 @@ impl From<amzn_codewhisperer_client::types::Profile> for AuthProfile {
     }
 }
 
+/// A saved chat session, without its conversation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionInfo {
+    /// The conversation id.
+    pub id: String,
+    /// The directory the session was last used from.
+    pub directory: String,
+    pub title: Option<String>,
+    pub agent: Option<String>,
+    pub model_id: Option<String>,
+    /// Unix timestamp (milliseconds) of the first time the session was saved.
+    pub created_at: i64,
+    /// Unix timestamp (milliseconds) of the last time the session was saved.
+    pub updated_at: i64,
+}
+
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize)]
 #[serde(transparent)]
 pub struct Secret(pub String);
@@ -157,8 +174,8 @@ impl<T> From<PoisonError<T>> for DatabaseError {
 pub enum Table {
     /// The state table contains persistent application state.
     State,
-    /// The conversations tables contains user chat conversations.
-    Conversations,
+    /// The sessions table contains user chat conversations, keyed by conversation id.
+    Sessions,
     /// The auth table contains SSO and Builder ID credentials.
     Auth,
 }
@@ -167,7 +184,7 @@ impl std::fmt::Display for Table {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Table::State => write!(f, "state"),
-            Table::Conversations => write!(f, "conversations"),
+            Table::Sessions => write!(f, "sessions"),
             Table::Auth => write!(f, "auth_kv"),
         }
     }
@@ -369,8 +386,8 @@ impl Database {
     //     self.delete_entry(Table::State, LAST_USED_MODEL_ID)
     // }
 
-    /// Get a chat conversation given a path to the conversation.
-    pub fn get_conversation_by_path(
+    /// Get the chat conversation that was last saved from the given directory.
+    pub fn get_latest_session_by_path(
         &mut self,
         path: impl AsRef<Path>,
     ) -> Result<Option<ConversationState>, DatabaseError> {
@@ -380,11 +397,21 @@ impl Database {
             None => return Ok(None),
         };
 
-        self.get_json_entry(Table::Conversations, path)
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT value FROM {} WHERE directory = ?1 ORDER BY updated_at DESC, rowid DESC LIMIT 1",
+            Table::Sessions
+        ))?;
+        match stmt.query_row([path], |row| row.get::<_, String>(0)) {
+            Ok(value) => Ok(Some(serde_json::from_str(&value)?)),
+            Err(Error::QueryReturnedNoRows) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
     }
 
-    /// Set a chat conversation given a path to the conversation.
-    pub fn set_conversation_by_path(
+    /// Save a chat conversation as a session of the given directory, replacing any previously
+    /// saved version of it.
+    pub fn set_session_by_path(
         &mut self,
         path: impl AsRef<Path>,
         state: &ConversationState,
@@ -395,7 +422,90 @@ impl Database {
             None => return Ok(0),
         };
 
-        self.set_json_entry(Table::Conversations, path, state)
+        // The title is only set on the first save so that renaming a session sticks.
+        Ok(self.pool.get()?.execute(
+            &format!(
+                "INSERT INTO {table} (id, directory, title, agent, model_id, created_at, updated_at, value)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6, ?7)
+                ON CONFLICT(id) DO UPDATE SET
+                    directory = excluded.directory,
+                    title = COALESCE({table}.title, excluded.title),
+                    agent = excluded.agent,
+                    model_id = excluded.model_id,
+                    updated_at = excluded.updated_at,
+                    value = excluded.value",
+                table = Table::Sessions
+            ),
+            params![
+                state.conversation_id(),
+                path,
+                state.title(),
+                state.current_profile(),
+                state.model_info.as_ref().map(|m| m.model_id.as_str()),
+                chrono::Utc::now().timestamp_millis(),
+                serde_json::to_string(state)?,
+            ],
+        )?)
+    }
+
+    /// Get a saved chat conversation given its id.
+    pub fn get_session(&self, id: &str) -> Result<Option<ConversationState>, DatabaseError> {
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(&format!("SELECT value FROM {} WHERE id = ?1", Table::Sessions))?;
+        match stmt.query_row([id], |row| row.get::<_, String>(0)) {
+            Ok(value) => Ok(Some(serde_json::from_str(&value)?)),
+            Err(Error::QueryReturnedNoRows) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// List the saved chat sessions, most recently updated first, optionally only those of the
+    /// given directory.
+    pub fn list_sessions(&self, path: Option<&Path>) -> Result<Vec<SessionInfo>, DatabaseError> {
+        let path = match path.map(|p| p.to_str()) {
+            Some(Some(path)) => Some(path),
+            Some(None) => return Ok(Vec::new()),
+            None => None,
+        };
+
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, directory, title, agent, model_id, created_at, updated_at FROM {}
+            WHERE ?1 IS NULL OR directory = ?1
+            ORDER BY updated_at DESC, rowid DESC",
+            Table::Sessions
+        ))?;
+        let rows = stmt.query_map([path], |row| {
+            Ok(SessionInfo {
+                id: row.get(0)?,
+                directory: row.get(1)?,
+                title: row.get(2)?,
+                agent: row.get(3)?,
+                model_id: row.get(4)?,
+                created_at: row.get(5)?,
+                updated_at: row.get(6)?,
+            })
+        })?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Set the title of a saved chat session. Returns whether the session exists.
+    pub fn rename_session(&self, id: &str, title: &str) -> Result<bool, DatabaseError> {
+        let updated = self.pool.get()?.execute(
+            &format!("UPDATE {} SET title = ?2 WHERE id = ?1", Table::Sessions),
+            params![id, title],
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// Delete a saved chat session. Returns whether the session existed.
+    pub fn delete_session(&self, id: &str) -> Result<bool, DatabaseError> {
+        let deleted = self
+            .pool
+            .get()?
+            .execute(&format!("DELETE FROM {} WHERE id = ?1", Table::Sessions), [id])?;
+        Ok(deleted > 0)
     }
 
     pub async fn get_secret(&self, key: &str) -> Result<Option<Secret>, DatabaseError> {
@@ -586,6 +696,79 @@ mod tests {
         assert_eq!(max_migration, Some(MIGRATIONS.len() as i64 - 1));
     }
 
+    #[test]
+    fn test_migrate_conversations_to_sessions() {
+        let pool = Pool::builder().build(SqliteConnectionManager::memory()).unwrap();
+        let conn = pool.get().unwrap();
+        for migration in &MIGRATIONS[..8] {
+            conn.execute_batch(migration.sql).unwrap();
+        }
+        let conversation = serde_json::json!({
+            "conversation_id": "conv-1",
+            "model_info": { "model_id": "model-1" },
+            "context_manager": { "current_profile": "my-agent" },
+        });
+        conn.execute("INSERT INTO conversations (key, value) VALUES (?1, ?2)", params![
+            "/workspace",
+            conversation.to_string()
+        ])
+        .unwrap();
+        conn.execute(
+            "INSERT INTO conversations (key, value) VALUES ('/broken', 'not json')",
+            [],
+        )
+        .unwrap();
+        // A copy of the same conversation in another directory
+        conn.execute("INSERT INTO conversations (key, value) VALUES (?1, ?2)", params![
+            "/workspace-copy",
+            conversation.to_string()
+        ])
+        .unwrap();
+
+        conn.execute_batch(MIGRATIONS[8].sql).unwrap();
+
+        let row = conn
+            .query_row(
+                "SELECT id, directory, title, agent, model_id, value FROM sessions WHERE directory = '/workspace'",
+                [],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, Option<String>>(2)?,
+                        row.get::<_, Option<String>>(3)?,
+                        row.get::<_, Option<String>>(4)?,
+                        row.get::<_, String>(5)?,
+                    ))
+                },
+            )
+            .unwrap();
+        assert_eq!(row.0, "conv-1");
+        assert_eq!(row.1, "/workspace");
+        assert_eq!(row.2, None);
+        assert_eq!(row.3.as_deref(), Some("my-agent"));
+        assert_eq!(row.4.as_deref(), Some("model-1"));
+        assert_eq!(row.5, conversation.to_string());
+
+        let (copy_id, copy_value) = conn
+            .query_row(
+                "SELECT id, value FROM sessions WHERE directory = '/workspace-copy'",
+                [],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+            )
+            .unwrap();
+        assert_ne!(copy_id, "conv-1");
+        let copy_value = serde_json::from_str::<serde_json::Value>(&copy_value).unwrap();
+        assert_eq!(copy_value["conversation_id"], copy_id.as_str());
+        assert_eq!(copy_value["model_info"]["model_id"], "model-1");
+
+        // The old table is left untouched
+        let count = conn
+            .query_row("SELECT COUNT(*) FROM conversations", [], |row| row.get::<_, i64>(0))
+            .unwrap();
+        assert_eq!(count, 3);
+    }
+
     #[test]
     fn list_migrations() {
         // Assert the migrations are in order
@@ -39,6 +39,10 @@ pub enum Setting {
     KnowledgeChunkOverlap,
     #[strum(message = "Type of knowledge index to use (string)")]
     KnowledgeIndexType,
+    #[strum(message = "Weight of semantic results in hybrid knowledge search (number)")]
+    KnowledgeHybridSemanticWeight,
+    #[strum(message = "Weight of BM25 results in hybrid knowledge search (number)")]
+    KnowledgeHybridBm25Weight,
     #[strum(message = "Key binding for fuzzy search command (single character)")]
     SkimCommandKey,
     #[strum(message = "Enable tangent mode feature (boolean)")]
@@ -59,6 +63,8 @@ pub enum Setting {
     ApiCodeWhispererService,
     #[strum(message = "Q service endpoint URL (string)")]
     ApiQService,
+    #[strum(message = "OpenAI-compatible API endpoint URL for local models (string)")]
+    ApiOpenAiEndpoint,
     #[strum(message = "MCP server initialization timeout (number)")]
     McpInitTimeout,
     #[strum(message = "Non-interactive MCP timeout (number)")]
@@ -77,6 +83,10 @@ pub enum Setting {
     ChatEnableHistoryHints,
     #[strum(message = "Enable the todo list feature (boolean)")]
     EnabledTodoList,
+    #[strum(message = "Maximum number of read-only tools run concurrently (number)")]
+    ChatMaxConcurrentTools,
+    #[strum(message = "Reject file writes from responses containing licensed code (boolean)")]
+    ChatBlockReferencedCode,
 }
 
 impl AsRef<str> for Setting {
@@ -93,6 +103,8 @@ impl AsRef<str> for Setting {
             Self::KnowledgeChunkSize => "knowledge.chunkSize",
             Self::KnowledgeChunkOverlap => "knowledge.chunkOverlap",
             Self::KnowledgeIndexType => "knowledge.indexType",
+            Self::KnowledgeHybridSemanticWeight => "knowledge.hybridSemanticWeight",
+            Self::KnowledgeHybridBm25Weight => "knowledge.hybridBm25Weight",
             Self::SkimCommandKey => "chat.skimCommandKey",
             Self::EnabledTangentMode => "chat.enableTangentMode",
             Self::TangentModeKey => "chat.tangentModeKey",
@@ -103,6 +115,7 @@ impl AsRef<str> for Setting {
             Self::ChatEnableNotifications => "chat.enableNotifications",
             Self::ApiCodeWhispererService => "api.codewhisperer.service",
             Self::ApiQService => "api.q.service",
+            Self::ApiOpenAiEndpoint => "api.openai.endpoint",
             Self::McpInitTimeout => "mcp.initTimeout",
             Self::McpNoInteractiveTimeout => "mcp.noInteractiveTimeout",
             Self::McpLoadedBefore => "mcp.loadedBefore",
@@ -112,6 +125,8 @@ impl AsRef<str> for Setting {
             Self::ChatDisableAutoCompaction => "chat.disableAutoCompaction",
             Self::ChatEnableHistoryHints => "chat.enableHistoryHints",
             Self::EnabledTodoList => "chat.enableTodoList",
+            Self::ChatMaxConcurrentTools => "chat.maxConcurrentTools",
+            Self::ChatBlockReferencedCode => "chat.blockReferencedCode",
         }
     }
 }
@@ -138,6 +153,8 @@ impl TryFrom<&str> for Setting {
             "knowledge.chunkSize" => Ok(Self::KnowledgeChunkSize),
             "knowledge.chunkOverlap" => Ok(Self::KnowledgeChunkOverlap),
             "knowledge.indexType" => Ok(Self::KnowledgeIndexType),
+            "knowledge.hybridSemanticWeight" => Ok(Self::KnowledgeHybridSemanticWeight),
+            "knowledge.hybridBm25Weight" => Ok(Self::KnowledgeHybridBm25Weight),
             "chat.skimCommandKey" => Ok(Self::SkimCommandKey),
             "chat.enableTangentMode" => Ok(Self::EnabledTangentMode),
             "chat.tangentModeKey" => Ok(Self::TangentModeKey),
@@ -148,6 +165,7 @@ impl TryFrom<&str> for Setting {
             "chat.enableNotifications" => Ok(Self::ChatEnableNotifications),
             "api.codewhisperer.service" => Ok(Self::ApiCodeWhispererService),
             "api.q.service" => Ok(Self::ApiQService),
+            "api.openai.endpoint" => Ok(Self::ApiOpenAiEndpoint),
             "mcp.initTimeout" => Ok(Self::McpInitTimeout),
             "mcp.noInteractiveTimeout" => Ok(Self::McpNoInteractiveTimeout),
             "mcp.loadedBefore" => Ok(Self::McpLoadedBefore),
@@ -157,6 +175,8 @@ impl TryFrom<&str> for Setting {
             "chat.disableAutoCompaction" => Ok(Self::ChatDisableAutoCompaction),
             "chat.enableHistoryHints" => Ok(Self::ChatEnableHistoryHints),
             "chat.enableTodoList" => Ok(Self::EnabledTodoList),
+            "chat.maxConcurrentTools" => Ok(Self::ChatMaxConcurrentTools),
+            "chat.blockReferencedCode" => Ok(Self::ChatBlockReferencedCode),
             _ => Err(DatabaseError::InvalidSetting(value.to_string())),
         }
     }
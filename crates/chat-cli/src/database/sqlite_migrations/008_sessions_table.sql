@@ -0,0 +1,41 @@
+CREATE TABLE sessions (
+    id TEXT PRIMARY KEY,
+    directory TEXT NOT NULL,
+    title TEXT,
+    agent TEXT,
+    model_id TEXT,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL,
+    value TEXT NOT NULL
+);
+
+CREATE INDEX sessions_directory_updated_at ON sessions (directory, updated_at);
+
+-- Conversations sharing an id, e.g. after a directory was copied, each get a new one so that none
+-- of them are lost. The conversations table is kept for older versions, rows that are not valid
+-- JSON are only left there.
+INSERT INTO sessions (id, directory, agent, model_id, created_at, updated_at, value)
+SELECT
+    id,
+    key,
+    json_extract(value, '$.context_manager.current_profile'),
+    COALESCE(json_extract(value, '$.model_info.model_id'), json_extract(value, '$.model')),
+    CAST(strftime('%s', 'now') AS INTEGER) * 1000,
+    CAST(strftime('%s', 'now') AS INTEGER) * 1000,
+    CASE
+        WHEN id IS json_extract(value, '$.conversation_id') THEN value
+        ELSE json_set(value, '$.conversation_id', id)
+    END
+FROM (
+    SELECT
+        key,
+        value,
+        CASE
+            WHEN json_extract(value, '$.conversation_id') IS NOT NULL
+                AND ROW_NUMBER() OVER (PARTITION BY json_extract(value, '$.conversation_id') ORDER BY key) = 1
+            THEN json_extract(value, '$.conversation_id')
+            ELSE lower(hex(randomblob(16)))
+        END AS id
+    FROM conversations
+    WHERE json_valid(value)
+);
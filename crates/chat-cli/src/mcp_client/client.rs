@@ -4,26 +4,50 @@ use std::process::Stdio;
 
 use regex::Regex;
 use reqwest::Client;
+use rmcp::handler::client::progress::{
+    ProgressDispatcher,
+    ProgressSubscriber,
+};
 use rmcp::model::{
+    CallToolRequest,
     CallToolRequestParam,
     CallToolResult,
+    ClientCapabilities,
+    ClientRequest,
+    ClientResult,
+    ElicitationCapability,
     ErrorCode,
     GetPromptRequestParam,
     GetPromptResult,
     Implementation,
     InitializeRequestParam,
     ListPromptsResult,
+    ListResourceTemplatesResult,
+    ListResourcesResult,
     ListToolsResult,
     LoggingLevel,
     LoggingMessageNotificationParam,
+    Meta,
+    NumberOrString,
     PaginatedRequestParam,
+    ProgressToken,
+    ReadResourceRequestParam,
+    ReadResourceResult,
+    ResourceUpdatedNotificationParam,
+    RootsCapabilities,
+    ServerInfo,
     ServerNotification,
     ServerRequest,
+    ServerResult,
+    SubscribeRequestParam,
+    UnsubscribeRequestParam,
 };
 use rmcp::service::{
     ClientInitializeError,
     DynService,
     NotificationContext,
+    PeerRequestOptions,
+    RequestHandle,
 };
 use rmcp::transport::auth::AuthClient;
 use rmcp::transport::{
@@ -47,10 +71,15 @@ use tracing::{
     debug,
     error,
     info,
+    warn,
 };
 
+use super::elicitation::ElicitationHandler;
 use super::messenger::Messenger;
 use super::oauth_util::HttpTransport;
+use super::resources::ResourceStore;
+use super::roots::RootsHandler;
+use super::sampling::SamplingHandler;
 use super::{
     AuthClientDropGuard,
     OauthUtilError,
@@ -236,6 +265,8 @@ impl Clone for InnerService {
 pub struct RunningService {
     pub inner_service: InnerService,
     auth_dropguard: Option<AuthClientDropGuard>,
+    /// Routes the progress notifications sent by the server to the requests they belong to
+    progress: ProgressDispatcher,
 }
 
 impl Clone for RunningService {
@@ -249,15 +280,81 @@ impl Clone for RunningService {
         RunningService {
             inner_service: self.inner_service.clone(),
             auth_dropguard,
+            progress: self.progress.clone(),
         }
     }
 }
 
 impl RunningService {
-    decorate_with_auth_retry!(CallToolRequestParam, call_tool, CallToolResult);
-
     decorate_with_auth_retry!(GetPromptRequestParam, get_prompt, GetPromptResult);
 
+    decorate_with_auth_retry!(ReadResourceRequestParam, read_resource, ReadResourceResult);
+
+    decorate_with_auth_retry!(SubscribeRequestParam, subscribe, ());
+
+    decorate_with_auth_retry!(UnsubscribeRequestParam, unsubscribe, ());
+
+    /// The information the server provided during initialization
+    pub fn server_info(&self) -> Option<&ServerInfo> {
+        match &self.inner_service {
+            InnerService::Original(rs) => rs.peer_info(),
+            InnerService::Peer(peer) => peer.peer_info(),
+        }
+    }
+
+    /// Whether the server sends update notifications for resources it has been subscribed to
+    pub fn supports_resource_subscription(&self) -> bool {
+        self.server_info()
+            .and_then(|info| info.capabilities.resources.as_ref())
+            .and_then(|resources| resources.subscribe)
+            .unwrap_or(false)
+    }
+
+    /// Sends a tools/call request carrying a progress token. Unlike [Self::call_tool], this returns
+    /// as soon as the request is sent: the returned handle is used to await the result or to
+    /// cancel the call, and the subscriber yields the progress reported by the server for it.
+    pub async fn call_tool_with_progress(
+        &self,
+        param: CallToolRequestParam,
+    ) -> Result<(RequestHandle<RoleClient>, ProgressSubscriber), rmcp::ServiceError> {
+        // The token is generated here rather than by rmcp so that we can subscribe to it before
+        // the request is sent, otherwise early notifications could be missed.
+        let progress_token = ProgressToken(NumberOrString::String(uuid::Uuid::new_v4().to_string().into()));
+        let subscriber = self.progress.subscribe(progress_token.clone()).await;
+
+        let mut meta = Meta::new();
+        meta.set_progress_token(progress_token);
+        let request = ClientRequest::CallToolRequest(CallToolRequest::new(param));
+        let options = PeerRequestOptions {
+            timeout: None,
+            meta: Some(meta),
+        };
+
+        let handle = match &self.inner_service {
+            InnerService::Original(rs) => rs.send_cancellable_request(request, options).await?,
+            InnerService::Peer(peer) => peer.send_cancellable_request(request, options).await?,
+        };
+
+        Ok((handle, subscriber))
+    }
+
+    /// Waits for the result of a request sent with [Self::call_tool_with_progress].
+    pub async fn await_tool_result(handle: RequestHandle<RoleClient>) -> Result<CallToolResult, rmcp::ServiceError> {
+        match handle.await_response().await? {
+            ServerResult::CallToolResult(result) => Ok(result),
+            _ => Err(rmcp::ServiceError::UnexpectedResponse),
+        }
+    }
+
+    /// Refreshes the access token of servers behind auth. Returns whether a failed request is
+    /// worth retrying.
+    pub async fn refresh_auth(&self) -> bool {
+        match self.get_auth_client() {
+            Some(auth_client) => auth_client.get_access_token().await.is_ok(),
+            None => false,
+        }
+    }
+
     pub fn get_auth_client(&self) -> Option<AuthClient<Client>> {
         self.auth_dropguard.as_ref().map(|a| a.auth_client.clone())
     }
@@ -298,14 +395,39 @@ pub struct McpClientService {
     pub config: CustomToolConfig,
     server_name: String,
     messenger: ServerMessenger,
+    /// Used to answer sampling requests. Sampling is not advertised to the server if this is
+    /// absent.
+    sampling: Option<SamplingHandler>,
+    /// Provides the roots exposed to the server
+    roots: RootsHandler,
+    /// Used to prompt the user for information requested by the server
+    elicitation: ElicitationHandler,
+    /// Holds the contents of attached resources, which are refreshed when the server notifies us
+    /// of an update
+    resources: ResourceStore,
+    /// Forwards progress notifications to the in-flight tool calls they belong to
+    progress: ProgressDispatcher,
 }
 
 impl McpClientService {
-    pub fn new(server_name: String, config: CustomToolConfig, messenger: ServerMessenger) -> Self {
+    pub fn new(
+        server_name: String,
+        config: CustomToolConfig,
+        messenger: ServerMessenger,
+        sampling: Option<SamplingHandler>,
+        roots: RootsHandler,
+        elicitation: ElicitationHandler,
+        resources: ResourceStore,
+    ) -> Self {
         Self {
             server_name,
             config,
             messenger,
+            sampling,
+            roots,
+            elicitation,
+            resources,
+            progress: ProgressDispatcher::new(),
         }
     }
 
@@ -316,6 +438,11 @@ impl McpClientService {
             let messenger_clone = self.messenger.clone();
             let server_name = self.server_name.clone();
             let backup_config = self.config.clone();
+            let backup_sampling = self.sampling.clone();
+            let roots = self.roots.clone();
+            let backup_elicitation = self.elicitation.clone();
+            let backup_resources = self.resources.clone();
+            let progress = self.progress.clone();
 
             let result: Result<_, McpClientError> = async {
                 let messenger_dup = messenger_clone.duplicate();
@@ -341,11 +468,18 @@ impl McpClientService {
                                         debug!("## mcp: first hand shake attempt failed: {:?}", e);
                                         let refresh_res =
                                             auth_dg.auth_client.get_access_token().await;
-                                        let new_self = McpClientService::new(
-                                            server_name.clone(),
-                                            backup_config,
-                                            messenger_clone.clone(),
-                                        );
+                                        let new_self = McpClientService {
+                                            progress: progress.clone(),
+                                            ..McpClientService::new(
+                                                server_name.clone(),
+                                                backup_config,
+                                                messenger_clone.clone(),
+                                                backup_sampling,
+                                                roots.clone(),
+                                                backup_elicitation,
+                                                backup_resources,
+                                            )
+                                        };
 
                                         let new_transport =
                                             get_http_transport(&os_clone, true, &url, Some(auth_dg.auth_client.clone()), &*messenger_dup).await?;
@@ -422,6 +556,8 @@ impl McpClientService {
                 },
             };
 
+            roots.register_peer(service.peer().clone());
+
             if let Some(mut stderr) = child_stderr {
                 let server_name_clone = server_name.clone();
                 tokio::spawn(async move {
@@ -469,6 +605,30 @@ impl McpClientService {
                                 service_method: list_prompts,
                                 result_field: prompts,
                                 messenger_method: send_prompts_list_result,
+                                service: service_clone.clone(),
+                                messenger: messenger_clone,
+                                server_name: server_name
+                            };
+                        }
+
+                        if init_result.capabilities.resources.is_some() {
+                            paginated_fetch! {
+                                final_result_type: ListResourcesResult,
+                                content_type: rmcp::model::Resource,
+                                service_method: list_resources,
+                                result_field: resources,
+                                messenger_method: send_resources_list_result,
+                                service: service_clone.clone(),
+                                messenger: messenger_clone,
+                                server_name: server_name
+                            };
+
+                            paginated_fetch! {
+                                final_result_type: ListResourceTemplatesResult,
+                                content_type: rmcp::model::ResourceTemplate,
+                                service_method: list_resource_templates,
+                                result_field: resource_templates,
+                                messenger_method: send_resource_templates_list_result,
                                 service: service_clone,
                                 messenger: messenger_clone,
                                 server_name: server_name
@@ -487,6 +647,7 @@ impl McpClientService {
             Ok(RunningService {
                 inner_service: InnerService::Original(service),
                 auth_dropguard,
+                progress,
             })
         });
 
@@ -589,25 +750,85 @@ impl McpClientService {
             server_name: self.server_name
         };
     }
+
+    async fn on_resource_list_changed(&self, context: NotificationContext<RoleClient>) {
+        let NotificationContext { peer, .. } = context;
+
+        paginated_fetch! {
+            final_result_type: ListResourcesResult,
+            content_type: rmcp::model::Resource,
+            service_method: list_resources,
+            result_field: resources,
+            messenger_method: send_resources_list_result,
+            service: peer.clone(),
+            messenger: self.messenger,
+            server_name: self.server_name
+        };
+
+        paginated_fetch! {
+            final_result_type: ListResourceTemplatesResult,
+            content_type: rmcp::model::ResourceTemplate,
+            service_method: list_resource_templates,
+            result_field: resource_templates,
+            messenger_method: send_resource_templates_list_result,
+            service: peer,
+            messenger: self.messenger,
+            server_name: self.server_name
+        };
+    }
+
+    /// Re-reads an attached resource so that the context reflects its latest contents.
+    async fn on_resource_updated(
+        &self,
+        params: ResourceUpdatedNotificationParam,
+        context: NotificationContext<RoleClient>,
+    ) {
+        let ResourceUpdatedNotificationParam { uri } = params;
+        if !self.resources.is_attached(&self.server_name, &uri) {
+            return;
+        }
+
+        match context
+            .peer
+            .read_resource(ReadResourceRequestParam { uri: uri.clone() })
+            .await
+        {
+            Ok(result) => {
+                debug!(target: "mcp", "Refreshed resource {uri} from {}", self.server_name);
+                self.resources.attach(&self.server_name, &uri, &result);
+            },
+            Err(e) => warn!(target: "mcp", "Failed to refresh resource {uri} from {}: {e}", self.server_name),
+        }
+    }
 }
 
 impl Service<RoleClient> for McpClientService {
     async fn handle_request(
         &self,
         request: <RoleClient as rmcp::service::ServiceRole>::PeerReq,
-        _context: rmcp::service::RequestContext<RoleClient>,
+        context: rmcp::service::RequestContext<RoleClient>,
     ) -> Result<<RoleClient as rmcp::service::ServiceRole>::Resp, rmcp::ErrorData> {
         match request {
             ServerRequest::PingRequest(_) => Err(rmcp::ErrorData::method_not_found::<rmcp::model::PingRequestMethod>()),
-            ServerRequest::CreateMessageRequest(_) => Err(rmcp::ErrorData::method_not_found::<
-                rmcp::model::CreateMessageRequestMethod,
-            >()),
-            ServerRequest::ListRootsRequest(_) => {
-                Err(rmcp::ErrorData::method_not_found::<rmcp::model::ListRootsRequestMethod>())
+            ServerRequest::CreateMessageRequest(request) => match &self.sampling {
+                Some(sampling) => tokio::select! {
+                    result = sampling.create_message(&self.server_name, &self.config.sampling, request.params) => {
+                        result.map(ClientResult::CreateMessageResult)
+                    },
+                    _ = context.ct.cancelled() => {
+                        Err(rmcp::ErrorData::internal_error("Request cancelled by the server", None))
+                    },
+                },
+                None => Err(rmcp::ErrorData::method_not_found::<
+                    rmcp::model::CreateMessageRequestMethod,
+                >()),
             },
-            ServerRequest::CreateElicitationRequest(_) => Err(rmcp::ErrorData::method_not_found::<
-                rmcp::model::ElicitationCreateRequestMethod,
-            >()),
+            ServerRequest::ListRootsRequest(_) => Ok(ClientResult::ListRootsResult(self.roots.list_roots())),
+            ServerRequest::CreateElicitationRequest(request) => self
+                .elicitation
+                .create_elicitation(&self.server_name, request.params)
+                .await
+                .map(ClientResult::CreateElicitationResult),
         }
     }
 
@@ -622,11 +843,17 @@ impl Service<RoleClient> for McpClientService {
                 self.on_logging_message(notification.params, context).await;
             },
             ServerNotification::PromptListChangedNotification(_) => self.on_prompt_list_changed(context).await,
-            // TODO: support these
-            ServerNotification::CancelledNotification(_) => (),
-            ServerNotification::ResourceUpdatedNotification(_) => (),
-            ServerNotification::ResourceListChangedNotification(_) => (),
-            ServerNotification::ProgressNotification(_) => (),
+            ServerNotification::ResourceUpdatedNotification(notification) => {
+                self.on_resource_updated(notification.params, context).await;
+            },
+            ServerNotification::ResourceListChangedNotification(_) => self.on_resource_list_changed(context).await,
+            // Requests being handled are cancelled by rmcp itself, there is nothing else to do
+            ServerNotification::CancelledNotification(notification) => {
+                debug!(target: "mcp", "{} cancelled request {}: {:?}", self.server_name, notification.params.request_id, notification.params.reason);
+            },
+            ServerNotification::ProgressNotification(notification) => {
+                self.progress.handle_notification(notification.params).await;
+            },
         };
         Ok(())
     }
@@ -634,7 +861,16 @@ impl Service<RoleClient> for McpClientService {
     fn get_info(&self) -> <RoleClient as rmcp::service::ServiceRole>::Info {
         InitializeRequestParam {
             protocol_version: Default::default(),
-            capabilities: Default::default(),
+            capabilities: ClientCapabilities {
+                sampling: self.sampling.as_ref().map(|_| Default::default()),
+                roots: Some(RootsCapabilities {
+                    list_changed: Some(true),
+                }),
+                elicitation: Some(ElicitationCapability {
+                    schema_validation: Some(true),
+                }),
+                ..Default::default()
+            },
             client_info: Implementation {
                 name: "Q DEV CLI".to_string(),
                 version: "1.0.0".to_string(),
@@ -736,4 +972,246 @@ mod tests {
         assert_eq!(env_vars.get("KEY1").unwrap(), "Value is test_value");
         assert_eq!(env_vars.get("KEY2").unwrap(), "No substitution");
     }
+
+    /// A server that does nothing on its own apart from serving a single resource, whose contents
+    /// reflect `version`. The test drives it through its peer to issue server initiated requests.
+    #[derive(Default)]
+    struct StubServer {
+        version: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+        /// Set once a tool call has been cancelled by the client
+        cancelled: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    }
+
+    impl rmcp::ServerHandler for StubServer {
+        /// Reports some progress then waits to be cancelled.
+        async fn call_tool(
+            &self,
+            _request: CallToolRequestParam,
+            context: rmcp::service::RequestContext<rmcp::RoleServer>,
+        ) -> Result<CallToolResult, ErrorData> {
+            let progress_token = context.meta.get_progress_token().unwrap();
+            context
+                .peer
+                .notify_progress(rmcp::model::ProgressNotificationParam {
+                    progress_token,
+                    progress: 1.0,
+                    total: Some(4.0),
+                    message: Some("building".to_string()),
+                })
+                .await
+                .unwrap();
+
+            tokio::select! {
+                _ = context.ct.cancelled() => {
+                    self.cancelled.store(true, std::sync::atomic::Ordering::SeqCst);
+                    Err(ErrorData::internal_error("cancelled", None))
+                },
+                _ = tokio::time::sleep(std::time::Duration::from_secs(5)) => Ok(CallToolResult::success(vec![])),
+            }
+        }
+
+        async fn read_resource(
+            &self,
+            request: ReadResourceRequestParam,
+            _context: rmcp::service::RequestContext<rmcp::RoleServer>,
+        ) -> Result<ReadResourceResult, ErrorData> {
+            let version = self.version.load(std::sync::atomic::Ordering::SeqCst);
+            Ok(ReadResourceResult {
+                contents: vec![rmcp::model::ResourceContents::text(format!("v{version}"), request.uri)],
+            })
+        }
+
+        async fn subscribe(
+            &self,
+            _request: SubscribeRequestParam,
+            _context: rmcp::service::RequestContext<rmcp::RoleServer>,
+        ) -> Result<(), ErrorData> {
+            Ok(())
+        }
+
+        fn get_info(&self) -> ServerInfo {
+            ServerInfo {
+                capabilities: rmcp::model::ServerCapabilities::builder()
+                    .enable_tools()
+                    .enable_resources()
+                    .enable_resources_subscribe()
+                    .build(),
+                ..Default::default()
+            }
+        }
+    }
+
+    /// Connects an [McpClientService] to a [StubServer] over a pipe using the same framing as
+    /// the stdio transport.
+    async fn connect_stub_server(
+        stub: StubServer,
+        interactive: bool,
+        resources: ResourceStore,
+    ) -> (
+        rmcp::service::RunningService<RoleClient, McpClientService>,
+        rmcp::service::RunningService<rmcp::RoleServer, StubServer>,
+    ) {
+        let (client_io, server_io) = tokio::io::duplex(4096);
+        let (_rx, messenger_builder) = crate::cli::chat::server_messenger::ServerMessengerBuilder::new(20);
+        let config = serde_json::from_value::<CustomToolConfig>(serde_json::json!({ "command": "stub" })).unwrap();
+        let service = McpClientService::new(
+            "stub".to_string(),
+            config,
+            messenger_builder.build_with_name("stub".to_string()),
+            None,
+            RootsHandler::default(),
+            ElicitationHandler::new(interactive),
+            resources,
+        );
+
+        let (client, server) = tokio::join!(service.serve(client_io), stub.serve(server_io));
+        (client.unwrap(), server.unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_elicitation_declined_when_non_interactive() {
+        let (client, server) = connect_stub_server(StubServer::default(), false, ResourceStore::default()).await;
+
+        let capabilities = &server.peer_info().unwrap().capabilities;
+        assert!(capabilities.elicitation.is_some());
+
+        let request = rmcp::model::CreateElicitationRequest::new(rmcp::model::CreateElicitationRequestParam {
+            message: "Which environment should be deployed?".to_string(),
+            requested_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "env": { "type": "string", "enum": ["dev", "prod"] }
+                },
+                "required": ["env"]
+            })
+            .as_object()
+            .unwrap()
+            .clone(),
+        });
+        let result = server
+            .send_request(ServerRequest::CreateElicitationRequest(request))
+            .await
+            .unwrap();
+
+        match result {
+            ClientResult::CreateElicitationResult(result) => {
+                assert_eq!(result.action, rmcp::model::ElicitationAction::Decline);
+                assert!(result.content.is_none());
+            },
+            other => panic!("Unexpected response: {other:?}"),
+        }
+
+        client.cancel().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_attached_resource_refreshed_on_update() {
+        let resources = ResourceStore::default();
+        let stub = StubServer::default();
+        let version = stub.version.clone();
+        let (client, server) = connect_stub_server(stub, false, resources.clone()).await;
+
+        let uri = "stub://notes";
+        let result = client
+            .read_resource(ReadResourceRequestParam { uri: uri.to_string() })
+            .await
+            .unwrap();
+        resources.attach("stub", uri, &result);
+        assert_eq!(resources.attached_content("stub", uri).as_deref(), Some("v0"));
+
+        version.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        server
+            .notify_resource_updated(ResourceUpdatedNotificationParam { uri: uri.to_string() })
+            .await
+            .unwrap();
+
+        for _ in 0..50 {
+            if resources.attached_content("stub", uri).as_deref() == Some("v1") {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(resources.attached_content("stub", uri).as_deref(), Some("v1"));
+
+        client.cancel().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_tool_call_progress_and_cancellation() {
+        use futures::StreamExt;
+
+        let stub = StubServer::default();
+        let cancelled = stub.cancelled.clone();
+        let (client, _server) = connect_stub_server(stub, false, ResourceStore::default()).await;
+        let running_service = RunningService {
+            inner_service: InnerService::Peer(client.peer().clone()),
+            auth_dropguard: None,
+            progress: client.service().progress.clone(),
+        };
+
+        let (handle, mut progress) = running_service
+            .call_tool_with_progress(CallToolRequestParam {
+                name: "build".into(),
+                arguments: None,
+            })
+            .await
+            .unwrap();
+
+        let notification = tokio::time::timeout(std::time::Duration::from_secs(5), progress.next())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(&notification.progress_token, progress.progress_token());
+        assert_eq!(notification.progress, 1.0);
+        assert_eq!(notification.message.as_deref(), Some("building"));
+
+        handle.cancel(Some("Cancelled by the user".to_string())).await.unwrap();
+        for _ in 0..50 {
+            if cancelled.load(std::sync::atomic::Ordering::SeqCst) {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert!(cancelled.load(std::sync::atomic::Ordering::SeqCst));
+
+        client.cancel().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_custom_tool_cancelled_by_session_ctrl_c() {
+        let os = crate::os::Os::new().await.unwrap();
+        let stub = StubServer::default();
+        let cancelled = stub.cancelled.clone();
+        let (client, _server) = connect_stub_server(stub, false, ResourceStore::default()).await;
+        let tool = crate::cli::chat::tools::custom_tool::CustomTool {
+            name: "build".to_string(),
+            server_name: "stub".to_string(),
+            client: RunningService {
+                inner_service: InnerService::Peer(client.peer().clone()),
+                auth_dropguard: None,
+                progress: client.service().progress.clone(),
+            },
+            params: None,
+            read_only: false,
+        };
+
+        let (ctrlc_tx, ctrlc_rx) = tokio::sync::broadcast::channel(4);
+        let mut updates = Vec::new();
+        let (result, _) = tokio::join!(tool.invoke(&os, &mut updates, ctrlc_rx), async {
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            ctrlc_tx.send(()).unwrap();
+        });
+        let err = result.unwrap_err();
+        assert!(err.to_string().contains("cancelled the tool call"), "{err}");
+
+        for _ in 0..50 {
+            if cancelled.load(std::sync::atomic::Ordering::SeqCst) {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert!(cancelled.load(std::sync::atomic::Ordering::SeqCst));
+
+        client.cancel().await.unwrap();
+    }
 }
@@ -0,0 +1,426 @@
+use std::io::{
+    ErrorKind,
+    IsTerminal,
+};
+use std::sync::Arc;
+
+use crossterm::{
+    execute,
+    style,
+};
+use dialoguer::{
+    Input,
+    Select,
+};
+use rmcp::ErrorData;
+use rmcp::model::{
+    CreateElicitationRequestParam,
+    CreateElicitationResult,
+    ElicitationAction,
+    JsonObject,
+};
+use serde_json::{
+    Map,
+    Value,
+};
+use tracing::info;
+
+use crate::util::dialoguer_theme;
+
+/// A single field of the form requested by an mcp server, as derived from the requested JSON
+/// schema.
+#[derive(Debug, Clone, PartialEq)]
+struct ElicitationField {
+    name: String,
+    title: Option<String>,
+    description: Option<String>,
+    required: bool,
+    default: Option<Value>,
+    kind: FieldKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum FieldKind {
+    String {
+        format: Option<String>,
+        min_length: Option<usize>,
+        max_length: Option<usize>,
+    },
+    Number {
+        integer: bool,
+        minimum: Option<f64>,
+        maximum: Option<f64>,
+    },
+    Boolean,
+    Enum {
+        values: Vec<String>,
+        names: Vec<String>,
+    },
+}
+
+impl ElicitationField {
+    fn label(&self) -> String {
+        let title = self.title.as_deref().unwrap_or(&self.name);
+        match &self.description {
+            Some(description) => format!("{title} ({description})"),
+            None => title.to_string(),
+        }
+    }
+
+    /// Validates and converts the text entered by the user. Returns [None] if an optional field
+    /// was left empty.
+    fn parse_input(&self, input: &str) -> Result<Option<Value>, String> {
+        let input = input.trim();
+        if input.is_empty() {
+            return match self.required {
+                true => Err("This field is required".to_string()),
+                false => Ok(None),
+            };
+        }
+
+        match &self.kind {
+            FieldKind::String {
+                format,
+                min_length,
+                max_length,
+            } => {
+                let len = input.chars().count();
+                if min_length.is_some_and(|min| len < min) || max_length.is_some_and(|max| len > max) {
+                    return Err(format!(
+                        "Expected between {} and {} characters",
+                        min_length.unwrap_or(0),
+                        max_length.map_or("any number of".to_string(), |max| max.to_string())
+                    ));
+                }
+                match format.as_deref() {
+                    Some("email") if !input.contains('@') => Err("Expected an email address".to_string()),
+                    Some("uri") if url::Url::parse(input).is_err() => Err("Expected a URI".to_string()),
+                    _ => Ok(Some(Value::String(input.to_string()))),
+                }
+            },
+            FieldKind::Number {
+                integer,
+                minimum,
+                maximum,
+            } => {
+                let number = input.parse::<f64>().map_err(|e| format!("Expected a number: {e}"))?;
+                if *integer && number.fract() != 0.0 {
+                    return Err("Expected an integer".to_string());
+                }
+                if minimum.is_some_and(|min| number < min) || maximum.is_some_and(|max| number > max) {
+                    return Err(format!(
+                        "Expected a number between {} and {}",
+                        minimum.map_or("-∞".to_string(), |min| min.to_string()),
+                        maximum.map_or("∞".to_string(), |max| max.to_string())
+                    ));
+                }
+                Ok(Some(match integer {
+                    true => Value::from(number as i64),
+                    false => Value::from(number),
+                }))
+            },
+            FieldKind::Boolean => match input.to_lowercase().as_str() {
+                "y" | "yes" | "true" => Ok(Some(Value::Bool(true))),
+                "n" | "no" | "false" => Ok(Some(Value::Bool(false))),
+                _ => Err("Expected yes or no".to_string()),
+            },
+            FieldKind::Enum { values, .. } => match values.iter().find(|value| value.as_str() == input) {
+                Some(value) => Ok(Some(Value::String(value.clone()))),
+                None => Err(format!("Expected one of {}", values.join(", "))),
+            },
+        }
+    }
+}
+
+/// Derives the fields to prompt for from the schema requested by the server. As per the MCP spec,
+/// only flat objects with primitive properties are supported.
+fn parse_fields(schema: &JsonObject) -> Result<Vec<ElicitationField>, String> {
+    let required = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|required| required.iter().filter_map(Value::as_str).collect::<Vec<_>>())
+        .unwrap_or_default();
+
+    let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+        return Ok(Vec::new());
+    };
+
+    properties
+        .iter()
+        .map(|(name, property)| {
+            let str_field = |key: &str| property.get(key).and_then(Value::as_str).map(str::to_string);
+            let usize_field = |key: &str| property.get(key).and_then(Value::as_u64).map(|v| v as usize);
+            let f64_field = |key: &str| property.get(key).and_then(Value::as_f64);
+
+            let kind = match (str_field("type").as_deref(), property.get("enum")) {
+                (Some("string"), Some(Value::Array(values))) => {
+                    let values = values
+                        .iter()
+                        .filter_map(Value::as_str)
+                        .map(str::to_string)
+                        .collect::<Vec<_>>();
+                    let names = property
+                        .get("enumNames")
+                        .and_then(Value::as_array)
+                        .map(|names| {
+                            names
+                                .iter()
+                                .filter_map(Value::as_str)
+                                .map(str::to_string)
+                                .collect::<Vec<_>>()
+                        })
+                        .filter(|names| names.len() == values.len())
+                        .unwrap_or_else(|| values.clone());
+                    FieldKind::Enum { values, names }
+                },
+                (Some("string"), _) => FieldKind::String {
+                    format: str_field("format"),
+                    min_length: usize_field("minLength"),
+                    max_length: usize_field("maxLength"),
+                },
+                (Some(ty @ ("number" | "integer")), _) => FieldKind::Number {
+                    integer: ty == "integer",
+                    minimum: f64_field("minimum"),
+                    maximum: f64_field("maximum"),
+                },
+                (Some("boolean"), _) => FieldKind::Boolean,
+                (ty, _) => return Err(format!("Unsupported type {} for field {name}", ty.unwrap_or("(none)"))),
+            };
+
+            Ok(ElicitationField {
+                name: name.clone(),
+                title: str_field("title"),
+                description: str_field("description"),
+                required: required.contains(&name.as_str()),
+                default: property.get("default").cloned(),
+                kind,
+            })
+        })
+        .collect()
+}
+
+/// Services `elicitation/create` requests sent by mcp servers by prompting the user with a form
+/// generated from the requested schema. Forms from different servers are shown one at a time.
+#[derive(Clone, Debug, Default)]
+pub struct ElicitationHandler {
+    interactive: bool,
+    /// Ensures only one form is displayed at a time
+    prompt_lock: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl ElicitationHandler {
+    pub fn new(interactive: bool) -> Self {
+        Self {
+            interactive,
+            prompt_lock: Default::default(),
+        }
+    }
+
+    pub async fn create_elicitation(
+        &self,
+        server_name: &str,
+        params: CreateElicitationRequestParam,
+    ) -> Result<CreateElicitationResult, ErrorData> {
+        let fields = parse_fields(&params.requested_schema).map_err(|e| ErrorData::invalid_params(e, None))?;
+
+        if !self.interactive || !std::io::stderr().is_terminal() {
+            info!(target: "mcp", "Declining elicitation request from {server_name} in non-interactive mode");
+            return Ok(CreateElicitationResult {
+                action: ElicitationAction::Decline,
+                content: None,
+            });
+        }
+
+        let _guard = self.prompt_lock.lock().await;
+        let server_name = server_name.to_string();
+        let result = tokio::task::spawn_blocking(move || prompt_user(&server_name, &params.message, &fields))
+            .await
+            .map_err(|e| ErrorData::internal_error(format!("Failed to prompt for elicitation: {e}"), None))?;
+
+        match result {
+            Ok(result) => Ok(result),
+            Err(dialoguer::Error::IO(e)) if e.kind() == ErrorKind::Interrupted => Ok(CreateElicitationResult {
+                action: ElicitationAction::Cancel,
+                content: None,
+            }),
+            Err(e) => Err(ErrorData::internal_error(
+                format!("Failed to prompt for elicitation: {e}"),
+                None,
+            )),
+        }
+    }
+}
+
+fn prompt_user(
+    server_name: &str,
+    message: &str,
+    fields: &[ElicitationField],
+) -> Result<CreateElicitationResult, dialoguer::Error> {
+    let theme = dialoguer_theme();
+    let _ = execute!(
+        std::io::stderr(),
+        style::Print("\n"),
+        style::SetForegroundColor(style::Color::Green),
+        style::Print(server_name),
+        style::ResetColor,
+        style::Print(" is requesting input:\n\n"),
+        style::Print(message),
+        style::Print("\n\n"),
+    );
+
+    let action = Select::with_theme(&theme)
+        .with_prompt("How would you like to respond?")
+        .items(&["Provide the requested information", "Decline", "Cancel"])
+        .default(0)
+        .interact_opt()?;
+    match action {
+        Some(0) => (),
+        Some(1) => {
+            return Ok(CreateElicitationResult {
+                action: ElicitationAction::Decline,
+                content: None,
+            });
+        },
+        _ => {
+            return Ok(CreateElicitationResult {
+                action: ElicitationAction::Cancel,
+                content: None,
+            });
+        },
+    }
+
+    let mut content = Map::new();
+    for field in fields {
+        let value = match &field.kind {
+            FieldKind::Boolean | FieldKind::Enum { .. } => {
+                let (mut items, values) = match &field.kind {
+                    FieldKind::Enum { values, names } => (
+                        names.clone(),
+                        values.iter().map(|v| Value::String(v.clone())).collect::<Vec<_>>(),
+                    ),
+                    _ => (vec!["Yes".to_string(), "No".to_string()], vec![
+                        Value::Bool(true),
+                        Value::Bool(false),
+                    ]),
+                };
+                if !field.required {
+                    items.push("(skip)".to_string());
+                }
+                let default = field
+                    .default
+                    .as_ref()
+                    .and_then(|default| values.iter().position(|v| v == default))
+                    .unwrap_or(0);
+
+                let Some(selection) = Select::with_theme(&theme)
+                    .with_prompt(field.label())
+                    .items(&items)
+                    .default(default)
+                    .interact_opt()?
+                else {
+                    return Ok(CreateElicitationResult {
+                        action: ElicitationAction::Cancel,
+                        content: None,
+                    });
+                };
+                values.get(selection).cloned()
+            },
+            FieldKind::String { .. } | FieldKind::Number { .. } => {
+                let mut input = Input::<String>::with_theme(&theme)
+                    .with_prompt(field.label())
+                    .allow_empty(!field.required)
+                    .validate_with(|input: &String| field.parse_input(input).map(|_| ()));
+                if let Some(default) = &field.default {
+                    input = input.default(match default {
+                        Value::String(s) => s.clone(),
+                        other => other.to_string(),
+                    });
+                }
+                let input = input.interact_text()?;
+                // Already validated above
+                field.parse_input(&input).ok().flatten()
+            },
+        };
+
+        if let Some(value) = value {
+            content.insert(field.name.clone(), value);
+        }
+    }
+
+    Ok(CreateElicitationResult {
+        action: ElicitationAction::Accept,
+        content: Some(Value::Object(content)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema() -> JsonObject {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "name": { "type": "string", "title": "Name", "minLength": 2 },
+                "age": { "type": "integer", "minimum": 0 },
+                "subscribe": { "type": "boolean", "default": true },
+                "color": { "type": "string", "enum": ["r", "g"], "enumNames": ["Red", "Green"] }
+            },
+            "required": ["name", "color"]
+        })
+        .as_object()
+        .unwrap()
+        .clone()
+    }
+
+    #[test]
+    fn test_parse_fields() {
+        let fields = parse_fields(&schema()).unwrap();
+        assert_eq!(fields.len(), 4);
+        assert_eq!(fields[0].name, "name");
+        assert!(fields[0].required);
+        assert!(matches!(fields[1].kind, FieldKind::Number { integer: true, .. }));
+        assert!(!fields[1].required);
+        assert_eq!(fields[2].kind, FieldKind::Boolean);
+        assert_eq!(fields[2].default, Some(Value::Bool(true)));
+        assert_eq!(fields[3].kind, FieldKind::Enum {
+            values: vec!["r".to_string(), "g".to_string()],
+            names: vec!["Red".to_string(), "Green".to_string()],
+        });
+
+        let nested = serde_json::json!({ "properties": { "a": { "type": "object" } } });
+        assert!(parse_fields(nested.as_object().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_parse_input() {
+        let fields = parse_fields(&schema()).unwrap();
+        let (name, age) = (&fields[0], &fields[1]);
+
+        assert!(name.parse_input("").is_err());
+        assert!(name.parse_input("a").is_err());
+        assert_eq!(name.parse_input("ab").unwrap(), Some(Value::from("ab")));
+
+        assert_eq!(age.parse_input("").unwrap(), None);
+        assert!(age.parse_input("1.5").is_err());
+        assert!(age.parse_input("-1").is_err());
+        assert_eq!(age.parse_input("42").unwrap(), Some(Value::from(42)));
+
+        assert_eq!(fields[2].parse_input("yes").unwrap(), Some(Value::Bool(true)));
+        assert!(fields[3].parse_input("b").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_non_interactive_declines() {
+        let handler = ElicitationHandler::new(false);
+        let result = handler
+            .create_elicitation("server", CreateElicitationRequestParam {
+                message: "What is your name?".to_string(),
+                requested_schema: schema(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(result.action, ElicitationAction::Decline);
+        assert!(result.content.is_none());
+    }
+}
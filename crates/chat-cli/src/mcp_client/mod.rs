@@ -1,6 +1,10 @@
 pub mod client;
+pub mod elicitation;
 pub mod messenger;
 pub mod oauth_util;
+pub mod resources;
+pub mod roots;
+pub mod sampling;
 
 pub use client::*;
 pub use oauth_util::*;
@@ -0,0 +1,206 @@
+use std::collections::{
+    BTreeMap,
+    HashMap,
+};
+use std::sync::Arc;
+
+use parking_lot::RwLock;
+use rmcp::model::{
+    ReadResourceResult,
+    Resource,
+    ResourceContents,
+    ResourceTemplate,
+};
+
+/// Prefix used to refer to an mcp resource as a context path, i.e. `mcp://<server>/<uri>`.
+pub const RESOURCE_PATH_PREFIX: &str = "mcp://";
+
+/// Maximum size in bytes of the text kept for a single resource.
+const MAX_RESOURCE_TEXT_SIZE: usize = 100_000;
+
+/// Keeps track of the resources offered by mcp servers, as well as the contents of the resources
+/// that have been attached to the context. The resource lists are populated by the orchestrator
+/// task of the [crate::cli::chat::tool_manager::ToolManager].
+#[derive(Clone, Debug, Default)]
+pub struct ResourceStore {
+    inner: Arc<RwLock<ResourceStoreInner>>,
+}
+
+#[derive(Debug, Default)]
+struct ResourceStoreInner {
+    resources: HashMap<String, Vec<Resource>>,
+    templates: HashMap<String, Vec<ResourceTemplate>>,
+    /// Text contents of attached resources, keyed by server name and uri
+    attached: HashMap<(String, String), String>,
+}
+
+impl ResourceStore {
+    /// Replaces the resources offered by the given server.
+    pub fn set_resources(&self, server_name: &str, resources: Vec<Resource>) {
+        self.inner.write().resources.insert(server_name.to_string(), resources);
+    }
+
+    /// Replaces the resource templates offered by the given server.
+    pub fn set_templates(&self, server_name: &str, templates: Vec<ResourceTemplate>) {
+        self.inner.write().templates.insert(server_name.to_string(), templates);
+    }
+
+    /// Forgets the resources and resource templates offered by the given server. Attached contents
+    /// are kept since they are still referred to by the context.
+    pub fn remove_server(&self, server_name: &str) {
+        let mut inner = self.inner.write();
+        inner.resources.remove(server_name);
+        inner.templates.remove(server_name);
+    }
+
+    /// Returns the resources offered by each server, ordered by server name.
+    pub fn resources(&self) -> BTreeMap<String, Vec<Resource>> {
+        self.inner
+            .read()
+            .resources
+            .iter()
+            .map(|(server_name, resources)| (server_name.clone(), resources.clone()))
+            .collect()
+    }
+
+    /// Returns the resource templates offered by each server, ordered by server name.
+    pub fn templates(&self) -> BTreeMap<String, Vec<ResourceTemplate>> {
+        self.inner
+            .read()
+            .templates
+            .iter()
+            .map(|(server_name, templates)| (server_name.clone(), templates.clone()))
+            .collect()
+    }
+
+    /// Stores the contents of a resource attached to the context, replacing any previous one.
+    pub fn attach(&self, server_name: &str, uri: &str, result: &ReadResourceResult) {
+        self.inner
+            .write()
+            .attached
+            .insert((server_name.to_string(), uri.to_string()), contents_to_text(result));
+    }
+
+    /// Drops the contents of an attached resource. Returns whether the resource was attached.
+    pub fn detach(&self, server_name: &str, uri: &str) -> bool {
+        self.inner
+            .write()
+            .attached
+            .remove(&(server_name.to_string(), uri.to_string()))
+            .is_some()
+    }
+
+    pub fn is_attached(&self, server_name: &str, uri: &str) -> bool {
+        self.inner
+            .read()
+            .attached
+            .contains_key(&(server_name.to_string(), uri.to_string()))
+    }
+
+    /// Returns the last known contents of an attached resource.
+    pub fn attached_content(&self, server_name: &str, uri: &str) -> Option<String> {
+        self.inner
+            .read()
+            .attached
+            .get(&(server_name.to_string(), uri.to_string()))
+            .cloned()
+    }
+
+    /// Returns the last known contents of the attached resource referred to by a context path of
+    /// the form `mcp://<server>/<uri>`.
+    pub fn content_for_path(&self, path: &str) -> Option<String> {
+        let (server_name, uri) = parse_resource_path(path)?;
+        self.attached_content(server_name, uri)
+    }
+}
+
+/// Splits a context path of the form `mcp://<server>/<uri>` into the server name and the uri of
+/// the resource.
+pub fn parse_resource_path(path: &str) -> Option<(&str, &str)> {
+    let (server_name, uri) = path.strip_prefix(RESOURCE_PATH_PREFIX)?.split_once('/')?;
+    (!server_name.is_empty() && !uri.is_empty()).then_some((server_name, uri))
+}
+
+/// Flattens the contents of a resource into text. Binary contents are replaced by a placeholder
+/// since they cannot be shown to the model as is. The result is truncated to
+/// [MAX_RESOURCE_TEXT_SIZE].
+pub fn contents_to_text(result: &ReadResourceResult) -> String {
+    let mut text = result
+        .contents
+        .iter()
+        .map(|contents| match contents {
+            ResourceContents::TextResourceContents { text, .. } => text.clone(),
+            ResourceContents::BlobResourceContents { uri, mime_type, .. } => format!(
+                "[binary content of {uri} ({}) omitted]",
+                mime_type.as_deref().unwrap_or("unknown type")
+            ),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    if text.len() > MAX_RESOURCE_TEXT_SIZE {
+        let mut end = MAX_RESOURCE_TEXT_SIZE;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.truncate(end);
+        text.push_str("\n... truncated");
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_resource_path() {
+        assert_eq!(
+            parse_resource_path("mcp://github/repo://owner/name/README.md"),
+            Some(("github", "repo://owner/name/README.md"))
+        );
+        assert_eq!(parse_resource_path("mcp://db/schema"), Some(("db", "schema")));
+        assert_eq!(parse_resource_path("mcp://db"), None);
+        assert_eq!(parse_resource_path("mcp:///schema"), None);
+        assert_eq!(parse_resource_path("~/notes.md"), None);
+    }
+
+    #[test]
+    fn test_contents_to_text() {
+        let result = ReadResourceResult {
+            contents: vec![
+                ResourceContents::text("hello", "file:///a.txt"),
+                ResourceContents::BlobResourceContents {
+                    uri: "file:///b.png".to_string(),
+                    mime_type: Some("image/png".to_string()),
+                    blob: "aGVsbG8=".to_string(),
+                    meta: None,
+                },
+            ],
+        };
+        assert_eq!(
+            contents_to_text(&result),
+            "hello\n[binary content of file:///b.png (image/png) omitted]"
+        );
+    }
+
+    #[test]
+    fn test_attach_and_detach() {
+        let store = ResourceStore::default();
+        let result = ReadResourceResult {
+            contents: vec![ResourceContents::text("v1", "db://schema")],
+        };
+        store.attach("db", "db://schema", &result);
+        assert!(store.is_attached("db", "db://schema"));
+        assert_eq!(store.attached_content("db", "db://schema").as_deref(), Some("v1"));
+        assert_eq!(store.content_for_path("mcp://db/db://schema").as_deref(), Some("v1"));
+
+        store.remove_server("db");
+        assert!(store.is_attached("db", "db://schema"));
+
+        assert!(store.detach("db", "db://schema"));
+        assert!(!store.detach("db", "db://schema"));
+        assert_eq!(store.attached_content("db", "db://schema"), None);
+    }
+}
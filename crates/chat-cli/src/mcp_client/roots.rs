@@ -0,0 +1,232 @@
+use std::path::{
+    Component,
+    Path,
+    PathBuf,
+};
+use std::sync::Arc;
+
+use parking_lot::{
+    Mutex,
+    RwLock,
+};
+use rmcp::RoleClient;
+use rmcp::model::{
+    ListRootsResult,
+    Root,
+};
+use rmcp::service::Peer;
+use tracing::{
+    debug,
+    warn,
+};
+
+use crate::cli::Agent;
+use crate::os::Os;
+use crate::util::directories;
+
+/// Tools whose `allowedPaths` setting contributes to the roots exposed to mcp servers.
+const PATH_SCOPED_TOOLS: [&str; 2] = ["fs_read", "fs_write"];
+
+/// Keeps track of the directories the session is scoped to, which are exposed to mcp servers as
+/// roots, and of the servers that need to be told when said directories change.
+#[derive(Clone, Debug, Default)]
+pub struct RootsHandler {
+    roots: Arc<RwLock<Vec<PathBuf>>>,
+    peers: Arc<Mutex<Vec<Peer<RoleClient>>>>,
+}
+
+impl RootsHandler {
+    /// Registers a server to be notified when the roots change.
+    pub fn register_peer(&self, peer: Peer<RoleClient>) {
+        let mut peers = self.peers.lock();
+        peers.retain(|peer| !peer.is_transport_closed());
+        peers.push(peer);
+    }
+
+    pub fn list_roots(&self) -> ListRootsResult {
+        let roots = self
+            .roots
+            .read()
+            .iter()
+            .filter_map(|path| {
+                let uri = url::Url::from_file_path(path).ok()?;
+                Some(Root {
+                    uri: uri.to_string(),
+                    name: path.file_name().map(|name| name.to_string_lossy().to_string()),
+                })
+            })
+            .collect();
+
+        ListRootsResult { roots }
+    }
+
+    /// Replaces the current roots, sending `notifications/roots/list_changed` to every registered
+    /// server if they differ from the previous ones.
+    pub fn set_roots(&self, roots: Vec<PathBuf>) {
+        {
+            let mut current = self.roots.write();
+            if *current == roots {
+                return;
+            }
+            *current = roots;
+        }
+
+        let peers = {
+            let mut peers = self.peers.lock();
+            peers.retain(|peer| !peer.is_transport_closed());
+            peers.clone()
+        };
+        debug!(target: "mcp", "Roots changed, notifying {} servers", peers.len());
+
+        tokio::spawn(async move {
+            for peer in peers {
+                if let Err(e) = peer.notify_roots_list_changed().await {
+                    warn!(target: "mcp", "Failed to send roots list changed notification: {e}");
+                }
+            }
+        });
+    }
+}
+
+/// Gathers the directories the session is allowed to touch, which consist of the current working
+/// directory, the `allowedPaths` of the fs_read and fs_write tools settings, and the given context
+/// paths. Glob patterns are reduced to the directory preceding the first wildcard, and paths that
+/// already fall under another root are omitted.
+pub fn collect_roots<'a>(
+    os: &Os,
+    agent: Option<&Agent>,
+    context_paths: impl IntoIterator<Item = &'a str>,
+) -> Vec<PathBuf> {
+    let cwd = match os.env.current_dir() {
+        Ok(cwd) => cwd,
+        Err(e) => {
+            warn!("Failed to retrieve current directory for mcp roots: {e}");
+            return Vec::new();
+        },
+    };
+
+    let allowed_paths = agent
+        .into_iter()
+        .flat_map(|agent| {
+            PATH_SCOPED_TOOLS
+                .iter()
+                .filter_map(|tool| agent.tools_settings.get(*tool))
+                .filter_map(|settings| settings.get("allowedPaths"))
+                .filter_map(|paths| paths.as_array())
+                .flatten()
+                .filter_map(|path| path.as_str())
+        })
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+
+    let paths = allowed_paths
+        .into_iter()
+        .chain(context_paths.into_iter().map(str::to_string));
+
+    resolve_roots(os, cwd, paths)
+}
+
+/// Resolves the given paths against `cwd`, keeping only those that do not already fall under
+/// `cwd` or another resolved path.
+fn resolve_roots(os: &Os, cwd: PathBuf, paths: impl Iterator<Item = String>) -> Vec<PathBuf> {
+    let mut roots = vec![cwd];
+    for path in paths {
+        let Ok(expanded) = directories::canonicalizes_path(os, &path) else {
+            continue;
+        };
+        let Some(root) = strip_glob(Path::new(&expanded)) else {
+            continue;
+        };
+        let root = normalize(&roots[0].join(root));
+        if !roots.iter().any(|existing| root.starts_with(existing)) {
+            roots.push(root);
+        }
+    }
+
+    roots
+}
+
+/// Returns the portion of the path preceding the first component containing a glob character.
+fn strip_glob(path: &Path) -> Option<PathBuf> {
+    let root = path
+        .components()
+        .take_while(|component| !component.as_os_str().to_string_lossy().contains(['*', '?', '[', '{']))
+        .collect::<PathBuf>();
+
+    (!root.as_os_str().is_empty()).then_some(root)
+}
+
+/// Lexically resolves `.` and `..` components without touching the file system, since roots may
+/// not exist yet.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => {
+                normalized.pop();
+            },
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_glob() {
+        assert_eq!(strip_glob(Path::new("/a/b/**/*.rs")), Some(PathBuf::from("/a/b")));
+        assert_eq!(strip_glob(Path::new("/a/b/c.md")), Some(PathBuf::from("/a/b/c.md")));
+        assert_eq!(strip_glob(Path::new("*.md")), None);
+    }
+
+    #[tokio::test]
+    async fn test_collect_roots() {
+        let os = Os::new().await.unwrap();
+        let agent = serde_json::from_value::<Agent>(serde_json::json!({
+            "name": "test_agent",
+            "toolsSettings": {
+                "fs_read": { "allowedPaths": ["/tmp/allowed/**"] }
+            }
+        }))
+        .unwrap();
+
+        // Everything falls under the cwd of the fake environment
+        assert_eq!(collect_roots(&os, Some(&agent), ["docs/*.md"]), vec![
+            os.env.current_dir().unwrap()
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_roots() {
+        let os = Os::new().await.unwrap();
+        let cwd = PathBuf::from("/home/user/project");
+        let paths = [
+            "/tmp/allowed/**",
+            "./src",
+            "docs/*.md",
+            "/opt/rules/../notes/**/*.md",
+            "/tmp/allowed/a",
+        ];
+
+        let roots = resolve_roots(&os, cwd.clone(), paths.into_iter().map(str::to_string));
+        assert_eq!(roots, vec![
+            cwd,
+            PathBuf::from("/tmp/allowed"),
+            PathBuf::from("/opt/notes"),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_list_roots() {
+        let handler = RootsHandler::default();
+        handler.set_roots(vec![PathBuf::from("/home/user/project")]);
+        let ListRootsResult { roots } = handler.list_roots();
+        assert_eq!(roots.len(), 1);
+        assert_eq!(roots[0].uri, "file:///home/user/project");
+        assert_eq!(roots[0].name.as_deref(), Some("project"));
+    }
+}
@@ -0,0 +1,586 @@
+use std::collections::{
+    HashMap,
+    HashSet,
+};
+use std::io::IsTerminal;
+use std::sync::Arc;
+
+use crossterm::{
+    execute,
+    style,
+};
+use parking_lot::{
+    Mutex,
+    RwLock,
+};
+use rmcp::ErrorData;
+use rmcp::model::{
+    Content,
+    CreateMessageRequestParam,
+    CreateMessageResult,
+    ErrorCode,
+    Role,
+    SamplingMessage,
+};
+use schemars::JsonSchema;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use tracing::{
+    info,
+    warn,
+};
+
+use crate::api_client::ApiClient;
+use crate::api_client::model::{
+    AssistantResponseMessage,
+    ChatMessage,
+    ChatResponseStream,
+    ConversationState,
+    UserInputMessage,
+};
+use crate::cli::chat::token_counter::{
+    TokenCounter,
+    Tokenizer,
+};
+
+/// The number of tokens a server may consume through sampling in a single session when no budget
+/// is configured.
+pub const DEFAULT_SAMPLING_TOKEN_BUDGET: usize = 50_000;
+
+/// Error code used to signal that the user declined a sampling request, as per the MCP spec.
+const USER_REJECTED_CODE: ErrorCode = ErrorCode(-1);
+
+/// Maximum number of characters of the latest sampling message shown in the approval prompt.
+const PREVIEW_MAX_LEN: usize = 500;
+
+/// Per server settings governing server initiated sampling requests.
+#[derive(Clone, Serialize, Deserialize, Debug, Default, Eq, PartialEq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SamplingConfig {
+    /// Approve sampling requests from this server without prompting the user
+    #[serde(default)]
+    pub auto_approve: bool,
+    /// Maximum number of tokens this server may consume through sampling in a session
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_budget: Option<usize>,
+}
+
+impl SamplingConfig {
+    pub fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+
+    pub fn token_budget(&self) -> usize {
+        self.token_budget.unwrap_or(DEFAULT_SAMPLING_TOKEN_BUDGET)
+    }
+}
+
+/// Services `sampling/createMessage` requests sent by mcp servers by forwarding them to the model
+/// currently in use by the chat session.
+///
+/// This is shared between all mcp clients spawned by a
+/// [crate::cli::chat::tool_manager::ToolManager] so that token usage and session approvals are
+/// tracked per server across the whole session.
+#[derive(Clone, Debug)]
+pub struct SamplingHandler {
+    client: ApiClient,
+    interactive: bool,
+    /// The model used by the chat session, updated every time a message is sent
+    model_id: Arc<RwLock<Option<String>>>,
+    /// Tokens consumed through sampling, or reserved by requests in flight, keyed by server name
+    usage: Arc<Mutex<HashMap<String, usize>>>,
+    /// Servers the user has allowed to sample for the remainder of the session
+    session_approved: Arc<Mutex<HashSet<String>>>,
+    /// Ensures only one approval prompt is displayed at a time
+    approval_lock: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl SamplingHandler {
+    pub fn new(client: ApiClient, interactive: bool) -> Self {
+        Self {
+            client,
+            interactive,
+            model_id: Default::default(),
+            usage: Default::default(),
+            session_approved: Default::default(),
+            approval_lock: Default::default(),
+        }
+    }
+
+    pub fn set_model_id(&self, model_id: Option<String>) {
+        *self.model_id.write() = model_id;
+    }
+
+    #[cfg(test)]
+    fn tokens_used(&self, server_name: &str) -> usize {
+        self.usage.lock().get(server_name).copied().unwrap_or_default()
+    }
+
+    /// Answers a sampling request from `server_name`, subject to its token budget and to user
+    /// approval.
+    pub async fn create_message(
+        &self,
+        server_name: &str,
+        config: &SamplingConfig,
+        params: CreateMessageRequestParam,
+    ) -> Result<CreateMessageResult, ErrorData> {
+        let model_id = self.model_id.read().clone();
+        let conversation_state = build_conversation_state(&params, model_id.clone())?;
+        let prompt_tokens = count_conversation_tokens(&conversation_state);
+
+        // Reserve the most the request can consume, so concurrent requests cannot overrun the budget
+        let reservation = self.reserve(
+            server_name,
+            prompt_tokens + params.max_tokens as usize,
+            config.token_budget(),
+        )?;
+
+        self.approve(server_name, config, &conversation_state.user_input_message.content)
+            .await?;
+
+        let mut output = self
+            .client
+            .send_sampling_message(server_name, conversation_state)
+            .await
+            .map_err(|e| ErrorData::internal_error(format!("Sampling request failed: {e}"), None))?;
+
+        let mut text = String::new();
+        while let Some(event) = output
+            .recv()
+            .await
+            .map_err(|e| ErrorData::internal_error(format!("Sampling request failed: {e}"), None))?
+        {
+            if let ChatResponseStream::AssistantResponseEvent { content } = event {
+                text.push_str(&content);
+            }
+        }
+
+        let stop_reason = apply_limits(&mut text, &params);
+        let total_tokens = prompt_tokens + TokenCounter::count_tokens(&text);
+        reservation.settle(total_tokens);
+        info!(target: "mcp", "{server_name} consumed {total_tokens} tokens through sampling");
+
+        Ok(CreateMessageResult {
+            model: model_id.unwrap_or_else(|| "default".to_string()),
+            stop_reason: Some(stop_reason.to_string()),
+            message: SamplingMessage {
+                role: Role::Assistant,
+                content: Content::text(text),
+            },
+        })
+    }
+
+    /// Adds `tokens` to the usage of `server_name` if that stays within `budget`. The tokens are
+    /// released again when the returned reservation is dropped without being settled.
+    fn reserve(&self, server_name: &str, tokens: usize, budget: usize) -> Result<BudgetReservation<'_>, ErrorData> {
+        let mut usage = self.usage.lock();
+        let used = usage.entry(server_name.to_string()).or_default();
+        if *used + tokens > budget {
+            warn!(target: "mcp", "{server_name} exceeded its sampling token budget ({used} of {budget} used)");
+            return Err(ErrorData::invalid_request(
+                format!("Sampling token budget exhausted for server {server_name} ({used} of {budget} tokens used)"),
+                None,
+            ));
+        }
+        *used += tokens;
+
+        Ok(BudgetReservation {
+            usage: &self.usage,
+            server_name: server_name.to_string(),
+            tokens,
+        })
+    }
+
+    async fn approve(&self, server_name: &str, config: &SamplingConfig, preview: &str) -> Result<(), ErrorData> {
+        if config.auto_approve || self.session_approved.lock().contains(server_name) {
+            return Ok(());
+        }
+
+        if !self.interactive || !std::io::stderr().is_terminal() {
+            return Err(ErrorData::new(
+                USER_REJECTED_CODE,
+                format!(
+                    "Sampling request from {server_name} was not approved. Set sampling.autoApprove for this server to allow sampling in non-interactive mode"
+                ),
+                None,
+            ));
+        }
+
+        let _guard = self.approval_lock.lock().await;
+        // Another request from the same server might have been approved while we were waiting
+        if self.session_approved.lock().contains(server_name) {
+            return Ok(());
+        }
+
+        let server = server_name.to_string();
+        let preview = match preview.char_indices().nth(PREVIEW_MAX_LEN) {
+            Some((idx, _)) => format!("{}...", &preview[..idx]),
+            None => preview.to_string(),
+        };
+        let selection = tokio::task::spawn_blocking(move || {
+            let mut stderr = std::io::stderr();
+            let _ = execute!(
+                stderr,
+                style::Print("\n"),
+                style::SetForegroundColor(style::Color::Green),
+                style::Print(&server),
+                style::ResetColor,
+                style::Print(" is requesting a model completion (sampling):\n\n"),
+                style::SetForegroundColor(style::Color::DarkGrey),
+                style::Print(&preview),
+                style::ResetColor,
+                style::Print("\n\n"),
+            );
+            crate::util::choose("Allow this request?", &[
+                "Allow once",
+                "Allow for the rest of this session",
+                "Deny",
+            ])
+        })
+        .await;
+
+        match selection {
+            Ok(Ok(Some(0))) => Ok(()),
+            Ok(Ok(Some(1))) => {
+                self.session_approved.lock().insert(server_name.to_string());
+                Ok(())
+            },
+            _ => Err(ErrorData::new(
+                USER_REJECTED_CODE,
+                format!("User rejected sampling request from {server_name}"),
+                None,
+            )),
+        }
+    }
+}
+
+/// Tokens reserved from the budget of a server for a sampling request in flight.
+struct BudgetReservation<'a> {
+    usage: &'a Mutex<HashMap<String, usize>>,
+    server_name: String,
+    tokens: usize,
+}
+
+impl BudgetReservation<'_> {
+    /// Replaces the reserved tokens with the tokens the request actually consumed.
+    fn settle(mut self, tokens: usize) {
+        let mut usage = self.usage.lock();
+        let used = usage.entry(self.server_name.clone()).or_default();
+        *used = used.saturating_sub(self.tokens) + tokens;
+        self.tokens = 0;
+    }
+}
+
+impl Drop for BudgetReservation<'_> {
+    fn drop(&mut self) {
+        if let Some(used) = self.usage.lock().get_mut(&self.server_name) {
+            *used = used.saturating_sub(self.tokens);
+        }
+    }
+}
+
+/// Converts the messages of a sampling request into a conversation understood by the backend.
+///
+/// Consecutive messages from the same role are merged since the backend expects user and
+/// assistant messages to alternate. The system prompt, if any, is prepended to the first user
+/// message.
+fn build_conversation_state(
+    params: &CreateMessageRequestParam,
+    model_id: Option<String>,
+) -> Result<ConversationState, ErrorData> {
+    let mut turns = Vec::<(Role, String)>::new();
+    for message in &params.messages {
+        let Some(text) = message.content.as_text() else {
+            return Err(ErrorData::invalid_params(
+                "Only text content is supported in sampling requests",
+                None,
+            ));
+        };
+        match turns.last_mut() {
+            Some((role, content)) if *role == message.role => {
+                content.push_str("\n\n");
+                content.push_str(&text.text);
+            },
+            _ => turns.push((message.role.clone(), text.text.clone())),
+        }
+    }
+
+    match (turns.first(), turns.last()) {
+        (Some((Role::User, _)), Some((Role::User, _))) => (),
+        _ => {
+            return Err(ErrorData::invalid_params(
+                "Sampling messages must begin and end with a user message",
+                None,
+            ));
+        },
+    }
+
+    if let Some(system_prompt) = params.system_prompt.as_ref().filter(|p| !p.trim().is_empty()) {
+        let (_, content) = &mut turns[0];
+        *content = format!("{system_prompt}\n\n{content}");
+    }
+
+    let user_message = |content: String| UserInputMessage {
+        content,
+        user_input_message_context: None,
+        user_intent: None,
+        images: None,
+        model_id: model_id.clone(),
+    };
+
+    let (_, last) = turns.pop().expect("turns is not empty");
+    let history = turns
+        .into_iter()
+        .map(|(role, content)| match role {
+            Role::User => ChatMessage::UserInputMessage(user_message(content)),
+            Role::Assistant => ChatMessage::AssistantResponseMessage(AssistantResponseMessage {
+                message_id: None,
+                content,
+                tool_uses: None,
+            }),
+        })
+        .collect::<Vec<_>>();
+
+    Ok(ConversationState {
+        conversation_id: None,
+        user_input_message: user_message(last),
+        history: (!history.is_empty()).then_some(history),
+    })
+}
+
+fn count_conversation_tokens(state: &ConversationState) -> usize {
+    let history_tokens = state
+        .history
+        .iter()
+        .flatten()
+        .map(|msg| match msg {
+            ChatMessage::UserInputMessage(msg) => TokenCounter::count_tokens(&msg.content),
+            ChatMessage::AssistantResponseMessage(msg) => TokenCounter::count_tokens(&msg.content),
+        })
+        .sum::<usize>();
+
+    history_tokens + TokenCounter::count_tokens(&state.user_input_message.content)
+}
+
+/// Applies the stop sequences and max tokens requested by the server to the generated text, since
+/// the backend does not accept either. Returns the stop reason to report back.
+fn apply_limits(text: &mut String, params: &CreateMessageRequestParam) -> &'static str {
+    if let Some(idx) = params
+        .stop_sequences
+        .iter()
+        .flatten()
+        .filter(|seq| !seq.is_empty())
+        .filter_map(|seq| text.find(seq.as_str()))
+        .min()
+    {
+        text.truncate(idx);
+        return CreateMessageResult::STOP_REASON_END_SEQUENCE;
+    }
+
+    if Tokenizer::default().truncate(text, params.max_tokens as usize) {
+        return CreateMessageResult::STOP_REASON_END_MAX_TOKEN;
+    }
+
+    CreateMessageResult::STOP_REASON_END_TURN
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(role: Role, text: &str) -> SamplingMessage {
+        SamplingMessage {
+            role,
+            content: Content::text(text),
+        }
+    }
+
+    fn params(messages: Vec<SamplingMessage>) -> CreateMessageRequestParam {
+        CreateMessageRequestParam {
+            messages,
+            model_preferences: None,
+            system_prompt: None,
+            include_context: None,
+            temperature: None,
+            max_tokens: 100,
+            stop_sequences: None,
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn test_build_conversation_state() {
+        let mut params = params(vec![
+            message(Role::User, "first"),
+            message(Role::User, "second"),
+            message(Role::Assistant, "reply"),
+            message(Role::User, "last"),
+        ]);
+        params.system_prompt = Some("be brief".to_string());
+
+        let state = build_conversation_state(&params, Some("model".to_string())).unwrap();
+        assert_eq!(state.user_input_message.content, "last");
+        assert_eq!(state.user_input_message.model_id.as_deref(), Some("model"));
+
+        let history = state.history.unwrap();
+        assert_eq!(history.len(), 2);
+        assert!(
+            matches!(&history[0], ChatMessage::UserInputMessage(msg) if msg.content == "be brief\n\nfirst\n\nsecond")
+        );
+        assert!(matches!(&history[1], ChatMessage::AssistantResponseMessage(msg) if msg.content == "reply"));
+    }
+
+    #[test]
+    fn test_build_conversation_state_rejects_invalid() {
+        assert!(build_conversation_state(&params(vec![]), None).is_err());
+        assert!(build_conversation_state(&params(vec![message(Role::Assistant, "hi")]), None).is_err());
+        assert!(
+            build_conversation_state(
+                &params(vec![message(Role::User, "hi"), message(Role::Assistant, "prefill")]),
+                None
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_apply_limits() {
+        let mut params = params(vec![]);
+        params.stop_sequences = Some(vec!["STOP".to_string()]);
+        let mut text = "hello STOP world".to_string();
+        assert_eq!(
+            apply_limits(&mut text, &params),
+            CreateMessageResult::STOP_REASON_END_SEQUENCE
+        );
+        assert_eq!(text, "hello ");
+
+        params.stop_sequences = None;
+        params.max_tokens = 1;
+        let mut text = "a".repeat(10);
+        assert_eq!(
+            apply_limits(&mut text, &params),
+            CreateMessageResult::STOP_REASON_END_MAX_TOKEN
+        );
+        assert!(!text.is_empty() && text.len() < 10, "{text}");
+        assert_eq!(TokenCounter::count_tokens(&text), 1);
+    }
+
+    #[tokio::test]
+    async fn test_create_message() {
+        let mut os = crate::os::Os::new().await.unwrap();
+        os.client
+            .set_mock_output(serde_json::json!([["Hello", " there"], ["unused"]]));
+        let handler = SamplingHandler::new(os.client.clone(), false);
+        handler.set_model_id(Some("model".to_string()));
+
+        // Not approved in non-interactive mode
+        let request = params(vec![message(Role::User, "hi")]);
+        let err = handler
+            .create_message("server", &SamplingConfig::default(), request.clone())
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, USER_REJECTED_CODE);
+
+        // The budget covers the prompt and the 100 tokens the response may use
+        let config = SamplingConfig {
+            auto_approve: true,
+            token_budget: Some(120),
+        };
+        let result = handler
+            .create_message("server", &config, request.clone())
+            .await
+            .unwrap();
+        assert_eq!(result.model, "model");
+        assert_eq!(result.message.content.as_text().unwrap().text, "Hello there");
+        assert!(handler.tokens_used("server") > 0);
+        assert!(handler.tokens_used("server") < 10);
+
+        // Budget is tracked per server
+        let large_request = params(vec![message(Role::User, &"hello ".repeat(20))]);
+        assert!(handler.create_message("server", &config, large_request).await.is_err());
+        assert_eq!(handler.tokens_used("other_server"), 0);
+    }
+
+    #[tokio::test]
+    async fn test_create_message_reserves_budget() {
+        let mut os = crate::os::Os::new().await.unwrap();
+        os.client.set_mock_output(serde_json::json!([["Hello"]]));
+        let handler = SamplingHandler::new(os.client.clone(), false);
+        let config = SamplingConfig {
+            auto_approve: true,
+            token_budget: Some(150),
+        };
+        let request = params(vec![message(Role::User, "hi")]);
+        let reserved = prompt_tokens(&request) + 100;
+
+        // Each request fits the budget on its own, but not while the other is in flight
+        let in_flight = handler.reserve("server", reserved, 150).unwrap();
+        let err = handler
+            .create_message("server", &config, request.clone())
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, ErrorCode::INVALID_REQUEST);
+        assert!(handler.reserve("server", reserved, 150).is_err());
+
+        // Only the tokens actually used stay consumed once the request is done
+        in_flight.settle(5);
+        assert_eq!(handler.tokens_used("server"), 5);
+        assert!(handler.create_message("server", &config, request.clone()).await.is_ok());
+        let used = handler.tokens_used("server");
+        assert!(used > 5 && used < 15, "{used}");
+
+        // A rejected request releases its reservation
+        let err = handler
+            .create_message("other_server", &SamplingConfig::default(), request)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, USER_REJECTED_CODE);
+        assert_eq!(handler.tokens_used("other_server"), 0);
+    }
+
+    fn prompt_tokens(params: &CreateMessageRequestParam) -> usize {
+        count_conversation_tokens(&build_conversation_state(params, None).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_record_and_replay_sampling() {
+        use crate::api_client::recording::{
+            RECORD_FILE_ENV,
+            REPLAY_FILE_ENV,
+        };
+        use crate::os::{
+            Env,
+            Fs,
+        };
+
+        let fs = Fs::new();
+        let mut database = crate::database::Database::new().await.unwrap();
+        let config = SamplingConfig {
+            auto_approve: true,
+            token_budget: None,
+        };
+        let request = || params(vec![message(Role::User, "summarize")]);
+
+        let env = Env::from_slice(&[(RECORD_FILE_ENV, "/recording.jsonl")]);
+        let mut client = ApiClient::new(&env, &fs, &mut database, None).await.unwrap();
+        client.set_mock_output(serde_json::json!([["A summary"]]));
+        let handler = SamplingHandler::new(client, false);
+        handler.create_message("server", &config, request()).await.unwrap();
+
+        let env = Env::from_slice(&[(REPLAY_FILE_ENV, "/recording.jsonl")]);
+        let client = ApiClient::new(&env, &fs, &mut database, None).await.unwrap();
+        let handler = SamplingHandler::new(client, false);
+        let result = handler.create_message("server", &config, request()).await.unwrap();
+        assert_eq!(result.message.content.as_text().unwrap().text, "A summary");
+
+        // The exchange was recorded for the server that sent it
+        assert!(
+            handler
+                .create_message("other_server", &config, request())
+                .await
+                .is_err()
+        );
+    }
+}
@@ -230,6 +230,28 @@ impl Fs {
         }
     }
 
+    /// Appends `contents` to the end of a file, creating it if it does not exist.
+    pub async fn append(&self, path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> io::Result<()> {
+        use tokio::io::AsyncWriteExt;
+
+        let path = match self {
+            Self::Real => path.as_ref().to_path_buf(),
+            Self::Chroot(root) => append(root.path(), path),
+            Self::Fake(map) => {
+                let Ok(mut lock) = map.lock() else {
+                    return Err(io::Error::other("poisoned lock"));
+                };
+                lock.entry(path.as_ref().to_owned())
+                    .or_default()
+                    .extend_from_slice(contents.as_ref());
+                return Ok(());
+            },
+        };
+        let mut file = fs::OpenOptions::new().create(true).append(true).open(path).await?;
+        file.write_all(contents.as_ref()).await?;
+        file.flush().await
+    }
+
     /// Removes a file from the filesystem.
     ///
     /// Note that there is no guarantee that the file is immediately deleted (e.g.
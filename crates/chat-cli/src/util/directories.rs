@@ -45,6 +45,7 @@ type Result<T, E = DirectoryError> = std::result::Result<T, E>;
 const WORKSPACE_AGENT_DIR_RELATIVE: &str = ".amazonq/cli-agents";
 const GLOBAL_AGENT_DIR_RELATIVE_TO_HOME: &str = ".aws/amazonq/cli-agents";
 const CLI_BASH_HISTORY_PATH: &str = ".aws/amazonq/.cli_bash_history";
+const WORKSPACE_CODE_REFERENCE_LOG_RELATIVE: &str = ".amazonq/code-references.jsonl";
 
 /// The directory of the users home
 ///
@@ -180,6 +181,12 @@ pub fn chat_local_agent_dir(os: &Os) -> Result<PathBuf> {
     Ok(cwd.join(WORKSPACE_AGENT_DIR_RELATIVE))
 }
 
+/// The path to the log of the code references returned in `q chat` for the current workspace.
+pub fn chat_code_reference_log_path(os: &Os) -> Result<PathBuf> {
+    let cwd = os.env.current_dir()?;
+    Ok(cwd.join(WORKSPACE_CODE_REFERENCE_LOG_RELATIVE))
+}
+
 /// Canonicalizes path given by expanding the path given
 pub fn canonicalizes_path(os: &Os, path_as_str: &str) -> Result<String> {
     let context = |input: &str| Ok(os.env.get(input).ok());
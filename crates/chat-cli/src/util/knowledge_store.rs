@@ -5,13 +5,17 @@ use std::sync::{
 };
 
 use eyre::Result;
-use semantic_search_client::KnowledgeContext;
 use semantic_search_client::client::AsyncSemanticSearchClient;
 use semantic_search_client::embedding::EmbeddingType;
 use semantic_search_client::types::{
     AddContextRequest,
+    SearchFilter,
     SearchResult,
 };
+use semantic_search_client::{
+    KnowledgeContext,
+    WatchStatus,
+};
 use tokio::sync::Mutex;
 use uuid::Uuid;
 
@@ -104,6 +108,9 @@ impl std::fmt::Display for KnowledgeError {
 
 impl std::error::Error for KnowledgeError {}
 
+static ASYNC_INSTANCE: Lazy<tokio::sync::Mutex<Option<Arc<Mutex<KnowledgeStore>>>>> =
+    Lazy::new(|| tokio::sync::Mutex::new(None));
+
 /// Async knowledge store - manages agent specific knowledge bases
 pub struct KnowledgeStore {
     agent_client: AsyncSemanticSearchClient,
@@ -116,9 +123,6 @@ impl KnowledgeStore {
         os: &Os,
         agent: Option<&crate::cli::Agent>,
     ) -> Result<Arc<Mutex<Self>>, directories::DirectoryError> {
-        static ASYNC_INSTANCE: Lazy<tokio::sync::Mutex<Option<Arc<Mutex<KnowledgeStore>>>>> =
-            Lazy::new(|| tokio::sync::Mutex::new(None));
-
         if cfg!(test) {
             // For tests, create a new instance each time
             let store = Self::new_with_os_settings(os, agent)
@@ -152,6 +156,16 @@ impl KnowledgeStore {
         }
     }
 
+    /// Mark the session as idle or active, if a knowledge base is loaded
+    ///
+    /// Updates of watched contexts are held back while the session stays idle.
+    pub async fn set_session_idle(idle: bool) {
+        let instance = ASYNC_INSTANCE.lock().await.clone();
+        if let Some(store) = instance {
+            store.lock().await.agent_client.set_idle(idle);
+        }
+    }
+
     /// Migrate legacy knowledge base from old location if needed
     async fn migrate_legacy_knowledge_base(agent_dir: &PathBuf) -> bool {
         let mut migrated = false;
@@ -212,7 +226,10 @@ impl KnowledgeStore {
         os: &crate::os::Os,
         base_dir: PathBuf,
     ) -> semantic_search_client::config::SemanticSearchConfig {
-        use semantic_search_client::config::SemanticSearchConfig;
+        use semantic_search_client::config::{
+            HybridSearchConfig,
+            SemanticSearchConfig,
+        };
         use semantic_search_client::embedding::EmbeddingType;
 
         use crate::database::settings::Setting;
@@ -245,11 +262,28 @@ impl KnowledgeStore {
             .and_then(|s| EmbeddingType::from_str(&s))
             .unwrap_or_default();
 
+        let hybrid_weight = |setting: Setting, default: f32| {
+            os.database
+                .settings
+                .get(setting)
+                .and_then(|value| value.as_f64())
+                .map_or(default, |weight| weight as f32)
+        };
+        let hybrid = HybridSearchConfig {
+            semantic_weight: hybrid_weight(
+                Setting::KnowledgeHybridSemanticWeight,
+                default_config.hybrid.semantic_weight,
+            ),
+            bm25_weight: hybrid_weight(Setting::KnowledgeHybridBm25Weight, default_config.hybrid.bm25_weight),
+            ..default_config.hybrid
+        };
+
         SemanticSearchConfig {
             chunk_size,
             chunk_overlap,
             max_files,
             embedding_type,
+            hybrid,
             base_dir,
             ..default_config
         }
@@ -313,7 +347,10 @@ impl KnowledgeStore {
                 Some(s) => match EmbeddingType::from_str(s) {
                     Some(et) => Some(et),
                     None => {
-                        return Err(format!("Invalid embedding type '{}'. Valid options are: fast, best", s));
+                        return Err(format!(
+                            "Invalid embedding type '{}'. Valid options are: fast, best, hybrid",
+                            s
+                        ));
                     },
                 },
                 None => None,
@@ -357,12 +394,17 @@ impl KnowledgeStore {
     }
 
     /// Search - delegates to async client
-    pub async fn search(&self, query: &str, context_id: Option<&str>) -> Result<Vec<SearchResult>, KnowledgeError> {
+    pub async fn search(
+        &self,
+        query: &str,
+        context_id: Option<&str>,
+        filter: &SearchFilter,
+    ) -> Result<Vec<SearchResult>, KnowledgeError> {
         if let Some(context_id) = context_id {
             // Search specific context
             let results = self
                 .agent_client
-                .search_context(context_id, query, None)
+                .search_context(context_id, query, None, filter)
                 .await
                 .map_err(|e| KnowledgeError::SearchError(e.to_string()))?;
             Ok(results)
@@ -372,7 +414,7 @@ impl KnowledgeStore {
 
             let agent_results = self
                 .agent_client
-                .search_all(query, None)
+                .search_all(query, None, filter)
                 .await
                 .map_err(|e| KnowledgeError::SearchError(e.to_string()))?;
 
@@ -483,23 +525,68 @@ impl KnowledgeStore {
             .map_err(|e| e.to_string())
     }
 
+    /// Turn automatic re-indexing on or off for the context with the given path or name
+    pub async fn set_watch(&mut self, path_or_name: &str, watch: bool) -> Result<String, String> {
+        let context = match self.agent_client.get_context_by_path(path_or_name).await {
+            Some(context) => context,
+            None => self
+                .agent_client
+                .get_context_by_name(path_or_name)
+                .await
+                .ok_or_else(|| format!("No context found with path or name '{}'", path_or_name))?,
+        };
+
+        self.agent_client
+            .set_context_watch(&context.id, watch)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(if watch {
+            format!(
+                "👀 Watching '{}'\n📁 Path: {}\n✅ Changed files will be re-indexed automatically",
+                context.name,
+                context.source_path.as_deref().unwrap_or_default()
+            )
+        } else {
+            format!("Stopped watching '{}'", context.name)
+        })
+    }
+
+    /// Freshness of a watched context, `None` if the context is not watched
+    pub fn watch_status(&self, context_id: &str) -> Option<WatchStatus> {
+        self.agent_client.watch_status(context_id)
+    }
+
+    /// Start re-indexing the files of a context that changed since it was last indexed
+    async fn start_incremental_update(&self, context: &KnowledgeContext) -> Result<String, String> {
+        let (operation_id, _) = self
+            .agent_client
+            .update_context(&context.id)
+            .await
+            .map_err(|e| format!("Failed to start update: {}", e))?;
+
+        Ok(format!(
+            "🔄 Started updating '{}'\n📁 Path: {}\n🆔 Operation ID: {}\n✅ Only added, changed and removed files will be re-indexed",
+            context.name,
+            context.source_path.as_deref().unwrap_or_default(),
+            &operation_id.to_string()[..8]
+        ))
+    }
+
+    /// Whether `path_str` points at the path a context was indexed from
+    fn is_source_path(context: &KnowledgeContext, path_str: &str) -> bool {
+        let canonical = |path: &str| std::path::PathBuf::from(path).canonicalize().ok();
+        context
+            .source_path
+            .as_deref()
+            .and_then(canonical)
+            .is_some_and(|source| canonical(path_str) == Some(source))
+    }
+
     /// Update context by path
     pub async fn update_by_path(&mut self, path_str: &str) -> Result<String, String> {
         if let Some(context) = self.agent_client.get_context_by_path(path_str).await {
-            // Remove the existing context first
-            self.agent_client
-                .remove_context_by_id(&context.id)
-                .await
-                .map_err(|e| e.to_string())?;
-
-            // Then add it back with the same name and original patterns (agent scope)
-            let options = AddOptions {
-                description: None,
-                include_patterns: context.include_patterns.clone(),
-                exclude_patterns: context.exclude_patterns.clone(),
-                embedding_type: None,
-            };
-            self.add(&context.name, path_str, options).await
+            self.start_incremental_update(&context).await
         } else {
             // Debug: List all available contexts
             let available_paths = self.agent_client.list_context_paths().await;
@@ -523,9 +610,13 @@ impl KnowledgeStore {
             .find(|c| c.id == context_id)
             .ok_or_else(|| format!("Context '{}' not found", context_id))?;
 
+        if Self::is_source_path(context, path_str) {
+            return self.start_incremental_update(context).await;
+        }
+
         let context_name = context.name.clone();
 
-        // Remove the existing context first
+        // The path changed, remove the existing context first
         self.agent_client
             .remove_context_by_id(context_id)
             .await
@@ -544,7 +635,11 @@ impl KnowledgeStore {
     /// Update context by name
     pub async fn update_context_by_name(&mut self, name: &str, path_str: &str) -> Result<String, String> {
         if let Some(context) = self.agent_client.get_context_by_name(name).await {
-            // Remove the existing context first
+            if Self::is_source_path(&context, path_str) {
+                return self.start_incremental_update(&context).await;
+            }
+
+            // The path changed, remove the existing context first
             self.agent_client
                 .remove_context_by_id(&context.id)
                 .await
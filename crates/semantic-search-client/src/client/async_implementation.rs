@@ -12,6 +12,10 @@ use super::background::BackgroundWorker;
 use super::context::ContextManager;
 use super::model::ModelDownloader;
 use super::operation::OperationManager;
+use super::watcher::{
+    ContextWatcher,
+    UpdateQueue,
+};
 use crate::client::embedder_factory;
 use crate::config::{
     self,
@@ -32,6 +36,8 @@ pub struct AsyncSemanticSearchClient {
     job_tx: mpsc::UnboundedSender<IndexingJob>,
     context_manager: ContextManager,
     operation_manager: OperationManager,
+    updates: UpdateQueue,
+    watcher: ContextWatcher,
 }
 
 impl AsyncSemanticSearchClient {
@@ -100,6 +106,13 @@ impl AsyncSemanticSearchClient {
 
         tokio::spawn(worker.run());
 
+        let updates = UpdateQueue {
+            context_manager: context_manager.clone(),
+            operation_manager: operation_manager.clone(),
+            job_tx: job_tx.clone(),
+        };
+        let watcher = ContextWatcher::new(updates.clone(), config.watch, &base_dir);
+
         let client = Self {
             base_dir,
             embedder,
@@ -107,9 +120,16 @@ impl AsyncSemanticSearchClient {
             job_tx,
             context_manager,
             operation_manager,
+            updates,
+            watcher,
         };
 
         client.context_manager.load_persistent_contexts().await?;
+        for context in client.get_contexts().await.iter().filter(|context| context.watch) {
+            if let Err(e) = client.watcher.watch(context) {
+                tracing::warn!("Failed to watch context {}: {}", context.id, e);
+            }
+        }
         Ok(client)
     }
 
@@ -319,6 +339,95 @@ impl AsyncSemanticSearchClient {
         Ok((operation_id, cancel_token))
     }
 
+    /// Re-indexes the files of an existing context that changed since it was last indexed.
+    ///
+    /// Files are compared against the manifest recorded when the context was indexed. Only added
+    /// or modified files are chunked and embedded again, and the chunks of removed files are
+    /// deleted. Contexts indexed before manifests existed are rebuilt once.
+    ///
+    /// # Arguments
+    ///
+    /// * `context_id` - The unique identifier of the context to update
+    ///
+    /// # Returns
+    ///
+    /// Returns a `Result<(Uuid, CancellationToken)>` for tracking the update operation.
+    ///
+    /// # Errors
+    ///
+    /// This method will return an error if:
+    /// - The context does not exist or was not created from a path
+    /// - The context is already being updated
+    /// - The background worker is unavailable
+    pub async fn update_context(&self, context_id: &str) -> Result<(Uuid, CancellationToken)> {
+        let update = self.updates.queue(context_id).await?;
+        Ok((update.id, update.cancel))
+    }
+
+    /// Turns automatic re-indexing of a context on or off.
+    ///
+    /// A watched context has its source path watched for changes. Once the files stop changing
+    /// for the configured debounce period, the changed files are re-indexed as with
+    /// [`Self::update_context`]. The setting is saved with the context and watching resumes when
+    /// the client is created again.
+    ///
+    /// # Arguments
+    ///
+    /// * `context_id` - The unique identifier of the context
+    /// * `watch` - Whether the context should be watched
+    ///
+    /// # Errors
+    ///
+    /// This method will return an error if:
+    /// - The context does not exist or was not created from a path
+    /// - The source path cannot be watched
+    /// - The context metadata cannot be saved
+    pub async fn set_context_watch(&self, context_id: &str, watch: bool) -> Result<()> {
+        let context = {
+            let mut contexts = self.context_manager.get_contexts_ref().write().await;
+            let context = contexts
+                .get_mut(context_id)
+                .ok_or_else(|| SemanticSearchError::ContextNotFound(context_id.to_string()))?;
+            if watch && context.source_path.is_none() {
+                return Err(SemanticSearchError::InvalidArgument(format!(
+                    "Context '{}' was not created from a path",
+                    context.name
+                )));
+            }
+            context.watch = watch;
+            context.clone()
+        };
+
+        if watch {
+            self.watcher.watch(&context)?;
+        } else {
+            self.watcher.unwatch(context_id);
+        }
+
+        self.context_manager.save_contexts_metadata(&self.base_dir).await
+    }
+
+    /// Returns how fresh a watched context is.
+    ///
+    /// # Arguments
+    ///
+    /// * `context_id` - The unique identifier of the context
+    ///
+    /// # Returns
+    ///
+    /// Returns `Some(WatchStatus)` if the context is watched, `None` otherwise.
+    pub fn watch_status(&self, context_id: &str) -> Option<WatchStatus> {
+        self.watcher.status(context_id)
+    }
+
+    /// Marks the session using this client as idle or active.
+    ///
+    /// Watched contexts keep recording changes while the session is idle, but once it has been
+    /// idle for the configured pause their updates wait until it is marked active again.
+    pub fn set_idle(&self, idle: bool) {
+        self.watcher.set_idle(idle);
+    }
+
     /// Retrieves all available contexts in the knowledge base.
     ///
     /// This method returns a list of all contexts (both persistent and volatile)
@@ -352,14 +461,15 @@ impl AsyncSemanticSearchClient {
     /// Performs a semantic search across all available contexts.
     ///
     /// This method searches through all indexed contexts using the provided query text,
-    /// returning the most relevant results ranked by semantic similarity or BM25 score
-    /// depending on the context type.
+    /// returning the most relevant results ranked by semantic similarity, BM25 score or the
+    /// fusion of both depending on the context type.
     ///
     /// # Arguments
     ///
     /// * `query_text` - The search query string
     /// * `result_limit` - Optional limit on the number of results per context. If `None`, uses the
     ///   default limit from configuration
+    /// * `filter` - Path, file type and context name the results must match
     ///
     /// # Returns
     ///
@@ -377,25 +487,37 @@ impl AsyncSemanticSearchClient {
     /// # Examples
     ///
     /// ```no_run
-    /// use semantic_search_client::AsyncSemanticSearchClient;
+    /// use semantic_search_client::{
+    ///     AsyncSemanticSearchClient,
+    ///     SearchFilter,
+    /// };
     ///
     /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
     /// let client = AsyncSemanticSearchClient::new_with_default_dir().await?;
     /// let results = client
-    ///     .search_all("machine learning algorithms", Some(10))
+    ///     .search_all(
+    ///         "machine learning algorithms",
+    ///         Some(10),
+    ///         &SearchFilter::default(),
+    ///     )
     ///     .await?;
     ///
     /// for (context_id, search_results) in results {
     ///     println!(
     ///         "Results from context {}: {} matches",
     ///         context_id,
-    ///         search_results.results.len()
+    ///         search_results.len()
     ///     );
-    ///     for result in search_results.results.iter().take(3) {
+    ///     for result in search_results.iter().take(3) {
     ///         println!(
-    ///             "  Score: {:.3} - {}",
-    ///             result.score,
-    ///             result.text.chars().take(100).collect::<String>()
+    ///             "  Distance: {:.3} - {}",
+    ///             result.distance,
+    ///             result
+    ///                 .text()
+    ///                 .unwrap_or_default()
+    ///                 .chars()
+    ///                 .take(100)
+    ///                 .collect::<String>()
     ///         );
     ///     }
     /// }
@@ -406,6 +528,7 @@ impl AsyncSemanticSearchClient {
         &self,
         query_text: &str,
         result_limit: Option<usize>,
+        filter: &SearchFilter,
     ) -> Result<Vec<(ContextId, SearchResults)>> {
         if query_text.is_empty() {
             return Err(SemanticSearchError::InvalidArgument(
@@ -415,7 +538,13 @@ impl AsyncSemanticSearchClient {
 
         let effective_limit = result_limit.unwrap_or(self.config.default_results);
         self.context_manager
-            .search_all(query_text, effective_limit, &*self.embedder)
+            .search_all(
+                query_text,
+                effective_limit,
+                filter,
+                &self.config.hybrid,
+                &*self.embedder,
+            )
             .await
     }
 
@@ -427,6 +556,7 @@ impl AsyncSemanticSearchClient {
     /// * `query_text` - Search query
     /// * `result_limit` - Maximum number of results to return (if None, uses default_results from
     ///   config)
+    /// * `filter` - Path, file type and context name the results must match
     ///
     /// # Returns
     ///
@@ -436,6 +566,7 @@ impl AsyncSemanticSearchClient {
         context_id: &str,
         query_text: &str,
         result_limit: Option<usize>,
+        filter: &SearchFilter,
     ) -> Result<SearchResults> {
         if context_id.is_empty() {
             return Err(SemanticSearchError::InvalidArgument(
@@ -452,7 +583,14 @@ impl AsyncSemanticSearchClient {
         let effective_limit = result_limit.unwrap_or(self.config.default_results);
 
         self.context_manager
-            .search_context(context_id, query_text, effective_limit, &*self.embedder)
+            .search_context(
+                context_id,
+                query_text,
+                effective_limit,
+                filter,
+                &self.config.hybrid,
+                &*self.embedder,
+            )
             .await?
             .ok_or_else(|| SemanticSearchError::ContextNotFound(context_id.to_string()))
     }
@@ -546,6 +684,8 @@ impl AsyncSemanticSearchClient {
     ///
     /// Returns a `Result<(Uuid, CancellationToken)>` for tracking the clear operation.
     pub async fn clear_all(&self) -> Result<(Uuid, CancellationToken)> {
+        self.watcher.unwatch_all();
+
         let operation_id = Uuid::new_v4();
         let cancel_token = CancellationToken::new();
 
@@ -574,6 +714,7 @@ impl AsyncSemanticSearchClient {
     ///
     /// Returns a `Result<(Uuid, CancellationToken)>` for tracking the clear operation. immediately
     pub async fn clear_all_immediate(&self) -> Result<usize> {
+        self.watcher.unwatch_all();
         self.context_manager.clear_all_immediate(&self.base_dir).await
     }
 
@@ -586,6 +727,7 @@ impl AsyncSemanticSearchClient {
     ///
     /// * `context_id` - The unique identifier of the context to remove
     pub async fn remove_context_by_id(&self, context_id: &str) -> Result<()> {
+        self.watcher.unwatch(context_id);
         self.context_manager
             .remove_context_by_id(context_id, &self.base_dir)
             .await
@@ -3,15 +3,21 @@ use std::sync::Arc;
 
 use tokio::sync::{
     Semaphore,
+    SemaphorePermit,
     mpsc,
 };
 use tokio_util::sync::CancellationToken;
 use tracing::debug;
 use uuid::Uuid;
 
+use super::super::context::manifest::{
+    FileState,
+    ManifestDiff,
+};
 use super::super::context::{
     ContextCreator,
     ContextManager,
+    ContextManifest,
 };
 use super::super::operation::OperationManager;
 use super::file_processor::FileProcessor;
@@ -93,6 +99,9 @@ impl BackgroundWorker {
 
                     self.process_add_directory(id, params, cancel).await;
                 },
+                IndexingJob::Update { id, cancel, context_id } => {
+                    self.process_update(id, context_id, cancel).await;
+                },
                 IndexingJob::Clear { id, cancel } => {
                     self.process_clear(id, cancel).await;
                 },
@@ -114,14 +123,33 @@ impl BackgroundWorker {
             return;
         }
 
+        let Some(_permit) = self.acquire_indexing_slot(operation_id).await else {
+            return;
+        };
+
+        let result = self.perform_indexing(operation_id, params, cancel_token).await;
+
+        match result {
+            Ok(context_id) => {
+                debug!("Successfully indexed context: {}", context_id);
+                self.mark_operation_completed(operation_id).await;
+            },
+            Err(e) => {
+                tracing::error!("Indexing failed: {}", e);
+                self.mark_operation_failed(operation_id, e).await;
+            },
+        }
+    }
+
+    async fn acquire_indexing_slot(&self, operation_id: Uuid) -> Option<SemaphorePermit<'_>> {
         self.update_operation_status(operation_id, "Waiting in queue...".to_string())
             .await;
 
-        let _permit = match self.indexing_semaphore.try_acquire() {
+        match self.indexing_semaphore.try_acquire() {
             Ok(permit) => {
                 self.update_operation_status(operation_id, "Acquired slot, starting indexing...".to_string())
                     .await;
-                permit
+                Some(permit)
             },
             Err(_) => {
                 self.update_operation_status(
@@ -136,28 +164,15 @@ impl BackgroundWorker {
                     Ok(permit) => {
                         self.update_operation_status(operation_id, "Acquired slot, starting indexing...".to_string())
                             .await;
-                        permit
+                        Some(permit)
                     },
                     Err(_) => {
                         self.mark_operation_failed(operation_id, "Semaphore unavailable".to_string())
                             .await;
-                        return;
+                        None
                     },
                 }
             },
-        };
-
-        let result = self.perform_indexing(operation_id, params, cancel_token).await;
-
-        match result {
-            Ok(context_id) => {
-                debug!("Successfully indexed context: {}", context_id);
-                self.mark_operation_completed(operation_id).await;
-            },
-            Err(e) => {
-                tracing::error!("Indexing failed: {}", e);
-                self.mark_operation_failed(operation_id, e).await;
-            },
         }
     }
 
@@ -221,7 +236,7 @@ impl BackgroundWorker {
             return Err("Failed: Operation was cancelled before file processing".to_string());
         }
 
-        let items = self
+        let (items, file_states) = self
             .file_processor
             .process_directory_files(
                 &params.path,
@@ -253,6 +268,8 @@ impl BackgroundWorker {
             )
             .await?;
 
+        Self::save_manifest(&context_dir, &params.name, &file_states, &items);
+
         self.store_context_metadata(
             &context_id,
             &params.name,
@@ -269,6 +286,193 @@ impl BackgroundWorker {
         Ok(context_id)
     }
 
+    /// Record the files of a new context so later updates only re-index what changed
+    fn save_manifest(context_dir: &std::path::Path, name: &str, files: &[FileState], items: &[serde_json::Value]) {
+        let mut manifest = ContextManifest::default();
+        manifest.record(files, items, 0);
+        if let Err(e) = manifest.save(context_dir) {
+            tracing::warn!("Failed to save manifest for {}: {}", name, e);
+        }
+    }
+
+    async fn process_update(&self, operation_id: Uuid, context_id: String, cancel_token: CancellationToken) {
+        debug!("Processing Update job: {}", context_id);
+
+        if cancel_token.is_cancelled() {
+            self.mark_operation_cancelled(operation_id).await;
+            return;
+        }
+
+        let Some(_permit) = self.acquire_indexing_slot(operation_id).await else {
+            return;
+        };
+
+        match self.perform_update(operation_id, &context_id, &cancel_token).await {
+            Ok(()) => {
+                debug!("Successfully updated context: {}", context_id);
+                self.mark_operation_completed(operation_id).await;
+            },
+            Err(e) if cancel_token.is_cancelled() => {
+                debug!("Update of {} stopped: {}", context_id, e);
+                self.mark_operation_cancelled(operation_id).await;
+            },
+            Err(e) => {
+                tracing::error!("Update failed: {}", e);
+                self.mark_operation_failed(operation_id, e).await;
+            },
+        }
+    }
+
+    async fn perform_update(
+        &self,
+        operation_id: Uuid,
+        context_id: &str,
+        cancel_token: &CancellationToken,
+    ) -> std::result::Result<(), String> {
+        let context = self
+            .context_manager
+            .get_contexts_ref()
+            .read()
+            .await
+            .get(context_id)
+            .cloned()
+            .ok_or_else(|| format!("Context '{}' not found", context_id))?;
+        let source_path = context
+            .source_path
+            .as_ref()
+            .map(PathBuf::from)
+            .ok_or_else(|| format!("Context '{}' has no source path", context.name))?;
+        if !source_path.exists() {
+            return Err(format!("Path '{}' does not exist", source_path.display()));
+        }
+
+        let context_dir = if context.persistent {
+            self.base_dir.join(context_id)
+        } else {
+            std::env::temp_dir().join("semantic_search").join(context_id)
+        };
+        let to_patterns = |patterns: &Vec<String>| (!patterns.is_empty()).then(|| patterns.clone());
+        let include_patterns = to_patterns(&context.include_patterns);
+        let exclude_patterns = to_patterns(&context.exclude_patterns);
+
+        self.update_operation_status(operation_id, "Collecting files...".to_string())
+            .await;
+        let files = self
+            .file_processor
+            .collect_files(&source_path, &include_patterns, &exclude_patterns)
+            .await?;
+        if files.len() > self.config.max_files {
+            return Err(format!(
+                "Failed: Directory contains more than {} files, which exceeds the maximum limit",
+                self.config.max_files
+            ));
+        }
+
+        if cancel_token.is_cancelled() {
+            return Err("Operation was cancelled before checking files".to_string());
+        }
+
+        // Contexts indexed before manifests existed are rebuilt from scratch once
+        let existing_manifest =
+            ContextManifest::load(&context_dir).map_err(|e| format!("Failed to load manifest: {}", e))?;
+        let rebuild = existing_manifest.is_none();
+        let mut manifest = existing_manifest.unwrap_or_default();
+
+        let mut diff = ManifestDiff::default();
+        for (checked, batch) in files.chunks(100).enumerate() {
+            if cancel_token.is_cancelled() {
+                return Err("Operation was cancelled while checking files".to_string());
+            }
+            self.update_operation_progress(
+                operation_id,
+                (checked * 100) as u64,
+                files.len() as u64,
+                format!("Checking files for changes ({}/{})", checked * 100, files.len()),
+            )
+            .await;
+            let batch_diff = manifest.diff_files(batch);
+            diff.changed.extend(batch_diff.changed);
+            diff.unchanged += batch_diff.unchanged;
+        }
+        diff.removed = manifest.removed_files(&files);
+
+        if diff.is_empty() && !rebuild {
+            manifest
+                .save(&context_dir)
+                .map_err(|e| format!("Failed to save manifest: {}", e))?;
+            self.update_operation_status(operation_id, format!("'{}' is already up to date", context.name))
+                .await;
+            return Ok(());
+        }
+
+        self.update_operation_status(
+            operation_id,
+            format!(
+                "Re-indexing {} changed files, removing {} files...",
+                diff.changed.len(),
+                diff.removed.len()
+            ),
+        )
+        .await;
+
+        let changed_files: Vec<PathBuf> = diff.changed.iter().map(|file| file.path.clone()).collect();
+        let items = self
+            .file_processor
+            .process_files(&changed_files, operation_id, cancel_token, &self.operation_manager)
+            .await?;
+
+        if cancel_token.is_cancelled() {
+            return Err("Operation was cancelled before updating the context".to_string());
+        }
+
+        let stale_ids = (!rebuild).then(|| manifest.stale_chunk_ids(&diff));
+        let first_id = self
+            .context_creator
+            .update_context(
+                context_id,
+                &items,
+                stale_ids,
+                context.embedding_type,
+                operation_id,
+                cancel_token,
+                &self.operation_manager,
+                &*self.embedder,
+                &self.context_manager,
+            )
+            .await?;
+
+        for removed in &diff.removed {
+            manifest.files.remove(removed);
+        }
+        manifest.record(&diff.changed, &items, first_id);
+        manifest
+            .save(&context_dir)
+            .map_err(|e| format!("Failed to save manifest: {}", e))?;
+
+        {
+            let mut contexts = self.context_manager.get_contexts_ref().write().await;
+            if let Some(context) = contexts.get_mut(context_id) {
+                context.item_count = manifest.files.len();
+                context.updated_at = chrono::Utc::now();
+            }
+        }
+        if context.persistent {
+            self.context_manager
+                .save_contexts_metadata(&self.base_dir)
+                .await
+                .map_err(|e| format!("Failed to save contexts metadata: {}", e))?;
+        }
+
+        debug!(
+            "Updated context {}: {} changed, {} removed, {} unchanged",
+            context_id,
+            diff.changed.len(),
+            diff.removed.len(),
+            diff.unchanged
+        );
+        Ok(())
+    }
+
     async fn process_clear(&self, operation_id: Uuid, cancel_token: CancellationToken) {
         debug!("Processing Clear job");
 
@@ -379,7 +583,15 @@ impl BackgroundWorker {
         }
     }
 
+    async fn set_operation_state(&self, operation_id: Uuid, state: OperationState) {
+        let operations = self.operation_manager.get_active_operations_ref().read().await;
+        if let Some(operation) = operations.get(&operation_id) {
+            operation.state.send_replace(state);
+        }
+    }
+
     async fn mark_operation_completed(&self, operation_id: Uuid) {
+        self.set_operation_state(operation_id, OperationState::Completed).await;
         if let Ok(mut operations) = self.operation_manager.get_active_operations_ref().try_write() {
             operations.remove(&operation_id);
         }
@@ -387,6 +599,7 @@ impl BackgroundWorker {
     }
 
     async fn mark_operation_failed(&self, operation_id: Uuid, error: String) {
+        self.set_operation_state(operation_id, OperationState::Failed).await;
         if let Ok(mut operations) = self.operation_manager.get_active_operations_ref().try_write() {
             if let Some(operation) = operations.get_mut(&operation_id) {
                 if let Ok(mut progress) = operation.progress.try_lock() {
@@ -398,6 +611,7 @@ impl BackgroundWorker {
     }
 
     async fn mark_operation_cancelled(&self, operation_id: Uuid) {
+        self.set_operation_state(operation_id, OperationState::Cancelled).await;
         if let Ok(mut operations) = self.operation_manager.get_active_operations_ref().try_write() {
             if let Some(operation) = operations.get_mut(&operation_id) {
                 if let Ok(mut progress) = operation.progress.try_lock() {
@@ -1,8 +1,12 @@
-use std::path::Path;
+use std::path::{
+    Path,
+    PathBuf,
+};
 
 use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 
+use super::super::context::manifest::FileState;
 use super::super::operation::OperationManager;
 use crate::config::SemanticSearchConfig;
 use crate::processing::process_file_with_config;
@@ -91,6 +95,9 @@ impl FileProcessor {
     }
 
     /// Process directory files
+    ///
+    /// Returns the chunks along with the state of each processed file, read right before the file
+    /// is processed so that later changes are picked up by the next update.
     #[allow(clippy::too_many_arguments)]
     pub async fn process_directory_files(
         &self,
@@ -101,7 +108,7 @@ impl FileProcessor {
         include_patterns: &Option<Vec<String>>,
         exclude_patterns: &Option<Vec<String>>,
         operation_manager: &OperationManager,
-    ) -> std::result::Result<Vec<serde_json::Value>, String> {
+    ) -> std::result::Result<(Vec<serde_json::Value>, Vec<FileState>), String> {
         self.update_operation_status(
             operation_manager,
             operation_id,
@@ -112,6 +119,7 @@ impl FileProcessor {
         let pattern_filter = Self::create_pattern_filter(include_patterns, exclude_patterns)?;
         let mut processed_files = 0;
         let mut items = Vec::new();
+        let mut states = Vec::new();
 
         for entry in walkdir::WalkDir::new(dir_path)
             .follow_links(true)
@@ -138,10 +146,14 @@ impl FileProcessor {
                 continue;
             }
 
+            let Ok(state) = FileState::read(path) else {
+                continue;
+            };
             match process_file_with_config(path, Some(self.config.chunk_size), Some(self.config.chunk_overlap)) {
                 Ok(mut file_items) => items.append(&mut file_items),
                 Err(_) => continue,
             }
+            states.push(state);
 
             processed_files += 1;
 
@@ -157,6 +169,80 @@ impl FileProcessor {
             }
         }
 
+        Ok((items, states))
+    }
+
+    /// Collect the files of a directory that match the patterns
+    ///
+    /// Stops after `max_files + 1` files so callers can detect directories over the limit.
+    pub async fn collect_files(
+        &self,
+        dir_path: &Path,
+        include_patterns: &Option<Vec<String>>,
+        exclude_patterns: &Option<Vec<String>>,
+    ) -> std::result::Result<Vec<PathBuf>, String> {
+        let dir_path = dir_path.to_path_buf();
+        let pattern_filter = Self::create_pattern_filter(include_patterns, exclude_patterns)?;
+        let max_files = self.config.max_files;
+
+        tokio::task::spawn_blocking(move || {
+            walkdir::WalkDir::new(&dir_path)
+                .follow_links(true)
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .filter(|e| e.file_type().is_file())
+                .filter(|e| {
+                    !e.path()
+                        .file_name()
+                        .and_then(|n| n.to_str())
+                        .is_some_and(|s| s.starts_with('.'))
+                })
+                .filter(|e| {
+                    pattern_filter
+                        .as_ref()
+                        .is_none_or(|filter| filter.should_include(e.path()))
+                })
+                .take(max_files + 1)
+                .map(|e| e.into_path())
+                .collect()
+        })
+        .await
+        .map_err(|e| format!("File collection task failed: {}", e))
+    }
+
+    /// Process a list of files into chunks
+    pub async fn process_files(
+        &self,
+        files: &[PathBuf],
+        operation_id: Uuid,
+        cancel_token: &CancellationToken,
+        operation_manager: &OperationManager,
+    ) -> std::result::Result<Vec<serde_json::Value>, String> {
+        let mut items = Vec::new();
+
+        for (processed_files, path) in files.iter().enumerate() {
+            if cancel_token.is_cancelled() {
+                return Err("Operation was cancelled during file processing".to_string());
+            }
+
+            if processed_files % 10 == 0 {
+                self.update_operation_progress(
+                    operation_manager,
+                    operation_id,
+                    processed_files as u64,
+                    files.len() as u64,
+                    format!("Indexing changed files ({}/{})", processed_files, files.len()),
+                )
+                .await;
+            }
+
+            if let Ok(mut file_items) =
+                process_file_with_config(path, Some(self.config.chunk_size), Some(self.config.chunk_overlap))
+            {
+                items.append(&mut file_items);
+            }
+        }
+
         Ok(items)
     }
 
@@ -1,3 +1,7 @@
+use std::collections::{
+    HashMap,
+    HashSet,
+};
 use std::fs::{
     self,
     File,
@@ -18,6 +22,9 @@ pub struct BM25Context {
     /// Data points stored in this context
     data_points: Vec<BM25DataPoint>,
 
+    /// Position of each data point in `data_points`, keyed by data point ID
+    positions: HashMap<usize, usize>,
+
     /// BM25 search index (rebuilt from data points)
     index: Option<BM25Index>,
 
@@ -39,6 +46,7 @@ impl BM25Context {
         // Create a new instance
         let mut context = Self {
             data_points: Vec::new(),
+            positions: HashMap::new(),
             index: None,
             data_path: data_path.clone(),
             avgdl,
@@ -49,6 +57,7 @@ impl BM25Context {
             let file = File::open(&data_path)?;
             let reader = BufReader::new(file);
             context.data_points = serde_json::from_reader(reader)?;
+            context.update_positions();
         }
 
         // If we have data points, rebuild the index
@@ -86,6 +95,7 @@ impl BM25Context {
 
         // Add to our data points
         self.data_points.extend(data_points);
+        self.update_positions();
 
         // Always rebuild index when we have data points
         if !self.data_points.is_empty() {
@@ -95,6 +105,38 @@ impl BM25Context {
         Ok(count)
     }
 
+    /// Remove the data points with the given IDs from the context
+    pub fn remove_data_points(&mut self, ids: &HashSet<usize>) -> usize {
+        let before = self.data_points.len();
+        self.data_points.retain(|point| !ids.contains(&point.id));
+        let removed = before - self.data_points.len();
+
+        if removed > 0 {
+            self.update_positions();
+            if let Some(index) = &self.index {
+                for id in ids {
+                    index.remove_document(*id);
+                }
+            }
+        }
+
+        removed
+    }
+
+    /// ID to use for the next data point added to the context
+    pub fn next_id(&self) -> usize {
+        self.data_points.iter().map(|point| point.id + 1).max().unwrap_or(0)
+    }
+
+    fn update_positions(&mut self) {
+        self.positions = self
+            .data_points
+            .iter()
+            .enumerate()
+            .map(|(position, point)| (point.id, position))
+            .collect();
+    }
+
     /// Search the context
     pub fn search(&self, query: &str, limit: usize) -> Vec<(usize, f32)> {
         match &self.index {
@@ -112,8 +154,8 @@ impl BM25Context {
         &self.data_points
     }
 
-    /// Get a specific data point by index
-    pub fn get_data_point(&self, index: usize) -> Option<&BM25DataPoint> {
-        self.data_points.get(index)
+    /// Get a specific data point by ID
+    pub fn get_data_point(&self, id: usize) -> Option<&BM25DataPoint> {
+        self.data_points.get(*self.positions.get(&id)?)
     }
 }
@@ -1,4 +1,7 @@
-use std::collections::HashMap;
+use std::collections::{
+    HashMap,
+    HashSet,
+};
 use std::path::Path;
 use std::sync::Arc;
 
@@ -50,7 +53,7 @@ impl ContextCreator {
         embedder: &dyn TextEmbedderTrait,
         context_manager: &ContextManager,
     ) -> std::result::Result<(), String> {
-        if embedding_type.is_bm25() {
+        if embedding_type.has_bm25_index() {
             self.create_bm25_context(
                 context_dir,
                 items,
@@ -59,8 +62,9 @@ impl ContextCreator {
                 operation_manager,
                 context_manager,
             )
-            .await
-        } else {
+            .await?;
+        }
+        if embedding_type.has_vector_index() {
             self.create_semantic_context(
                 context_dir,
                 items,
@@ -70,8 +74,139 @@ impl ContextCreator {
                 embedder,
                 context_manager,
             )
-            .await
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Apply an incremental update to a loaded context
+    ///
+    /// Removes the data points in `stale_ids`, or every data point when `None`, and adds data
+    /// points for `items`. Returns the ID of the first added data point.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn update_context(
+        &self,
+        context_id: &str,
+        items: &[serde_json::Value],
+        stale_ids: Option<HashSet<usize>>,
+        embedding_type: EmbeddingType,
+        operation_id: Uuid,
+        cancel_token: &CancellationToken,
+        operation_manager: &OperationManager,
+        embedder: &dyn TextEmbedderTrait,
+        context_manager: &ContextManager,
+    ) -> std::result::Result<usize, String> {
+        let not_loaded = || format!("Context '{}' is not loaded", context_id);
+        let bm25_context = match embedding_type.has_bm25_index() {
+            true => Some(
+                context_manager
+                    .get_bm25_contexts_ref()
+                    .read()
+                    .await
+                    .get(context_id)
+                    .cloned()
+                    .ok_or_else(not_loaded)?,
+            ),
+            false => None,
+        };
+        let semantic_context = match embedding_type.has_vector_index() {
+            true => Some(
+                context_manager
+                    .get_volatile_contexts_ref()
+                    .read()
+                    .await
+                    .get(context_id)
+                    .cloned()
+                    .ok_or_else(not_loaded)?,
+            ),
+            false => None,
+        };
+
+        // Hybrid contexts share data point IDs between their two indexes
+        let mut first_id = 0;
+        if let Some(context) = &bm25_context {
+            first_id = first_id.max(context.lock().await.next_id());
+        }
+        if let Some(context) = &semantic_context {
+            first_id = first_id.max(context.lock().await.next_id());
+        }
+
+        let mut bm25_data_points = Vec::new();
+        if bm25_context.is_some() {
+            for (i, item) in items.iter().enumerate() {
+                if cancel_token.is_cancelled() {
+                    return Err("Operation was cancelled during BM25 data point creation".to_string());
+                }
+                let data_point = Self::create_bm25_data_point_from_item(item, first_id + i)
+                    .map_err(|e| format!("Failed to create BM25 data point: {}", e))?;
+                bm25_data_points.push(data_point);
+            }
+        }
+
+        let mut data_points = Vec::new();
+        if semantic_context.is_some() {
+            let total_items = items.len();
+            for (i, item) in items.iter().enumerate() {
+                if cancel_token.is_cancelled() {
+                    return Err("Operation was cancelled during embedding generation".to_string());
+                }
+
+                if i % 10 == 0 {
+                    self.update_operation_progress(
+                        operation_manager,
+                        operation_id,
+                        i as u64,
+                        total_items as u64,
+                        format!("Generating embeddings ({}/{})", i, total_items),
+                    )
+                    .await;
+                }
+
+                let data_point = Self::create_data_point_from_item(item, first_id + i, embedder)
+                    .map_err(|e| format!("Failed to create data point: {}", e))?;
+                data_points.push(data_point);
+            }
+        }
+
+        if cancel_token.is_cancelled() {
+            return Err("Operation was cancelled before updating index".to_string());
+        }
+
+        if let Some(context) = bm25_context {
+            self.update_operation_status(operation_manager, operation_id, "Updating BM25 index...".to_string())
+                .await;
+
+            let mut context = context.lock().await;
+            let stale_ids = stale_ids
+                .clone()
+                .unwrap_or_else(|| context.get_data_points().iter().map(|p| p.id).collect());
+            context.remove_data_points(&stale_ids);
+            context
+                .add_data_points(bm25_data_points)
+                .map_err(|e| format!("Failed to add BM25 data points: {}", e))?;
+            context
+                .save()
+                .map_err(|e| format!("Failed to save BM25 context: {}", e))?;
         }
+
+        if let Some(context) = semantic_context {
+            self.update_operation_status(operation_manager, operation_id, "Updating vector index...".to_string())
+                .await;
+
+            let mut context = context.lock().await;
+            let stale_ids = stale_ids.unwrap_or_else(|| context.get_data_points().iter().map(|p| p.id).collect());
+            context
+                .remove_data_points(&stale_ids)
+                .map_err(|e| format!("Failed to remove data points: {}", e))?;
+            context
+                .add_data_points(data_points)
+                .map_err(|e| format!("Failed to add data points: {}", e))?;
+            context
+                .save()
+                .map_err(|e| format!("Failed to save semantic context: {}", e))?;
+        }
+
+        Ok(first_id)
     }
 
     async fn create_bm25_context(
@@ -17,6 +17,7 @@ use super::{
     SemanticContext,
 };
 use crate::client::utils;
+use crate::config::HybridSearchConfig;
 use crate::embedding::{
     EmbeddingType,
     TextEmbedderTrait,
@@ -25,6 +26,8 @@ use crate::error::{
     Result,
     SemanticSearchError,
 };
+use crate::index::reciprocal_rank_fusion;
+use crate::pattern_filter::PatternFilter;
 use crate::types::*;
 
 type VolatileContexts = Arc<RwLock<HashMap<ContextId, Arc<Mutex<SemanticContext>>>>>;
@@ -33,6 +36,93 @@ type BM25Contexts = Arc<RwLock<HashMap<ContextId, Arc<Mutex<BM25Context>>>>>;
 const SEMANTIC_DATA_FILE: &str = "data.json";
 const BM25_DATA_FILE: &str = "data.bm25.json";
 const DEFAULT_BM25_SCORE: f64 = 100.0;
+/// Candidates fetched per requested result when results are filtered by path or file type
+const FILTER_CANDIDATE_FACTOR: usize = 10;
+/// Candidates fetched from each index per requested result of a hybrid search
+const HYBRID_CANDIDATE_FACTOR: usize = 3;
+
+/// Compiled form of a [`SearchFilter`]
+struct ResultFilter {
+    path: Option<PatternFilter>,
+    file_type: Option<String>,
+    context_name: Option<glob::Pattern>,
+    filters_results: bool,
+}
+
+impl ResultFilter {
+    fn new(filter: &SearchFilter) -> Result<Self> {
+        let path = filter
+            .path
+            .as_ref()
+            .map(|glob| PatternFilter::new(std::slice::from_ref(glob), &[]))
+            .transpose()
+            .map_err(SemanticSearchError::InvalidArgument)?;
+        let context_name = filter
+            .context_name
+            .as_deref()
+            .map(glob::Pattern::new)
+            .transpose()
+            .map_err(|e| SemanticSearchError::InvalidArgument(format!("Invalid context name pattern: {}", e)))?;
+
+        Ok(Self {
+            path,
+            file_type: filter
+                .file_type
+                .as_ref()
+                .map(|file_type| file_type.trim_start_matches('.').to_string()),
+            context_name,
+            filters_results: filter.filters_results(),
+        })
+    }
+
+    fn matches_context(&self, context: &KnowledgeContext) -> bool {
+        self.context_name
+            .as_ref()
+            .is_none_or(|pattern| pattern.matches(&context.name))
+    }
+
+    fn matches_result(&self, result: &SearchResult) -> bool {
+        let path = result.path().map(Path::new);
+
+        if let Some(filter) = &self.path {
+            if !path.is_some_and(|path| filter.should_include(path)) {
+                return false;
+            }
+        }
+
+        if let Some(file_type) = &self.file_type {
+            let payload_type = result.point.payload.get("file_type").and_then(|v| v.as_str());
+            let extension = path.and_then(|path| path.extension()).and_then(|ext| ext.to_str());
+            if !payload_type
+                .into_iter()
+                .chain(extension)
+                .any(|candidate| candidate.eq_ignore_ascii_case(file_type))
+            {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Number of results to fetch so that `limit` remain after filtering
+    fn candidate_limit(&self, limit: usize) -> usize {
+        if self.filters_results {
+            limit * FILTER_CANDIDATE_FACTOR
+        } else {
+            limit
+        }
+    }
+
+    fn apply(&self, results: SearchResults, limit: usize) -> Option<SearchResults> {
+        let results: SearchResults = results
+            .into_iter()
+            .filter(|result| self.matches_result(result))
+            .take(limit)
+            .collect();
+        if results.is_empty() { None } else { Some(results) }
+    }
+}
 
 #[derive(Clone)]
 /// Context manager for handling contexts
@@ -77,18 +167,20 @@ impl ContextManager {
         &self,
         query_text: &str,
         effective_limit: usize,
+        filter: &SearchFilter,
+        hybrid: &HybridSearchConfig,
         embedder: &dyn TextEmbedderTrait,
     ) -> Result<Vec<(ContextId, SearchResults)>> {
+        let filter = ResultFilter::new(filter)?;
         let mut all_results = Vec::new();
         let contexts_metadata = self.contexts.read().await;
 
         for (context_id, context_meta) in contexts_metadata.iter() {
-            if context_meta.embedding_type.is_bm25() {
-                if let Some(results) = self.search_bm25_context(context_id, query_text, effective_limit).await {
-                    all_results.push((context_id.clone(), results));
-                }
-            } else if let Some(results) = self
-                .search_semantic_context(context_id, query_text, effective_limit, embedder)
+            if !filter.matches_context(context_meta) {
+                continue;
+            }
+            if let Some(results) = self
+                .search_filtered_context(context_meta, query_text, effective_limit, &filter, hybrid, embedder)
                 .await?
             {
                 all_results.push((context_id.clone(), results));
@@ -113,19 +205,98 @@ impl ContextManager {
         context_id: &str,
         query_text: &str,
         effective_limit: usize,
+        filter: &SearchFilter,
+        hybrid: &HybridSearchConfig,
         embedder: &dyn TextEmbedderTrait,
     ) -> Result<Option<SearchResults>> {
+        let filter = ResultFilter::new(filter)?;
         let contexts_metadata = self.contexts.read().await;
         let context_meta = contexts_metadata
             .get(context_id)
             .ok_or_else(|| SemanticSearchError::ContextNotFound(context_id.to_string()))?;
 
-        if context_meta.embedding_type.is_bm25() {
-            Ok(self.search_bm25_context(context_id, query_text, effective_limit).await)
+        if !filter.matches_context(context_meta) {
+            return Ok(None);
+        }
+        self.search_filtered_context(context_meta, query_text, effective_limit, &filter, hybrid, embedder)
+            .await
+    }
+
+    async fn search_filtered_context(
+        &self,
+        context_meta: &KnowledgeContext,
+        query_text: &str,
+        limit: usize,
+        filter: &ResultFilter,
+        hybrid: &HybridSearchConfig,
+        embedder: &dyn TextEmbedderTrait,
+    ) -> Result<Option<SearchResults>> {
+        let context_id = context_meta.id.as_str();
+        let candidates = filter.candidate_limit(limit);
+
+        let results = if context_meta.embedding_type.is_hybrid() {
+            self.search_hybrid_context(context_id, query_text, candidates, hybrid, embedder)
+                .await?
+        } else if context_meta.embedding_type.is_bm25() {
+            self.search_bm25_context(context_id, query_text, candidates).await
         } else {
-            self.search_semantic_context(context_id, query_text, effective_limit, embedder)
-                .await
+            self.search_semantic_context(context_id, query_text, candidates, embedder)
+                .await?
+        };
+
+        Ok(results.and_then(|results| filter.apply(results, limit)))
+    }
+
+    /// Search both indexes of a hybrid context and merge the rankings with reciprocal-rank fusion
+    ///
+    /// The fused score is turned into a distance between 0 and 1 so that hybrid results sort the
+    /// same way as semantic ones, with 0 for a document ranked first by both indexes.
+    async fn search_hybrid_context(
+        &self,
+        context_id: &str,
+        query_text: &str,
+        limit: usize,
+        hybrid: &HybridSearchConfig,
+        embedder: &dyn TextEmbedderTrait,
+    ) -> Result<Option<SearchResults>> {
+        let candidates = limit * HYBRID_CANDIDATE_FACTOR;
+        let semantic = self
+            .search_semantic_context(context_id, query_text, candidates, embedder)
+            .await?
+            .unwrap_or_default();
+        let bm25 = self
+            .search_bm25_context(context_id, query_text, candidates)
+            .await
+            .unwrap_or_default();
+
+        let semantic_ids: Vec<usize> = semantic.iter().map(|result| result.point.id).collect();
+        let bm25_ids: Vec<usize> = bm25.iter().map(|result| result.point.id).collect();
+        let fused = reciprocal_rank_fusion(
+            &[(&semantic_ids, hybrid.semantic_weight), (&bm25_ids, hybrid.bm25_weight)],
+            hybrid.rrf_k,
+        );
+
+        // Prefer the semantic copy of a data point, BM25 results carry no vector
+        let mut points: HashMap<usize, DataPoint> = HashMap::new();
+        for result in semantic.into_iter().chain(bm25) {
+            points.entry(result.point.id).or_insert(result.point);
         }
+
+        let best_score = (hybrid.semantic_weight + hybrid.bm25_weight) / (hybrid.rrf_k + 1.0);
+        let results: SearchResults = fused
+            .into_iter()
+            .take(limit)
+            .filter_map(|(id, score)| {
+                let distance = if best_score > 0.0 {
+                    1.0 - score / best_score
+                } else {
+                    1.0
+                };
+                points.remove(&id).map(|point| SearchResult::new(point, distance))
+            })
+            .collect();
+
+        Ok(if results.is_empty() { None } else { Some(results) })
     }
 
     async fn search_bm25_context(&self, context_id: &str, query_text: &str, limit: usize) -> Option<SearchResults> {
@@ -139,7 +310,7 @@ impl ContextManager {
         let results: Vec<SearchResult> = search_results
             .into_iter()
             .filter_map(|(id, score)| {
-                context.get_data_points().get(id).map(|data_point| {
+                context.get_data_point(id).map(|data_point| {
                     let vector = vec![0.0; 384];
                     let point = DataPoint {
                         id: data_point.id,
@@ -261,11 +432,13 @@ impl ContextManager {
             return Ok(());
         }
 
-        if embedding_type.is_bm25() {
-            self.load_bm25_context(context_id, &context_dir).await
-        } else {
-            self.load_semantic_context(context_id, &context_dir).await
+        if embedding_type.has_bm25_index() {
+            self.load_bm25_context(context_id, &context_dir).await?;
         }
+        if embedding_type.has_vector_index() {
+            self.load_semantic_context(context_id, &context_dir).await?;
+        }
+        Ok(())
     }
 
     async fn get_context_embedding_type(&self, context_id: &str) -> Option<EmbeddingType> {
@@ -324,6 +497,11 @@ impl ContextManager {
             volatile_contexts.clear();
         }
 
+        {
+            let mut bm25_contexts = self.bm25_contexts.write().await;
+            bm25_contexts.clear();
+        }
+
         if base_dir.exists() {
             std::fs::remove_dir_all(base_dir).map_err(SemanticSearchError::IoError)?;
             std::fs::create_dir_all(base_dir).map_err(SemanticSearchError::IoError)?;
@@ -344,6 +522,11 @@ impl ContextManager {
             volatile_contexts.remove(context_id);
         }
 
+        {
+            let mut bm25_contexts = self.bm25_contexts.write().await;
+            bm25_contexts.remove(context_id);
+        }
+
         let context_dir = base_dir.join(context_id);
         if context_dir.exists() {
             tokio::fs::remove_dir_all(&context_dir).await.map_err(|e| {
@@ -429,3 +612,116 @@ impl ContextManager {
         &self.bm25_contexts
     }
 }
+
+#[cfg(test)]
+#[cfg(not(all(target_os = "linux", target_arch = "aarch64")))]
+mod tests {
+    use std::collections::HashSet;
+
+    use tokio_util::sync::CancellationToken;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::client::context::ContextCreator;
+    use crate::client::operation::OperationManager;
+    use crate::embedding::MockTextEmbedder;
+
+    fn item(text: &str, path: &str) -> serde_json::Value {
+        serde_json::json!({ "text": text, "path": path })
+    }
+
+    async fn search(manager: &ContextManager, context_id: &str, query: &str) -> SearchResults {
+        manager
+            .search_context(
+                context_id,
+                query,
+                10,
+                &SearchFilter::default(),
+                &HybridSearchConfig::default(),
+                &MockTextEmbedder::new(384),
+            )
+            .await
+            .unwrap()
+            .unwrap_or_default()
+    }
+
+    fn ids(results: &SearchResults) -> HashSet<usize> {
+        results.iter().map(|result| result.point.id).collect()
+    }
+
+    #[tokio::test]
+    async fn test_hybrid_context_update() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = ContextManager::new(temp_dir.path()).await.unwrap();
+        let creator = ContextCreator::new();
+        let operation_manager = OperationManager::new();
+        let embedder = MockTextEmbedder::new(384);
+        let context_id = "hybrid";
+        let context_dir = temp_dir.path().join(context_id);
+        std::fs::create_dir_all(&context_dir).unwrap();
+
+        let items = [
+            item("alpha apples", "/a.txt"),
+            item("bravo bananas", "/b.txt"),
+            item("charlie cherries", "/c.txt"),
+        ];
+        creator
+            .create_context(
+                &context_dir,
+                &items,
+                EmbeddingType::Hybrid,
+                Uuid::new_v4(),
+                &CancellationToken::new(),
+                &operation_manager,
+                &embedder,
+                &manager,
+            )
+            .await
+            .unwrap();
+        manager.get_contexts_ref().write().await.insert(
+            context_id.to_string(),
+            KnowledgeContext::new(
+                context_id.to_string(),
+                "hybrid",
+                "",
+                false,
+                None,
+                (Vec::new(), Vec::new()),
+                items.len(),
+                EmbeddingType::Hybrid,
+            ),
+        );
+
+        // A chunk ranked first by both indexes comes first, with a distance of 0
+        let results = search(&manager, context_id, "bravo bananas").await;
+        assert_eq!(results[0].point.id, 1);
+        assert!(results[0].distance.abs() < 1e-6);
+        assert!(results.windows(2).all(|pair| pair[0].distance <= pair[1].distance));
+
+        let first_id = creator
+            .update_context(
+                context_id,
+                &[item("bravo blueberries", "/b.txt")],
+                Some(HashSet::from([1])),
+                EmbeddingType::Hybrid,
+                Uuid::new_v4(),
+                &CancellationToken::new(),
+                &operation_manager,
+                &embedder,
+                &manager,
+            )
+            .await
+            .unwrap();
+        assert_eq!(first_id, 3);
+
+        let results = search(&manager, context_id, "bravo blueberries").await;
+        assert_eq!(results[0].point.id, 3);
+        assert_eq!(results[0].path(), Some("/b.txt"));
+        assert!(results[0].distance.abs() < 1e-6);
+
+        // The removed chunk is gone from both indexes
+        let results = search(&manager, context_id, "bravo bananas").await;
+        assert!(!ids(&results).contains(&1));
+        assert_eq!(ids(&results), HashSet::from([0, 2, 3]));
+    }
+}
@@ -0,0 +1,296 @@
+use std::collections::{
+    BTreeMap,
+    HashSet,
+};
+use std::fs::File;
+use std::io::Read;
+use std::path::{
+    Path,
+    PathBuf,
+};
+use std::time::UNIX_EPOCH;
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use sha2::{
+    Digest,
+    Sha256,
+};
+
+use crate::client::utils;
+use crate::error::Result;
+
+/// Name of the manifest file in a context directory
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Per-file record of what was indexed into a context
+///
+/// The manifest lets an update re-index only the files that were added or changed since the
+/// context was last indexed, and drop the chunks of files that were removed.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ContextManifest {
+    /// Indexed files keyed by path
+    pub files: BTreeMap<String, ManifestEntry>,
+}
+
+/// Manifest record for a single file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// Modification time in milliseconds since the Unix epoch
+    pub mtime: u64,
+    /// Hex encoded SHA-256 of the file content
+    pub hash: String,
+    /// IDs of the data points created from the file
+    pub chunk_ids: Vec<usize>,
+}
+
+/// Modification time and content hash of a file on disk
+#[derive(Debug, Clone)]
+pub struct FileState {
+    /// Path of the file
+    pub path: PathBuf,
+    /// Modification time in milliseconds since the Unix epoch
+    pub mtime: u64,
+    /// Hex encoded SHA-256 of the file content
+    pub hash: String,
+}
+
+/// Files that differ between a manifest and the directory it was built from
+#[derive(Debug, Default)]
+pub struct ManifestDiff {
+    /// Files that are new or whose content changed
+    pub changed: Vec<FileState>,
+    /// Manifest keys of files that no longer exist or no longer match the patterns
+    pub removed: Vec<String>,
+    /// Number of files whose content is unchanged
+    pub unchanged: usize,
+}
+
+impl ManifestDiff {
+    /// Whether the directory matches the manifest
+    pub fn is_empty(&self) -> bool {
+        self.changed.is_empty() && self.removed.is_empty()
+    }
+}
+
+impl FileState {
+    /// Read the modification time and content hash of a file
+    pub fn read(path: &Path) -> std::io::Result<Self> {
+        Ok(Self {
+            path: path.to_path_buf(),
+            mtime: file_mtime(path)?,
+            hash: hash_file(path)?,
+        })
+    }
+}
+
+impl ContextManifest {
+    /// Load the manifest of a context
+    ///
+    /// Returns `None` if the context was indexed before manifests existed.
+    pub fn load(context_dir: &Path) -> Result<Option<Self>> {
+        let path = context_dir.join(MANIFEST_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let json = std::fs::read_to_string(path)?;
+        Ok(Some(serde_json::from_str(&json)?))
+    }
+
+    /// Save the manifest into a context directory
+    pub fn save(&self, context_dir: &Path) -> Result<()> {
+        utils::save_json_to_file(&context_dir.join(MANIFEST_FILE), self)
+    }
+
+    /// Record indexed files and the data points created from them
+    ///
+    /// `items` are the processed chunks of `files`, in the order their data points were created,
+    /// starting with ID `first_id`. Chunks are matched to files through their `path` field.
+    pub fn record(&mut self, files: &[FileState], items: &[serde_json::Value], first_id: usize) {
+        for file in files {
+            self.files.insert(key(&file.path), ManifestEntry {
+                mtime: file.mtime,
+                hash: file.hash.clone(),
+                chunk_ids: Vec::new(),
+            });
+        }
+
+        for (i, item) in items.iter().enumerate() {
+            let Some(path) = item.get("path").and_then(|path| path.as_str()) else {
+                continue;
+            };
+            if let Some(entry) = self.files.get_mut(path) {
+                entry.chunk_ids.push(first_id + i);
+            }
+        }
+    }
+
+    /// Find the files that were added or changed since they were recorded
+    ///
+    /// A file is only hashed when its modification time differs from the manifest. Files that
+    /// were touched without changing their content get their recorded modification time updated.
+    /// The `removed` list of the returned diff is left empty, see [`Self::removed_files`].
+    pub fn diff_files(&mut self, files: &[PathBuf]) -> ManifestDiff {
+        let mut diff = ManifestDiff::default();
+
+        for path in files {
+            let Ok(mtime) = file_mtime(path) else {
+                continue;
+            };
+
+            match self.files.get_mut(&key(path)) {
+                Some(entry) if entry.mtime == mtime => diff.unchanged += 1,
+                entry => {
+                    let Ok(hash) = hash_file(path) else {
+                        continue;
+                    };
+                    match entry {
+                        Some(entry) if entry.hash == hash => {
+                            entry.mtime = mtime;
+                            diff.unchanged += 1;
+                        },
+                        _ => diff.changed.push(FileState {
+                            path: path.clone(),
+                            mtime,
+                            hash,
+                        }),
+                    }
+                },
+            }
+        }
+
+        diff
+    }
+
+    /// Manifest keys of the recorded files missing from `files`
+    pub fn removed_files(&self, files: &[PathBuf]) -> Vec<String> {
+        let current: HashSet<String> = files.iter().map(|path| key(path)).collect();
+        self.files
+            .keys()
+            .filter(|key| !current.contains(*key))
+            .cloned()
+            .collect()
+    }
+
+    /// IDs of the data points that become stale when applying `diff`
+    pub fn stale_chunk_ids(&self, diff: &ManifestDiff) -> HashSet<usize> {
+        diff.changed
+            .iter()
+            .map(|file| key(&file.path))
+            .chain(diff.removed.iter().cloned())
+            .filter_map(|key| self.files.get(&key))
+            .flat_map(|entry| entry.chunk_ids.iter().copied())
+            .collect()
+    }
+}
+
+/// Manifest key of a file, matching the `path` recorded in the payload of its chunks
+fn key(path: &Path) -> String {
+    path.to_string_lossy().to_string()
+}
+
+fn file_mtime(path: &Path) -> std::io::Result<u64> {
+    let modified = std::fs::metadata(path)?.modified()?;
+    Ok(modified
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_millis() as u64))
+}
+
+fn hash_file(path: &Path) -> std::io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0; 8192];
+
+    loop {
+        let bytes_read = file.read(&mut buffer)?;
+        if bytes_read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..bytes_read]);
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn write(dir: &TempDir, name: &str, content: &str) -> PathBuf {
+        let path = dir.path().join(name);
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn chunk(path: &Path) -> serde_json::Value {
+        json!({ "text": "chunk", "path": key(path) })
+    }
+
+    #[test]
+    fn test_record_assigns_chunk_ids() {
+        let dir = TempDir::new().unwrap();
+        let a = write(&dir, "a.txt", "a");
+        let b = write(&dir, "b.txt", "b");
+        let empty = write(&dir, "empty.bin", "");
+
+        let files = [&a, &b, &empty].map(|path| FileState::read(path).unwrap());
+        let mut manifest = ContextManifest::default();
+        manifest.record(&files, &[chunk(&a), chunk(&b), chunk(&a)], 10);
+
+        assert_eq!(manifest.files[&key(&a)].chunk_ids, vec![10, 12]);
+        assert_eq!(manifest.files[&key(&b)].chunk_ids, vec![11]);
+        assert!(manifest.files[&key(&empty)].chunk_ids.is_empty());
+    }
+
+    #[test]
+    fn test_diff() {
+        let dir = TempDir::new().unwrap();
+        let kept = write(&dir, "kept.txt", "kept");
+        let touched = write(&dir, "touched.txt", "touched");
+        let edited = write(&dir, "edited.txt", "edited");
+        let removed = write(&dir, "removed.txt", "removed");
+
+        let files = [&kept, &touched, &edited, &removed].map(|path| FileState::read(path).unwrap());
+        let mut manifest = ContextManifest::default();
+        manifest.record(
+            &files,
+            &[chunk(&kept), chunk(&touched), chunk(&edited), chunk(&removed)],
+            0,
+        );
+        manifest.files.get_mut(&key(&touched)).unwrap().mtime = 0;
+        manifest.files.get_mut(&key(&edited)).unwrap().mtime = 0;
+
+        std::fs::write(&edited, "edited again").unwrap();
+        std::fs::remove_file(&removed).unwrap();
+        let added = write(&dir, "added.txt", "added");
+
+        let files = [kept, touched.clone(), edited.clone(), added.clone()];
+        let mut diff = manifest.diff_files(&files);
+        diff.removed = manifest.removed_files(&files);
+        let changed: Vec<_> = diff.changed.iter().map(|file| file.path.clone()).collect();
+        assert_eq!(changed, vec![edited, added]);
+        assert_eq!(diff.removed, vec![key(&removed)]);
+        assert_eq!(diff.unchanged, 2);
+        assert_ne!(manifest.files[&key(&touched)].mtime, 0);
+        assert_eq!(manifest.stale_chunk_ids(&diff), HashSet::from([2, 3]));
+    }
+
+    #[test]
+    fn test_load_missing_manifest() {
+        let dir = TempDir::new().unwrap();
+        assert!(ContextManifest::load(dir.path()).unwrap().is_none());
+
+        let mut manifest = ContextManifest::default();
+        manifest.record(&[FileState::read(&write(&dir, "a.txt", "a")).unwrap()], &[], 0);
+        manifest.save(dir.path()).unwrap();
+        assert_eq!(
+            ContextManifest::load(dir.path()).unwrap().unwrap().files,
+            manifest.files
+        );
+    }
+}
@@ -4,10 +4,13 @@ pub mod bm25_context;
 pub mod context_creator;
 /// Context management
 pub mod context_manager;
+/// Per-file manifests for incremental re-indexing
+pub mod manifest;
 /// Semantic context implementation
 pub mod semantic_context;
 
 pub use bm25_context::BM25Context;
 pub use context_creator::ContextCreator;
 pub use context_manager::ContextManager;
+pub use manifest::ContextManifest;
 pub use semantic_context::SemanticContext;
@@ -1,3 +1,7 @@
+use std::collections::{
+    HashMap,
+    HashSet,
+};
 use std::fs::{
     self,
     File,
@@ -19,10 +23,15 @@ use crate::types::{
 pub struct SemanticContext {
     /// The data points stored in the index
     pub(crate) data_points: Vec<DataPoint>,
+    /// Position of each data point in `data_points`, keyed by data point ID
+    positions: HashMap<usize, usize>,
     /// The vector index for fast approximate nearest neighbor search
     index: Option<VectorIndex>,
     /// Path to save/load the data points
     data_path: PathBuf,
+    /// ID to use for the next data point, never lowered by removals since removed IDs stay in the
+    /// vector index until it is rebuilt
+    next_id: usize,
 }
 
 impl SemanticContext {
@@ -36,8 +45,10 @@ impl SemanticContext {
         // Create a new instance
         let mut context = Self {
             data_points: Vec::new(),
+            positions: HashMap::new(),
             index: None,
             data_path: data_path.clone(),
+            next_id: 0,
         };
 
         // Load data points if the file exists
@@ -45,6 +56,8 @@ impl SemanticContext {
             let file = File::open(&data_path)?;
             let reader = BufReader::new(file);
             context.data_points = serde_json::from_reader(reader)?;
+            context.update_positions();
+            context.update_next_id();
         }
 
         // If we have data points, rebuild the index
@@ -71,8 +84,8 @@ impl SemanticContext {
         let index = VectorIndex::new(self.data_points.len().max(100));
 
         // Add all data points to the index
-        for (i, point) in self.data_points.iter().enumerate() {
-            index.insert(&point.vector, i);
+        for point in &self.data_points {
+            index.insert(&point.vector, point.id);
         }
 
         // Set the new index
@@ -94,6 +107,8 @@ impl SemanticContext {
         let start_idx = self.data_points.len();
         self.data_points.extend(data_points);
         let end_idx = self.data_points.len();
+        self.update_positions();
+        self.update_next_id();
 
         // Update the index
         self.update_index_by_range(start_idx, end_idx)?;
@@ -115,12 +130,57 @@ impl SemanticContext {
 
         // Add only the points in the specified range to the index
         for i in start_idx..end_idx {
-            index.insert(&self.data_points[i].vector, i);
+            index.insert(&self.data_points[i].vector, self.data_points[i].id);
         }
 
         Ok(())
     }
 
+    /// Remove the data points with the given IDs from the context
+    ///
+    /// The index is rebuilt once it holds more removed vectors than live ones.
+    pub fn remove_data_points(&mut self, ids: &HashSet<usize>) -> Result<usize> {
+        let (removed, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut self.data_points)
+            .into_iter()
+            .partition(|point| ids.contains(&point.id));
+        self.data_points = kept;
+
+        if removed.is_empty() {
+            return Ok(0);
+        }
+
+        self.update_positions();
+        if let Some(index) = &self.index {
+            for point in &removed {
+                index.remove(point.id);
+            }
+            if index.deleted_count() > self.data_points.len() {
+                self.rebuild_index()?;
+            }
+        }
+
+        Ok(removed.len())
+    }
+
+    /// ID to use for the next data point added to the context
+    pub fn next_id(&self) -> usize {
+        self.next_id
+    }
+
+    fn update_next_id(&mut self) {
+        let max_id = self.data_points.iter().map(|point| point.id + 1).max().unwrap_or(0);
+        self.next_id = self.next_id.max(max_id);
+    }
+
+    fn update_positions(&mut self) {
+        self.positions = self
+            .data_points
+            .iter()
+            .enumerate()
+            .map(|(position, point)| (point.id, position))
+            .collect();
+    }
+
     /// Search for similar items to the given vector
     pub fn search(&self, query_vector: &[f32], limit: usize) -> Result<Vec<SearchResult>> {
         let index = match &self.index {
@@ -134,9 +194,9 @@ impl SemanticContext {
         // Convert the results to our SearchResult type
         let search_results = results
             .into_iter()
-            .map(|(id, distance)| {
-                let point = self.data_points[id].clone();
-                SearchResult::new(point, distance)
+            .filter_map(|(id, distance)| {
+                let point = self.data_points[*self.positions.get(&id)?].clone();
+                Some(SearchResult::new(point, distance))
             })
             .collect();
 
@@ -23,7 +23,9 @@ pub fn create_embedder(embedding_type: EmbeddingType) -> Result<Box<dyn TextEmbe
     let embedder: Box<dyn TextEmbedderTrait> = match embedding_type {
         EmbeddingType::Fast => Box::new(MockTextEmbedder::new(384)), // BM25 doesn't use embeddings
         #[cfg(not(all(target_os = "linux", target_arch = "aarch64")))]
-        EmbeddingType::Best => Box::new(CandleTextEmbedder::with_model_type(ModelType::MiniLML6V2)?),
+        EmbeddingType::Best | EmbeddingType::Hybrid => {
+            Box::new(CandleTextEmbedder::with_model_type(ModelType::MiniLML6V2)?)
+        },
         #[cfg(test)]
         EmbeddingType::Mock => Box::new(MockTextEmbedder::new(384)),
     };
@@ -46,7 +48,9 @@ pub fn create_embedder(embedding_type: EmbeddingType) -> Result<Box<dyn TextEmbe
     let embedder: Box<dyn TextEmbedderTrait> = match embedding_type {
         EmbeddingType::Fast => Box::new(MockTextEmbedder::new(384)), // BM25 doesn't use embeddings
         #[cfg(not(target_arch = "aarch64"))]
-        EmbeddingType::Best => Box::new(CandleTextEmbedder::with_model_type(ModelType::MiniLML6V2)?),
+        EmbeddingType::Best | EmbeddingType::Hybrid => {
+            Box::new(CandleTextEmbedder::with_model_type(ModelType::MiniLML6V2)?)
+        },
         #[cfg(test)]
         EmbeddingType::Mock => Box::new(MockTextEmbedder::new(384)),
     };
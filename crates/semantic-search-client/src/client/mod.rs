@@ -11,6 +11,8 @@ pub mod context;
 pub mod model;
 /// Operation management modules
 pub mod operation;
+/// File watching of contexts that are kept up to date
+pub mod watcher;
 
 /// Embedder factory utilities
 pub mod embedder_factory;
@@ -24,6 +26,7 @@ pub mod hosted_model_client;
 pub use async_implementation::AsyncSemanticSearchClient;
 pub use context::{
     BM25Context,
+    ContextManifest,
     SemanticContext,
 };
 #[cfg(not(all(target_os = "linux", target_arch = "aarch64")))]
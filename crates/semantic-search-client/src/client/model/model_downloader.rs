@@ -14,7 +14,7 @@ impl ModelDownloader {
     pub async fn ensure_models_downloaded(embedding_type: &EmbeddingType) -> Result<()> {
         match embedding_type {
             #[cfg(not(all(target_os = "linux", target_arch = "aarch64")))]
-            EmbeddingType::Best => {
+            EmbeddingType::Best | EmbeddingType::Hybrid => {
                 Self::download_best_model().await?;
             },
             EmbeddingType::Fast => {
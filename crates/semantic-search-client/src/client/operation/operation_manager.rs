@@ -8,6 +8,7 @@ use std::time::{
 use tokio::sync::{
     Mutex,
     RwLock,
+    watch,
 };
 use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
@@ -47,22 +48,27 @@ impl OperationManager {
     }
 
     /// Register operation
-    pub async fn register_operation(
+    ///
+    /// Returns a receiver of the state of the operation.
+    pub(crate) async fn register_operation(
         &self,
         operation_id: Uuid,
         operation_type: OperationType,
         cancel_token: CancellationToken,
-    ) {
+    ) -> watch::Receiver<OperationState> {
+        let (state, state_rx) = watch::channel(OperationState::Running);
         let handle = OperationHandle {
             operation_type,
             started_at: SystemTime::now(),
             progress: Arc::new(Mutex::new(ProgressInfo::new())),
             cancel_token,
             task_handle: None,
+            state,
         };
 
         let mut operations = self.active_operations.write().await;
         operations.insert(operation_id, handle);
+        state_rx
     }
 
     /// Cancel operation
@@ -71,6 +77,7 @@ impl OperationManager {
 
         if let Some(handle) = operations.get_mut(&operation_id) {
             handle.cancel_token.cancel();
+            handle.state.send_replace(OperationState::Cancelled);
 
             if let Some(task_handle) = &handle.task_handle {
                 task_handle.abort();
@@ -128,6 +135,7 @@ impl OperationManager {
 
         for handle in operations.values_mut() {
             handle.cancel_token.cancel();
+            handle.state.send_replace(OperationState::Cancelled);
 
             if let Some(task_handle) = &handle.task_handle {
                 task_handle.abort();
@@ -0,0 +1,519 @@
+use std::collections::{
+    HashMap,
+    HashSet,
+};
+use std::path::{
+    Path,
+    PathBuf,
+};
+use std::sync::{
+    Arc,
+    Mutex,
+};
+use std::time::{
+    Duration,
+    Instant,
+};
+
+use chrono::{
+    DateTime,
+    Utc,
+};
+use notify::{
+    EventKind,
+    RecommendedWatcher,
+    RecursiveMode,
+    Watcher,
+};
+use tokio::sync::{
+    mpsc,
+    watch,
+};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use tracing::{
+    debug,
+    warn,
+};
+use uuid::Uuid;
+
+use super::context::ContextManager;
+use super::operation::OperationManager;
+use crate::config::WatchConfig;
+use crate::error::{
+    Result,
+    SemanticSearchError,
+};
+use crate::pattern_filter::PatternFilter;
+use crate::types::{
+    ContextId,
+    IndexingJob,
+    KnowledgeContext,
+    OperationState,
+    OperationType,
+    WatchStatus,
+};
+
+/// Update queued on the background worker
+pub(crate) struct QueuedUpdate {
+    pub(crate) id: Uuid,
+    pub(crate) cancel: CancellationToken,
+    pub(crate) state: watch::Receiver<OperationState>,
+}
+
+/// Queues incremental updates of contexts on the background worker
+#[derive(Clone)]
+pub(crate) struct UpdateQueue {
+    pub(crate) context_manager: ContextManager,
+    pub(crate) operation_manager: OperationManager,
+    pub(crate) job_tx: mpsc::UnboundedSender<IndexingJob>,
+}
+
+impl UpdateQueue {
+    /// Queue an update of a context, unless one is already running for its source path
+    pub(crate) async fn queue(&self, context_id: &str) -> Result<QueuedUpdate> {
+        let context = self
+            .context_manager
+            .get_contexts_ref()
+            .read()
+            .await
+            .get(context_id)
+            .cloned()
+            .ok_or_else(|| SemanticSearchError::ContextNotFound(context_id.to_string()))?;
+        let source_path = context.source_path.clone().ok_or_else(|| {
+            SemanticSearchError::InvalidArgument(format!("Context '{}' was not created from a path", context.name))
+        })?;
+
+        for handle in self.operation_manager.get_active_operations().read().await.values() {
+            if let OperationType::Updating { path, .. } = &handle.operation_type {
+                if *path == source_path && *handle.state.borrow() == OperationState::Running {
+                    return Err(SemanticSearchError::InvalidArgument(format!(
+                        "Already updating this path: {}",
+                        path
+                    )));
+                }
+            }
+        }
+
+        let operation_id = Uuid::new_v4();
+        let cancel_token = CancellationToken::new();
+
+        let state = self
+            .operation_manager
+            .register_operation(
+                operation_id,
+                OperationType::Updating {
+                    name: context.name.clone(),
+                    path: source_path,
+                },
+                cancel_token.clone(),
+            )
+            .await;
+
+        let job = IndexingJob::Update {
+            id: operation_id,
+            cancel: cancel_token.clone(),
+            context_id: context_id.to_string(),
+        };
+
+        self.job_tx
+            .send(job)
+            .map_err(|_send_error| SemanticSearchError::OperationFailed("Background worker unavailable".to_string()))?;
+
+        Ok(QueuedUpdate {
+            id: operation_id,
+            cancel: cancel_token,
+            state,
+        })
+    }
+}
+
+/// Files changed since a watched context was last updated
+#[derive(Default)]
+struct PendingChanges {
+    paths: HashSet<PathBuf>,
+    since: Option<DateTime<Utc>>,
+    /// Changes picked up by the running update, kept until it completes
+    updating: Option<(HashSet<PathBuf>, Option<DateTime<Utc>>)>,
+    /// Why the context stopped being watched
+    error: Option<String>,
+}
+
+impl PendingChanges {
+    fn record(&mut self, path: PathBuf) {
+        self.since.get_or_insert_with(Utc::now);
+        self.paths.insert(path);
+    }
+
+    /// Hand the pending changes to an update that was just queued
+    fn start_update(&mut self) {
+        let paths = std::mem::take(&mut self.paths);
+        self.updating = Some((paths, self.since.take()));
+    }
+
+    /// Drop the changes of the running update once it completed, or keep them pending otherwise
+    fn finish_update(&mut self, completed: bool) {
+        let Some((paths, since)) = self.updating.take() else {
+            return;
+        };
+        if !completed {
+            self.paths.extend(paths);
+            self.since = earliest(self.since, since);
+        }
+    }
+
+    fn pending_changes(&self) -> usize {
+        match &self.updating {
+            Some((paths, _)) => paths.union(&self.paths).count(),
+            None => self.paths.len(),
+        }
+    }
+
+    fn stale_since(&self) -> Option<DateTime<Utc>> {
+        earliest(self.since, self.updating.as_ref().and_then(|(_, since)| *since))
+    }
+}
+
+fn earliest(a: Option<DateTime<Utc>>, b: Option<DateTime<Utc>>) -> Option<DateTime<Utc>> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Describe a file watcher error, with a hint when the system ran out of watches
+fn watch_error_message(error: &notify::Error) -> String {
+    match error.kind {
+        notify::ErrorKind::MaxFilesWatch => "Too many files to watch. Raise the limit with `sudo sysctl \
+             fs.inotify.max_user_watches=524288`, or watch a smaller directory"
+            .to_string(),
+        _ => error.to_string(),
+    }
+}
+
+/// File watcher of a single context, stopped when dropped
+struct ContextWatch {
+    _watcher: RecommendedWatcher,
+    task: JoinHandle<()>,
+    changes: Arc<Mutex<PendingChanges>>,
+}
+
+impl Drop for ContextWatch {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Watches the source paths of contexts and re-indexes them once their files stop changing
+///
+/// Every change that would affect the context, after the hidden file and pattern rules used
+/// when indexing, is recorded as pending. Once no change happened for the debounce period an
+/// incremental update is queued on the background worker. While the session has been idle for
+/// longer than the configured pause, changes keep being recorded but updates wait until the
+/// session is active again.
+pub struct ContextWatcher {
+    updates: UpdateQueue,
+    config: WatchConfig,
+    base_dir: PathBuf,
+    idle_since: watch::Sender<Option<Instant>>,
+    watches: Mutex<HashMap<ContextId, ContextWatch>>,
+}
+
+impl ContextWatcher {
+    pub(crate) fn new(updates: UpdateQueue, config: WatchConfig, base_dir: &Path) -> Self {
+        Self {
+            updates,
+            config,
+            // Indexes are written below the base directory, which may itself be watched
+            base_dir: base_dir.canonicalize().unwrap_or_else(|_err| base_dir.to_path_buf()),
+            idle_since: watch::Sender::new(None),
+            watches: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Start watching the source path of a context
+    ///
+    /// The context is checked for changes once when the watch starts, so edits made while it was
+    /// not watched are picked up as well. Watching a context again replaces its watcher.
+    pub fn watch(&self, context: &KnowledgeContext) -> Result<()> {
+        let source_path = context.source_path.as_deref().map(PathBuf::from).ok_or_else(|| {
+            SemanticSearchError::InvalidArgument(format!("Context '{}' was not created from a path", context.name))
+        })?;
+        let pattern_filter = PatternFilter::new(&context.include_patterns, &context.exclude_patterns)
+            .map_err(SemanticSearchError::InvalidArgument)?;
+
+        let changes = Arc::new(Mutex::new(PendingChanges::default()));
+        let (signal_tx, signal_rx) = mpsc::unbounded_channel();
+
+        let base_dir = self.base_dir.clone();
+        let recorded = changes.clone();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+            // Reads, including the ones of the indexer itself, do not change the context
+            Ok(event) if matches!(event.kind, EventKind::Access(_)) => {},
+            Ok(event) => {
+                let mut paths = event
+                    .paths
+                    .into_iter()
+                    .filter(|path| is_indexed_path(path, &base_dir, &pattern_filter))
+                    .peekable();
+                if paths.peek().is_none() {
+                    return;
+                }
+                if let Ok(mut changes) = recorded.lock() {
+                    paths.for_each(|path| changes.record(path));
+                }
+                signal_tx.send(()).ok();
+            },
+            Err(e) => {
+                let message = watch_error_message(&e);
+                warn!("File watcher error: {}", message);
+                if matches!(e.kind, notify::ErrorKind::MaxFilesWatch) {
+                    if let Ok(mut changes) = recorded.lock() {
+                        changes.error = Some(message);
+                    }
+                }
+            },
+        })
+        .map_err(|e| SemanticSearchError::OperationFailed(format!("Failed to create file watcher: {}", e)))?;
+
+        let mode = if source_path.is_dir() {
+            RecursiveMode::Recursive
+        } else {
+            RecursiveMode::NonRecursive
+        };
+        watcher.watch(&source_path, mode).map_err(|e| {
+            SemanticSearchError::OperationFailed(format!(
+                "Failed to watch {}: {}",
+                source_path.display(),
+                watch_error_message(&e)
+            ))
+        })?;
+
+        let task = tokio::spawn(debounce_changes(
+            context.id.clone(),
+            signal_rx,
+            changes.clone(),
+            self.updates.clone(),
+            self.idle_since.subscribe(),
+            self.config,
+        ));
+
+        if let Ok(mut watches) = self.watches.lock() {
+            watches.insert(context.id.clone(), ContextWatch {
+                _watcher: watcher,
+                task,
+                changes,
+            });
+        }
+        debug!("Watching {} for context {}", source_path.display(), context.id);
+        Ok(())
+    }
+
+    /// Stop watching the source path of a context
+    pub fn unwatch(&self, context_id: &str) {
+        if let Ok(mut watches) = self.watches.lock() {
+            watches.remove(context_id);
+        }
+    }
+
+    /// Stop watching all contexts
+    pub fn unwatch_all(&self) {
+        if let Ok(mut watches) = self.watches.lock() {
+            watches.clear();
+        }
+    }
+
+    /// Mark the session as idle or active
+    ///
+    /// Updates are held back once the session has been idle for `idle_pause_secs`, and resume as
+    /// soon as it is marked active.
+    pub fn set_idle(&self, idle: bool) {
+        self.idle_since.send_if_modified(|since| match (idle, since.is_some()) {
+            (true, false) => {
+                *since = Some(Instant::now());
+                true
+            },
+            (false, true) => {
+                *since = None;
+                true
+            },
+            _ => false,
+        });
+    }
+
+    /// Freshness of a watched context, `None` if the context is not watched
+    pub fn status(&self, context_id: &str) -> Option<WatchStatus> {
+        let watches = self.watches.lock().ok()?;
+        let changes = watches.get(context_id)?.changes.lock().ok()?;
+        let idle_pause = Duration::from_secs(self.config.idle_pause_secs);
+
+        Some(WatchStatus {
+            paused: is_paused(*self.idle_since.borrow(), idle_pause),
+            pending_changes: changes.pending_changes(),
+            stale_since: changes.stale_since(),
+            updating: changes.updating.is_some(),
+            error: changes.error.clone(),
+        })
+    }
+}
+
+/// Whether a change to `path` may change what is indexed for a context
+fn is_indexed_path(path: &Path, base_dir: &Path, pattern_filter: &PatternFilter) -> bool {
+    !path.starts_with(base_dir)
+        && !path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|s| s.starts_with('.'))
+        && pattern_filter.should_include(path)
+}
+
+fn is_paused(idle_since: Option<Instant>, idle_pause: Duration) -> bool {
+    idle_since.is_some_and(|since| since.elapsed() >= idle_pause)
+}
+
+/// Queue an update of a context each time its files stop changing
+async fn debounce_changes(
+    context_id: ContextId,
+    mut signal_rx: mpsc::UnboundedReceiver<()>,
+    changes: Arc<Mutex<PendingChanges>>,
+    updates: UpdateQueue,
+    mut idle_since: watch::Receiver<Option<Instant>>,
+    config: WatchConfig,
+) {
+    let debounce = Duration::from_millis(config.debounce_ms);
+    let idle_pause = Duration::from_secs(config.idle_pause_secs);
+
+    // The first pass picks up changes made while the context was not watched
+    let mut changed = true;
+    loop {
+        if !changed && signal_rx.recv().await.is_none() {
+            return;
+        }
+        changed = false;
+
+        // Wait for the files to stop changing
+        loop {
+            match tokio::time::timeout(debounce, signal_rx.recv()).await {
+                Ok(Some(())) => continue,
+                Ok(None) => return,
+                Err(_elapsed) => {},
+            }
+
+            // Hold the update back while the session is idle
+            while is_paused(*idle_since.borrow_and_update(), idle_pause) {
+                if idle_since.changed().await.is_err() {
+                    return;
+                }
+            }
+            while signal_rx.try_recv().is_ok() {}
+
+            match updates.queue(&context_id).await {
+                Ok(mut update) => {
+                    debug!("Queued update {} of watched context {}", update.id, context_id);
+                    if let Ok(mut changes) = changes.lock() {
+                        changes.start_update();
+                    }
+                    // A dropped operation ended without completing
+                    let completed = update
+                        .state
+                        .wait_for(|state| *state != OperationState::Running)
+                        .await
+                        .is_ok_and(|state| *state == OperationState::Completed);
+                    // Changes of a failed update stay pending until the files change again
+                    if let Ok(mut changes) = changes.lock() {
+                        changes.finish_update(completed);
+                    }
+                    break;
+                },
+                Err(SemanticSearchError::InvalidArgument(e)) => {
+                    // An update is already running, try again after another quiet period
+                    debug!("Postponing update of watched context {}: {}", context_id, e);
+                },
+                Err(e) => {
+                    warn!("Stopped watching context {}: {}", context_id, e);
+                    if let Ok(mut changes) = changes.lock() {
+                        changes.error = Some(e.to_string());
+                    }
+                    return;
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::embedding::EmbeddingType;
+
+    #[tokio::test]
+    async fn test_queue_skips_running_updates_only() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let context_manager = ContextManager::new(temp_dir.path()).await.unwrap();
+        context_manager.get_contexts_ref().write().await.insert(
+            "docs".to_string(),
+            KnowledgeContext::new(
+                "docs".to_string(),
+                "docs",
+                "",
+                true,
+                Some("/docs".to_string()),
+                (Vec::new(), Vec::new()),
+                0,
+                EmbeddingType::Fast,
+            ),
+        );
+        let (job_tx, _job_rx) = mpsc::unbounded_channel();
+        let updates = UpdateQueue {
+            context_manager,
+            operation_manager: OperationManager::new(),
+            job_tx,
+        };
+
+        let first = updates.queue("docs").await.unwrap();
+        // Only the state of the running update matters, not its progress message
+        updates.operation_manager.get_active_operations().read().await[&first.id]
+            .progress
+            .lock()
+            .await
+            .message = "Retrying after failed embedding".to_string();
+        assert!(matches!(
+            updates.queue("docs").await,
+            Err(SemanticSearchError::InvalidArgument(_))
+        ));
+
+        // An update that ended no longer blocks a new one
+        updates.operation_manager.get_active_operations().read().await[&first.id]
+            .state
+            .send_replace(OperationState::Failed);
+        let second = updates.queue("docs").await.unwrap();
+        assert_eq!(*second.state.borrow(), OperationState::Running);
+    }
+
+    #[test]
+    fn test_pending_changes_kept_until_update_completes() {
+        let mut changes = PendingChanges::default();
+        changes.record(PathBuf::from("a.md"));
+        changes.record(PathBuf::from("b.md"));
+        let since = changes.since;
+
+        changes.start_update();
+        changes.record(PathBuf::from("a.md"));
+        assert!(changes.updating.is_some());
+        assert_eq!(changes.pending_changes(), 2);
+        assert_eq!(changes.stale_since(), since);
+
+        // A failed update leaves its changes pending
+        changes.finish_update(false);
+        assert!(changes.updating.is_none());
+        assert_eq!(changes.pending_changes(), 2);
+        assert_eq!(changes.stale_since(), since);
+
+        // A completed update only drops the changes it picked up
+        changes.start_update();
+        changes.record(PathBuf::from("c.md"));
+        changes.finish_update(true);
+        assert_eq!(changes.paths, HashSet::from([PathBuf::from("c.md")]));
+        assert!(changes.stale_since() > since);
+    }
+}
@@ -47,6 +47,56 @@ pub struct SemanticSearchConfig {
 
     /// Embedding engine type to use
     pub embedding_type: EmbeddingType,
+
+    /// Weights used to merge the rankings of hybrid contexts
+    #[serde(default)]
+    pub hybrid: HybridSearchConfig,
+
+    /// Timing of the file watchers that keep watched contexts up to date
+    #[serde(default)]
+    pub watch: WatchConfig,
+}
+
+/// Weights of the rankings merged by reciprocal-rank fusion in hybrid contexts
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct HybridSearchConfig {
+    /// Weight of the semantic ranking
+    pub semantic_weight: f32,
+
+    /// Weight of the BM25 ranking
+    pub bm25_weight: f32,
+
+    /// Rank offset of the fusion, higher values flatten the difference between ranks
+    pub rrf_k: f32,
+}
+
+impl Default for HybridSearchConfig {
+    fn default() -> Self {
+        Self {
+            semantic_weight: 1.0,
+            bm25_weight: 1.0,
+            rrf_k: 60.0,
+        }
+    }
+}
+
+/// Timing of the file watchers of watched contexts
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WatchConfig {
+    /// Quiet period in milliseconds after the last change before a context is updated
+    pub debounce_ms: u64,
+
+    /// Seconds the session must be idle before updates are held back until it is active again
+    pub idle_pause_secs: u64,
+}
+
+impl Default for WatchConfig {
+    fn default() -> Self {
+        Self {
+            debounce_ms: 2000,
+            idle_pause_secs: 300,
+        }
+    }
 }
 
 impl SemanticSearchConfig {
@@ -93,6 +143,8 @@ impl Default for SemanticSearchConfig {
             max_files: 10000, // Default limit of 10000 files
             hosted_models_base_url: "https://desktop-release.q.us-east-1.amazonaws.com/models".to_string(),
             embedding_type: EmbeddingType::default(),
+            hybrid: HybridSearchConfig::default(),
+            watch: WatchConfig::default(),
         }
     }
 }
@@ -341,6 +393,8 @@ mod tests {
             max_files: 10000,
             hosted_models_base_url: "http://test.example.com/models".to_string(),
             embedding_type: EmbeddingType::default(),
+            hybrid: HybridSearchConfig::default(),
+            watch: WatchConfig::default(),
         };
 
         // Update the config
@@ -13,6 +13,10 @@ pub enum EmbeddingType {
     /// Best embedding using all-MiniLM-L6-v2 (not available on Linux ARM)
     #[cfg(not(all(target_os = "linux", target_arch = "aarch64")))]
     Best,
+    /// Both a BM25 and an all-MiniLM-L6-v2 index, with results merged by reciprocal-rank fusion
+    /// (not available on Linux ARM)
+    #[cfg(not(all(target_os = "linux", target_arch = "aarch64")))]
+    Hybrid,
     /// Use Mock embedding engine (only available in tests)
     #[cfg(test)]
     Mock,
@@ -43,21 +47,40 @@ impl EmbeddingType {
     pub fn to_model_type(&self) -> Option<super::ModelType> {
         match self {
             Self::Fast => None, // BM25 doesn't use Candle models
-            Self::Best => Some(super::ModelType::MiniLML6V2),
+            Self::Best | Self::Hybrid => Some(super::ModelType::MiniLML6V2),
             #[cfg(test)]
             Self::Mock => None,
         }
     }
 
-    /// Check if this embedding type uses BM25
+    /// Check if this embedding type uses BM25 only
     pub fn is_bm25(&self) -> bool {
         matches!(self, Self::Fast)
     }
 
+    /// Check if this embedding type combines BM25 and semantic search
+    pub fn is_hybrid(&self) -> bool {
+        match self {
+            #[cfg(not(all(target_os = "linux", target_arch = "aarch64")))]
+            Self::Hybrid => true,
+            _ => false,
+        }
+    }
+
+    /// Check if contexts of this embedding type have a BM25 index
+    pub fn has_bm25_index(&self) -> bool {
+        self.is_bm25() || self.is_hybrid()
+    }
+
+    /// Check if contexts of this embedding type have a vector index
+    pub fn has_vector_index(&self) -> bool {
+        !self.is_bm25()
+    }
+
     /// Check if this embedding type uses Candle
     #[cfg(not(all(target_os = "linux", target_arch = "aarch64")))]
     pub fn is_candle(&self) -> bool {
-        matches!(self, Self::Best)
+        matches!(self, Self::Best | Self::Hybrid)
     }
 
     /// Get a human-readable description of the embedding type
@@ -66,6 +89,8 @@ impl EmbeddingType {
             Self::Fast => "Fast",
             #[cfg(not(all(target_os = "linux", target_arch = "aarch64")))]
             Self::Best => "Best",
+            #[cfg(not(all(target_os = "linux", target_arch = "aarch64")))]
+            Self::Hybrid => "Hybrid",
             #[cfg(test)]
             Self::Mock => "Mock",
         }
@@ -78,6 +103,8 @@ impl EmbeddingType {
             "fast" => Some(Self::Fast),
             #[cfg(not(all(target_os = "linux", target_arch = "aarch64")))]
             "best" => Some(Self::Best),
+            #[cfg(not(all(target_os = "linux", target_arch = "aarch64")))]
+            "hybrid" => Some(Self::Hybrid),
             #[cfg(test)]
             "mock" => Some(Self::Mock),
             _ => None,
@@ -90,6 +117,8 @@ impl EmbeddingType {
             Self::Fast => "Fast",
             #[cfg(not(all(target_os = "linux", target_arch = "aarch64")))]
             Self::Best => "Best",
+            #[cfg(not(all(target_os = "linux", target_arch = "aarch64")))]
+            Self::Hybrid => "Hybrid",
             #[cfg(test)]
             Self::Mock => "Mock",
         }
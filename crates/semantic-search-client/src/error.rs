@@ -25,6 +25,8 @@ pub enum SemanticSearchError {
     InvalidArgument(String),
     /// Embedding error
     EmbeddingError(String),
+    /// Text could not be extracted from a document
+    ExtractionError(String),
 }
 
 impl fmt::Display for SemanticSearchError {
@@ -38,6 +40,7 @@ impl fmt::Display for SemanticSearchError {
             SemanticSearchError::OperationFailed(msg) => write!(f, "Operation failed: {}", msg),
             SemanticSearchError::InvalidArgument(msg) => write!(f, "Invalid argument: {}", msg),
             SemanticSearchError::EmbeddingError(msg) => write!(f, "Embedding error: {}", msg),
+            SemanticSearchError::ExtractionError(msg) => write!(f, "Text extraction error: {}", msg),
         }
     }
 }
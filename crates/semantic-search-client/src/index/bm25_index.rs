@@ -141,10 +141,16 @@ impl BM25Index {
     }
 
     /// Remove a document from the index
-    pub fn remove_document(&self, id: usize) {
+    ///
+    /// Returns `true` if the document was in the index.
+    pub fn remove_document(&self, id: usize) -> bool {
         let mut engine = self.engine.write().unwrap();
+        if engine.get(&id).is_none() {
+            return false;
+        }
         engine.remove(&id);
         self.doc_count.fetch_sub(1, std::sync::atomic::Ordering::SeqCst);
+        true
     }
 
     /// Get the number of documents in the index
@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+
+/// Merge rankings of document IDs with weighted reciprocal-rank fusion
+///
+/// Each document scores `weight / (k + rank)` for every ranking it appears in, with ranks
+/// starting at 1. Only the rank of a document matters, not the score it had in its ranking, so
+/// rankings with incomparable scores such as BM25 and cosine distance can be merged.
+///
+/// # Arguments
+///
+/// * `rankings` - Document IDs ordered from best to worst, with the weight of the ranking
+/// * `k` - Rank offset, higher values flatten the difference between ranks
+///
+/// # Returns
+///
+/// A vector of (id, score) pairs ordered from highest to lowest score
+pub fn reciprocal_rank_fusion(rankings: &[(&[usize], f32)], k: f32) -> Vec<(usize, f32)> {
+    let mut scores: HashMap<usize, f32> = HashMap::new();
+    let mut first_seen = Vec::new();
+
+    for (ranking, weight) in rankings {
+        for (rank, id) in ranking.iter().enumerate() {
+            let score = scores.entry(*id).or_insert_with(|| {
+                first_seen.push(*id);
+                0.0
+            });
+            *score += weight / (k + rank as f32 + 1.0);
+        }
+    }
+
+    // Ties keep the order in which documents were first ranked
+    let mut fused: Vec<(usize, f32)> = first_seen.into_iter().map(|id| (id, scores[&id])).collect();
+    fused.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    fused
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_documents_in_both_rankings_win() {
+        let semantic = [1, 2, 3];
+        let bm25 = [4, 3, 5];
+        let fused = reciprocal_rank_fusion(&[(&semantic, 1.0), (&bm25, 1.0)], 60.0);
+
+        let ids: Vec<_> = fused.iter().map(|(id, _)| *id).collect();
+        assert_eq!(ids, vec![3, 1, 4, 2, 5]);
+        assert!((fused[0].1 - (1.0 / 63.0 + 1.0 / 62.0)).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_weights() {
+        let semantic = [1, 2];
+        let bm25 = [2, 1];
+
+        let fused = reciprocal_rank_fusion(&[(&semantic, 1.0), (&bm25, 2.0)], 60.0);
+        assert_eq!(fused[0].0, 2);
+
+        let fused = reciprocal_rank_fusion(&[(&semantic, 2.0), (&bm25, 1.0)], 60.0);
+        assert_eq!(fused[0].0, 1);
+
+        // A ranking with no weight does not change the order
+        let fused = reciprocal_rank_fusion(&[(&semantic, 1.0), (&bm25, 0.0)], 60.0);
+        assert_eq!(fused[0].0, 1);
+    }
+
+    #[test]
+    fn test_empty_rankings() {
+        assert!(reciprocal_rank_fusion(&[(&[], 1.0), (&[], 1.0)], 60.0).is_empty());
+    }
+}
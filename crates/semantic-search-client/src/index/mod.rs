@@ -1,5 +1,7 @@
 mod bm25_index;
+mod fusion;
 mod vector_index;
 
 pub use bm25_index::BM25Index;
+pub use fusion::reciprocal_rank_fusion;
 pub use vector_index::VectorIndex;
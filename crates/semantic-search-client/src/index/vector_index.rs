@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::sync::RwLock;
 
 use hnsw_rs::hnsw::Hnsw;
@@ -11,8 +12,11 @@ use tracing::{
 pub struct VectorIndex {
     /// The HNSW index protected by RwLock for thread safety
     index: RwLock<Hnsw<'static, f32, DistCosine>>,
-    /// Counter to track the number of elements
-    count: std::sync::atomic::AtomicUsize,
+    /// IDs of the vectors that can be returned by [`Self::search`]
+    ids: RwLock<HashSet<usize>>,
+    /// IDs removed from the index, HNSW graphs do not support deleting nodes so these are filtered
+    /// out at search time until the index is rebuilt
+    deleted: RwLock<HashSet<usize>>,
 }
 
 impl VectorIndex {
@@ -39,7 +43,8 @@ impl VectorIndex {
         debug!("Vector index created successfully");
         Self {
             index: RwLock::new(index),
-            count: std::sync::atomic::AtomicUsize::new(0),
+            ids: RwLock::new(HashSet::new()),
+            deleted: RwLock::new(HashSet::new()),
         }
     }
 
@@ -52,7 +57,7 @@ impl VectorIndex {
     pub fn insert(&self, vector: &[f32], id: usize) {
         let index = self.index.read().unwrap();
         index.insert((vector, id));
-        self.count.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        self.ids.write().unwrap().insert(id);
     }
 
     /// Search for nearest neighbors
@@ -68,7 +73,13 @@ impl VectorIndex {
     /// A vector of (id, distance) pairs
     pub fn search(&self, query: &[f32], limit: usize, ef_search: usize) -> Vec<(usize, f32)> {
         let index = self.index.read().unwrap();
-        let results = index.search(query, limit, ef_search);
+        let deleted = self.deleted.read().unwrap();
+        let results = if deleted.is_empty() {
+            index.search(query, limit, ef_search)
+        } else {
+            let filter = |id: &usize| !deleted.contains(id);
+            index.search_filter(query, limit, ef_search.max(limit), Some(&filter))
+        };
 
         results
             .into_iter()
@@ -76,13 +87,41 @@ impl VectorIndex {
             .collect()
     }
 
+    /// Remove a vector from the index
+    ///
+    /// The vector stays in the underlying graph but is never returned by [`Self::search`].
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID the vector was inserted with
+    ///
+    /// # Returns
+    ///
+    /// `true` if a vector with the ID was in the index
+    pub fn remove(&self, id: usize) -> bool {
+        let removed = self.ids.write().unwrap().remove(&id);
+        if removed {
+            self.deleted.write().unwrap().insert(id);
+        }
+        removed
+    }
+
+    /// Get the number of removed vectors still held by the underlying graph
+    ///
+    /// # Returns
+    ///
+    /// The number of removed vectors
+    pub fn deleted_count(&self) -> usize {
+        self.deleted.read().unwrap().len()
+    }
+
     /// Get the number of elements in the index
     ///
     /// # Returns
     ///
     /// The number of elements in the index
     pub fn len(&self) -> usize {
-        self.count.load(std::sync::atomic::Ordering::SeqCst)
+        self.ids.read().unwrap().len()
     }
 
     /// Check if the index is empty
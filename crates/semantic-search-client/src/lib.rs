@@ -45,6 +45,8 @@ pub use types::{
     OperationType,
     ProgressInfo,
     ProgressStatus,
+    SearchFilter,
     SearchResult,
     SystemStatus,
+    WatchStatus,
 };
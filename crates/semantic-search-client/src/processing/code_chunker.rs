@@ -0,0 +1,472 @@
+use tree_sitter::{
+    Language,
+    Node,
+    Parser,
+};
+
+use crate::config;
+use crate::processing::text_chunker::{
+    TextChunk,
+    chunk_lines,
+    lines_chunk,
+};
+
+/// Node kinds of a language used to split source code at definition boundaries
+struct Grammar {
+    language: Language,
+    /// Functions, types and other items that become chunks of their own
+    definitions: &'static [&'static str],
+    /// Definitions whose members are chunked separately when the whole definition is too long
+    containers: &'static [&'static str],
+    /// Nodes wrapping a definition, such as exports and decorators
+    wrappers: &'static [&'static str],
+    /// Comments and attributes that belong to the definition following them
+    leading: &'static [&'static str],
+    /// Separator between the names of a container and its members
+    separator: &'static str,
+}
+
+const RUST_DEFINITIONS: &[&str] = &[
+    "function_item",
+    "function_signature_item",
+    "impl_item",
+    "trait_item",
+    "struct_item",
+    "enum_item",
+    "union_item",
+    "mod_item",
+    "macro_definition",
+    "const_item",
+    "static_item",
+    "type_item",
+];
+const PYTHON_DEFINITIONS: &[&str] = &["function_definition", "class_definition"];
+const JAVASCRIPT_DEFINITIONS: &[&str] = &[
+    "function_declaration",
+    "generator_function_declaration",
+    "class_declaration",
+    "method_definition",
+    "lexical_declaration",
+];
+const TYPESCRIPT_DEFINITIONS: &[&str] = &[
+    "function_declaration",
+    "generator_function_declaration",
+    "function_signature",
+    "class_declaration",
+    "abstract_class_declaration",
+    "interface_declaration",
+    "type_alias_declaration",
+    "enum_declaration",
+    "internal_module",
+    "method_definition",
+    "method_signature",
+    "abstract_method_signature",
+    "lexical_declaration",
+];
+const GO_DEFINITIONS: &[&str] = &["function_declaration", "method_declaration", "type_declaration"];
+const JAVA_DEFINITIONS: &[&str] = &[
+    "class_declaration",
+    "interface_declaration",
+    "enum_declaration",
+    "record_declaration",
+    "annotation_type_declaration",
+    "method_declaration",
+    "constructor_declaration",
+];
+const JAVA_CONTAINERS: &[&str] = &[
+    "class_declaration",
+    "interface_declaration",
+    "enum_declaration",
+    "record_declaration",
+];
+
+impl Grammar {
+    /// Grammar for a file extension, or `None` for languages without syntax-aware chunking
+    fn for_extension(extension: &str) -> Option<Self> {
+        let grammar = match extension.to_lowercase().as_str() {
+            "rs" => Self {
+                language: tree_sitter_rust::LANGUAGE.into(),
+                definitions: RUST_DEFINITIONS,
+                containers: &["impl_item", "trait_item", "mod_item"],
+                wrappers: &[],
+                leading: &["line_comment", "block_comment", "attribute_item"],
+                separator: "::",
+            },
+            "py" => Self {
+                language: tree_sitter_python::LANGUAGE.into(),
+                definitions: PYTHON_DEFINITIONS,
+                containers: &["class_definition"],
+                wrappers: &["decorated_definition"],
+                leading: &["comment"],
+                separator: ".",
+            },
+            "js" | "jsx" | "mjs" | "cjs" => Self {
+                language: tree_sitter_javascript::LANGUAGE.into(),
+                definitions: JAVASCRIPT_DEFINITIONS,
+                containers: &["class_declaration"],
+                wrappers: &["export_statement"],
+                leading: &["comment"],
+                separator: ".",
+            },
+            "ts" | "tsx" | "mts" | "cts" => Self {
+                language: if extension.eq_ignore_ascii_case("tsx") {
+                    tree_sitter_typescript::LANGUAGE_TSX.into()
+                } else {
+                    tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into()
+                },
+                definitions: TYPESCRIPT_DEFINITIONS,
+                containers: &[
+                    "class_declaration",
+                    "abstract_class_declaration",
+                    "interface_declaration",
+                    "internal_module",
+                ],
+                wrappers: &["export_statement"],
+                leading: &["comment"],
+                separator: ".",
+            },
+            "go" => Self {
+                language: tree_sitter_go::LANGUAGE.into(),
+                definitions: GO_DEFINITIONS,
+                containers: &[],
+                wrappers: &[],
+                leading: &["comment"],
+                separator: ".",
+            },
+            "java" => Self {
+                language: tree_sitter_java::LANGUAGE.into(),
+                definitions: JAVA_DEFINITIONS,
+                containers: JAVA_CONTAINERS,
+                wrappers: &[],
+                leading: &["line_comment", "block_comment"],
+                separator: ".",
+            },
+            _ => return None,
+        };
+        Some(grammar)
+    }
+}
+
+/// Lines of a source file that form one chunk, or several when longer than the chunk size
+struct Segment {
+    /// First line, starting at 0
+    start: usize,
+    /// Line after the last one
+    end: usize,
+    symbol: Option<String>,
+}
+
+struct Splitter<'a> {
+    grammar: Grammar,
+    source: &'a str,
+    word_counts: Vec<usize>,
+    chunk_size: usize,
+}
+
+impl Splitter<'_> {
+    fn text(&self, node: Node<'_>) -> &str {
+        &self.source[node.byte_range()]
+    }
+
+    fn words(&self, start: usize, end: usize) -> usize {
+        self.word_counts[start..end].iter().sum()
+    }
+
+    /// The definition a top-level node declares, looking through wrappers such as `export`
+    fn definition<'tree>(&self, node: Node<'tree>) -> Option<Node<'tree>> {
+        if self.grammar.definitions.contains(&node.kind()) {
+            return Some(node);
+        }
+        if self.grammar.wrappers.contains(&node.kind()) {
+            let mut cursor = node.walk();
+            return node
+                .named_children(&mut cursor)
+                .find(|child| self.grammar.definitions.contains(&child.kind()));
+        }
+        None
+    }
+
+    /// Name of a definition, e.g. the function name or the type of an `impl` block
+    fn name(&self, node: Node<'_>, depth: usize) -> Option<String> {
+        let name = match node.kind() {
+            "impl_item" => node.child_by_field_name("type"),
+            _ => node.child_by_field_name("name"),
+        };
+        if let Some(name) = name {
+            return Some(self.text(name).to_string());
+        }
+
+        // Declarations such as `const f = () => {}` or Go's `type T struct {}` name an inner node
+        if depth == 0 {
+            return None;
+        }
+        let mut cursor = node.walk();
+        node.named_children(&mut cursor)
+            .find_map(|child| self.name(child, depth - 1))
+    }
+
+    /// Collect the definitions among the children of `parent`, splitting oversized containers
+    /// into their members
+    fn definitions(&self, parent: Node<'_>, scope: Option<&str>, segments: &mut Vec<Segment>) {
+        let mut leading_start = None;
+        let mut cursor = parent.walk();
+
+        for child in parent.named_children(&mut cursor) {
+            if self.grammar.leading.contains(&child.kind()) {
+                leading_start.get_or_insert(child.start_position().row);
+                continue;
+            }
+            let leading = leading_start.take();
+            let Some(definition) = self.definition(child) else {
+                continue;
+            };
+
+            let previous_end = segments.last().map_or(0, |segment| segment.end);
+            let start = leading.unwrap_or(child.start_position().row).max(previous_end);
+            let end = child.end_position().row + 1;
+            if start >= end {
+                continue;
+            }
+
+            let symbol = match (scope, self.name(definition, 2)) {
+                (Some(scope), Some(name)) => Some(format!("{scope}{}{name}", self.grammar.separator)),
+                (None, Some(name)) => Some(name),
+                (scope, None) => scope.map(str::to_string),
+            };
+
+            let body = definition.child_by_field_name("body");
+            match body {
+                Some(body)
+                    if self.words(start, end) > self.chunk_size
+                        && self.grammar.containers.contains(&definition.kind()) =>
+                {
+                    let mut members = Vec::new();
+                    self.definitions(body, symbol.as_deref(), &mut members);
+                    fill_gaps(start, end, members, symbol.as_deref(), segments);
+                },
+                _ => segments.push(Segment { start, end, symbol }),
+            }
+        }
+    }
+}
+
+/// Add `members` to `segments`, with the lines between them in segments of their own
+fn fill_gaps(start: usize, end: usize, members: Vec<Segment>, symbol: Option<&str>, segments: &mut Vec<Segment>) {
+    let mut position = start;
+    for member in members {
+        if member.start > position {
+            segments.push(Segment {
+                start: position,
+                end: member.start,
+                symbol: symbol.map(str::to_string),
+            });
+        }
+        position = member.end;
+        segments.push(member);
+    }
+    if end > position {
+        segments.push(Segment {
+            start: position,
+            end,
+            symbol: symbol.map(str::to_string),
+        });
+    }
+}
+
+/// Chunk source code at function, type and other definition boundaries
+///
+/// Definitions up to `chunk_size` words, with the comments and attributes directly above them,
+/// become one chunk each, recorded with their name as symbol. Longer classes, traits, `impl`
+/// blocks and modules are split into their members, qualified with the container name, e.g.
+/// `Parser::parse`. Anything else that is too long, and the code between definitions, is split
+/// into line windows.
+///
+/// # Arguments
+///
+/// * `text` - The source code to chunk
+/// * `extension` - Extension of the source file, which selects the grammar
+/// * `chunk_size` - Optional chunk size (if None, uses config value)
+/// * `overlap` - Optional overlap size (if None, uses config value)
+///
+/// # Returns
+///
+/// The chunks, or `None` if there is no grammar for the extension or the code cannot be parsed
+pub fn chunk_code(
+    text: &str,
+    extension: &str,
+    chunk_size: Option<usize>,
+    overlap: Option<usize>,
+) -> Option<Vec<TextChunk>> {
+    let grammar = Grammar::for_extension(extension)?;
+    let config = config::get_config();
+    let chunk_size = chunk_size.unwrap_or(config.chunk_size);
+    let overlap = overlap.unwrap_or(config.chunk_overlap);
+
+    let mut parser = Parser::new();
+    parser.set_language(&grammar.language).ok()?;
+    let tree = parser.parse(text, None)?;
+
+    let lines: Vec<&str> = text.lines().collect();
+    let splitter = Splitter {
+        grammar,
+        source: text,
+        word_counts: lines.iter().map(|line| line.split_whitespace().count()).collect(),
+        chunk_size,
+    };
+
+    let mut definitions = Vec::new();
+    splitter.definitions(tree.root_node(), None, &mut definitions);
+    let mut segments = Vec::new();
+    fill_gaps(0, lines.len(), definitions, None, &mut segments);
+
+    let mut chunks = Vec::new();
+    for segment in segments {
+        let end = segment.end.min(lines.len());
+        if segment.start >= end {
+            continue;
+        }
+        // Skip what is left between definitions when it is only closing braces
+        let section = &lines[segment.start..end];
+        if !section.iter().any(|line| line.chars().any(char::is_alphanumeric)) {
+            continue;
+        }
+
+        if splitter.words(segment.start, end) <= chunk_size {
+            chunks.extend(lines_chunk(&lines, 1, segment.start, end, segment.symbol.as_deref()));
+        } else {
+            chunks.extend(chunk_lines(
+                section,
+                segment.start + 1,
+                chunk_size,
+                overlap,
+                segment.symbol.as_deref(),
+            ));
+        }
+    }
+
+    Some(chunks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn symbols(chunks: &[TextChunk]) -> Vec<(Option<&str>, usize, usize)> {
+        chunks
+            .iter()
+            .map(|chunk| (chunk.symbol.as_deref(), chunk.start_line, chunk.end_line))
+            .collect()
+    }
+
+    #[test]
+    fn test_rust_items() {
+        let code = "use std::fmt;
+
+/// A point
+#[derive(Debug)]
+struct Point {
+    x: i32,
+}
+
+fn origin() -> Point {
+    Point { x: 0 }
+}
+";
+        let chunks = chunk_code(code, "rs", Some(100), Some(0)).unwrap();
+
+        assert_eq!(symbols(&chunks), vec![
+            (None, 1, 1),
+            (Some("Point"), 3, 7),
+            (Some("origin"), 9, 11),
+        ]);
+        assert_eq!(chunks[2].text, "fn origin() -> Point {\n    Point { x: 0 }\n}");
+    }
+
+    #[test]
+    fn test_large_impl_is_split_into_methods() {
+        let code = "impl Parser {
+    const LIMIT: usize = 10;
+
+    fn new() -> Self {
+        Self { position: 0, tokens: Vec::new() }
+    }
+
+    fn parse(&mut self) -> Result<Tree> {
+        let tree = Tree::default();
+        Ok(tree)
+    }
+}
+";
+        let chunks = chunk_code(code, "rs", Some(20), Some(0)).unwrap();
+
+        assert_eq!(symbols(&chunks), vec![
+            (Some("Parser"), 1, 1),
+            (Some("Parser::LIMIT"), 2, 2),
+            (Some("Parser::new"), 4, 6),
+            (Some("Parser::parse"), 8, 11),
+        ]);
+    }
+
+    #[test]
+    fn test_python_decorated_method() {
+        let code = "class Greeter:
+    \"\"\"Says hello.\"\"\"
+
+    @staticmethod
+    def greet(name):
+        return f\"Hello {name}\"
+";
+        let chunks = chunk_code(code, "py", Some(6), Some(0)).unwrap();
+
+        assert_eq!(symbols(&chunks), vec![
+            (Some("Greeter"), 1, 2),
+            (Some("Greeter.greet"), 4, 6),
+        ]);
+    }
+
+    #[test]
+    fn test_typescript_exports() {
+        let code = "export interface Options { verbose: boolean }
+
+export const run = (options: Options) => {
+    return options.verbose;
+};
+";
+        let chunks = chunk_code(code, "ts", Some(100), Some(0)).unwrap();
+
+        assert_eq!(symbols(&chunks), vec![(Some("Options"), 1, 1), (Some("run"), 3, 5)]);
+    }
+
+    #[test]
+    fn test_go_and_java() {
+        let go = "package main\n\ntype Server struct{}\n\nfunc (s *Server) Start() error {\n\treturn nil\n}\n";
+        let chunks = chunk_code(go, "go", Some(100), Some(0)).unwrap();
+        assert_eq!(symbols(&chunks), vec![
+            (None, 1, 1),
+            (Some("Server"), 3, 3),
+            (Some("Start"), 5, 7),
+        ]);
+
+        let java = "class App {\n    void run() {\n        System.out.println(1);\n    }\n}\n";
+        let chunks = chunk_code(java, "java", Some(100), Some(0)).unwrap();
+        assert_eq!(symbols(&chunks), vec![(Some("App"), 1, 5)]);
+    }
+
+    #[test]
+    fn test_long_function_falls_back_to_line_windows() {
+        let body: String = (0..10).map(|i| format!("    let v{i} = {i};\n")).collect();
+        let code = format!("fn long() {{\n{body}}}\n");
+
+        let chunks = chunk_code(&code, "rs", Some(12), Some(0)).unwrap();
+
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|chunk| chunk.symbol.as_deref() == Some("long")));
+        assert_eq!(chunks[0].start_line, 1);
+        assert_eq!(chunks.last().unwrap().end_line, 12);
+    }
+
+    #[test]
+    fn test_unsupported_language() {
+        assert!(chunk_code("int main() {}", "c", None, None).is_none());
+    }
+}
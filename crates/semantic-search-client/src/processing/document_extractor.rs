@@ -0,0 +1,580 @@
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::io::{
+    Cursor,
+    Read,
+};
+use std::panic::UnwindSafe;
+use std::path::Path;
+use std::sync::Once;
+
+use quick_xml::Reader;
+use quick_xml::events::{
+    BytesStart,
+    Event,
+};
+use serde_json::Value;
+use zip::ZipArchive;
+
+use crate::error::{
+    Result,
+    SemanticSearchError,
+};
+
+/// Largest uncompressed size of a part of an Office document that is read, as a small file can
+/// expand to gigabytes
+const MAX_ZIP_ENTRY_SIZE: u64 = 64 * 1024 * 1024;
+
+thread_local! {
+    /// Set while [catch_unwind_quietly] runs on this thread
+    static QUIET_PANICS: Cell<bool> = const { Cell::new(false) };
+}
+
+/// A page, slide, sheet or notebook cell of a document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocumentSection {
+    /// Kind of section, also the payload key of its number: `page`, `slide`, `sheet` or `cell`
+    pub kind: &'static str,
+    /// Number of the section in the document, starting at 1
+    pub number: usize,
+    /// Sheet name or notebook cell type
+    pub name: Option<String>,
+    /// Extracted text
+    pub text: String,
+}
+
+impl DocumentSection {
+    fn new(kind: &'static str, number: usize, text: String) -> Self {
+        Self {
+            kind,
+            number,
+            name: None,
+            text,
+        }
+    }
+}
+
+/// Extract the text of a PDF, Office document or Jupyter notebook, by section
+///
+/// Sections without text are left out.
+///
+/// # Arguments
+///
+/// * `path` - Path to a `.pdf`, `.docx`, `.pptx`, `.xlsx` or `.ipynb` file
+pub fn extract_document(path: &Path) -> Result<Vec<DocumentSection>> {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase())
+        .unwrap_or_default();
+    let bytes = std::fs::read(path)?;
+
+    let sections = match extension.as_str() {
+        "pdf" => extract_pdf(&bytes),
+        "docx" => extract_docx(&bytes),
+        "pptx" => extract_pptx(&bytes),
+        "xlsx" => extract_xlsx(&bytes),
+        "ipynb" => extract_notebook(&String::from_utf8_lossy(&bytes)),
+        _ => Err(SemanticSearchError::ExtractionError(format!(
+            "Unsupported document type: {}",
+            path.display()
+        ))),
+    };
+
+    sections.map_err(|e| SemanticSearchError::ExtractionError(format!("{}: {}", path.display(), e)))
+}
+
+/// Extract the text of each page of a PDF
+pub fn extract_pdf(bytes: &[u8]) -> Result<Vec<DocumentSection>> {
+    // The PDF parser panics on some malformed files instead of returning an error
+    let pages = catch_unwind_quietly(|| pdf_extract::extract_text_from_mem_by_pages(bytes))
+        .map_err(|_panic| SemanticSearchError::ExtractionError("PDF parser crashed".to_string()))?
+        .map_err(|e| SemanticSearchError::ExtractionError(e.to_string()))?;
+
+    Ok(pages
+        .into_iter()
+        .enumerate()
+        .map(|(i, text)| DocumentSection::new("page", i + 1, text))
+        .filter(|section| !section.text.trim().is_empty())
+        .collect())
+}
+
+/// Extract the text of a Word document by page
+///
+/// Pages are delimited by explicit page breaks and by the breaks Word recorded when it last laid
+/// out the document. Breaks with no text between them count as one.
+pub fn extract_docx(bytes: &[u8]) -> Result<Vec<DocumentSection>> {
+    let xml = read_zip_entry(&mut open_zip(bytes)?, "word/document.xml")?;
+    let mut pages = Vec::new();
+    let mut text = String::new();
+    let mut in_text = false;
+    let mut after_break = false;
+
+    walk_xml(&xml, |node| match node {
+        XmlNode::Start(name, element) => match name {
+            "t" => in_text = true,
+            "tab" => text.push('\t'),
+            "br" if attribute(element, "w:type").as_deref() != Some("page") => text.push('\n'),
+            "br" | "lastRenderedPageBreak" if !after_break => {
+                pages.push(std::mem::take(&mut text));
+                after_break = true;
+            },
+            _ => {},
+        },
+        XmlNode::End(name) => match name {
+            "t" => in_text = false,
+            "p" => text.push('\n'),
+            _ => {},
+        },
+        XmlNode::Text(content) if in_text => {
+            text.push_str(content);
+            after_break &= content.trim().is_empty();
+        },
+        XmlNode::Text(_) => {},
+    })?;
+    pages.push(text);
+
+    Ok(pages
+        .into_iter()
+        .enumerate()
+        .map(|(i, text)| DocumentSection::new("page", i + 1, text))
+        .filter(|section| !section.text.trim().is_empty())
+        .collect())
+}
+
+/// Extract the text of each slide of a PowerPoint presentation
+pub fn extract_pptx(bytes: &[u8]) -> Result<Vec<DocumentSection>> {
+    let mut archive = open_zip(bytes)?;
+    let mut slides: Vec<(usize, String)> = archive
+        .file_names()
+        .filter_map(|name| {
+            let number = name.strip_prefix("ppt/slides/slide")?.strip_suffix(".xml")?;
+            Some((number.parse().ok()?, name.to_string()))
+        })
+        .collect();
+    slides.sort();
+
+    let mut sections = Vec::new();
+    for (number, name) in slides {
+        let text = drawing_text(&read_zip_entry(&mut archive, &name)?)?;
+        if !text.trim().is_empty() {
+            sections.push(DocumentSection::new("slide", number, text));
+        }
+    }
+    Ok(sections)
+}
+
+/// Extract the text of each sheet of an Excel workbook, one row per line with tab separated cells
+pub fn extract_xlsx(bytes: &[u8]) -> Result<Vec<DocumentSection>> {
+    let mut archive = open_zip(bytes)?;
+    let shared_strings = match read_zip_entry(&mut archive, "xl/sharedStrings.xml") {
+        Ok(xml) => shared_strings(&xml)?,
+        Err(_) => Vec::new(),
+    };
+
+    // Sheets are listed in the workbook and their files found through its relationships
+    let mut targets = HashMap::new();
+    walk_xml(&read_zip_entry(&mut archive, "xl/_rels/workbook.xml.rels")?, |node| {
+        if let XmlNode::Start("Relationship", element) = node {
+            if let (Some(id), Some(target)) = (attribute(element, "Id"), attribute(element, "Target")) {
+                targets.insert(id, target);
+            }
+        }
+    })?;
+    let mut sheets = Vec::new();
+    walk_xml(&read_zip_entry(&mut archive, "xl/workbook.xml")?, |node| {
+        if let XmlNode::Start("sheet", element) = node {
+            if let (Some(name), Some(id)) = (attribute(element, "name"), attribute(element, "r:id")) {
+                sheets.push((name, id));
+            }
+        }
+    })?;
+
+    let mut sections = Vec::new();
+    for (i, (name, id)) in sheets.into_iter().enumerate() {
+        let Some(target) = targets.get(&id) else {
+            continue;
+        };
+        let path = match target.strip_prefix('/') {
+            Some(absolute) => absolute.to_string(),
+            None => format!("xl/{}", target),
+        };
+        let text = sheet_text(&read_zip_entry(&mut archive, &path)?, &shared_strings)?;
+        if !text.trim().is_empty() {
+            sections.push(DocumentSection {
+                name: Some(name),
+                ..DocumentSection::new("sheet", i + 1, text)
+            });
+        }
+    }
+    Ok(sections)
+}
+
+/// Extract the source and text outputs of each cell of a Jupyter notebook
+pub fn extract_notebook(json: &str) -> Result<Vec<DocumentSection>> {
+    let notebook: Value = serde_json::from_str(json)?;
+    let cells = notebook
+        .get("cells")
+        .and_then(|cells| cells.as_array())
+        .ok_or_else(|| SemanticSearchError::ExtractionError("Notebook has no cells".to_string()))?;
+
+    let mut sections = Vec::new();
+    for (i, cell) in cells.iter().enumerate() {
+        let mut text = multiline_text(cell.get("source"));
+        for output in cell.get("outputs").and_then(|o| o.as_array()).into_iter().flatten() {
+            let output_text = match output.get("output_type").and_then(|t| t.as_str()) {
+                Some("stream") => multiline_text(output.get("text")),
+                Some("execute_result" | "display_data") => {
+                    multiline_text(output.get("data").and_then(|data| data.get("text/plain")))
+                },
+                Some("error") => format!(
+                    "{}: {}",
+                    output.get("ename").and_then(|v| v.as_str()).unwrap_or_default(),
+                    output.get("evalue").and_then(|v| v.as_str()).unwrap_or_default()
+                ),
+                _ => String::new(),
+            };
+            if !output_text.trim().is_empty() {
+                text.push_str("\n\n");
+                text.push_str(&output_text);
+            }
+        }
+
+        if !text.trim().is_empty() {
+            sections.push(DocumentSection {
+                name: cell.get("cell_type").and_then(|t| t.as_str()).map(str::to_string),
+                ..DocumentSection::new("cell", i + 1, text)
+            });
+        }
+    }
+    Ok(sections)
+}
+
+/// Remove the tags, comments, scripts and styles of an HTML document and decode its entities
+///
+/// Line breaks inside removed markup are kept, so each line of the result matches the same line
+/// of the document.
+pub fn strip_html(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        text.push_str(&decode_entities(&rest[..start]));
+        let markup = &rest[start..];
+        let lowercase = markup.get(..7).unwrap_or(markup).to_ascii_lowercase();
+
+        let end = if markup.starts_with("<!--") {
+            markup.find("-->").map(|end| end + 3)
+        } else if let Some(tag) = ["<script", "<style"].into_iter().find(|tag| lowercase.starts_with(tag)) {
+            let closing = format!("</{}", &tag[1..]);
+            markup
+                .to_ascii_lowercase()
+                .find(&closing)
+                .and_then(|close| markup[close..].find('>').map(|end| close + end + 1))
+        } else {
+            markup.find('>').map(|end| end + 1)
+        }
+        .unwrap_or(markup.len());
+
+        // Tags separate words, e.g. `<td>a</td><td>b</td>`
+        let newlines = markup[..end].matches('\n').count();
+        if newlines == 0 {
+            text.push(' ');
+        }
+        text.extend(std::iter::repeat_n('\n', newlines));
+        rest = &markup[end..];
+    }
+    text.push_str(&decode_entities(rest));
+
+    text
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest
+            .get(1..rest.len().min(12))
+            .and_then(|candidate| candidate.find(';').map(|end| &candidate[..end]));
+        let character = entity.and_then(|entity| match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            _ => {
+                let number = entity.strip_prefix('#')?;
+                let code = match number.strip_prefix(['x', 'X']) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                    None => number.parse().ok()?,
+                };
+                char::from_u32(code)
+            },
+        });
+
+        match (entity, character) {
+            (Some(entity), Some(character)) => {
+                decoded.push(character);
+                rest = &rest[entity.len() + 2..];
+            },
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            },
+        }
+    }
+    decoded.push_str(rest);
+
+    decoded
+}
+
+/// Text of a notebook field, stored either as a string or as a list of lines
+fn multiline_text(value: Option<&Value>) -> String {
+    match value {
+        Some(Value::String(text)) => text.clone(),
+        Some(Value::Array(lines)) => lines.iter().filter_map(|line| line.as_str()).collect(),
+        _ => String::new(),
+    }
+}
+
+fn open_zip(bytes: &[u8]) -> Result<ZipArchive<Cursor<&[u8]>>> {
+    ZipArchive::new(Cursor::new(bytes)).map_err(|e| SemanticSearchError::ExtractionError(e.to_string()))
+}
+
+fn read_zip_entry(archive: &mut ZipArchive<Cursor<&[u8]>>, name: &str) -> Result<String> {
+    read_zip_entry_with_limit(archive, name, MAX_ZIP_ENTRY_SIZE)
+}
+
+fn read_zip_entry_with_limit(archive: &mut ZipArchive<Cursor<&[u8]>>, name: &str, limit: u64) -> Result<String> {
+    let entry = archive
+        .by_name(name)
+        .map_err(|e| SemanticSearchError::ExtractionError(format!("{}: {}", name, e)))?;
+    let too_large = || SemanticSearchError::ExtractionError(format!("{}: larger than {} bytes", name, limit));
+    if entry.size() > limit {
+        return Err(too_large());
+    }
+
+    // The size recorded in the archive may be wrong
+    let mut content = String::new();
+    entry.take(limit + 1).read_to_string(&mut content)?;
+    if content.len() as u64 > limit {
+        return Err(too_large());
+    }
+    Ok(content)
+}
+
+/// Run `f`, catching its panics without printing them to stderr
+fn catch_unwind_quietly<T>(f: impl FnOnce() -> T + UnwindSafe) -> std::thread::Result<T> {
+    static QUIET_HOOK: Once = Once::new();
+    QUIET_HOOK.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| match QUIET_PANICS.with(Cell::get) {
+            true => tracing::debug!("Caught panic: {}", info),
+            false => previous(info),
+        }));
+    });
+
+    QUIET_PANICS.with(|quiet| quiet.set(true));
+    let result = std::panic::catch_unwind(f);
+    QUIET_PANICS.with(|quiet| quiet.set(false));
+    result
+}
+
+/// Text of the `a:t` runs of a DrawingML part, one paragraph per line
+fn drawing_text(xml: &str) -> Result<String> {
+    let mut text = String::new();
+    let mut in_text = false;
+
+    walk_xml(xml, |node| match node {
+        XmlNode::Start("t", _) => in_text = true,
+        XmlNode::End("t") => in_text = false,
+        XmlNode::End("p") => text.push('\n'),
+        XmlNode::Text(content) if in_text => text.push_str(content),
+        _ => {},
+    })?;
+
+    Ok(text)
+}
+
+fn shared_strings(xml: &str) -> Result<Vec<String>> {
+    let mut strings = Vec::new();
+    let mut current = String::new();
+    let mut in_text = false;
+
+    walk_xml(xml, |node| match node {
+        XmlNode::Start("t", _) => in_text = true,
+        XmlNode::End("t") => in_text = false,
+        XmlNode::End("si") => strings.push(std::mem::take(&mut current)),
+        XmlNode::Text(content) if in_text => current.push_str(content),
+        _ => {},
+    })?;
+
+    Ok(strings)
+}
+
+fn sheet_text(xml: &str, shared_strings: &[String]) -> Result<String> {
+    let mut text = String::new();
+    let mut row: Vec<String> = Vec::new();
+    let mut cell_type = None;
+    let mut value = String::new();
+    let mut in_value = false;
+
+    walk_xml(xml, |node| match node {
+        XmlNode::Start("c", element) => {
+            cell_type = attribute(element, "t");
+            value.clear();
+        },
+        XmlNode::Start("v" | "t", _) => in_value = true,
+        XmlNode::End("v" | "t") => in_value = false,
+        XmlNode::Text(content) if in_value => value.push_str(content),
+        XmlNode::End("c") => {
+            let cell = match cell_type.as_deref() {
+                Some("s") => value
+                    .trim()
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|index| shared_strings.get(index).cloned())
+                    .unwrap_or_default(),
+                _ => value.clone(),
+            };
+            row.push(cell);
+        },
+        XmlNode::End("row") => {
+            let line = row.join("\t");
+            if !line.trim().is_empty() {
+                text.push_str(line.trim_end());
+                text.push('\n');
+            }
+            row.clear();
+        },
+        _ => {},
+    })?;
+
+    Ok(text)
+}
+
+/// An element boundary or text of an XML document, with names stripped of their namespace
+enum XmlNode<'a> {
+    Start(&'a str, &'a BytesStart<'a>),
+    End(&'a str),
+    Text(&'a str),
+}
+
+fn walk_xml(xml: &str, mut visit: impl FnMut(XmlNode<'_>)) -> Result<()> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().expand_empty_elements = true;
+    let xml_error = |e: quick_xml::Error| SemanticSearchError::ExtractionError(format!("Invalid XML: {}", e));
+
+    loop {
+        match reader.read_event().map_err(xml_error)? {
+            Event::Start(element) => {
+                let local_name = element.local_name();
+                let name = String::from_utf8_lossy(local_name.as_ref());
+                visit(XmlNode::Start(&name, &element));
+            },
+            Event::End(element) => {
+                let local_name = element.local_name();
+                visit(XmlNode::End(&String::from_utf8_lossy(local_name.as_ref())));
+            },
+            Event::Text(content) => {
+                let content = content.decode().map_err(|e| xml_error(e.into()))?;
+                visit(XmlNode::Text(&content));
+            },
+            Event::CData(content) => {
+                let content = content.decode().map_err(|e| xml_error(e.into()))?;
+                visit(XmlNode::Text(&content));
+            },
+            Event::GeneralRef(reference) => {
+                let entity = format!("&{};", reference.decode().map_err(|e| xml_error(e.into()))?);
+                let content = quick_xml::escape::unescape(&entity).map_err(|e| xml_error(e.into()))?;
+                visit(XmlNode::Text(&content));
+            },
+            Event::Eof => return Ok(()),
+            _ => {},
+        }
+    }
+}
+
+/// Unescaped value of an attribute, matched by its qualified name
+fn attribute(element: &BytesStart<'_>, name: &str) -> Option<String> {
+    let attribute = element.try_get_attribute(name).ok()??;
+    attribute.unescape_value().ok().map(|value| value.into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_html_keeps_lines() {
+        let html = "<html>\n<head><style>\nbody { color: red; }\n</style></head>\n<body>\n<p>Fish &amp; chips</p><p>&#x263A;&lt;3 &unknown;</p>\n<!-- a\ncomment --><SCRIPT>alert(1)</SCRIPT>\n</body>";
+
+        let text = strip_html(html);
+
+        let lines: Vec<&str> = text.lines().map(str::trim).collect();
+        assert_eq!(html.lines().count(), lines.len());
+        assert_eq!(lines[5], "Fish & chips  \u{263A}<3 &unknown;");
+        assert!(!text.contains("color") && !text.contains("alert") && !text.contains("comment"));
+    }
+
+    #[test]
+    fn test_notebook_cells_and_outputs() {
+        let notebook = r##"{
+            "cells": [
+                {"cell_type": "markdown", "source": ["# Title\n", "Intro"]},
+                {"cell_type": "code", "source": "", "outputs": []},
+                {"cell_type": "code", "source": "print(1)", "outputs": [
+                    {"output_type": "stream", "text": ["1\n"]},
+                    {"output_type": "execute_result", "data": {"text/plain": "42"}},
+                    {"output_type": "error", "ename": "ValueError", "evalue": "bad"}
+                ]}
+            ]
+        }"##;
+
+        let sections = extract_notebook(notebook).unwrap();
+
+        assert_eq!(sections.len(), 2);
+        assert_eq!(sections[0].text, "# Title\nIntro");
+        assert_eq!(sections[0].name.as_deref(), Some("markdown"));
+        assert_eq!((sections[1].kind, sections[1].number), ("cell", 3));
+        assert_eq!(sections[1].text, "print(1)\n\n1\n\n\n42\n\nValueError: bad");
+    }
+
+    #[test]
+    fn test_zip_entry_size_limit() {
+        use std::io::Write;
+
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        writer.start_file("word/document.xml", options).unwrap();
+        writer.write_all(&[b'a'; 100]).unwrap();
+        let bytes = writer.finish().unwrap().into_inner();
+
+        let mut archive = open_zip(&bytes).unwrap();
+        assert_eq!(
+            read_zip_entry_with_limit(&mut archive, "word/document.xml", 100)
+                .unwrap()
+                .len(),
+            100
+        );
+        assert!(read_zip_entry_with_limit(&mut archive, "word/document.xml", 99).is_err());
+    }
+
+    #[test]
+    fn test_catch_unwind_quietly() {
+        assert!(catch_unwind_quietly(|| panic!("malformed")).is_err());
+        assert_eq!(catch_unwind_quietly(|| 1).unwrap(), 1);
+        assert!(!QUIET_PANICS.with(Cell::get));
+    }
+
+    #[test]
+    fn test_invalid_documents() {
+        assert!(extract_docx(b"not a zip").is_err());
+        assert!(extract_pdf(b"not a pdf").is_err());
+        assert!(extract_notebook("{}").is_err());
+    }
+}
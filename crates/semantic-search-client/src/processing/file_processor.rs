@@ -7,7 +7,17 @@ use crate::error::{
     Result,
     SemanticSearchError,
 };
-use crate::processing::text_chunker::chunk_text;
+use crate::processing::code_chunker::chunk_code;
+use crate::processing::document_extractor::{
+    extract_document,
+    strip_html,
+};
+use crate::processing::text_chunker::{
+    chunk_lines,
+    chunk_markdown,
+    chunk_text,
+    chunk_text_with_lines,
+};
 use crate::types::FileType;
 
 /// Determine the file type based on extension
@@ -41,6 +51,12 @@ pub fn get_file_type(path: &Path) -> FileType {
 
         // Web and markup formats (text-based)
         Some("svg") => FileType::Text,
+        Some("html" | "htm") => FileType::Html,
+
+        // Documents whose text is extracted
+        Some("pdf") => FileType::Pdf,
+        Some("docx" | "pptx" | "xlsx") => FileType::Office,
+        Some("ipynb") => FileType::Notebook,
 
         // Code file extensions
         Some("rs") => FileType::Code,
@@ -55,7 +71,7 @@ pub fn get_file_type(path: &Path) -> FileType {
         Some("kt" | "kts") => FileType::Code,
         Some("cs") => FileType::Code,
         Some("sh" | "bash" | "zsh") => FileType::Code,
-        Some("html" | "htm" | "xml") => FileType::Code,
+        Some("xml") => FileType::Code,
         Some("css" | "scss" | "sass" | "less") => FileType::Code,
         Some("sql") => FileType::Code,
         Some("yaml" | "yml") => FileType::Code,
@@ -71,7 +87,7 @@ pub fn get_file_type(path: &Path) -> FileType {
             _ => FileType::Unknown,
         },
 
-        // Default to unknown (includes office docs, PDFs, etc.)
+        // Default to unknown (includes images, archives, etc.)
         _ => FileType::Unknown,
     }
 }
@@ -113,6 +129,10 @@ pub fn process_file_with_config(
     }
 
     let file_type = get_file_type(path);
+    if matches!(file_type, FileType::Pdf | FileType::Office | FileType::Notebook) {
+        return process_document(path, file_type, chunk_size, chunk_overlap);
+    }
+
     let content = fs::read_to_string(path).map_err(|e| {
         SemanticSearchError::IoError(std::io::Error::new(
             e.kind(),
@@ -121,10 +141,30 @@ pub fn process_file_with_config(
     })?;
 
     match file_type {
-        FileType::Text | FileType::Markdown | FileType::Code | FileType::Json => {
+        FileType::Text | FileType::Markdown | FileType::Code | FileType::Json | FileType::Html => {
             // For text-based files (including JSON), chunk the content and create multiple data points
-            // Use the configured chunk size and overlap
-            let chunks = chunk_text(&content, chunk_size, chunk_overlap);
+            // Use the configured chunk size and overlap. Code is split at definitions and Markdown at
+            // headings, keeping their lines intact
+            let chunks = match file_type {
+                FileType::Code => {
+                    let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or_default();
+                    chunk_code(&content, extension, chunk_size, chunk_overlap).unwrap_or_else(|| {
+                        let config = crate::config::get_config();
+                        let lines: Vec<&str> = content.lines().collect();
+                        chunk_lines(
+                            &lines,
+                            1,
+                            chunk_size.unwrap_or(config.chunk_size),
+                            chunk_overlap.unwrap_or(config.chunk_overlap),
+                            None,
+                        )
+                    })
+                },
+                FileType::Markdown => chunk_markdown(&content, chunk_size, chunk_overlap),
+                // Markup is removed line by line, so chunks keep the line numbers of the page
+                FileType::Html => chunk_text_with_lines(&strip_html(&content), chunk_size, chunk_overlap),
+                _ => chunk_text_with_lines(&content, chunk_size, chunk_overlap),
+            };
             let path_str = path.to_string_lossy().to_string();
             let file_type_str = format!("{:?}", file_type);
 
@@ -132,11 +172,19 @@ pub fn process_file_with_config(
 
             for (i, chunk) in chunks.iter().enumerate() {
                 let mut metadata = serde_json::Map::new();
-                metadata.insert("text".to_string(), Value::String(chunk.clone()));
+                metadata.insert("text".to_string(), Value::String(chunk.text.clone()));
                 metadata.insert("path".to_string(), Value::String(path_str.clone()));
                 metadata.insert("file_type".to_string(), Value::String(file_type_str.clone()));
                 metadata.insert("chunk_index".to_string(), Value::Number((i as u64).into()));
                 metadata.insert("total_chunks".to_string(), Value::Number((chunks.len() as u64).into()));
+                metadata.insert(
+                    "start_line".to_string(),
+                    Value::Number((chunk.start_line as u64).into()),
+                );
+                metadata.insert("end_line".to_string(), Value::Number((chunk.end_line as u64).into()));
+                if let Some(symbol) = &chunk.symbol {
+                    metadata.insert("symbol".to_string(), Value::String(symbol.clone()));
+                }
 
                 // For code files, add additional metadata
                 if file_type == FileType::Code {
@@ -156,19 +204,12 @@ pub fn process_file_with_config(
 
             // If no chunks were created (empty file), create at least one entry
             if results.is_empty() {
-                let mut metadata = serde_json::Map::new();
-                metadata.insert("text".to_string(), Value::String(String::new()));
-                metadata.insert("path".to_string(), Value::String(path_str));
-                metadata.insert("file_type".to_string(), Value::String(file_type_str));
-                metadata.insert("chunk_index".to_string(), Value::Number(0.into()));
-                metadata.insert("total_chunks".to_string(), Value::Number(1.into()));
-
-                results.push(Value::Object(metadata));
+                results.push(empty_file_entry(path_str, file_type_str));
             }
 
             Ok(results)
         },
-        FileType::Unknown => {
+        FileType::Unknown | FileType::Pdf | FileType::Office | FileType::Notebook => {
             // For unknown file types, just store the path
             let mut metadata = serde_json::Map::new();
             metadata.insert("path".to_string(), Value::String(path.to_string_lossy().to_string()));
@@ -179,6 +220,61 @@ pub fn process_file_with_config(
     }
 }
 
+/// Extract the text of a PDF, Office document or notebook and chunk each page, slide, sheet or
+/// cell separately, recording its number in the payload
+fn process_document(
+    path: &Path,
+    file_type: FileType,
+    chunk_size: Option<usize>,
+    chunk_overlap: Option<usize>,
+) -> Result<Vec<Value>> {
+    let sections = extract_document(path)?;
+    let path_str = path.to_string_lossy().to_string();
+    let file_type_str = format!("{:?}", file_type);
+
+    let chunks: Vec<_> = sections
+        .iter()
+        .flat_map(|section| {
+            chunk_text(&section.text, chunk_size, chunk_overlap)
+                .into_iter()
+                .map(move |text| (section, text))
+        })
+        .collect();
+
+    let mut results = Vec::new();
+    for (i, (section, text)) in chunks.iter().enumerate() {
+        let mut metadata = serde_json::Map::new();
+        metadata.insert("text".to_string(), Value::String(text.clone()));
+        metadata.insert("path".to_string(), Value::String(path_str.clone()));
+        metadata.insert("file_type".to_string(), Value::String(file_type_str.clone()));
+        metadata.insert("chunk_index".to_string(), Value::Number((i as u64).into()));
+        metadata.insert("total_chunks".to_string(), Value::Number((chunks.len() as u64).into()));
+        metadata.insert(section.kind.to_string(), Value::Number((section.number as u64).into()));
+        if let Some(name) = &section.name {
+            metadata.insert("section_name".to_string(), Value::String(name.clone()));
+        }
+
+        results.push(Value::Object(metadata));
+    }
+
+    // Scanned PDFs and empty documents still get an entry
+    if results.is_empty() {
+        results.push(empty_file_entry(path_str, file_type_str));
+    }
+
+    Ok(results)
+}
+
+fn empty_file_entry(path: String, file_type: String) -> Value {
+    let mut metadata = serde_json::Map::new();
+    metadata.insert("text".to_string(), Value::String(String::new()));
+    metadata.insert("path".to_string(), Value::String(path));
+    metadata.insert("file_type".to_string(), Value::String(file_type));
+    metadata.insert("chunk_index".to_string(), Value::Number(0.into()));
+    metadata.insert("total_chunks".to_string(), Value::Number(1.into()));
+    Value::Object(metadata)
+}
+
 /// Process a directory and extract content from all files
 ///
 /// # Arguments
@@ -240,7 +336,7 @@ mod tests {
             ("component.tsx", FileType::Code),
             ("Main.java", FileType::Code),
             ("main.c", FileType::Code),
-            ("index.html", FileType::Code),
+            ("index.html", FileType::Html),
             ("styles.css", FileType::Code),
             ("config.yaml", FileType::Code),
             ("Cargo.toml", FileType::Code),
@@ -260,9 +356,15 @@ mod tests {
             ("Main.RS", FileType::Code),
             ("README.MD", FileType::Markdown),
             ("notes.TXT", FileType::Text),
+            // Documents
+            ("index.htm", FileType::Html),
+            ("document.pdf", FileType::Pdf),
+            ("report.docx", FileType::Office),
+            ("slides.pptx", FileType::Office),
+            ("budget.xlsx", FileType::Office),
+            ("analysis.ipynb", FileType::Notebook),
             // Unknown files
             ("image.png", FileType::Unknown),
-            ("document.pdf", FileType::Unknown),
             ("binary.exe", FileType::Unknown),
             ("unknown_file", FileType::Unknown),
         ];
@@ -281,7 +383,7 @@ mod tests {
     fn test_unknown_file_types() {
         // Binary files and unsupported formats
         assert_eq!(get_file_type(&PathBuf::from("image.png")), FileType::Unknown);
-        assert_eq!(get_file_type(&PathBuf::from("document.pdf")), FileType::Unknown);
+        assert_eq!(get_file_type(&PathBuf::from("document.doc")), FileType::Unknown);
         assert_eq!(get_file_type(&PathBuf::from("archive.zip")), FileType::Unknown);
         assert_eq!(get_file_type(&PathBuf::from("binary.exe")), FileType::Unknown);
         assert_eq!(get_file_type(&PathBuf::from("data.db")), FileType::Unknown);
@@ -1,12 +1,28 @@
+/// Syntax-aware chunking of source code at definition boundaries
+pub mod code_chunker;
+/// Text extraction from PDF, Office, notebook and HTML documents
+pub mod document_extractor;
 /// File processing utilities for handling different file types and extracting content
 pub mod file_processor;
 /// Text chunking utilities for breaking down text into manageable pieces for embedding
 pub mod text_chunker;
 
+pub use code_chunker::chunk_code;
+pub use document_extractor::{
+    DocumentSection,
+    extract_document,
+    strip_html,
+};
 pub use file_processor::{
     get_file_type,
     process_directory,
     process_file,
     process_file_with_config,
 };
-pub use text_chunker::chunk_text;
+pub use text_chunker::{
+    TextChunk,
+    chunk_lines,
+    chunk_markdown,
+    chunk_text,
+    chunk_text_with_lines,
+};
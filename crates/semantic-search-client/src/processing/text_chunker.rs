@@ -1,5 +1,18 @@
 use crate::config;
 
+/// A chunk of text with the lines it spans
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextChunk {
+    /// The chunk text, either its words joined by single spaces or its original lines
+    pub text: String,
+    /// Line of the first word, starting at 1
+    pub start_line: usize,
+    /// Line of the last word
+    pub end_line: usize,
+    /// Function, type or heading the chunk belongs to
+    pub symbol: Option<String>,
+}
+
 /// Chunk text into smaller pieces with overlap
 ///
 /// # Arguments
@@ -12,13 +25,35 @@ use crate::config;
 ///
 /// A vector of string chunks
 pub fn chunk_text(text: &str, chunk_size: Option<usize>, overlap: Option<usize>) -> Vec<String> {
+    chunk_text_with_lines(text, chunk_size, overlap)
+        .into_iter()
+        .map(|chunk| chunk.text)
+        .collect()
+}
+
+/// Chunk text into smaller pieces with overlap, keeping track of the lines each chunk spans
+///
+/// # Arguments
+///
+/// * `text` - The text to chunk
+/// * `chunk_size` - Optional chunk size (if None, uses config value)
+/// * `overlap` - Optional overlap size (if None, uses config value)
+///
+/// # Returns
+///
+/// A vector of chunks with their line ranges
+pub fn chunk_text_with_lines(text: &str, chunk_size: Option<usize>, overlap: Option<usize>) -> Vec<TextChunk> {
     // Get configuration values or use provided values
     let config = config::get_config();
     let chunk_size = chunk_size.unwrap_or(config.chunk_size);
     let overlap = overlap.unwrap_or(config.chunk_overlap);
 
     let mut chunks = Vec::new();
-    let words: Vec<&str> = text.split_whitespace().collect();
+    let words: Vec<(&str, usize)> = text
+        .lines()
+        .enumerate()
+        .flat_map(|(line, content)| content.split_whitespace().map(move |word| (word, line + 1)))
+        .collect();
 
     if words.is_empty() {
         return chunks;
@@ -27,8 +62,13 @@ pub fn chunk_text(text: &str, chunk_size: Option<usize>, overlap: Option<usize>)
     let mut i = 0;
     while i < words.len() {
         let end = (i + chunk_size).min(words.len());
-        let chunk = words[i..end].join(" ");
-        chunks.push(chunk);
+        let chunk = &words[i..end];
+        chunks.push(TextChunk {
+            text: chunk.iter().map(|(word, _)| *word).collect::<Vec<_>>().join(" "),
+            start_line: chunk[0].1,
+            end_line: chunk[chunk.len() - 1].1,
+            symbol: None,
+        });
 
         // Move forward by chunk_size - overlap
         i += chunk_size - overlap;
@@ -40,6 +80,166 @@ pub fn chunk_text(text: &str, chunk_size: Option<usize>, overlap: Option<usize>)
     chunks
 }
 
+/// Chunk lines of text into windows of whole lines, keeping newlines and indentation
+///
+/// Each window holds as many lines as fit in `chunk_size` words, and starts with the trailing
+/// lines of the previous window that fit in `overlap` words. A single line longer than
+/// `chunk_size` words is split into windows of words with [`chunk_text_with_lines`]. Blank lines
+/// at the edges of a window are dropped.
+///
+/// # Arguments
+///
+/// * `lines` - The lines to chunk
+/// * `first_line` - Line number of the first line, starting at 1
+/// * `chunk_size` - Maximum number of words in a window
+/// * `overlap` - Maximum number of words repeated from the previous window
+/// * `symbol` - Symbol recorded on every window
+pub fn chunk_lines(
+    lines: &[&str],
+    first_line: usize,
+    chunk_size: usize,
+    overlap: usize,
+    symbol: Option<&str>,
+) -> Vec<TextChunk> {
+    let word_counts: Vec<usize> = lines.iter().map(|line| line.split_whitespace().count()).collect();
+    let mut chunks = Vec::new();
+
+    let mut start = 0;
+    while start < lines.len() {
+        let mut end = start;
+        let mut words = 0;
+        while end < lines.len() && (words == 0 || words + word_counts[end] <= chunk_size) {
+            words += word_counts[end];
+            end += 1;
+        }
+        if words > chunk_size {
+            let overlap = overlap.min(chunk_size.saturating_sub(1));
+            chunks.extend(
+                chunk_text_with_lines(lines[start], Some(chunk_size), Some(overlap))
+                    .into_iter()
+                    .map(|chunk| TextChunk {
+                        start_line: first_line + start,
+                        end_line: first_line + start,
+                        symbol: symbol.map(str::to_string),
+                        ..chunk
+                    }),
+            );
+        } else {
+            chunks.extend(lines_chunk(lines, first_line, start, end, symbol));
+        }
+
+        if end >= lines.len() {
+            break;
+        }
+
+        // Step back over the lines that fit in the overlap, always moving forward
+        let mut next = end;
+        let mut overlap_words = 0;
+        while next > start + 1 && overlap_words + word_counts[next - 1] <= overlap {
+            overlap_words += word_counts[next - 1];
+            next -= 1;
+        }
+        start = next;
+    }
+
+    chunks
+}
+
+/// Create a chunk from `lines[start..end]` without its leading and trailing blank lines
+pub(crate) fn lines_chunk(
+    lines: &[&str],
+    first_line: usize,
+    start: usize,
+    end: usize,
+    symbol: Option<&str>,
+) -> Option<TextChunk> {
+    let is_blank = |line: &&&str| line.trim().is_empty();
+    let start = start + lines[start..end].iter().take_while(is_blank).count();
+    let end = end - lines[start..end].iter().rev().take_while(is_blank).count();
+    if start >= end {
+        return None;
+    }
+
+    Some(TextChunk {
+        text: lines[start..end].join("\n"),
+        start_line: first_line + start,
+        end_line: first_line + end - 1,
+        symbol: symbol.map(str::to_string),
+    })
+}
+
+/// Chunk Markdown by heading section
+///
+/// Each section runs from a heading to the next heading of any level and is recorded with the
+/// path of headings leading to it, e.g. `Install > Linux`. Sections longer than `chunk_size`
+/// words are split with [`chunk_lines`]. Headings inside fenced code blocks are ignored.
+///
+/// # Arguments
+///
+/// * `text` - The Markdown to chunk
+/// * `chunk_size` - Optional chunk size (if None, uses config value)
+/// * `overlap` - Optional overlap size (if None, uses config value)
+pub fn chunk_markdown(text: &str, chunk_size: Option<usize>, overlap: Option<usize>) -> Vec<TextChunk> {
+    let config = config::get_config();
+    let chunk_size = chunk_size.unwrap_or(config.chunk_size);
+    let overlap = overlap.unwrap_or(config.chunk_overlap);
+
+    let lines: Vec<&str> = text.lines().collect();
+    let mut chunks = Vec::new();
+    let mut headings: Vec<(usize, &str)> = Vec::new();
+    let mut section_start = 0;
+    let mut fence: Option<&str> = None;
+
+    let mut push_section = |start: usize, end: usize, headings: &[(usize, &str)]| {
+        let symbol =
+            (!headings.is_empty()).then(|| headings.iter().map(|(_, title)| *title).collect::<Vec<_>>().join(" > "));
+        chunks.extend(chunk_lines(
+            &lines[start..end],
+            start + 1,
+            chunk_size,
+            overlap,
+            symbol.as_deref(),
+        ));
+    };
+
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = line.trim_start();
+        if let Some(marker) = fence {
+            if trimmed.starts_with(marker) {
+                fence = None;
+            }
+            continue;
+        }
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            fence = Some(&trimmed[..3]);
+            continue;
+        }
+
+        let Some((level, title)) = parse_heading(line) else {
+            continue;
+        };
+        push_section(section_start, i, &headings);
+        section_start = i;
+        while headings.last().is_some_and(|(parent, _)| *parent >= level) {
+            headings.pop();
+        }
+        headings.push((level, title));
+    }
+    push_section(section_start, lines.len(), &headings);
+
+    chunks
+}
+
+/// Parse an ATX heading such as `## Install` into its level and title
+fn parse_heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    let rest = &line[level..];
+    if !(1..=6).contains(&level) || !(rest.is_empty() || rest.starts_with([' ', '\t'])) {
+        return None;
+    }
+    Some((level, rest.trim().trim_end_matches('#').trim_end()))
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::Once;
@@ -62,6 +262,8 @@ mod tests {
                     max_files: 1000, // Add missing max_files field
                     hosted_models_base_url: "http://test.example.com/models".to_string(),
                     embedding_type: crate::embedding::EmbeddingType::default(),
+                    hybrid: crate::config::HybridSearchConfig::default(),
+                    watch: crate::config::WatchConfig::default(),
                 };
                 // Use a different approach that doesn't access private static
                 let _ = crate::config::init_config(&std::env::temp_dir());
@@ -106,6 +308,69 @@ mod tests {
         assert!(chunks[4].ends_with("word199"));
     }
 
+    #[test]
+    fn test_chunk_text_with_lines() {
+        setup();
+        let text = "fn main() {\n    println!(\"hello\");\n\n    println!(\"world\");\n}\n";
+
+        let chunks = chunk_text_with_lines(text, Some(3), Some(1));
+
+        let ranges: Vec<_> = chunks.iter().map(|c| (c.start_line, c.end_line)).collect();
+        assert_eq!(ranges, vec![(1, 1), (1, 4), (4, 5)]);
+        assert_eq!(chunks[0].text, "fn main() {");
+        assert_eq!(chunks[1].text, "{ println!(\"hello\"); println!(\"world\");");
+    }
+
+    #[test]
+    fn test_chunk_lines() {
+        let lines = ["one two", "three four", "", "five six", "seven"];
+
+        let chunks = chunk_lines(&lines, 10, 4, 2, Some("sym"));
+
+        let ranges: Vec<_> = chunks.iter().map(|c| (c.start_line, c.end_line)).collect();
+        assert_eq!(ranges, vec![(10, 11), (11, 13), (13, 14)]);
+        assert_eq!(chunks[0].text, "one two\nthree four");
+        assert_eq!(chunks[1].text, "three four\n\nfive six");
+        assert!(chunks.iter().all(|c| c.symbol.as_deref() == Some("sym")));
+    }
+
+    #[test]
+    fn test_chunk_lines_long_line() {
+        let lines = ["a b c d e f", "g"];
+
+        let chunks = chunk_lines(&lines, 7, 3, 1, Some("sym"));
+
+        let texts: Vec<_> = chunks.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(texts, vec!["a b c", "c d e", "e f", "g"]);
+        let ranges: Vec<_> = chunks.iter().map(|c| (c.start_line, c.end_line)).collect();
+        assert_eq!(ranges, vec![(7, 7), (7, 7), (7, 7), (8, 8)]);
+        assert!(chunks.iter().all(|c| c.symbol.as_deref() == Some("sym")));
+
+        // The overlap is capped so that long lines are still split
+        assert_eq!(chunk_lines(&["a b c d"], 1, 2, 5, None).len(), 4);
+    }
+
+    #[test]
+    fn test_chunk_markdown() {
+        setup();
+        let text =
+            "Intro text\n\n# Install\nRun it.\n## Linux\nUse apt.\n```sh\n# not a heading\n```\n# Usage ##\nCall it.\n";
+
+        let chunks = chunk_markdown(text, Some(100), Some(0));
+
+        let sections: Vec<_> = chunks
+            .iter()
+            .map(|c| (c.symbol.as_deref(), c.start_line, c.end_line))
+            .collect();
+        assert_eq!(sections, vec![
+            (None, 1, 1),
+            (Some("Install"), 3, 4),
+            (Some("Install > Linux"), 5, 9),
+            (Some("Usage"), 10, 11),
+        ]);
+        assert_eq!(chunks[2].text, "## Linux\nUse apt.\n```sh\n# not a heading\n```");
+    }
+
     #[test]
     fn test_chunk_text_with_config_defaults() {
         setup();
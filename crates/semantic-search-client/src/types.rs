@@ -107,6 +107,10 @@ pub struct KnowledgeContext {
     /// Embedding type used for this context
     #[serde(default)]
     pub embedding_type: EmbeddingType,
+
+    /// Whether changes to the source path are re-indexed automatically
+    #[serde(default)]
+    pub watch: bool,
 }
 
 impl KnowledgeContext {
@@ -135,10 +139,30 @@ impl KnowledgeContext {
             persistent,
             item_count,
             embedding_type,
+            watch: false,
         }
     }
 }
 
+/// Freshness of a context whose source path is watched for changes
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WatchStatus {
+    /// Whether updates are held back because the session is idle
+    pub paused: bool,
+
+    /// Number of files changed since the context was last updated
+    pub pending_changes: usize,
+
+    /// When the oldest change that is not indexed yet happened
+    pub stale_since: Option<DateTime<Utc>>,
+
+    /// Whether an update of the pending changes is running
+    pub updating: bool,
+
+    /// Why the context stopped being watched, if it did
+    pub error: Option<String>,
+}
+
 /// A data point in the semantic index
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DataPoint {
@@ -185,6 +209,55 @@ impl SearchResult {
     pub fn text(&self) -> Option<&str> {
         self.point.payload.get("text").and_then(|v| v.as_str())
     }
+
+    /// Get the path of the file this result was indexed from
+    pub fn path(&self) -> Option<&str> {
+        self.point.payload.get("path").and_then(|v| v.as_str())
+    }
+
+    /// Get the function, type or Markdown heading this result belongs to
+    pub fn symbol(&self) -> Option<&str> {
+        self.point.payload.get("symbol").and_then(|v| v.as_str())
+    }
+
+    /// Get the page, slide, sheet or notebook cell of a document this result comes from, as the
+    /// kind of section and its number starting at 1
+    pub fn section(&self) -> Option<(&'static str, usize)> {
+        ["page", "slide", "sheet", "cell"].into_iter().find_map(|kind| {
+            let number = self.point.payload.get(kind)?.as_u64()?;
+            Some((kind, number as usize))
+        })
+    }
+
+    /// Get the first and last line of the file covered by this result, starting at 1
+    ///
+    /// Returns `None` for results indexed before line ranges were recorded.
+    pub fn line_range(&self) -> Option<(usize, usize)> {
+        let start = self.point.payload.get("start_line")?.as_u64()?;
+        let end = self.point.payload.get("end_line")?.as_u64()?;
+        Some((start as usize, end as usize))
+    }
+}
+
+/// Restrictions on the results of a search
+///
+/// All set fields must match for a result to be returned.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchFilter {
+    /// Glob the path of the source file must match, e.g. `src/**/*.rs`
+    pub path: Option<String>,
+    /// File type (`code`, `markdown`, `text`, `html`, `pdf`, `office`, `notebook`) or extension
+    /// (`rs`, `py`, `docx`) of the source file
+    pub file_type: Option<String>,
+    /// Glob the name of the context must match
+    pub context_name: Option<String>,
+}
+
+impl SearchFilter {
+    /// Whether the filter restricts individual results rather than whole contexts
+    pub fn filters_results(&self) -> bool {
+        self.path.is_some() || self.file_type.is_some()
+    }
 }
 
 /// File type for processing
@@ -198,6 +271,14 @@ pub enum FileType {
     Json,
     /// Source code file (programming languages)
     Code,
+    /// HTML page, indexed without its markup
+    Html,
+    /// PDF document
+    Pdf,
+    /// Word, PowerPoint or Excel document
+    Office,
+    /// Jupyter notebook
+    Notebook,
     /// Unknown file type
     Unknown,
 }
@@ -234,6 +315,17 @@ pub struct OperationHandle {
     pub(crate) cancel_token: CancellationToken,
     /// Task handle for proper cancellation
     pub(crate) task_handle: Option<tokio::task::AbortHandle>,
+    /// Whether the operation is still running or how it ended
+    pub(crate) state: tokio::sync::watch::Sender<OperationState>,
+}
+
+/// State of an operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum OperationState {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
 }
 
 /// Type of operation being performed
@@ -246,6 +338,13 @@ pub enum OperationType {
         /// Path being indexed
         path: String,
     },
+    /// Re-indexing the changed files of a context
+    Updating {
+        /// Display name for the operation
+        name: String,
+        /// Path being re-indexed
+        path: String,
+    },
     /// Clearing all contexts
     Clearing,
 }
@@ -255,6 +354,7 @@ impl OperationType {
     pub fn display_name(&self) -> String {
         match self {
             OperationType::Indexing { name, .. } => format!("Indexing '{}'", name),
+            OperationType::Updating { name, .. } => format!("Updating '{}'", name),
             OperationType::Clearing => "Clearing all".to_string(),
         }
     }
@@ -391,6 +491,15 @@ pub enum IndexingJob {
         /// Embedding type
         embedding_type: Option<EmbeddingType>,
     },
+    /// Re-index the added, changed and removed files of an existing context
+    Update {
+        /// Operation ID
+        id: Uuid,
+        /// Cancellation token
+        cancel: CancellationToken,
+        /// ID of the context to update
+        context_id: String,
+    },
     /// Clear all contexts job
     Clear {
         /// Operation ID
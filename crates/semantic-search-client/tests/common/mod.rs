@@ -0,0 +1,40 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use semantic_search_client::AsyncSemanticSearchClient;
+use semantic_search_client::types::AddContextRequest;
+
+/// Wait for the background operations of a client to finish, failing the test if one of them
+/// failed or they take more than 10 seconds
+pub async fn wait_for_operations(client: &AsyncSemanticSearchClient) {
+    for _ in 0..200 {
+        let status = client.get_status_data().await.unwrap();
+        if status.operations.is_empty() {
+            return;
+        }
+        assert!(
+            status.operations.iter().all(|op| !op.is_failed),
+            "operation failed: {:?}",
+            status.operations
+        );
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("operations did not finish");
+}
+
+/// Add a persistent context indexing `path` and wait for it to be indexed
+pub async fn add_context(client: &AsyncSemanticSearchClient, path: PathBuf, name: &str) {
+    client
+        .add_context(AddContextRequest {
+            path,
+            name: name.to_string(),
+            description: String::new(),
+            persistent: true,
+            include_patterns: None,
+            exclude_patterns: None,
+            embedding_type: None,
+        })
+        .await
+        .unwrap();
+    wait_for_operations(client).await;
+}
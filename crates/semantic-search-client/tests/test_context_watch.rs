@@ -0,0 +1,116 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use common::{
+    add_context,
+    wait_for_operations,
+};
+use semantic_search_client::config::WatchConfig;
+use semantic_search_client::embedding::EmbeddingType;
+use semantic_search_client::{
+    AsyncSemanticSearchClient,
+    SearchFilter,
+    SemanticSearchConfig,
+};
+use tempfile::TempDir;
+
+async fn finds(client: &AsyncSemanticSearchClient, context_id: &str, query: &str, file: &str) -> bool {
+    client
+        .search_context(context_id, query, Some(10), &SearchFilter::default())
+        .await
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|result| result.path())
+        .any(|path| Path::new(path).file_name().is_some_and(|name| name == file))
+}
+
+async fn wait_until_found(client: &AsyncSemanticSearchClient, context_id: &str, query: &str, file: &str) {
+    for _ in 0..200 {
+        if finds(client, context_id, query, file).await {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("{} was not re-indexed", file);
+}
+
+/// Client with a watched context indexed from `source`
+async fn watched_client(temp_dir: &TempDir, idle_pause_secs: u64) -> (AsyncSemanticSearchClient, String) {
+    let base_dir = temp_dir.path().join("knowledge");
+    let source_dir = temp_dir.path().join("source");
+    fs::create_dir_all(&source_dir).unwrap();
+    fs::write(source_dir.join("a.txt"), "alpha apples").unwrap();
+
+    let config = SemanticSearchConfig {
+        base_dir: base_dir.clone(),
+        embedding_type: EmbeddingType::Fast,
+        watch: WatchConfig {
+            debounce_ms: 100,
+            idle_pause_secs,
+        },
+        ..Default::default()
+    };
+    let client = AsyncSemanticSearchClient::with_config(&base_dir, config).await.unwrap();
+
+    add_context(&client, source_dir, "source").await;
+
+    let context_id = client.get_contexts().await[0].id.clone();
+    client.set_context_watch(&context_id, true).await.unwrap();
+    // Let the check made when the watch starts finish
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    wait_for_operations(&client).await;
+
+    (client, context_id)
+}
+
+#[tokio::test]
+async fn test_watched_context_reindexes_changes() {
+    let temp_dir = TempDir::new().unwrap();
+    let (client, context_id) = watched_client(&temp_dir, 300).await;
+    let source_dir = temp_dir.path().join("source");
+
+    assert_eq!(client.watch_status(&context_id).unwrap().pending_changes, 0);
+
+    fs::write(source_dir.join("b.txt"), "bravo bananas").unwrap();
+    wait_until_found(&client, &context_id, "bananas", "b.txt").await;
+    wait_for_operations(&client).await;
+
+    let status = client.watch_status(&context_id).unwrap();
+    assert_eq!(status.pending_changes, 0);
+    assert!(status.stale_since.is_none());
+
+    // The setting is saved with the context
+    let contexts = fs::read_to_string(temp_dir.path().join("knowledge/contexts.json")).unwrap();
+    let contexts: serde_json::Value = serde_json::from_str(&contexts).unwrap();
+    assert_eq!(contexts[&context_id]["watch"], true);
+
+    client.set_context_watch(&context_id, false).await.unwrap();
+    assert!(client.watch_status(&context_id).is_none());
+    assert!(!client.get_contexts().await[0].watch);
+}
+
+#[tokio::test]
+async fn test_updates_wait_while_session_is_idle() {
+    let temp_dir = TempDir::new().unwrap();
+    let (client, context_id) = watched_client(&temp_dir, 0).await;
+    let source_dir = temp_dir.path().join("source");
+
+    client.set_idle(true);
+    fs::write(source_dir.join("c.txt"), "charlie cherries").unwrap();
+    fs::write(source_dir.join(".hidden.txt"), "hidden hazelnuts").unwrap();
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    let status = client.watch_status(&context_id).unwrap();
+    assert!(status.paused);
+    // Hidden files are not indexed, so they do not make the context stale
+    assert_eq!(status.pending_changes, 1);
+    assert!(status.stale_since.is_some());
+    assert!(!finds(&client, &context_id, "cherries", "c.txt").await);
+
+    client.set_idle(false);
+    wait_until_found(&client, &context_id, "cherries", "c.txt").await;
+    assert!(!client.watch_status(&context_id).unwrap().paused);
+}
@@ -0,0 +1,123 @@
+use std::path::{
+    Path,
+    PathBuf,
+};
+
+use semantic_search_client::config;
+use semantic_search_client::processing::document_extractor::extract_document;
+use semantic_search_client::processing::file_processor::process_file;
+use serde_json::Value;
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/documents")
+        .join(name)
+}
+
+/// Sections of a document as (kind, number, name, text with whitespace collapsed)
+fn sections(name: &str) -> Vec<(&'static str, usize, Option<String>, String)> {
+    extract_document(&fixture(name))
+        .unwrap()
+        .into_iter()
+        .map(|section| {
+            let text = section.text.split_whitespace().collect::<Vec<_>>().join(" ");
+            (section.kind, section.number, section.name, text)
+        })
+        .collect()
+}
+
+#[test]
+fn test_extract_pdf_pages() {
+    assert_eq!(sections("runbook.pdf"), vec![
+        ("page", 1, None, "Restart the ingestion service".to_string()),
+        ("page", 2, None, "Rotate the database credentials".to_string()),
+    ]);
+}
+
+#[test]
+fn test_extract_docx_pages() {
+    assert_eq!(sections("design.docx"), vec![
+        (
+            "page",
+            1,
+            None,
+            "Design overview Queues & workers scale out".to_string()
+        ),
+        ("page", 2, None, "Failure modes".to_string()),
+    ]);
+}
+
+#[test]
+fn test_extract_pptx_slides() {
+    // Slides are ordered by number and empty slides are skipped
+    assert_eq!(sections("roadmap.pptx"), vec![
+        ("slide", 1, None, "Roadmap Q1 goals".to_string()),
+        ("slide", 10, None, "Launch checklist".to_string()),
+    ]);
+}
+
+#[test]
+fn test_extract_xlsx_sheets() {
+    let sheets = extract_document(&fixture("budget.xlsx")).unwrap();
+
+    assert_eq!(sheets.len(), 2);
+    assert_eq!(sheets[0].name.as_deref(), Some("Costs"));
+    assert_eq!(sheets[0].text, "Item\tCost\nCompute instances\t1200\n");
+    assert_eq!((sheets[1].number, sheets[1].name.as_deref()), (2, Some("Notes")));
+    assert_eq!(sheets[1].text, "Reviewed monthly\n");
+}
+
+#[test]
+fn test_extract_notebook_cells() {
+    assert_eq!(sections("analysis.ipynb"), vec![
+        (
+            "cell",
+            1,
+            Some("markdown".to_string()),
+            "# Latency analysis Load the traces.".to_string()
+        ),
+        (
+            "cell",
+            2,
+            Some("code".to_string()),
+            "p99 = traces.latency.quantile(0.99) print(p99) 250 ms".to_string()
+        ),
+    ]);
+}
+
+#[test]
+fn test_process_documents_records_sections() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    config::init_config(temp_dir.path()).unwrap();
+
+    let items = process_file(&fixture("runbook.pdf")).unwrap();
+    let pages: Vec<_> = items.iter().filter_map(|item| item.get("page")?.as_u64()).collect();
+    assert_eq!(pages, vec![1, 2]);
+    assert_eq!(items[0].get("file_type").and_then(Value::as_str), Some("Pdf"));
+
+    let items = process_file(&fixture("budget.xlsx")).unwrap();
+    assert_eq!(items[0].get("sheet").and_then(Value::as_u64), Some(1));
+    assert_eq!(items[0].get("section_name").and_then(Value::as_str), Some("Costs"));
+
+    let items = process_file(&fixture("analysis.ipynb")).unwrap();
+    let cells: Vec<_> = items.iter().filter_map(|item| item.get("cell")?.as_u64()).collect();
+    assert_eq!(cells, vec![1, 2]);
+}
+
+#[test]
+fn test_process_html_strips_markup() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    config::init_config(temp_dir.path()).unwrap();
+
+    let items = process_file(&fixture("status.html")).unwrap();
+
+    assert_eq!(items.len(), 1);
+    let text = items[0].get("text").and_then(Value::as_str).unwrap();
+    assert_eq!(
+        text,
+        "Service status All systems operational Next maintenance window: Sunday & Monday"
+    );
+    // Line numbers are those of the page, which starts with the doctype
+    assert_eq!(items[0].get("start_line").and_then(Value::as_u64), Some(4));
+    assert_eq!(items[0].get("end_line").and_then(Value::as_u64), Some(11));
+}
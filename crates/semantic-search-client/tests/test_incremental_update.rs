@@ -0,0 +1,91 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+
+use common::{
+    add_context,
+    wait_for_operations,
+};
+use semantic_search_client::client::ContextManifest;
+use semantic_search_client::embedding::EmbeddingType;
+use semantic_search_client::{
+    AsyncSemanticSearchClient,
+    SearchFilter,
+    SemanticSearchConfig,
+};
+use tempfile::TempDir;
+
+async fn matching_paths(client: &AsyncSemanticSearchClient, context_id: &str, query: &str) -> Vec<String> {
+    client
+        .search_context(context_id, query, Some(10), &SearchFilter::default())
+        .await
+        .map(|results| {
+            results
+                .into_iter()
+                .filter_map(|result| result.point.payload.get("path")?.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn file_name(path: &str) -> &str {
+    Path::new(path).file_name().unwrap().to_str().unwrap()
+}
+
+#[tokio::test]
+async fn test_update_reindexes_only_changed_files() {
+    let temp_dir = TempDir::new().unwrap();
+    let base_dir = temp_dir.path().join("knowledge");
+    let source_dir = temp_dir.path().join("source");
+    fs::create_dir_all(&source_dir).unwrap();
+    fs::write(source_dir.join("a.txt"), "alpha apples").unwrap();
+    fs::write(source_dir.join("b.txt"), "bravo bananas").unwrap();
+    fs::write(source_dir.join("c.txt"), "charlie cherries").unwrap();
+
+    let config = SemanticSearchConfig {
+        base_dir: base_dir.clone(),
+        embedding_type: EmbeddingType::Fast,
+        ..Default::default()
+    };
+    let client = AsyncSemanticSearchClient::with_config(&base_dir, config).await.unwrap();
+
+    add_context(&client, source_dir.clone(), "source").await;
+
+    let context_id = client.get_contexts().await[0].id.clone();
+    let manifest = ContextManifest::load(&base_dir.join(&context_id)).unwrap().unwrap();
+    assert_eq!(manifest.files.len(), 3);
+    let a_key = source_dir
+        .canonicalize()
+        .unwrap()
+        .join("a.txt")
+        .to_string_lossy()
+        .to_string();
+    let a_chunks = manifest.files[&a_key].chunk_ids.clone();
+
+    fs::write(source_dir.join("b.txt"), "bravo blueberries").unwrap();
+    fs::remove_file(source_dir.join("c.txt")).unwrap();
+    fs::write(source_dir.join("d.txt"), "delta dates").unwrap();
+
+    client.update_context(&context_id).await.unwrap();
+    wait_for_operations(&client).await;
+
+    let manifest = ContextManifest::load(&base_dir.join(&context_id)).unwrap().unwrap();
+    let mut files: Vec<_> = manifest.files.keys().map(|path| file_name(path).to_string()).collect();
+    files.sort();
+    assert_eq!(files, vec!["a.txt", "b.txt", "d.txt"]);
+    // Unchanged files keep their chunks
+    assert_eq!(manifest.files[&a_key].chunk_ids, a_chunks);
+
+    let paths = matching_paths(&client, &context_id, "apples").await;
+    assert_eq!(paths.iter().map(|p| file_name(p)).collect::<Vec<_>>(), vec!["a.txt"]);
+    let paths = matching_paths(&client, &context_id, "blueberries").await;
+    assert_eq!(paths.iter().map(|p| file_name(p)).collect::<Vec<_>>(), vec!["b.txt"]);
+    let paths = matching_paths(&client, &context_id, "dates").await;
+    assert_eq!(paths.iter().map(|p| file_name(p)).collect::<Vec<_>>(), vec!["d.txt"]);
+    assert!(matching_paths(&client, &context_id, "bananas").await.is_empty());
+    assert!(matching_paths(&client, &context_id, "cherries").await.is_empty());
+
+    let context = client.get_contexts().await.remove(0);
+    assert_eq!(context.item_count, 3);
+}
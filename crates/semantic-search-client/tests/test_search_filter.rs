@@ -0,0 +1,94 @@
+mod common;
+
+use std::fs;
+
+use common::add_context;
+use semantic_search_client::embedding::EmbeddingType;
+use semantic_search_client::{
+    AsyncSemanticSearchClient,
+    SearchFilter,
+    SearchResult,
+    SemanticSearchConfig,
+    SemanticSearchError,
+};
+use tempfile::TempDir;
+
+fn file_names(results: &[(String, Vec<SearchResult>)]) -> Vec<String> {
+    let mut names: Vec<String> = results
+        .iter()
+        .flat_map(|(_, results)| results.iter())
+        .filter_map(|result| result.path())
+        .map(|path| path.rsplit('/').next().unwrap().to_string())
+        .collect();
+    names.sort();
+    names
+}
+
+#[tokio::test]
+async fn test_search_filters() {
+    let temp_dir = TempDir::new().unwrap();
+    let base_dir = temp_dir.path().join("knowledge");
+    let docs_dir = temp_dir.path().join("docs");
+    let code_dir = temp_dir.path().join("code");
+    fs::create_dir_all(docs_dir.join("guide")).unwrap();
+    fs::create_dir_all(&code_dir).unwrap();
+    fs::write(
+        docs_dir.join("guide/setup.md"),
+        "# Setup\n\nInstall the widget first.\n",
+    )
+    .unwrap();
+    fs::write(docs_dir.join("notes.txt"), "The widget is blue.").unwrap();
+    fs::write(
+        code_dir.join("widget.rs"),
+        "fn build_widget() -> Widget {\n    Widget::default()\n}\n",
+    )
+    .unwrap();
+
+    let config = SemanticSearchConfig {
+        base_dir: base_dir.clone(),
+        embedding_type: EmbeddingType::Fast,
+        ..Default::default()
+    };
+    let client = AsyncSemanticSearchClient::with_config(&base_dir, config).await.unwrap();
+    add_context(&client, docs_dir, "docs").await;
+    add_context(&client, code_dir, "code").await;
+
+    let all = client
+        .search_all("widget", None, &SearchFilter::default())
+        .await
+        .unwrap();
+    assert_eq!(file_names(&all), vec!["notes.txt", "setup.md", "widget.rs"]);
+
+    let filter = SearchFilter {
+        context_name: Some("do*".to_string()),
+        ..Default::default()
+    };
+    let results = client.search_all("widget", None, &filter).await.unwrap();
+    assert_eq!(file_names(&results), vec!["notes.txt", "setup.md"]);
+
+    let filter = SearchFilter {
+        path: Some("guide/*.md".to_string()),
+        ..Default::default()
+    };
+    let results = client.search_all("widget", None, &filter).await.unwrap();
+    assert_eq!(file_names(&results), vec!["setup.md"]);
+
+    for file_type in ["code", "rs", ".RS"] {
+        let filter = SearchFilter {
+            file_type: Some(file_type.to_string()),
+            ..Default::default()
+        };
+        let results = client.search_all("widget", None, &filter).await.unwrap();
+        assert_eq!(file_names(&results), vec!["widget.rs"], "file type {file_type}");
+        assert_eq!(results[0].1[0].line_range(), Some((1, 3)));
+    }
+
+    let filter = SearchFilter {
+        path: Some("[".to_string()),
+        ..Default::default()
+    };
+    assert!(matches!(
+        client.search_all("widget", None, &filter).await,
+        Err(SemanticSearchError::InvalidArgument(_))
+    ));
+}
@@ -98,3 +98,68 @@ fn test_add_data_points() {
     // Clean up
     fs::remove_dir_all(temp_dir).unwrap_or(());
 }
+
+#[test]
+fn test_remove_data_points() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let data_path = temp_dir.path().join("data.json");
+    let mut semantic_context = SemanticContext::new(data_path.clone()).unwrap();
+
+    // Data points pointing in different directions
+    let data_points = (0..3)
+        .map(|id| {
+            let mut vector = vec![0.0; 384];
+            vector[id] = 1.0;
+            let mut payload = HashMap::new();
+            payload.insert("text".to_string(), Value::String(format!("data point {id}")));
+            DataPoint { id, payload, vector }
+        })
+        .collect();
+    semantic_context.add_data_points(data_points).unwrap();
+
+    let removed = semantic_context.remove_data_points(&[0, 7].into()).unwrap();
+    assert_eq!(removed, 1);
+    assert_eq!(semantic_context.get_data_points().len(), 2);
+    assert_eq!(semantic_context.next_id(), 3);
+
+    // Search results map back to the right data points after the removal shifted them
+    let mut query = vec![0.0; 384];
+    query[2] = 1.0;
+    let results = semantic_context.search(&query, 1).unwrap();
+    assert_eq!(results[0].point.id, 2);
+
+    // Removed data points stay removed after reloading
+    semantic_context.save().unwrap();
+    let loaded_context = SemanticContext::new(data_path).unwrap();
+    let ids: Vec<_> = loaded_context.get_data_points().iter().map(|p| p.id).collect();
+    assert_eq!(ids, vec![1, 2]);
+}
+
+#[test]
+fn test_removed_ids_are_not_reused() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let mut semantic_context = SemanticContext::new(temp_dir.path().join("data.json")).unwrap();
+
+    let data_point = |id: usize, axis: usize| {
+        let mut vector = vec![0.0; 384];
+        vector[axis] = 1.0;
+        let mut payload = HashMap::new();
+        payload.insert("text".to_string(), Value::String(format!("data point {id}")));
+        DataPoint { id, payload, vector }
+    };
+    semantic_context
+        .add_data_points((0..3).map(|id| data_point(id, id)).collect())
+        .unwrap();
+
+    // Removing the data point with the highest ID does not hand it out again
+    semantic_context.remove_data_points(&[2].into()).unwrap();
+    assert_eq!(semantic_context.next_id(), 3);
+
+    let id = semantic_context.next_id();
+    semantic_context.add_data_points(vec![data_point(id, 3)]).unwrap();
+
+    let mut query = vec![0.0; 384];
+    query[3] = 1.0;
+    let results = semantic_context.search(&query, 1).unwrap();
+    assert_eq!(results[0].point.id, 3);
+}
@@ -53,3 +53,43 @@ fn test_search() {
         assert!(results[0].0 <= 2);
     }
 }
+
+#[test]
+fn test_remove() {
+    let index = VectorIndex::new(384);
+
+    // Three vectors pointing in different directions
+    for id in 0..3 {
+        let mut vector = vec![0.0; 384];
+        vector[id] = 1.0;
+        index.insert(&vector, id);
+    }
+
+    assert!(index.remove(1));
+    assert!(!index.remove(1));
+    assert_eq!(index.len(), 2);
+    assert_eq!(index.deleted_count(), 1);
+
+    // The removed vector is never returned, even for an exact match
+    let mut query = vec![0.0; 384];
+    query[1] = 1.0;
+    let results = index.search(&query, 3, 100);
+    assert!(!results.is_empty());
+    assert!(results.iter().all(|(id, _)| *id != 1));
+}
+
+#[test]
+fn test_remove_unknown_id() {
+    let index = VectorIndex::new(384);
+    index.insert(&vec![0.1; 384], 0);
+
+    // Removing an ID that was never inserted leaves the index untouched
+    assert!(!index.remove(7));
+    assert_eq!(index.len(), 1);
+    assert_eq!(index.deleted_count(), 0);
+
+    assert!(index.remove(0));
+    assert!(!index.remove(0));
+    assert_eq!(index.len(), 0);
+    assert!(index.is_empty());
+}